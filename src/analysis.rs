@@ -0,0 +1,361 @@
+//! Measurements over whole animations.
+
+use crate::math;
+use crate::skeleton::Skeleton;
+use crate::{Animation, EditOperation, JointData, Pose};
+use glam::{Quat, Vec3};
+
+/// Samples per second used to measure motion.
+const SAMPLE_RATE: f32 = 30.0;
+const MAX_SAMPLES: usize = 4096;
+/// Extremes closer together than this are treated as one pose hit.
+const MIN_SPACING_S: f32 = 0.15;
+
+/// Times in seconds of the animation's extreme poses ("hits"), in order.
+///
+/// A hit is where joints reverse or sharply change direction: the summed change in angular
+/// velocity across all joints peaks above its average. Of several peaks within 0.15 s only
+/// the strongest is kept. Position keys are ignored.
+///
+/// # Example
+///
+/// ```rust
+/// use avatar_anim::analysis::find_extremes;
+/// use avatar_anim::{Animation, JointData, RotationKey};
+/// use glam::Quat;
+///
+/// // Raise the head, then lower it again: one hit at the top, halfway through.
+/// let mut anim = Animation::new();
+/// anim.header.duration = 2.0;
+/// anim.joints.push(JointData {
+///     name: "mHead".into(),
+///     rotation_keys: vec![
+///         RotationKey { time: 0, rot: Quat::IDENTITY },
+///         RotationKey { time: 32768, rot: Quat::from_rotation_y(-0.5) },
+///         RotationKey { time: 65535, rot: Quat::IDENTITY },
+///     ],
+///     ..Default::default()
+/// });
+/// let hits = find_extremes(&anim);
+/// assert_eq!(hits.len(), 1);
+/// assert!((hits[0] - 1.0).abs() < 0.05);
+/// ```
+pub fn find_extremes(anim: &Animation) -> Vec<f32> {
+    let duration = anim.header.duration;
+    if !(duration.is_finite() && duration > 0.0) {
+        return Vec::new();
+    }
+    let n = ((duration * SAMPLE_RATE).ceil() as usize).clamp(2, MAX_SAMPLES);
+    let dt = duration / n as f32;
+    let tick = |i: usize| i as f32 / n as f32 * u16::MAX as f32;
+
+    // Angular velocity of every joint over each sample interval.
+    let velocity: Vec<Vec<Vec3>> = (0..n)
+        .map(|i| {
+            anim.joints
+                .iter()
+                .map(
+                    |j| match (j.rotation_at(tick(i)), j.rotation_at(tick(i + 1))) {
+                        (Some(a), Some(b)) => math::angular_velocity(a, b, dt),
+                        _ => Vec3::ZERO,
+                    },
+                )
+                .collect()
+        })
+        .collect();
+    // Change of velocity at each interior sample.
+    let reversal: Vec<f32> = (1..n)
+        .map(|i| {
+            velocity[i]
+                .iter()
+                .zip(&velocity[i - 1])
+                .map(|(a, b)| (*a - *b).length())
+                .sum()
+        })
+        .collect();
+    let mean = reversal.iter().sum::<f32>() / reversal.len() as f32;
+    let time = |r: usize| (r + 1) as f32 * dt;
+    let window = (MIN_SPACING_S / dt).ceil() as usize;
+
+    (0..reversal.len())
+        .filter(|&r| {
+            let lo = r.saturating_sub(window);
+            let hi = (r + window).min(reversal.len() - 1);
+            reversal[r] > mean
+                && (lo..=hi)
+                    .all(|o| reversal[o] < reversal[r] || (o >= r && reversal[o] <= reversal[r]))
+        })
+        .map(time)
+        .collect()
+}
+
+/// Approximate ground speed of a walking avatar, in meters per second.
+pub const WALK_SPEED: f32 = 3.2;
+/// Approximate ground speed of a running avatar, in meters per second.
+pub const RUN_SPEED: f32 = 5.13;
+
+/// Ground motion a locomotion cycle implies, from [`stride_profile`].
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct StrideProfile {
+    /// Speed in meters per second at which the planted foot stays put on the ground.
+    pub speed: f32,
+    /// Ground covered in one cycle (the loop, or the whole animation), in meters.
+    pub stride_length: f32,
+    /// Length of the cycle in seconds.
+    pub cycle: f32,
+}
+
+impl StrideProfile {
+    /// How fast the planted foot visibly slides when the avatar moves at `ground_speed`
+    /// meters per second, positive when the feet lag behind the body.
+    pub fn slip(&self, ground_speed: f32) -> f32 {
+        ground_speed - self.speed
+    }
+
+    /// Whether the feet slide by more than `tolerance` (a fraction of `ground_speed`), e.g.
+    /// `profile.slides_at(analysis::WALK_SPEED, 0.15)`.
+    pub fn slides_at(&self, ground_speed: f32, tolerance: f32) -> bool {
+        self.slip(ground_speed).abs() > ground_speed.abs() * tolerance
+    }
+}
+
+/// Positions of `targets` in the animation's root frame at `tick`, with bind translations
+/// from `skeleton` and only the root's position keys applied.
+fn joint_positions(
+    skeleton: &Skeleton,
+    tracks: &[Option<&JointData>],
+    targets: &[usize],
+    tick: f32,
+) -> Vec<Vec3> {
+    let mut pose = Pose::new();
+    for (joint, track) in skeleton.joints().iter().zip(tracks) {
+        let Some(track) = track else {
+            continue;
+        };
+        let mut sample = track.sample_at(tick);
+        if joint.parent.is_some() {
+            sample.position = None;
+        }
+        pose.joints.insert(joint.name.clone(), sample);
+    }
+    let world = skeleton.world_transforms(&pose);
+    targets.iter().map(|&i| world[i].translation).collect()
+}
+
+/// Estimate the ground speed and stride length a walk or run cycle implies.
+///
+/// SL locomotion animations play in place while the avatar moves forward (+X), so whichever
+/// ankle is lower is taken as planted and its backward speed is the speed at which it would
+/// stay put on the ground. The median over the cycle is used, sampled at 30 fps. Compare the
+/// result with [`WALK_SPEED`] or [`RUN_SPEED`] to spot animations that will foot-slide.
+///
+/// Returns `None` if `skeleton` has no `mAnkleLeft`/`mAnkleRight` or the animation has no
+/// duration.
+pub fn stride_profile(anim: &Animation, skeleton: &Skeleton) -> Option<StrideProfile> {
+    let feet = [
+        skeleton.index_of("mAnkleLeft")?,
+        skeleton.index_of("mAnkleRight")?,
+    ];
+    let duration = anim.header.duration;
+    if !(duration.is_finite() && duration > 0.0) {
+        return None;
+    }
+    let (start, end) =
+        if anim.header.looped != 0 && anim.header.loop_out_point > anim.header.loop_in_point {
+            (
+                anim.header.loop_in_point.max(0.0),
+                anim.header.loop_out_point.min(duration),
+            )
+        } else {
+            (0.0, duration)
+        };
+    let cycle = end - start;
+    if cycle <= 0.0 {
+        return None;
+    }
+
+    let tracks: Vec<Option<&JointData>> = skeleton
+        .joints()
+        .iter()
+        .map(|j| anim.joint(&j.name))
+        .collect();
+    let n = ((cycle * SAMPLE_RATE).ceil() as usize).clamp(2, MAX_SAMPLES);
+    let dt = cycle / n as f32;
+    let samples: Vec<Vec<Vec3>> = (0..=n)
+        .map(|i| {
+            let tick = anim.seconds_to_tick(start + i as f32 * dt);
+            joint_positions(skeleton, &tracks, &feet, tick)
+        })
+        .collect();
+    let mut speeds: Vec<f32> = samples
+        .windows(2)
+        .map(|pair| {
+            let planted = if pair[0][0].z <= pair[0][1].z { 0 } else { 1 };
+            -(pair[1][planted].x - pair[0][planted].x) / dt
+        })
+        .collect();
+    speeds.sort_by(f32::total_cmp);
+    let speed = speeds[speeds.len() / 2];
+    Some(StrideProfile {
+        speed,
+        stride_length: speed * cycle,
+        cycle,
+    })
+}
+
+/// Shortest loop [`suggest_loop`] considers, in seconds.
+const MIN_LOOP_S: f32 = 0.5;
+/// Most samples [`suggest_loop`] compares pairwise.
+const MAX_LOOP_SAMPLES: usize = 1024;
+
+/// Loop points proposed by [`suggest_loop`].
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct LoopSuggestion {
+    /// Seconds.
+    pub loop_in: f32,
+    /// Seconds.
+    pub loop_out: f32,
+    /// Pose mismatch across the seam: mean rotation difference in radians plus mean position
+    /// difference in meters, averaged over the seam frame and the one after it.
+    pub seam_error: f32,
+    /// `1.0` for a seamless loop, `0.0` when the seam is no better than joining two arbitrary
+    /// poses of the animation.
+    pub score: f32,
+}
+
+impl LoopSuggestion {
+    /// Set `anim`'s loop points to the suggestion and mark it looped.
+    pub fn apply(&self, anim: &mut Animation) {
+        let mark = anim.edit_mark();
+        anim.header.loop_in_point = self.loop_in;
+        anim.header.loop_out_point = self.loop_out;
+        anim.header.looped = 1;
+        anim.log_edit(
+            mark,
+            EditOperation::ApplyLoopSuggestion,
+            &[("loop_in", &self.loop_in), ("loop_out", &self.loop_out)],
+        );
+    }
+}
+
+/// Find the loop window whose end pose best matches its start.
+///
+/// Poses are sampled at up to 30 fps and compared pairwise. The autocorrelation over time
+/// lags gives the motion's period (the shortest lag nearly as self-similar as the best one,
+/// so a two-cycle loop doesn't win over a single cycle by a rounding error); the window of that
+/// length with the smoothest seam is suggested. Loops shorter than half a second are not
+/// considered.
+///
+/// Returns `None` if the animation has no keys or is too short to hold a loop.
+///
+/// # Example
+///
+/// ```rust
+/// use avatar_anim::analysis::suggest_loop;
+/// use avatar_anim::{Animation, JointData, RotationKey};
+/// use glam::Quat;
+///
+/// // A nod with a one-second period, played for three seconds.
+/// let mut anim = Animation::new();
+/// anim.header.duration = 3.0;
+/// anim.joints.push(JointData {
+///     name: "mHead".into(),
+///     rotation_keys: (0..=12)
+///         .map(|i| RotationKey {
+///             time: (i * 65535 / 12) as u16,
+///             rot: Quat::from_rotation_y(if i % 4 == 1 { 0.4 } else if i % 4 == 3 { -0.4 } else { 0.0 }),
+///         })
+///         .collect(),
+///     ..Default::default()
+/// });
+/// let suggestion = suggest_loop(&anim).unwrap();
+/// assert!((suggestion.loop_out - suggestion.loop_in - 1.0).abs() < 0.05);
+/// assert!(suggestion.score > 0.9);
+/// ```
+pub fn suggest_loop(anim: &Animation) -> Option<LoopSuggestion> {
+    let duration = anim.header.duration;
+    if !(duration.is_finite() && duration > 0.0) {
+        return None;
+    }
+    let rotated: Vec<&JointData> = anim
+        .joints
+        .iter()
+        .filter(|j| !j.rotation_keys.is_empty())
+        .collect();
+    let moved: Vec<&JointData> = anim
+        .joints
+        .iter()
+        .filter(|j| !j.position_keys.is_empty())
+        .collect();
+    if rotated.is_empty() && moved.is_empty() {
+        return None;
+    }
+    let n = ((duration * SAMPLE_RATE).ceil() as usize).clamp(2, MAX_LOOP_SAMPLES);
+    let dt = duration / n as f32;
+    let min_lag = (MIN_LOOP_S / dt).ceil() as usize;
+    if min_lag >= n {
+        return None;
+    }
+
+    let poses: Vec<(Vec<Quat>, Vec<Vec3>)> = (0..=n)
+        .map(|i| {
+            let tick = i as f32 / n as f32 * u16::MAX as f32;
+            (
+                rotated.iter().filter_map(|j| j.rotation_at(tick)).collect(),
+                moved.iter().filter_map(|j| j.position_at(tick)).collect(),
+            )
+        })
+        .collect();
+    let distance = |a: usize, b: usize| {
+        let (ra, pa) = &poses[a];
+        let (rb, pb) = &poses[b];
+        let mean = |sum: f32, count: usize| if count == 0 { 0.0 } else { sum / count as f32 };
+        let rotation: f32 = ra
+            .iter()
+            .zip(rb)
+            .map(|(x, y)| math::geodesic_distance(*x, *y))
+            .sum();
+        let position: f32 = pa.iter().zip(pb).map(|(x, y)| x.distance(*y)).sum();
+        mean(rotation, ra.len()) + mean(position, pa.len())
+    };
+    // Matching the following frame too keeps the velocity continuous across the seam.
+    let seam = |a: usize, b: usize| (distance(a, b) + distance(a + 1, b + 1)) / 2.0;
+
+    // Mean pose distance at every lag; `reference` averages over all of them.
+    let lags: Vec<(usize, f32)> = (min_lag..n)
+        .map(|lag| {
+            let total: f32 = (0..=n - lag).map(|i| distance(i, i + lag)).sum();
+            (lag, total / (n - lag + 1) as f32)
+        })
+        .collect();
+    let best = lags.iter().map(|&(_, d)| d).fold(f32::INFINITY, f32::min);
+    let reference = (0..=n)
+        .flat_map(|a| (a + 1..=n).map(move |b| (a, b)))
+        .map(|(a, b)| distance(a, b))
+        .sum::<f32>()
+        / (n * (n + 1) / 2) as f32;
+    let period = lags
+        .iter()
+        .find(|&&(_, d)| d <= best + 0.1 * (reference - best))
+        .map_or(min_lag, |&(lag, _)| lag);
+
+    let (start, seam_error) = (0..n - period)
+        .map(|i| (i, seam(i, i + period)))
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .expect("the period is shorter than the animation");
+    let score = if reference > 0.0 {
+        (1.0 - seam_error / reference).clamp(0.0, 1.0)
+    } else {
+        1.0
+    };
+    Some(LoopSuggestion {
+        loop_in: start as f32 * dt,
+        loop_out: (start + period) as f32 * dt,
+        seam_error: if seam_error.is_finite() {
+            seam_error
+        } else {
+            0.0
+        },
+        score,
+    })
+}
@@ -0,0 +1,43 @@
+//! `.anim` I/O on tokio's async readers and writers.
+
+use crate::{AnimError, Animation, ReadOptions, Result};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+impl Animation {
+    /// Load an animation from an async source such as an HTTP body, without blocking the
+    /// executor.
+    ///
+    /// The source is read to the end and then decoded in place; `.anim` assets are small, so
+    /// decoding needs no separate blocking task. Reading stops past
+    /// [`ReadOptions::max_bytes`] of the default options, so an oversized body fails with
+    /// [`AnimError::LimitExceeded`] instead of being buffered in full.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use avatar_anim::Animation;
+    ///
+    /// async fn decode(body: &[u8]) -> avatar_anim::Result<Animation> {
+    ///     Animation::from_async_reader(body).await
+    /// }
+    /// ```
+    pub async fn from_async_reader<R: AsyncRead + Unpin>(reader: R) -> Result<Self> {
+        let options = ReadOptions::default();
+        let mut bytes = Vec::new();
+        reader
+            .take((options.max_bytes as u64).saturating_add(1))
+            .read_to_end(&mut bytes)
+            .await
+            .map_err(AnimError::Io)?;
+        Self::from_bytes_with_options(&bytes, &options)
+    }
+
+    /// Write the `.anim` encoding to an async sink and flush it.
+    pub async fn to_async_writer<W: AsyncWrite + Unpin>(&self, mut writer: W) -> Result<()> {
+        writer
+            .write_all(&self.to_bytes()?)
+            .await
+            .map_err(AnimError::Io)?;
+        writer.flush().await.map_err(AnimError::Io)
+    }
+}
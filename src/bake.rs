@@ -0,0 +1,116 @@
+//! Skeleton selection and per-frame sampling shared by the frame-based exporters.
+
+use crate::skeleton::Skeleton;
+use crate::{AnimError, Animation, JointData, Result};
+use glam::{Quat, Vec3};
+
+/// The skeleton joints an exporter writes, with the animation's tracks for each.
+pub(crate) struct Rig<'a> {
+    pub skeleton: &'a Skeleton,
+    pub root: usize,
+    /// Kept children of every skeleton joint, in skeleton order.
+    pub children: Vec<Vec<usize>>,
+    tracks: Vec<Option<&'a JointData>>,
+}
+
+impl<'a> Rig<'a> {
+    /// Select the whole skeleton, or with `only_animated` just the joints `anim` has plus their
+    /// ancestors. Animated joints the skeleton doesn't know are ignored.
+    pub fn new(anim: &'a Animation, skeleton: &'a Skeleton, only_animated: bool) -> Result<Self> {
+        let joints = skeleton.joints();
+        let Some(root) = joints.iter().position(|j| j.parent.is_none()) else {
+            return Err(AnimError::InvalidStructure(
+                "Export needs a skeleton with a root joint".into(),
+            ));
+        };
+        let mut keep = vec![!only_animated; joints.len()];
+        keep[root] = true;
+        for joint in &anim.joints {
+            let mut index = skeleton.index_of(&joint.name);
+            while let Some(i) = index {
+                keep[i] = true;
+                index = joints[i].parent;
+            }
+        }
+        let mut children = vec![Vec::new(); joints.len()];
+        for (i, joint) in joints.iter().enumerate() {
+            if let Some(parent) = joint.parent.filter(|_| keep[i]) {
+                children[parent].push(i);
+            }
+        }
+        let tracks = joints.iter().map(|j| anim.joint(&j.name)).collect();
+        Ok(Self {
+            skeleton,
+            root,
+            children,
+            tracks,
+        })
+    }
+
+    /// Kept joints, depth first, parents before children.
+    pub fn order(&self) -> Vec<usize> {
+        let mut order = Vec::new();
+        let mut stack = vec![self.root];
+        while let Some(index) = stack.pop() {
+            order.push(index);
+            stack.extend(self.children[index].iter().rev());
+        }
+        order
+    }
+
+    /// Local rotation and translation of joint `index` at `tick`, or its bind pose for `None`.
+    ///
+    /// Only the root's position keys are applied, as an offset from its bind translation.
+    pub fn local(&self, index: usize, tick: Option<f32>) -> (Quat, Vec3) {
+        let bind = self.skeleton.joints()[index].position;
+        let sample = tick.zip(self.tracks[index]);
+        let rotation = sample
+            .and_then(|(t, track)| track.rotation_at(t))
+            .unwrap_or(Quat::IDENTITY);
+        let offset = sample
+            .filter(|_| index == self.root)
+            .and_then(|(t, track)| track.position_at(t))
+            .unwrap_or(Vec3::ZERO);
+        (rotation, bind + offset)
+    }
+}
+
+/// Most frames an export bakes, an hour at 60 fps.
+const MAX_FRAMES: usize = 1 << 18;
+
+/// Frame times at `rate` frames per second covering `[0, duration]`, as `(seconds, tick)`.
+///
+/// A duration that isn't a whole number of frames is rounded up; the last frame then holds
+/// the final pose. Fails on a duration that isn't a number or would need more than
+/// [`MAX_FRAMES`] frames.
+pub(crate) fn frames(duration: f32, rate: f32) -> Result<Vec<(f32, f32)>> {
+    if !(rate.is_finite() && rate > 0.0) {
+        return Err(AnimError::InvalidStructure(format!(
+            "Export needs a positive frame rate, got {rate}"
+        )));
+    }
+    if !duration.is_finite() {
+        return Err(AnimError::InvalidStructure(format!(
+            "Cannot export an animation lasting {duration}s"
+        )));
+    }
+    let duration = duration.max(0.0);
+    let span = (duration * rate).ceil();
+    if span >= MAX_FRAMES as f32 {
+        return Err(AnimError::InvalidStructure(format!(
+            "Exporting {duration}s at {rate} fps needs more than {MAX_FRAMES} frames"
+        )));
+    }
+    let count = span as usize + 1;
+    Ok((0..count)
+        .map(|frame| {
+            let t = frame as f32 / rate;
+            let tick = if duration > 0.0 {
+                (t / duration).min(1.0) * u16::MAX as f32
+            } else {
+                0.0
+            };
+            (t, tick)
+        })
+        .collect())
+}
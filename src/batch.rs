@@ -0,0 +1,173 @@
+//! Run the same edits over many files, reporting progress to an observer.
+//!
+//! ```rust,no_run
+//! use avatar_anim::batch::{Batch, BatchObserver};
+//! use std::path::{Path, PathBuf};
+//!
+//! struct Log;
+//!
+//! impl BatchObserver for Log {
+//!     fn warning(&mut self, input: &Path, op: &str, message: &str) {
+//!         eprintln!("{}: {op}: {message}", input.display());
+//!     }
+//! }
+//!
+//! let batch = Batch::new()
+//!     .op("priority", |anim, _| {
+//!         anim.set_priority(4);
+//!         Ok(())
+//!     })
+//!     .op("drop empty", |anim, ctx| {
+//!         let before = anim.joints.len();
+//!         anim.drop_empty_joints();
+//!         if anim.joints.len() != before {
+//!             ctx.warn("removed joints without keys");
+//!         }
+//!         Ok(())
+//!     });
+//! let jobs = [(PathBuf::from("in/walk.anim"), PathBuf::from("out/walk.anim"))];
+//! let report = batch.run(jobs, &mut Log);
+//! println!("{} written, {} failed", report.written, report.failed);
+//! ```
+
+use crate::{AnimError, Animation, Result, WriteOutcome};
+use std::path::{Path, PathBuf};
+
+/// Receives events while a [`Batch`] runs. Every method defaults to doing nothing.
+pub trait BatchObserver {
+    /// `input` is about to be loaded.
+    fn file_started(&mut self, input: &Path) {
+        let _ = input;
+    }
+
+    /// Operation `op` finished on `input`.
+    fn op_finished(&mut self, input: &Path, op: &str) {
+        let _ = (input, op);
+    }
+
+    /// Operation `op` reported a problem that did not stop processing.
+    fn warning(&mut self, input: &Path, op: &str, message: &str) {
+        let _ = (input, op, message);
+    }
+
+    /// `input` was processed and saved (or left alone) at `output`.
+    fn file_finished(&mut self, input: &Path, output: &Path, outcome: WriteOutcome) {
+        let _ = (input, output, outcome);
+    }
+
+    /// Loading, an operation or saving failed; the batch continues with the next file.
+    fn file_failed(&mut self, input: &Path, error: &AnimError) {
+        let _ = (input, error);
+    }
+}
+
+/// Observer that ignores every event.
+impl BatchObserver for () {}
+
+/// Handed to each operation so it can report warnings.
+pub struct OpContext<'a> {
+    observer: &'a mut dyn BatchObserver,
+    input: &'a Path,
+    op: &'a str,
+}
+
+impl OpContext<'_> {
+    /// File being processed.
+    pub fn input(&self) -> &Path {
+        self.input
+    }
+
+    /// Report a non-fatal problem to the observer.
+    pub fn warn(&mut self, message: impl AsRef<str>) {
+        self.observer.warning(self.input, self.op, message.as_ref());
+    }
+}
+
+type Op = Box<dyn Fn(&mut Animation, &mut OpContext<'_>) -> Result<()>>;
+
+/// Counts returned by [`Batch::run`].
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct BatchReport {
+    pub written: usize,
+    pub unchanged: usize,
+    pub failed: usize,
+}
+
+/// An ordered list of named operations applied to every input file.
+#[derive(Default)]
+pub struct Batch {
+    ops: Vec<(String, Op)>,
+    if_changed: bool,
+}
+
+impl Batch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append an operation; `name` identifies it in observer events.
+    pub fn op<F>(mut self, name: impl Into<String>, op: F) -> Self
+    where
+        F: Fn(&mut Animation, &mut OpContext<'_>) -> Result<()> + 'static,
+    {
+        self.ops.push((name.into(), Box::new(op)));
+        self
+    }
+
+    /// Leave outputs that already hold identical bytes untouched
+    /// (see [`Animation::to_file_if_changed`]).
+    pub fn if_changed(mut self, enabled: bool) -> Self {
+        self.if_changed = enabled;
+        self
+    }
+
+    /// Load each `(input, output)` pair with [`crate::open_any`], apply the operations in order
+    /// and save the result as `.anim`. A failing file is reported and skipped.
+    pub fn run<I>(&self, jobs: I, observer: &mut dyn BatchObserver) -> BatchReport
+    where
+        I: IntoIterator<Item = (PathBuf, PathBuf)>,
+    {
+        let mut report = BatchReport::default();
+        for (input, output) in jobs {
+            observer.file_started(&input);
+            match self.process(&input, &output, observer) {
+                Ok(outcome) => {
+                    match outcome {
+                        WriteOutcome::Written => report.written += 1,
+                        WriteOutcome::Unchanged => report.unchanged += 1,
+                    }
+                    observer.file_finished(&input, &output, outcome);
+                }
+                Err(error) => {
+                    report.failed += 1;
+                    observer.file_failed(&input, &error);
+                }
+            }
+        }
+        report
+    }
+
+    fn process(
+        &self,
+        input: &Path,
+        output: &Path,
+        observer: &mut dyn BatchObserver,
+    ) -> Result<WriteOutcome> {
+        let (mut anim, _) = crate::open_any(input)?;
+        for (name, op) in &self.ops {
+            let mut ctx = OpContext {
+                observer: &mut *observer,
+                input,
+                op: name,
+            };
+            op(&mut anim, &mut ctx)?;
+            observer.op_finished(input, name);
+        }
+        if self.if_changed {
+            anim.to_file_if_changed(output)
+        } else {
+            anim.to_file(output)?;
+            Ok(WriteOutcome::Written)
+        }
+    }
+}
@@ -0,0 +1,6 @@
+use avatar_anim::cli::{self, Cli};
+use clap::Parser;
+
+fn main() -> avatar_anim::Result<()> {
+    cli::run(Cli::parse())
+}
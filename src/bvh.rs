@@ -0,0 +1,424 @@
+//! Biovision BVH motion capture import and export.
+//!
+//! Joint names from QAvimator, Poser and the common Mixamo/CMU rigs are mapped to the SL
+//! skeleton (see [`joints::resolve_alias`], extended by [`BvhOptions::aliases`]); joints that don't map are skipped. Rotations are
+//! converted from BVH's Y-up frame ([`Frame::MayaYUp`]) and the root's translation becomes
+//! `mPelvis` position keys. [`Animation::to_bvh_writer`] goes the other way, writing SL
+//! joint names so the file imports back unchanged.
+
+use crate::bake::{self, Rig};
+use crate::frame::{Frame, RotationOrder};
+use crate::joints::{self, JointAliases};
+use crate::skeleton::Skeleton;
+use crate::{AnimError, Animation, AnimationHeader, JointData, PositionKey, Result, RotationKey};
+use glam::Vec3;
+use std::fmt::Write as _;
+use std::io::{Read, Write};
+
+/// Options for [`Animation::from_bvh_reader_with`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct BvhOptions {
+    /// Treat the first frame as a reference pose (usually a T-pose) and drop it, as the
+    /// viewer's uploader does. Root positions are always taken relative to the first frame.
+    pub skip_reference_frame: bool,
+    /// Factor from BVH position units to meters; BVH exporters for SL write inches.
+    pub position_scale: f32,
+    /// Joint names beyond the built-in ones; see [`joints::resolve_alias`].
+    pub aliases: JointAliases,
+}
+
+impl Default for BvhOptions {
+    fn default() -> Self {
+        Self {
+            skip_reference_frame: true,
+            position_scale: 0.0254,
+            aliases: JointAliases::default(),
+        }
+    }
+}
+
+/// Options for [`Animation::to_bvh_writer`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct BvhExportOptions {
+    /// Frames per second of the sampled motion.
+    pub frame_rate: f32,
+    /// Write only the joints the animation has (plus their ancestors, to keep the hierarchy
+    /// connected) instead of the whole skeleton.
+    pub only_animated_joints: bool,
+    /// Start with a rest-pose frame, as the viewer's uploader and [`BvhOptions`] expect.
+    pub reference_frame: bool,
+    /// Meters per BVH position unit; the default writes inches.
+    pub position_scale: f32,
+}
+
+impl Default for BvhExportOptions {
+    fn default() -> Self {
+        Self {
+            frame_rate: 30.0,
+            only_animated_joints: false,
+            reference_frame: true,
+            position_scale: 0.0254,
+        }
+    }
+}
+
+/// SL joint for a BVH joint name, or `None` if it has no counterpart.
+///
+/// The same as [`joints::resolve_alias`]; see [`BvhOptions::aliases`] for adding names.
+pub fn sl_joint_name(bvh_name: &str) -> Option<&str> {
+    joints::resolve_alias(bvh_name)
+}
+
+struct Tokens<'a> {
+    lines: std::str::Lines<'a>,
+    current: std::vec::IntoIter<&'a str>,
+    line: usize,
+}
+
+impl<'a> Tokens<'a> {
+    fn new(text: &'a str) -> Self {
+        Self {
+            lines: text.lines(),
+            current: Vec::new().into_iter(),
+            line: 0,
+        }
+    }
+
+    fn error(&self, message: impl std::fmt::Display) -> AnimError {
+        AnimError::InvalidStructure(format!("BVH line {}: {message}", self.line))
+    }
+
+    fn next(&mut self) -> Result<&'a str> {
+        loop {
+            if let Some(token) = self.current.next() {
+                return Ok(token);
+            }
+            let line = self
+                .lines
+                .next()
+                .ok_or_else(|| self.error("unexpected end of file"))?;
+            self.line += 1;
+            self.current = line.split_whitespace().collect::<Vec<_>>().into_iter();
+        }
+    }
+
+    fn expect(&mut self, word: &str) -> Result<()> {
+        let token = self.next()?;
+        if token.eq_ignore_ascii_case(word) {
+            Ok(())
+        } else {
+            Err(self.error(format!("expected '{word}', found '{token}'")))
+        }
+    }
+
+    fn number<T: std::str::FromStr>(&mut self) -> Result<T> {
+        let token = self.next()?;
+        token
+            .parse()
+            .map_err(|_| self.error(format!("expected a number, found '{token}'")))
+    }
+}
+
+struct BvhJoint {
+    name: String,
+    channels: Vec<String>,
+}
+
+/// Parse one `ROOT`/`JOINT` block body (after its name), appending joints in file order.
+fn parse_joint(tokens: &mut Tokens, name: String, joints: &mut Vec<BvhJoint>) -> Result<()> {
+    tokens.expect("{")?;
+    let index = joints.len();
+    joints.push(BvhJoint {
+        name,
+        channels: Vec::new(),
+    });
+    loop {
+        match tokens.next()? {
+            "}" => return Ok(()),
+            t if t.eq_ignore_ascii_case("OFFSET") => {
+                for _ in 0..3 {
+                    tokens.number::<f32>()?;
+                }
+            }
+            t if t.eq_ignore_ascii_case("CHANNELS") => {
+                let count: usize = tokens.number()?;
+                if count > 6 {
+                    return Err(tokens.error(format!("{count} channels on one joint")));
+                }
+                for _ in 0..count {
+                    let channel = tokens.next()?.to_string();
+                    joints[index].channels.push(channel);
+                }
+            }
+            t if t.eq_ignore_ascii_case("JOINT") => {
+                let child = tokens.next()?.to_string();
+                parse_joint(tokens, child, joints)?;
+            }
+            t if t.eq_ignore_ascii_case("End") => {
+                tokens.expect("Site")?;
+                tokens.expect("{")?;
+                tokens.expect("OFFSET")?;
+                for _ in 0..3 {
+                    tokens.number::<f32>()?;
+                }
+                tokens.expect("}")?;
+            }
+            other => return Err(tokens.error(format!("unexpected '{other}'"))),
+        }
+    }
+}
+
+fn tick(frame: usize, frames: usize) -> u16 {
+    if frames <= 1 {
+        0
+    } else {
+        ((frame as f64 / (frames - 1) as f64) * u16::MAX as f64).round() as u16
+    }
+}
+
+impl Animation {
+    /// Parse a BVH file with [`BvhOptions::default`].
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use avatar_anim::Animation;
+    ///
+    /// # fn main() -> avatar_anim::Result<()> {
+    /// let file = std::fs::File::open("dance.bvh").map_err(avatar_anim::AnimError::Io)?;
+    /// let mut anim = Animation::from_bvh_reader(file)?;
+    /// anim.reduce_keys(0.005).to_file("dance.anim")?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn from_bvh_reader<R: Read>(reader: R) -> Result<Self> {
+        Self::from_bvh_reader_with(reader, &BvhOptions::default())
+    }
+
+    /// Parse a BVH file.
+    ///
+    /// Every frame becomes a key on every mapped joint, spread evenly over the `u16` timeline;
+    /// use [`Animation::reduce_keys`] to thin them out. The header gets the BVH duration,
+    /// priority 3 and no looping.
+    pub fn from_bvh_reader_with<R: Read>(mut reader: R, options: &BvhOptions) -> Result<Self> {
+        let mut text = String::new();
+        reader.read_to_string(&mut text).map_err(AnimError::Io)?;
+        let mut tokens = Tokens::new(&text);
+
+        tokens.expect("HIERARCHY")?;
+        tokens.expect("ROOT")?;
+        let mut joints = Vec::new();
+        let root = tokens.next()?.to_string();
+        parse_joint(&mut tokens, root, &mut joints)?;
+
+        tokens.expect("MOTION")?;
+        tokens.expect("Frames:")?;
+        let frames: usize = tokens.number()?;
+        tokens.expect("Frame")?;
+        tokens.expect("Time:")?;
+        let frame_time: f32 = tokens.number()?;
+        if !(frame_time.is_finite() && frame_time > 0.0) {
+            return Err(tokens.error(format!("invalid frame time {frame_time}")));
+        }
+
+        let columns: usize = joints.iter().map(|j| j.channels.len()).sum();
+        let mut values = Vec::with_capacity(frames.saturating_mul(columns).min(1 << 24));
+        for _ in 0..frames.saturating_mul(columns) {
+            values.push(tokens.number::<f32>()?);
+        }
+        let skip = usize::from(options.skip_reference_frame && frames > 1);
+        let used = frames - skip;
+
+        let mut anim = Animation {
+            header: AnimationHeader {
+                base_priority: 3,
+                duration: frame_time * used.saturating_sub(1) as f32,
+                looped: 0,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        anim.header.loop_out_point = anim.header.duration;
+
+        let mut column = 0;
+        for (index, joint) in joints.iter().enumerate() {
+            let first_column = column;
+            column += joint.channels.len();
+            let Some(name) = options.aliases.resolve(&joint.name) else {
+                continue;
+            };
+            if anim.joint(name).is_some() {
+                continue;
+            }
+            let order = RotationOrder::from_bvh_channels(&joint.channels);
+            let rotation_columns: Vec<usize> = (0..joint.channels.len())
+                .filter(|&c| joint.channels[c].to_ascii_lowercase().ends_with("rotation"))
+                .map(|c| first_column + c)
+                .collect();
+            let position_column = |axis: &str| {
+                joint
+                    .channels
+                    .iter()
+                    .position(|c| c.eq_ignore_ascii_case(axis))
+                    .map(|c| first_column + c)
+            };
+            let position_columns = [
+                position_column("Xposition"),
+                position_column("Yposition"),
+                position_column("Zposition"),
+            ];
+            let row = |frame: usize| &values[frame * columns..(frame + 1) * columns];
+
+            let mut data = JointData {
+                name: name.to_string(),
+                priority: anim.header.base_priority,
+                ..Default::default()
+            };
+            if let Some(order) = order {
+                for frame in skip..frames {
+                    let row = row(frame);
+                    let degrees = [0, 1, 2].map(|i| row[rotation_columns[i]]);
+                    data.rotation_keys.push(RotationKey {
+                        time: tick(frame - skip, used),
+                        rot: Frame::MayaYUp.rot_to_sl(order.compose(degrees)),
+                    });
+                }
+            }
+            if index == 0 && position_columns.iter().any(Option::is_some) && frames > 0 {
+                let at = |frame: usize| {
+                    let row = row(frame);
+                    Vec3::from_array(position_columns.map(|c| c.map_or(0.0, |c| row[c])))
+                };
+                let reference = at(0);
+                for frame in skip..frames {
+                    let offset = (at(frame) - reference) * options.position_scale;
+                    data.position_keys.push(PositionKey {
+                        time: tick(frame - skip, used),
+                        pos: Frame::MayaYUp.vec_to_sl(offset),
+                    });
+                }
+            }
+            anim.joints.push(data);
+        }
+        Ok(anim)
+    }
+
+    /// Load a BVH file with [`BvhOptions::default`].
+    pub fn from_bvh_file<P: AsRef<std::path::Path>>(path: P) -> Result<Self> {
+        let file = std::fs::File::open(path).map_err(AnimError::Io)?;
+        Self::from_bvh_reader(std::io::BufReader::new(file))
+    }
+
+    /// Write the animation as BVH, with the hierarchy and bind offsets taken from `skeleton`.
+    ///
+    /// Every joint gets `Zrotation Xrotation Yrotation` channels and the root also gets
+    /// position channels carrying its bind translation plus the animation's position keys.
+    /// Frames are sampled every `1 / frame_rate` seconds up to the duration; a duration that
+    /// isn't a whole number of frames is rounded up and the last frame holds the final pose.
+    ///
+    /// Joints the skeleton doesn't know and position keys on joints other than the root are
+    /// not written, since BVH offsets are fixed per joint.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use avatar_anim::bvh::BvhExportOptions;
+    /// use avatar_anim::{Animation, Skeleton};
+    ///
+    /// # fn main() -> avatar_anim::Result<()> {
+    /// let anim = Animation::from_file("wave.anim")?;
+    /// let file = std::fs::File::create("wave.bvh").map_err(avatar_anim::AnimError::Io)?;
+    /// let options = BvhExportOptions {
+    ///     only_animated_joints: true,
+    ///     ..Default::default()
+    /// };
+    /// anim.to_bvh_writer(file, &Skeleton::second_life(), &options)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Fails on a non-positive frame rate or position scale, an empty skeleton, or a write error.
+    pub fn to_bvh_writer<W: Write>(
+        &self,
+        mut writer: W,
+        skeleton: &Skeleton,
+        options: &BvhExportOptions,
+    ) -> Result<()> {
+        let scale = options.position_scale;
+        if !(scale.is_finite() && scale > 0.0) {
+            return Err(AnimError::InvalidStructure(format!(
+                "BVH export needs a positive position scale, got {scale}"
+            )));
+        }
+        let frames = bake::frames(self.header.duration, options.frame_rate)?;
+        let rig = Rig::new(self, skeleton, options.only_animated_joints)?;
+        let joints = skeleton.joints();
+
+        let order = RotationOrder::Zxy;
+        let inches = |v: Vec3| Frame::MayaYUp.vec_from_sl(v) / scale;
+        let mut text = String::from("HIERARCHY\n");
+        let mut stack = vec![(rig.root, 0usize, false)];
+        while let Some((index, depth, close)) = stack.pop() {
+            let pad = "  ".repeat(depth);
+            if close {
+                let _ = writeln!(text, "{pad}}}");
+                continue;
+            }
+            let joint = &joints[index];
+            let offset = inches(joint.position);
+            let kind = if index == rig.root { "ROOT" } else { "JOINT" };
+            let _ = writeln!(text, "{pad}{kind} {}\n{pad}{{", joint.name);
+            let _ = writeln!(text, "{pad}  OFFSET {} {} {}", offset.x, offset.y, offset.z);
+            let [a, b, c] = order.bvh_channels();
+            if index == rig.root {
+                let _ = writeln!(
+                    text,
+                    "{pad}  CHANNELS 6 Xposition Yposition Zposition {a} {b} {c}"
+                );
+            } else {
+                let _ = writeln!(text, "{pad}  CHANNELS 3 {a} {b} {c}");
+            }
+            stack.push((index, depth, true));
+            if rig.children[index].is_empty() {
+                let _ = writeln!(
+                    text,
+                    "{pad}  End Site\n{pad}  {{\n{pad}    OFFSET {} {} {}\n{pad}  }}",
+                    offset.x, offset.y, offset.z
+                );
+            }
+            for &child in rig.children[index].iter().rev() {
+                stack.push((child, depth + 1, false));
+            }
+        }
+
+        let reference = usize::from(options.reference_frame);
+        let _ = writeln!(
+            text,
+            "MOTION\nFrames: {}\nFrame Time: {}",
+            frames.len() + reference,
+            1.0 / options.frame_rate
+        );
+        let written = rig.order();
+        let mut write_frame = |tick: Option<f32>| {
+            let mut row = Vec::new();
+            for &index in &written {
+                let (rot, pos) = rig.local(index, tick);
+                if index == rig.root {
+                    row.extend(inches(pos).to_array());
+                }
+                row.extend(order.decompose(Frame::MayaYUp.rot_from_sl(rot)));
+            }
+            let line: Vec<String> = row.iter().map(f32::to_string).collect();
+            let _ = writeln!(text, "{}", line.join(" "));
+        };
+        if options.reference_frame {
+            write_frame(None);
+        }
+        for &(_, tick) in &frames {
+            write_frame(Some(tick));
+        }
+        writer.write_all(text.as_bytes()).map_err(AnimError::Io)
+    }
+}
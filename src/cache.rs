@@ -0,0 +1,230 @@
+//! A shared, size-bounded cache of parsed animations for interactive tools.
+//!
+//! [`AnimCache::open`] parses a file the first time it is asked for and hands out the same
+//! [`Arc<Animation>`] afterwards, as long as the file's modification time and length are
+//! unchanged. The least recently used entries are evicted past the configured capacity. The
+//! cache locks internally, so one instance can be shared between threads, for example a
+//! library view, a preview and a mixer working on the same files.
+//!
+//! ```rust,no_run
+//! use avatar_anim::cache::AnimCache;
+//!
+//! # fn main() -> avatar_anim::Result<()> {
+//! let cache = AnimCache::new(256);
+//! let walk = cache.open("walk.anim")?;
+//! let again = cache.open("walk.anim")?; // not parsed again
+//! assert!(std::sync::Arc::ptr_eq(&walk, &again));
+//! assert_eq!(cache.stats().hits, 1);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{AnimError, Animation, Result};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::SystemTime;
+
+/// Counters of an [`AnimCache`] since it was created or last cleared.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct CacheStats {
+    /// Lookups answered from the cache.
+    pub hits: u64,
+    /// Lookups that parsed the file, including reloads of changed files.
+    pub misses: u64,
+    /// Entries dropped to stay within the capacity.
+    pub evictions: u64,
+    /// Entries currently held.
+    pub len: usize,
+}
+
+impl CacheStats {
+    /// Share of lookups answered from the cache, `0.0` before the first lookup.
+    pub fn hit_rate(&self) -> f32 {
+        let total = self.hits + self.misses;
+        if total == 0 {
+            0.0
+        } else {
+            self.hits as f32 / total as f32
+        }
+    }
+}
+
+struct Entry {
+    anim: Arc<Animation>,
+    fingerprint: u64,
+    modified: Option<SystemTime>,
+    len: u64,
+    last_used: u64,
+}
+
+#[derive(Default)]
+struct State {
+    entries: HashMap<PathBuf, Entry>,
+    capacity: usize,
+    clock: u64,
+    stats: CacheStats,
+}
+
+impl State {
+    fn evict(&mut self) {
+        while self.entries.len() > self.capacity {
+            let Some(oldest) = self
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(path, _)| path.clone())
+            else {
+                break;
+            };
+            self.entries.remove(&oldest);
+            self.stats.evictions += 1;
+        }
+    }
+}
+
+/// Least-recently-used cache of parsed animations, keyed by path and checked against the
+/// file's modification time and length.
+pub struct AnimCache {
+    state: Mutex<State>,
+}
+
+impl AnimCache {
+    /// A cache holding at most `capacity` animations; 0 disables caching.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            state: Mutex::new(State {
+                capacity,
+                ..Default::default()
+            }),
+        }
+    }
+
+    fn state(&self) -> MutexGuard<'_, State> {
+        // The state stays consistent even if a holder panicked.
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// The animation at `path` in any format [`crate::open_any`] reads, parsed only if it is
+    /// not cached or the file changed since.
+    ///
+    /// # Errors
+    ///
+    /// Fails like [`crate::open_any`], or with [`AnimError::Io`] if the file can't be
+    /// inspected. Failures are not cached.
+    pub fn open<P: AsRef<Path>>(&self, path: P) -> Result<Arc<Animation>> {
+        let path = path.as_ref();
+        let meta = std::fs::metadata(path).map_err(AnimError::Io)?;
+        let (modified, len) = (meta.modified().ok(), meta.len());
+        {
+            let mut state = self.state();
+            state.clock += 1;
+            let clock = state.clock;
+            if let Some(entry) = state.entries.get_mut(path)
+                && entry.modified.is_some()
+                && entry.modified == modified
+                && entry.len == len
+            {
+                entry.last_used = clock;
+                let anim = entry.anim.clone();
+                state.stats.hits += 1;
+                return Ok(anim);
+            }
+        }
+
+        // Parse without holding the lock so other lookups aren't blocked.
+        let anim = Arc::new(crate::open_any(path)?.0);
+        let mut state = self.state();
+        state.stats.misses += 1;
+        state.clock += 1;
+        let last_used = state.clock;
+        state.entries.insert(
+            path.to_path_buf(),
+            Entry {
+                fingerprint: anim.fingerprint(),
+                anim: anim.clone(),
+                modified,
+                len,
+                last_used,
+            },
+        );
+        state.evict();
+        Ok(anim)
+    }
+
+    /// A cached animation with this [`Animation::fingerprint`], whichever path it came from.
+    /// Does not count as a hit or a miss.
+    pub fn get_by_fingerprint(&self, fingerprint: u64) -> Option<Arc<Animation>> {
+        self.state()
+            .entries
+            .values()
+            .find(|entry| entry.fingerprint == fingerprint)
+            .map(|entry| entry.anim.clone())
+    }
+
+    /// Forget `path`, so the next [`AnimCache::open`] parses it again.
+    pub fn invalidate<P: AsRef<Path>>(&self, path: P) {
+        self.state().entries.remove(path.as_ref());
+    }
+
+    /// Drop every entry and reset the statistics.
+    pub fn clear(&self) {
+        let mut state = self.state();
+        state.entries.clear();
+        state.stats = CacheStats::default();
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.state().capacity
+    }
+
+    /// Change the capacity, evicting the least recently used entries beyond it.
+    pub fn set_capacity(&self, capacity: usize) {
+        let mut state = self.state();
+        state.capacity = capacity;
+        state.evict();
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        let state = self.state();
+        CacheStats {
+            len: state.entries.len(),
+            ..state.stats
+        }
+    }
+}
+
+impl std::fmt::Debug for AnimCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AnimCache")
+            .field("capacity", &self.capacity())
+            .field("stats", &self.stats())
+            .finish()
+    }
+}
+
+/// The files below `dir` that pass `keep`, sorted, descending into subdirectories. Symlinked
+/// directories are followed, but each directory only once, so a link back up the tree can't
+/// loop forever. Shared by the tools that scan a library.
+pub(crate) fn files_below(dir: &Path, keep: impl Fn(&Path) -> bool) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    let mut visited = HashSet::new();
+    while let Some(dir) = pending.pop() {
+        if !visited.insert(std::fs::canonicalize(&dir).map_err(AnimError::Io)?) {
+            continue;
+        }
+        for entry in std::fs::read_dir(&dir).map_err(AnimError::Io)? {
+            let path = entry.map_err(AnimError::Io)?.path();
+            if path.is_dir() {
+                pending.push(path);
+            } else if keep(&path) {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
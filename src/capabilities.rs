@@ -0,0 +1,78 @@
+//! What this build of the crate can do, for frontends that bundle different builds.
+
+use crate::{ReadOptions, SourceFormat};
+
+/// Every Cargo feature, with whether it is compiled in.
+const FEATURES: [(&str, bool); 9] = [
+    ("bvh", cfg!(feature = "bvh")),
+    ("collada", cfg!(feature = "collada")),
+    ("llsd", cfg!(feature = "llsd")),
+    ("serde", cfg!(feature = "serde")),
+    ("toml", cfg!(feature = "toml")),
+    ("gltf", cfg!(feature = "gltf")),
+    ("gzip", cfg!(feature = "gzip")),
+    ("tokio", cfg!(feature = "tokio")),
+    ("cli", cfg!(feature = "cli")),
+];
+
+/// Description of the compiled-in features, formats and limits, from [`capabilities`].
+#[non_exhaustive]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Capabilities {
+    /// Crate version, e.g. `"0.3.0"`.
+    pub version: &'static str,
+    /// Enabled Cargo features, in `Cargo.toml` order.
+    pub features: Vec<&'static str>,
+    /// Formats [`crate::open_as`] and [`crate::Animation::to_format`] both handle in this
+    /// build. Export-only COLLADA is reported by `collada_export` instead.
+    pub formats: Vec<SourceFormat>,
+    /// Whether [`crate::open_any`] undoes gzip compression.
+    pub gzip_input: bool,
+    /// Whether COLLADA export is available.
+    pub collada_export: bool,
+    /// Limits [`crate::Animation::from_bytes`] and [`crate::Animation::from_file`] enforce.
+    pub read_limits: ReadOptions,
+}
+
+impl Capabilities {
+    /// Whether the Cargo feature `name` is compiled in.
+    pub fn has_feature(&self, name: &str) -> bool {
+        self.features.contains(&name)
+    }
+
+    /// Whether `format` can be read and written.
+    pub fn supports(&self, format: SourceFormat) -> bool {
+        self.formats.contains(&format)
+    }
+}
+
+/// Describe this build, so a frontend can adapt its menus at runtime.
+///
+/// # Example
+///
+/// ```rust
+/// use avatar_anim::SourceFormat;
+///
+/// let caps = avatar_anim::capabilities();
+/// assert!(caps.supports(SourceFormat::Anim));
+/// for format in &caps.formats {
+///     println!("Import/export {}", format.name());
+/// }
+/// ```
+pub fn capabilities() -> Capabilities {
+    Capabilities {
+        version: env!("CARGO_PKG_VERSION"),
+        features: FEATURES
+            .iter()
+            .filter(|(_, enabled)| *enabled)
+            .map(|(name, _)| *name)
+            .collect(),
+        formats: SourceFormat::ALL
+            .into_iter()
+            .filter(|format| format.is_supported() && !format.is_export_only())
+            .collect(),
+        gzip_input: cfg!(feature = "gzip"),
+        collada_export: cfg!(feature = "collada"),
+        read_limits: ReadOptions::default(),
+    }
+}
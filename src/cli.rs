@@ -0,0 +1,1301 @@
+//! The `animctl` command line, built with the `cli` feature.
+//!
+//! [`run`] executes a parsed [`Cli`]; each command is also available as a `run_*` function
+//! for tools that want the command's behavior without going through argument parsing.
+//!
+//! ```rust,no_run
+//! use avatar_anim::cli::{ConvertOptions, run_convert};
+//!
+//! # fn main() -> avatar_anim::Result<()> {
+//! run_convert(ConvertOptions {
+//!     input: "pose.xml".into(),
+//!     output: Some("pose.anim".into()),
+//!     priority: Some(4),
+//!     ..Default::default()
+//! })?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::EditOperation;
+use crate::batch::{Batch, BatchObserver};
+use crate::dashboard::LibraryStats;
+use crate::joints::{Locale, display_name};
+use crate::manifest::{self, LslTemplate, ManifestEntry};
+use crate::prelude::*;
+use crate::skeleton::BodyPreset;
+use clap::{Args, Parser, Subcommand, ValueEnum, ValueHint};
+use clap_complete::{
+    generate,
+    shells::{Bash, Elvish, Fish, PowerShell, Zsh},
+};
+use std::fs;
+use std::io::{self, Write as _};
+use std::path::PathBuf;
+
+/// Inspect and manipulate Second Life `.anim` and Firestorm poser LLSD XML files.
+///
+/// Common tasks:
+///   animctl info walk.anim
+///   animctl list-poses --full
+///   animctl clean last input.anim -o cleaned.anim
+///   animctl strip rotation input.anim stripped.anim
+///   animctl convert -i pose.xml -o pose.anim -p 6 --drop Pelvis,Head
+///   animctl convert -i pose.xml --insert Spine:rot<0.1,0.2,0.0>@120 --insert Pelvis:pos<0,0,0.05>
+///   animctl convert -i base.anim --drop-rotations --insert Head:rot@42 -o - > head_only.anim
+///   animctl convert -i walk.bvh -o walk.json
+///   animctl convert -i gesture.anim --fix-ease -o gesture.anim
+///   animctl convert -i pose.anim --to llsd-binary -o - > pose.llsd
+///   animctl sync dance.anim --bpm 128 -o dance_128.anim
+///   animctl suggest-loop idle.anim --apply
+///   animctl key mirror pose.anim --joint mShoulderLeft --to mShoulderRight
+///   animctl extract-poses dance.anim --times 0.5,1.2,2.0 --out-dir poses/ --format llsd
+///   animctl manifest anims/ -o set.json
+///   animctl lsl-gen anims/ --template dance-hud
+///   animctl dashboard anims/ --format html -o dashboard.html
+///   animctl retarget-batch walk.anim --bodies legacy,athletic,petite --out-dir build/ --if-changed
+///
+/// Use --verbose on convert for detailed stats and full structure dump to stderr.
+#[derive(Parser, Debug)]
+#[command(name = "animctl", version, about = "Second Life animation utility", long_about = None)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Commands,
+    /// Print the edits made to each written animation to stderr
+    #[arg(long, global = true, value_enum)]
+    pub edit_log: Option<EditLogFormat>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Commands {
+    /// Show a summary of an animation file
+    Info {
+        #[arg(value_hint=ValueHint::FilePath)]
+        file: PathBuf,
+        /// Explain what the header values mean instead of only listing them
+        #[arg(long)]
+        deep: bool,
+    },
+    /// Clean duplicate keyframes with strategy
+    Clean {
+        #[arg(value_enum)]
+        strategy: Strategy,
+        #[arg(value_hint=ValueHint::FilePath)]
+        input: PathBuf,
+        #[arg(value_hint=ValueHint::FilePath)]
+        output: Option<PathBuf>,
+    },
+    /// Strip position or rotation keys
+    Strip {
+        #[arg(value_enum)]
+        kind: StripKind,
+        #[arg(value_hint=ValueHint::FilePath)]
+        input: PathBuf,
+        #[arg(value_hint=ValueHint::FilePath)]
+        output: PathBuf,
+    },
+    /// List available Firestorm poser files (LLSD) in default or specified directory
+    #[command(alias = "ls")]
+    ListPoses {
+        /// Override directory (defaults to detected Firestorm poses dir)
+        #[arg(short, long)]
+        dir: Option<PathBuf>,
+        /// Show full paths
+        #[arg(long)]
+        full: bool,
+    },
+    /// Convert between any two supported formats, applying filters & edits
+    Convert(ConvertOptions),
+    /// List joints or inspect keys of a specific joint
+    Joints {
+        /// Animation file (.anim)
+        #[arg(value_hint=ValueHint::FilePath)]
+        file: PathBuf,
+        /// Show compact key list for this joint name instead of all joint names; rotations are
+        /// the roll, pitch and yaw degrees the poser panel shows
+        #[arg(short = 'j', long = "joint", value_name = "NAME")]
+        joint: Option<String>,
+        /// Also include a summary count line for each joint when listing all
+        #[arg(long = "summary")]
+        summary: bool,
+        /// Show friendly labels (e.g. "Left Shoulder") next to joint names
+        #[arg(long = "friendly-names")]
+        friendly_names: bool,
+        /// Language for friendly labels (en, de, fr, es)
+        #[arg(long = "locale", default_value = "en", requires = "friendly_names")]
+        locale: String,
+    },
+    /// Write one retargeted copy of an animation per body preset
+    RetargetBatch {
+        /// Animation file (.anim)
+        #[arg(value_hint=ValueHint::FilePath)]
+        input: PathBuf,
+        /// Target body presets (comma separated: legacy,athletic,petite,tall)
+        #[arg(long = "bodies", value_name = "LIST")]
+        bodies: String,
+        /// Body preset the input was authored for
+        #[arg(long = "from", default_value = "legacy")]
+        from: String,
+        /// Output directory; files are named <stem>_<preset>.anim
+        #[arg(long = "out-dir", value_hint=ValueHint::DirPath)]
+        out_dir: PathBuf,
+        /// Skip outputs whose bytes would not change
+        #[arg(long = "if-changed")]
+        if_changed: bool,
+    },
+    /// Retime an animation so its extreme poses land on a beat grid
+    Sync {
+        /// Animation file
+        #[arg(value_hint=ValueHint::FilePath)]
+        input: PathBuf,
+        /// Tempo of the track in beats per minute
+        #[arg(long = "bpm")]
+        bpm: f32,
+        /// Time of the first beat in seconds
+        #[arg(long = "offset", default_value_t = 0.0)]
+        offset: f32,
+        /// Output file (overwrites input if omitted)
+        #[arg(short = 'o', long = "output", value_hint=ValueHint::FilePath)]
+        output: Option<PathBuf>,
+    },
+    /// Suggest loop points where the motion repeats most seamlessly
+    SuggestLoop {
+        /// Animation file
+        #[arg(value_hint=ValueHint::FilePath)]
+        file: PathBuf,
+        /// Write the suggested loop points into the file
+        #[arg(long)]
+        apply: bool,
+        /// Output file for --apply (overwrites input if omitted)
+        #[arg(short = 'o', long = "output", value_hint=ValueHint::FilePath)]
+        output: Option<PathBuf>,
+    },
+    /// Freeze frames of an animation and save each as a pose file
+    ExtractPoses {
+        /// Animation file
+        #[arg(value_hint=ValueHint::FilePath)]
+        file: PathBuf,
+        /// Comma separated times in seconds
+        #[arg(long, value_delimiter = ',', required = true)]
+        times: Vec<f32>,
+        /// Directory for the pose files (created if missing)
+        #[arg(long = "out-dir", value_hint=ValueHint::DirPath)]
+        out_dir: PathBuf,
+        #[arg(long, value_enum, default_value = "llsd")]
+        format: PoseFormat,
+    },
+    /// Write a manifest describing every .anim file in a directory
+    Manifest {
+        /// Directory containing .anim files
+        #[arg(value_hint=ValueHint::DirPath)]
+        dir: PathBuf,
+        /// Output file (stdout if omitted)
+        #[arg(short = 'o', long = "output", value_hint=ValueHint::FilePath)]
+        output: Option<PathBuf>,
+        /// Output format
+        #[arg(value_enum, short = 'f', long = "format", default_value = "json")]
+        format: ManifestFormat,
+    },
+    /// Generate LSL list initializers for the .anim files in a directory
+    LslGen {
+        /// Directory containing .anim files
+        #[arg(value_hint=ValueHint::DirPath)]
+        dir: PathBuf,
+        /// Script template to generate for
+        #[arg(value_enum, short = 't', long = "template", default_value = "ao")]
+        template: LslTemplateKind,
+        /// Output file (stdout if omitted)
+        #[arg(short = 'o', long = "output", value_hint=ValueHint::FilePath)]
+        output: Option<PathBuf>,
+    },
+    /// Summarize a whole library: priorities, durations, most keyed joints and duplicates
+    Dashboard {
+        /// Directory searched, with its subdirectories, for .anim files
+        #[arg(value_hint=ValueHint::DirPath)]
+        dir: PathBuf,
+        /// Output file (stdout if omitted)
+        #[arg(short = 'o', long = "output", value_hint=ValueHint::FilePath)]
+        output: Option<PathBuf>,
+        /// Output format
+        #[arg(value_enum, short = 'f', long = "format", default_value = "text")]
+        format: DashboardFormat,
+        /// Number of most keyed joints to list
+        #[arg(long = "top", default_value_t = 10)]
+        top: usize,
+    },
+    /// Read an animation from a pre-1.0 viewer export and report its legacy quirks
+    InspectLegacy {
+        /// Animation file (.anim)
+        #[arg(value_hint=ValueHint::FilePath)]
+        file: PathBuf,
+        /// Write the animation in the modern layout to this file
+        #[arg(long = "rewrite", value_name = "OUT", value_hint=ValueHint::FilePath)]
+        rewrite: Option<PathBuf>,
+    },
+    /// Edit the keys of single joints
+    Key {
+        #[command(subcommand)]
+        command: KeyCommands,
+    },
+    /// Generate shell completion script to stdout
+    Complete {
+        /// Target shell (bash|zsh|fish|powershell|elvish)
+        #[arg(value_enum, short = 's', long = "shell")]
+        shell: ShellKind,
+    },
+}
+
+/// Subcommands of `key`.
+#[derive(Subcommand, Debug)]
+pub enum KeyCommands {
+    /// Copy a joint's keys, mirrored left/right, onto its opposite-side joint
+    Mirror {
+        /// Animation file
+        #[arg(value_hint=ValueHint::FilePath)]
+        file: PathBuf,
+        /// Joint whose keys are copied
+        #[arg(short = 'j', long = "joint", value_name = "NAME")]
+        joint: String,
+        /// Joint receiving the mirrored keys (defaults to the opposite-side joint)
+        #[arg(long = "to", value_name = "NAME")]
+        to: Option<String>,
+        /// Output file (overwrites input if omitted)
+        #[arg(short = 'o', long = "output", value_hint=ValueHint::FilePath)]
+        output: Option<PathBuf>,
+    },
+}
+
+/// Options of the `convert` command, see [`run_convert`].
+#[derive(Args, Debug, Default)]
+pub struct ConvertOptions {
+    /// Input file (.anim, .bvh, .glb, LLSD pose: XML, notation or binary; gzip accepted)
+    #[arg(short = 'i', long = "input", value_hint=ValueHint::FilePath)]
+    pub input: PathBuf,
+    /// Optional output file. Use '-' to write to stdout.
+    /// If omitted (and not verbose) prints a simple parse success message to stderr.
+    #[arg(short = 'o', long = "output", value_hint=ValueHint::FilePath)]
+    pub output: Option<PathBuf>,
+    /// Input format (anim, llsd-xml, llsd-notation, llsd-binary, bvh, gltf, json);
+    /// detected from the content if omitted
+    #[arg(long, value_parser = parse_format)]
+    pub from: Option<SourceFormat>,
+    /// Output format (any input format, or the export-only collada); taken from the output
+    /// file extension if omitted, else anim
+    #[arg(long, value_parser = parse_format)]
+    pub to: Option<SourceFormat>,
+    /// Set priority (0..=7) across animation and joints.
+    #[arg(short = 'p', long = "priority")]
+    pub priority: Option<i32>,
+    /// Drop all position keys (after inserts)
+    #[arg(long = "drop-positions")]
+    pub drop_positions: bool,
+    /// Drop all rotation keys (after inserts)
+    #[arg(long = "drop-rotations")]
+    pub drop_rotations: bool,
+    /// Drop position keys for named joints (comma separated list)
+    #[arg(long = "drop-position")]
+    pub drop_position_named: Option<String>,
+    /// Drop rotation keys for named joints (comma separated list)
+    #[arg(long = "drop-rotation")]
+    pub drop_rotation_named: Option<String>,
+    /// Drop entire joints (comma separated list)
+    #[arg(long = "drop")]
+    pub drop_joints: Option<String>,
+    /// Verbose: detailed stats + full structure debug to stderr (stdout kept clean for binary output)
+    #[arg(short = 'v', long = "verbose")]
+    pub verbose: bool,
+    /// Leave the output file alone if it already holds identical bytes
+    #[arg(long = "if-changed")]
+    pub if_changed: bool,
+    /// Rebalance ease in/out windows that overlap, swallow the loop or fade out motion
+    #[arg(long = "fix-ease")]
+    pub fix_ease: bool,
+    /// Insert synthetic key(s) (repeatable)
+    #[arg(
+        long = "insert",
+        value_name = "SPEC",
+        long_help = "Insert synthetic key(s). Repeat --insert for multiple.
+Syntax:
+    joint:pos<x,y,z>[@time]
+    joint:rot<roll,pitch,yaw>[@time]
+
+Notes:
+    • Angles are roll(X), pitch(Y), yaw(Z) in radians, roll applied first like the poser's.
+    • <...> block optional; omitted => 0,0,0 (identity rotation / zero position).
+    • @time optional; omitted => 65535 (max time, end of animation).
+
+Examples:
+    --insert Head:rot<0.1,0.2,0.0>@120
+    --insert Pelvis:pos<0,0,0.05>
+    --insert Spine:rot@42    (identity rotation at 42)
+    --insert Pelvis:pos      (zero position at end)"
+    )]
+    pub insert: Vec<String>,
+}
+
+#[derive(Copy, Clone, Debug, ValueEnum)]
+pub enum ShellKind {
+    Bash,
+    Zsh,
+    Fish,
+    Powershell,
+    Elvish,
+}
+
+#[derive(Copy, Clone, Debug, ValueEnum)]
+pub enum Strategy {
+    First,
+    Last,
+    Average,
+}
+
+impl From<Strategy> for DuplicateKeyStrategy {
+    fn from(s: Strategy) -> Self {
+        match s {
+            Strategy::First => DuplicateKeyStrategy::KeepFirst,
+            Strategy::Last => DuplicateKeyStrategy::KeepLast,
+            Strategy::Average => DuplicateKeyStrategy::Average,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, ValueEnum)]
+pub enum EditLogFormat {
+    /// One line per edit
+    Text,
+    /// The JSON of `EditLog::to_json`
+    Json,
+}
+
+#[derive(Copy, Clone, Debug, ValueEnum)]
+pub enum ManifestFormat {
+    Json,
+    Notecard,
+}
+
+#[derive(Copy, Clone, Debug, ValueEnum)]
+pub enum DashboardFormat {
+    Text,
+    Json,
+    /// A standalone page with bar charts
+    Html,
+}
+
+#[derive(Copy, Clone, Debug, ValueEnum)]
+pub enum PoseFormat {
+    /// Firestorm poser LLSD-XML
+    Llsd,
+    /// Binary LLSD
+    LlsdBinary,
+    /// Single-frame .anim
+    Anim,
+}
+
+#[derive(Copy, Clone, Debug, ValueEnum)]
+pub enum LslTemplateKind {
+    Ao,
+    DanceHud,
+}
+
+impl From<LslTemplateKind> for LslTemplate {
+    fn from(t: LslTemplateKind) -> Self {
+        match t {
+            LslTemplateKind::Ao => LslTemplate::Ao,
+            LslTemplateKind::DanceHud => LslTemplate::DanceHud,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, ValueEnum)]
+pub enum StripKind {
+    Position,
+    Rotation,
+    Both,
+}
+
+thread_local! {
+    /// How [`run`] was asked to print edit logs, read by [`print_edit_log`].
+    static EDIT_LOG_FORMAT: std::cell::Cell<Option<EditLogFormat>> =
+        const { std::cell::Cell::new(None) };
+}
+
+/// Print the edit log of an animation about to be written, if `--edit-log` was given.
+fn print_edit_log(anim: &Animation) {
+    let Some(log) = &anim.edit_log else {
+        return;
+    };
+    match EDIT_LOG_FORMAT.get() {
+        Some(EditLogFormat::Text) => eprint!("{log}"),
+        Some(EditLogFormat::Json) => eprint!("{}", log.to_json()),
+        None => {}
+    }
+}
+
+/// Run a parsed command line.
+pub fn run(cli: Cli) -> Result<()> {
+    EDIT_LOG_FORMAT.set(cli.edit_log);
+    match cli.command {
+        Commands::Info { file, deep } => run_info(file, deep)?,
+        Commands::Clean {
+            strategy,
+            input,
+            output,
+        } => run_clean(strategy.into(), input, output)?,
+        Commands::Strip {
+            kind,
+            input,
+            output,
+        } => run_strip(kind, input, output)?,
+        Commands::ListPoses { dir, full } => run_list_poses(dir, full)?,
+        Commands::Convert(options) => run_convert(options)?,
+        Commands::Joints {
+            file,
+            joint,
+            summary,
+            friendly_names,
+            locale,
+        } => {
+            let locale = friendly_names
+                .then(|| {
+                    Locale::from_tag(&locale).ok_or_else(|| {
+                        AnimError::InvalidStructure(format!("Unsupported locale '{}'", locale))
+                    })
+                })
+                .transpose()?;
+            run_joints(file, joint, summary, locale)?
+        }
+        Commands::RetargetBatch {
+            input,
+            bodies,
+            from,
+            out_dir,
+            if_changed,
+        } => run_retarget_batch(input, bodies, from, out_dir, if_changed)?,
+        Commands::Sync {
+            input,
+            bpm,
+            offset,
+            output,
+        } => run_sync(input, bpm, offset, output)?,
+        Commands::SuggestLoop {
+            file,
+            apply,
+            output,
+        } => run_suggest_loop(file, apply, output)?,
+        Commands::ExtractPoses {
+            file,
+            times,
+            out_dir,
+            format,
+        } => run_extract_poses(file, times, out_dir, format)?,
+        Commands::Manifest {
+            dir,
+            output,
+            format,
+        } => run_manifest(dir, output, format)?,
+        Commands::LslGen {
+            dir,
+            template,
+            output,
+        } => run_lsl_gen(dir, template.into(), output)?,
+        Commands::Dashboard {
+            dir,
+            output,
+            format,
+            top,
+        } => run_dashboard(dir, output, format, top)?,
+        Commands::InspectLegacy { file, rewrite } => run_inspect_legacy(file, rewrite)?,
+        Commands::Key { command } => match command {
+            KeyCommands::Mirror {
+                file,
+                joint,
+                to,
+                output,
+            } => run_key_mirror(file, joint, to, output)?,
+        },
+        Commands::Complete { shell } => run_complete(shell)?,
+    }
+    Ok(())
+}
+
+fn firestorm_pose_dir() -> Option<PathBuf> {
+    #[cfg(target_os = "linux")]
+    {
+        let home = std::env::var_os("HOME")?;
+        let p = PathBuf::from(home).join(".firestorm_x64/user_settings/poses");
+        if p.is_dir() {
+            return Some(p);
+        }
+    }
+    #[cfg(target_os = "windows")]
+    {
+        if let Some(roaming) = std::env::var_os("APPDATA") {
+            let p = PathBuf::from(roaming).join("Firestorm_x64/user_settings/poses");
+            if p.is_dir() {
+                return Some(p);
+            }
+        }
+    }
+    #[cfg(target_os = "macos")]
+    {
+        let home = std::env::var_os("HOME")?;
+        let p = PathBuf::from(home)
+            .join("Library/Application Support/Firestorm_x64/user_settings/poses");
+        if p.is_dir() {
+            return Some(p);
+        }
+    }
+    None
+}
+
+pub fn run_info(path: PathBuf, deep: bool) -> Result<()> {
+    let (anim, format) = crate::open_any(&path)?;
+    println!("File: {}", path.display());
+    println!("Format: {}", format.name());
+    println!(
+        "Version: {}.{}",
+        anim.header.version, anim.header.sub_version
+    );
+    println!("Priority: {}", anim.header.base_priority);
+    println!("Duration: {:.3}s", anim.header.duration);
+    println!("Joints: {}", anim.joints.len());
+    let (rot_keys, pos_keys): (usize, usize) = anim.joints.iter().fold((0, 0), |acc, j| {
+        (acc.0 + j.rotation_keys.len(), acc.1 + j.position_keys.len())
+    });
+    println!("Rotation keys: {}  Position keys: {}", rot_keys, pos_keys);
+    for problem in anim.check_ease() {
+        println!("Ease warning: {problem}");
+    }
+    if deep {
+        print_header_explanation(&anim);
+    }
+    Ok(())
+}
+
+/// Read `path` with [`Animation::read_legacy`], list the quirks found and optionally save
+/// the modern layout to `rewrite`.
+pub fn run_inspect_legacy(path: PathBuf, rewrite: Option<PathBuf>) -> Result<()> {
+    let bytes = std::fs::read(&path).map_err(AnimError::Io)?;
+    let legacy = Animation::read_legacy(&bytes)?;
+    println!("File: {}", path.display());
+    println!("Stored version: {}.{}", legacy.version.0, legacy.version.1);
+    if legacy.is_legacy() {
+        println!("Legacy quirks:");
+        for quirk in &legacy.quirks {
+            println!("  - {quirk}");
+        }
+    } else {
+        println!("Legacy quirks: none, already in the modern layout");
+    }
+    let anim = &legacy.animation;
+    println!("Duration: {:.3}s", anim.header.duration);
+    println!(
+        "Joints: {}  Constraints: {}",
+        anim.joints.len(),
+        anim.constraints.len()
+    );
+    if let Some(out) = rewrite {
+        anim.to_file(&out)?;
+        println!("Rewrote as version 1.0: {}", out.display());
+    }
+    Ok(())
+}
+
+fn print_header_explanation(anim: &Animation) {
+    let h = &anim.header;
+    println!();
+    if (h.version, h.sub_version) != (1, 0) {
+        println!(
+            "Format: version {}.{} is not the 1.0 the viewer writes; other readers may refuse it.",
+            h.version, h.sub_version
+        );
+    }
+    println!(
+        "Priority: base {} (0 lowest, 6 highest for uploads); joints may override it.",
+        h.base_priority
+    );
+    let overrides: Vec<&JointData> = anim
+        .joints
+        .iter()
+        .filter(|j| j.priority != h.base_priority)
+        .collect();
+    if overrides.is_empty() {
+        println!("  Every joint uses the base priority.");
+    }
+    for joint in overrides {
+        let relation = if joint.priority > h.base_priority {
+            "wins over"
+        } else {
+            "yields to"
+        };
+        println!(
+            "  {} overrides it with {} ({} other animations at the base priority).",
+            joint.name, joint.priority, relation
+        );
+    }
+    match h.hand_pose_name() {
+        Some(name) => println!(
+            "Hand pose: {} = {}; the viewer holds it while playing.",
+            h.hand_pose, name
+        ),
+        None => println!(
+            "Hand pose: {} is not a pose the viewer knows; it falls back to Spread.",
+            h.hand_pose
+        ),
+    }
+    if h.emote_name.is_empty() {
+        println!("Emote: none; the face is left alone.");
+    } else {
+        println!(
+            "Emote: plays facial expression \"{}\" alongside.",
+            h.emote_name
+        );
+    }
+    let usable = h.duration.is_finite()
+        && h.duration >= 0.0
+        && h.loop_in_point.is_finite()
+        && h.loop_out_point.is_finite();
+    if h.looped == 0 {
+        println!("Loop: off; plays once over {:.3}s and stops.", h.duration);
+    } else if !usable {
+        println!(
+            "Loop: on, but invalid: loop points {} to {} don't fit a duration of {}s; the viewer won't loop it as intended.",
+            h.loop_in_point, h.loop_out_point, h.duration
+        );
+    } else {
+        let start = h.loop_in_point.clamp(0.0, h.duration);
+        let end = h.loop_out_point.clamp(start, h.duration);
+        println!(
+            "Loop: on; plays up to {:.3}s, then jumps back to {:.3}s and repeats until {:.3}s ({:.3}s per cycle).",
+            end,
+            start,
+            end,
+            end - start
+        );
+        if end - start <= f32::EPSILON {
+            println!("  The loop region is empty, so the animation freezes on one frame.");
+        }
+        if h.ease_in_duration > start && start > 0.0 {
+            println!(
+                "  Ease-in ({:.3}s) runs past the loop start ({:.3}s); the first cycle blends in differently from later ones.",
+                h.ease_in_duration, start
+            );
+        }
+    }
+    println!(
+        "Ease: blends in over {:.3}s and out over {:.3}s when started or stopped.",
+        h.ease_in_duration, h.ease_out_duration
+    );
+    if h.ease_in_duration + h.ease_out_duration > h.duration && h.looped == 0 {
+        println!("  The ease windows overlap; the animation never reaches full strength.");
+    }
+}
+
+pub fn run_clean(
+    strategy: DuplicateKeyStrategy,
+    input: PathBuf,
+    output: Option<PathBuf>,
+) -> Result<()> {
+    let mut anim = load(&input)?;
+    anim.cleanup_keys_with(strategy);
+    print_edit_log(&anim);
+    let out = output.unwrap_or(input);
+    anim.to_file(out)?;
+    Ok(())
+}
+
+pub fn run_sync(input: PathBuf, bpm: f32, offset: f32, output: Option<PathBuf>) -> Result<()> {
+    if !(bpm.is_finite() && bpm > 0.0) {
+        return Err(AnimError::InvalidStructure(format!(
+            "Invalid tempo {bpm} BPM"
+        )));
+    }
+    if !offset.is_finite() {
+        return Err(AnimError::InvalidStructure(format!(
+            "Invalid beat offset {offset}s"
+        )));
+    }
+    let mut anim = load(&input)?;
+    let before = anim.header.duration;
+    if !(before.is_finite() && before > 0.0) {
+        return Err(AnimError::InvalidStructure(format!(
+            "Cannot sync an animation lasting {before}s"
+        )));
+    }
+    // Enough beats for the warp to stretch the animation to several times its length; more
+    // beats than there are ticks can't pin anything further.
+    const MAX_BEATS: f32 = 65_536.0;
+    let beat = 60.0 / bpm;
+    let span = ((4.0 * before + offset.abs()) / beat).ceil();
+    if span >= MAX_BEATS {
+        return Err(AnimError::InvalidStructure(format!(
+            "Syncing {before}s to {bpm} BPM needs more than {MAX_BEATS} beats"
+        )));
+    }
+    let count = span as usize + 1;
+    let beats: Vec<f32> = (0..count).map(|i| offset + i as f32 * beat).collect();
+    anim.sync_to_beats(&beats)?;
+    eprintln!(
+        "Synced to {bpm} BPM: {:.3}s -> {:.3}s",
+        before, anim.header.duration
+    );
+    print_edit_log(&anim);
+    anim.to_file(output.unwrap_or(input))?;
+    Ok(())
+}
+
+pub fn run_suggest_loop(file: PathBuf, apply: bool, output: Option<PathBuf>) -> Result<()> {
+    let mut anim = load(&file)?;
+    let Some(suggestion) = crate::analysis::suggest_loop(&anim) else {
+        return Err(AnimError::InvalidStructure(format!(
+            "{} is too short or has no keys to loop",
+            file.display()
+        )));
+    };
+    println!(
+        "Loop in: {:.3}s  out: {:.3}s  (score {:.2}, seam error {:.4})",
+        suggestion.loop_in, suggestion.loop_out, suggestion.score, suggestion.seam_error
+    );
+    if apply {
+        suggestion.apply(&mut anim);
+        print_edit_log(&anim);
+        anim.to_file(output.unwrap_or(file))?;
+    }
+    Ok(())
+}
+
+/// Mirror the keys of `joint` onto `to`, or its opposite-side joint, with
+/// [`Animation::mirror_joint`].
+pub fn run_key_mirror(
+    file: PathBuf,
+    joint: String,
+    to: Option<String>,
+    output: Option<PathBuf>,
+) -> Result<()> {
+    let to = match to.or_else(|| crate::joints::opposite_joint(&joint)) {
+        Some(to) => to,
+        None => {
+            return Err(AnimError::InvalidStructure(format!(
+                "'{joint}' has no opposite-side joint; pass --to"
+            )));
+        }
+    };
+    let mut anim = load(&file)?;
+    anim.mirror_joint(&joint, &to)?;
+    eprintln!("Mirrored {joint} onto {to}");
+    print_edit_log(&anim);
+    anim.to_file(output.unwrap_or(file))?;
+    Ok(())
+}
+
+pub fn run_extract_poses(
+    file: PathBuf,
+    times: Vec<f32>,
+    out_dir: PathBuf,
+    format: PoseFormat,
+) -> Result<()> {
+    let anim = load(&file)?;
+    fs::create_dir_all(&out_dir).map_err(AnimError::Io)?;
+    let stem = file
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "pose".into());
+    let extension = match format {
+        PoseFormat::Llsd | PoseFormat::LlsdBinary => "xml",
+        PoseFormat::Anim => "anim",
+    };
+    for t in times {
+        if !(t.is_finite() && (0.0..=anim.header.duration).contains(&t)) {
+            return Err(AnimError::InvalidStructure(format!(
+                "Time {t}s is outside 0..={}s",
+                anim.header.duration
+            )));
+        }
+        let pose = anim.pose_at(t);
+        let path = out_dir.join(format!("{stem}_{t:.2}s.{extension}"));
+        match format {
+            PoseFormat::Llsd => pose.to_llsd_file(&path)?,
+            PoseFormat::LlsdBinary => pose.to_llsd_binary_file(&path)?,
+            PoseFormat::Anim => pose.to_file(&path)?,
+        }
+        println!("{}", path.display());
+    }
+    Ok(())
+}
+
+pub fn run_strip(kind: StripKind, input: PathBuf, output: PathBuf) -> Result<()> {
+    let mut anim = load(&input)?;
+    match kind {
+        StripKind::Position => {
+            anim.drop_position_keys();
+        }
+        StripKind::Rotation => {
+            anim.drop_rotation_keys();
+        }
+        StripKind::Both => {
+            anim.drop_position_keys().drop_rotation_keys();
+        }
+    }
+    print_edit_log(&anim);
+    anim.to_file(output)?;
+    Ok(())
+}
+
+pub fn run_list_poses(dir: Option<PathBuf>, full: bool) -> Result<()> {
+    let base = dir.or_else(firestorm_pose_dir).ok_or_else(|| {
+        AnimError::InvalidStructure("Could not determine Firestorm pose directory".into())
+    })?;
+    let mut entries: Vec<_> = fs::read_dir(&base)
+        .map_err(AnimError::Io)?
+        .filter_map(|e| e.ok())
+        .filter(|e| e.path().extension().is_some_and(|ext| ext == "xml"))
+        .collect();
+    entries.sort_by_key(|e| e.file_name());
+    for ent in entries {
+        let name = ent.file_name();
+        let name = name.to_string_lossy();
+        if full {
+            println!("{}", ent.path().display());
+        } else {
+            println!("{}", name);
+        }
+    }
+    Ok(())
+}
+
+fn parse_format(name: &str) -> std::result::Result<SourceFormat, String> {
+    let format = SourceFormat::from_name(name).ok_or_else(|| {
+        let names: Vec<_> = SourceFormat::ALL.iter().map(|f| f.name()).collect();
+        format!(
+            "unknown format '{name}', expected one of {}",
+            names.join(", ")
+        )
+    })?;
+    if format.is_supported() {
+        Ok(format)
+    } else {
+        Err(format!("{name} support is not compiled in"))
+    }
+}
+
+fn parse_csv_list(input: &Option<String>) -> Vec<String> {
+    input
+        .as_ref()
+        .map(|s| {
+            s.split(',')
+                .filter(|p| !p.is_empty())
+                .map(|s| s.trim().to_string())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Load any supported input (binary .anim, BVH, LLSD poses, gzip-compressed or not), with
+/// an edit log started.
+fn load(path: &std::path::Path) -> Result<Animation> {
+    let (mut anim, _) = crate::open_any(path)?;
+    anim.start_edit_log();
+    Ok(anim)
+}
+
+/// Read `options.input`, apply the requested edits and write the result.
+pub fn run_convert(options: ConvertOptions) -> Result<()> {
+    let ConvertOptions {
+        input,
+        output,
+        from,
+        to,
+        priority,
+        drop_positions,
+        drop_rotations,
+        drop_position_named,
+        drop_rotation_named,
+        drop_joints,
+        verbose,
+        if_changed,
+        fix_ease,
+        insert: inserts,
+    } = options;
+    let mut anim = match from {
+        Some(format) => crate::open_as(&input, format)?,
+        None => load(&input)?,
+    };
+    anim.start_edit_log();
+
+    // Process inserts before drops (so dropped joints remove inserted keys if targeted later)
+    if !inserts.is_empty() {
+        for spec in inserts {
+            let mark = anim.edit_mark();
+            if let Err(e) = apply_insert(&mut anim, &spec) {
+                let mut stderr = io::stderr();
+                writeln!(stderr, "Failed to parse --insert '{}': {}", spec, e).ok();
+            } else {
+                anim.log_edit(mark, EditOperation::Insert, &[("spec", &spec)]);
+            }
+        }
+    }
+
+    // Drop entire joints first if requested
+    let drop_joint_list = parse_csv_list(&drop_joints);
+    if !drop_joint_list.is_empty() {
+        let mark = anim.edit_mark();
+        anim.joints
+            .retain(|j| !drop_joint_list.iter().any(|n| n == &j.name));
+        anim.log_edit(
+            mark,
+            EditOperation::DropJoints,
+            &[("joints", &drop_joint_list.join(","))],
+        );
+    }
+
+    // Named position drops
+    let named_pos = parse_csv_list(&drop_position_named);
+    if !named_pos.is_empty() {
+        let set: std::collections::HashSet<&str> = named_pos.iter().map(|s| s.as_str()).collect();
+        anim.drop_position(|j| set.contains(j.name.as_str()));
+    }
+
+    // Named rotation drops
+    let named_rot = parse_csv_list(&drop_rotation_named);
+    if !named_rot.is_empty() {
+        let set: std::collections::HashSet<&str> = named_rot.iter().map(|s| s.as_str()).collect();
+        anim.drop_rotation(|j| set.contains(j.name.as_str()));
+    }
+
+    if drop_positions {
+        anim.drop_position_keys();
+    }
+    if drop_rotations {
+        anim.drop_rotation_keys();
+    }
+
+    if let Some(p) = priority {
+        anim.set_priority(p.clamp(0, 7));
+    }
+
+    // Clean duplicates with KeepLast as a sensible default when transforming
+    anim.cleanup_keys_with(DuplicateKeyStrategy::KeepLast);
+
+    if fix_ease {
+        let fix = anim.fix_ease_windows();
+        if fix.changed() {
+            eprintln!(
+                "Ease windows: in {:.3}s out {:.3}s -> in {:.3}s out {:.3}s",
+                fix.before.0, fix.before.1, fix.after.0, fix.after.1
+            );
+        }
+    }
+
+    print_edit_log(&anim);
+
+    // If verbose print stats to stderr
+    if verbose {
+        let total_rot: usize = anim.joints.iter().map(|j| j.rotation_keys.len()).sum();
+        let total_pos: usize = anim.joints.iter().map(|j| j.position_keys.len()).sum();
+        let mut stderr = io::stderr();
+        writeln!(stderr, "Input: {}", input.display()).ok();
+        writeln!(stderr, "Joints: {}", anim.joints.len()).ok();
+        writeln!(
+            stderr,
+            "Rotation keys: {} Position keys: {}",
+            total_rot, total_pos
+        )
+        .ok();
+        writeln!(stderr, "Priority: {}", anim.header.base_priority).ok();
+        writeln!(stderr, "Emote name: {}", anim.header.emote_name).ok();
+        writeln!(stderr, "Verbose debug: {:#?}", anim).ok();
+    }
+
+    if let Some(out) = output {
+        let format = to
+            .or_else(|| SourceFormat::from_extension(&out))
+            .unwrap_or(SourceFormat::Anim);
+        let bytes = anim.to_format(format)?;
+        // If writing to stdout requested (e.g., '-') treat specially
+        if out.as_os_str() == "-" {
+            let stdout = io::stdout();
+            let mut handle = stdout.lock();
+            handle.write_all(&bytes).map_err(AnimError::Io)?;
+        } else {
+            let outcome = if if_changed && fs::read(&out).is_ok_and(|existing| existing == bytes) {
+                WriteOutcome::Unchanged
+            } else {
+                fs::write(&out, &bytes).map_err(AnimError::Io)?;
+                WriteOutcome::Written
+            };
+            if !verbose {
+                let mut stderr = io::stderr();
+                match outcome {
+                    WriteOutcome::Written => {
+                        writeln!(stderr, "Wrote {} to {}", format.name(), out.display()).ok()
+                    }
+                    WriteOutcome::Unchanged => {
+                        writeln!(stderr, "Unchanged: {}", out.display()).ok()
+                    }
+                };
+            }
+        }
+    } else if !verbose {
+        let mut stderr = io::stderr();
+        writeln!(stderr, "Parsed animation successfully").ok();
+    }
+
+    Ok(())
+}
+
+fn apply_insert(anim: &mut Animation, spec: &str) -> std::result::Result<(), String> {
+    // Format: joint:pos<x,y,z>[@time]  OR joint:rot<r,p,y>[@time]
+    let (left, time_part) = if let Some(idx) = spec.rfind('@') {
+        (&spec[..idx], Some(&spec[idx + 1..]))
+    } else {
+        (spec, None)
+    };
+    let time: u16 = if let Some(tp) = time_part {
+        tp.parse().map_err(|_| "Invalid time")?
+    } else {
+        u16::MAX
+    };
+    let mut parts = left.splitn(2, ':');
+    let joint = parts.next().ok_or("Missing joint")?;
+    let payload = parts.next().ok_or("Missing payload")?;
+    let (kind, remainder) = if let Some(rest) = payload.strip_prefix("pos") {
+        ("pos", rest)
+    } else if let Some(rest) = payload.strip_prefix("rot") {
+        ("rot", rest)
+    } else {
+        return Err("Expected 'pos' or 'rot'".into());
+    };
+    let mut nums: Vec<f32> = Vec::new();
+    if let Some(start) = remainder.strip_prefix('<') {
+        let vec_part = start.strip_suffix('>').ok_or("Missing closing '>'")?;
+        for part in vec_part.split(',').filter(|s| !s.is_empty()) {
+            nums.push(part.trim().parse::<f32>().map_err(|_| "Invalid float")?);
+        }
+    }
+    if kind == "pos" {
+        while nums.len() < 3 {
+            nums.push(0.0);
+        }
+        let pos = glam::Vec3::new(nums[0], nums[1], nums[2]);
+        anim.joint_or_insert(joint)
+            .edit_positions(|track| track.insert(PositionKey { time, pos }));
+    } else {
+        // rot
+        while nums.len() < 3 {
+            nums.push(0.0);
+        }
+        let rot = crate::math::from_viewer_euler(glam::Vec3::new(nums[0], nums[1], nums[2]));
+        anim.joint_or_insert(joint)
+            .edit_rotations(|track| track.insert(RotationKey { time, rot }));
+    }
+    Ok(())
+}
+
+fn joint_label(name: &str, locale: Option<Locale>) -> String {
+    match locale {
+        Some(locale) => format!("{} ({})", name, display_name(name, locale)),
+        None => name.to_string(),
+    }
+}
+
+pub fn run_joints(
+    file: PathBuf,
+    joint: Option<String>,
+    summary: bool,
+    locale: Option<Locale>,
+) -> Result<()> {
+    let anim = load(&file)?;
+    if let Some(name) = joint {
+        if let Some(j) = anim.joints.iter().find(|j| j.name == name) {
+            // Compact format: times+values inline
+            // Rotation keys: t: roll,pitch,yaw in degrees, as the poser panel shows them
+            // Position keys: t: x,y,z
+            println!("Joint: {}", joint_label(&j.name, locale));
+            if !j.rotation_keys.is_empty() {
+                print!("rot[");
+                for (idx, k) in j.rotation_keys.iter().enumerate() {
+                    let euler = crate::math::euler_for_viewer(k.rot);
+                    if idx > 0 {
+                        print!(" ");
+                    }
+                    print!("{}:{:.2},{:.2},{:.2}", k.time, euler.x, euler.y, euler.z);
+                }
+                println!("]");
+            }
+            if !j.position_keys.is_empty() {
+                print!("pos[");
+                for (idx, k) in j.position_keys.iter().enumerate() {
+                    if idx > 0 {
+                        print!(" ");
+                    }
+                    print!("{}:{:.3},{:.3},{:.3}", k.time, k.pos.x, k.pos.y, k.pos.z);
+                }
+                println!("]");
+            }
+        } else {
+            eprintln!("Joint '{}' not found", name);
+            return Ok(());
+        }
+    } else {
+        // List all
+        for j in &anim.joints {
+            if summary {
+                println!(
+                    "{} (rot:{} pos:{})",
+                    joint_label(&j.name, locale),
+                    j.rotation_keys.len(),
+                    j.position_keys.len()
+                );
+            } else {
+                println!("{}", joint_label(&j.name, locale));
+            }
+        }
+    }
+    Ok(())
+}
+
+pub fn run_retarget_batch(
+    input: PathBuf,
+    bodies: String,
+    from: String,
+    out_dir: PathBuf,
+    if_changed: bool,
+) -> Result<()> {
+    let from = from.parse::<BodyPreset>()?.skeleton();
+    let presets = parse_csv_list(&Some(bodies))
+        .iter()
+        .map(|name| name.parse::<BodyPreset>())
+        .collect::<Result<Vec<_>>>()?;
+    let stem = input
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "animation".into());
+    fs::create_dir_all(&out_dir).map_err(AnimError::Io)?;
+    let mut failed = 0;
+    for preset in presets {
+        let (from, to) = (from.clone(), preset.skeleton());
+        let batch = Batch::new()
+            .op("retarget", move |anim, _| {
+                anim.retarget(&from, &to);
+                Ok(())
+            })
+            .if_changed(if_changed);
+        let out = out_dir.join(format!("{}_{}.anim", stem, preset.name()));
+        failed += batch.run([(input.clone(), out)], &mut CliObserver).failed;
+    }
+    if failed > 0 {
+        return Err(AnimError::InvalidStructure(format!(
+            "{failed} output(s) failed"
+        )));
+    }
+    Ok(())
+}
+
+/// Prints batch results the way single-file commands do: outputs on stdout, problems on stderr.
+struct CliObserver;
+
+impl BatchObserver for CliObserver {
+    fn warning(&mut self, input: &std::path::Path, op: &str, message: &str) {
+        eprintln!("warning: {}: {op}: {message}", input.display());
+    }
+
+    fn file_finished(
+        &mut self,
+        _: &std::path::Path,
+        output: &std::path::Path,
+        outcome: WriteOutcome,
+    ) {
+        match outcome {
+            WriteOutcome::Written => println!("{}", output.display()),
+            WriteOutcome::Unchanged => println!("{} (unchanged)", output.display()),
+        }
+    }
+
+    fn file_failed(&mut self, input: &std::path::Path, error: &AnimError) {
+        eprintln!("error: {}: {error}", input.display());
+    }
+}
+
+fn anim_files(dir: &std::path::Path) -> Result<Vec<PathBuf>> {
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)
+        .map_err(AnimError::Io)?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| {
+            p.extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("anim"))
+        })
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+fn manifest_entries(dir: &std::path::Path) -> Result<Vec<ManifestEntry>> {
+    let mut entries = Vec::new();
+    for path in anim_files(dir)? {
+        let anim = load(&path)?;
+        let name = path
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        entries.push(ManifestEntry::new(name, &anim));
+    }
+    Ok(entries)
+}
+
+fn write_text_output(output: Option<PathBuf>, text: &str) -> Result<()> {
+    match output {
+        Some(out) => fs::write(out, text).map_err(AnimError::Io)?,
+        None => print!("{}", text),
+    }
+    Ok(())
+}
+
+pub fn run_manifest(dir: PathBuf, output: Option<PathBuf>, format: ManifestFormat) -> Result<()> {
+    let entries = manifest_entries(&dir)?;
+    let text = match format {
+        ManifestFormat::Json => manifest::to_json(&entries),
+        ManifestFormat::Notecard => manifest::to_notecard(&entries),
+    };
+    write_text_output(output, &text)
+}
+
+pub fn run_lsl_gen(dir: PathBuf, template: LslTemplate, output: Option<PathBuf>) -> Result<()> {
+    let entries = manifest_entries(&dir)?;
+    write_text_output(output, &manifest::to_lsl(&entries, template))
+}
+
+/// Report library-wide statistics of the .anim files below `dir`; unreadable files are
+/// listed in the report and also warned about on stderr.
+pub fn run_dashboard(
+    dir: PathBuf,
+    output: Option<PathBuf>,
+    format: DashboardFormat,
+    top: usize,
+) -> Result<()> {
+    let stats = LibraryStats::from_dir(&dir)?;
+    for (name, error) in &stats.failures {
+        eprintln!("warning: {name}: {error}");
+    }
+    let text = match format {
+        DashboardFormat::Text => stats.to_text(top),
+        DashboardFormat::Json => stats.to_json(top),
+        DashboardFormat::Html => stats.to_html(top),
+    };
+    write_text_output(output, &text)
+}
+
+pub fn run_complete(shell: ShellKind) -> Result<()> {
+    use clap::CommandFactory;
+    use std::io::stdout;
+    let mut cmd = Cli::command();
+    let bin_name = cmd.get_name().to_string();
+    match shell {
+        ShellKind::Bash => generate(Bash, &mut cmd, &bin_name, &mut stdout()),
+        ShellKind::Zsh => generate(Zsh, &mut cmd, &bin_name, &mut stdout()),
+        ShellKind::Fish => generate(Fish, &mut cmd, &bin_name, &mut stdout()),
+        ShellKind::Powershell => generate(PowerShell, &mut cmd, &bin_name, &mut stdout()),
+        ShellKind::Elvish => generate(Elvish, &mut cmd, &bin_name, &mut stdout()),
+    }
+    Ok(())
+}
@@ -0,0 +1,212 @@
+//! COLLADA (`.dae`) animation export.
+//!
+//! The skeleton becomes a tree of `JOINT` nodes and every joint gets an `<animation>` with a
+//! baked `float4x4` transform per frame, the form Avastar and Blender's importer expect.
+//! Coordinates stay in SL's Z-up frame (`<up_axis>Z_UP</up_axis>`) and meters.
+
+use crate::bake::{self, Rig};
+use crate::skeleton::Skeleton;
+use crate::{AnimError, Animation, Result};
+use glam::Mat4;
+use std::fmt::Write as _;
+use std::io::Write;
+
+/// Options for [`Animation::to_collada_writer`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct ColladaOptions {
+    /// Frames per second of the sampled motion.
+    pub frame_rate: f32,
+    /// Write only the joints the animation has (plus their ancestors, to keep the hierarchy
+    /// connected) instead of the whole skeleton.
+    pub only_animated_joints: bool,
+}
+
+impl Default for ColladaOptions {
+    fn default() -> Self {
+        Self {
+            frame_rate: 30.0,
+            only_animated_joints: false,
+        }
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// A transform as COLLADA's row-major matrix text.
+fn matrix(m: Mat4) -> String {
+    let values: Vec<String> = m
+        .transpose()
+        .to_cols_array()
+        .iter()
+        .map(f32::to_string)
+        .collect();
+    values.join(" ")
+}
+
+/// Append one `<source>` holding `values` read `stride` at a time as `param`.
+fn source(out: &mut String, id: &str, array: &str, values: &[String], stride: usize, param: &str) {
+    let kind = if param == "INTERPOLATION" {
+        "name"
+    } else if stride == 16 {
+        "float4x4"
+    } else {
+        "float"
+    };
+    let _ = writeln!(
+        out,
+        r##"      <source id="{id}">
+        <{array} id="{id}-array" count="{}">{}</{array}>
+        <technique_common>
+          <accessor source="#{id}-array" count="{}" stride="{stride}">
+            <param name="{param}" type="{kind}"/>
+          </accessor>
+        </technique_common>
+      </source>"##,
+        values.len(),
+        values.join(" "),
+        values.len() / stride,
+    );
+}
+
+impl Animation {
+    /// Write the animation as a COLLADA document, with the hierarchy and bind translations
+    /// taken from `skeleton`.
+    ///
+    /// Every written joint is sampled every `1 / frame_rate` seconds like
+    /// [`Animation::to_bvh_writer`] does; position keys are applied to the root only.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use avatar_anim::collada::ColladaOptions;
+    /// use avatar_anim::{Animation, Skeleton};
+    ///
+    /// # fn main() -> avatar_anim::Result<()> {
+    /// let anim = Animation::from_file("wave.anim")?;
+    /// let file = std::fs::File::create("wave.dae").map_err(avatar_anim::AnimError::Io)?;
+    /// anim.to_collada_writer(file, &Skeleton::second_life(), &ColladaOptions::default())?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Fails on a non-positive frame rate, an empty skeleton, or a write error.
+    pub fn to_collada_writer<W: Write>(
+        &self,
+        mut writer: W,
+        skeleton: &Skeleton,
+        options: &ColladaOptions,
+    ) -> Result<()> {
+        let frames = bake::frames(self.header.duration, options.frame_rate)?;
+        let rig = Rig::new(self, skeleton, options.only_animated_joints)?;
+        let joints = skeleton.joints();
+        let transform = |index: usize, tick: Option<f32>| {
+            let (rot, pos) = rig.local(index, tick);
+            Mat4::from_rotation_translation(rot, pos)
+        };
+
+        let mut text = String::from(
+            r#"<?xml version="1.0" encoding="utf-8"?>
+<COLLADA xmlns="http://www.collada.org/2005/11/COLLADASchema" version="1.4.1">
+  <asset>
+    <contributor>
+      <authoring_tool>avatar-anim</authoring_tool>
+    </contributor>
+    <unit name="meter" meter="1"/>
+    <up_axis>Z_UP</up_axis>
+  </asset>
+  <library_animations>
+"#,
+        );
+        let times: Vec<String> = frames.iter().map(|(t, _)| t.to_string()).collect();
+        let linear = vec!["LINEAR".to_string(); frames.len()];
+        for index in rig.order() {
+            let name = escape(&joints[index].name);
+            let values: Vec<String> = frames
+                .iter()
+                .flat_map(|&(_, tick)| transform(index, Some(tick)).transpose().to_cols_array())
+                .map(|v| v.to_string())
+                .collect();
+            let _ = writeln!(text, r#"    <animation id="{name}-anim" name="{name}">"#);
+            source(
+                &mut text,
+                &format!("{name}-input"),
+                "float_array",
+                &times,
+                1,
+                "TIME",
+            );
+            source(
+                &mut text,
+                &format!("{name}-output"),
+                "float_array",
+                &values,
+                16,
+                "TRANSFORM",
+            );
+            source(
+                &mut text,
+                &format!("{name}-interpolation"),
+                "Name_array",
+                &linear,
+                1,
+                "INTERPOLATION",
+            );
+            let _ = writeln!(
+                text,
+                r##"      <sampler id="{name}-sampler">
+        <input semantic="INPUT" source="#{name}-input"/>
+        <input semantic="OUTPUT" source="#{name}-output"/>
+        <input semantic="INTERPOLATION" source="#{name}-interpolation"/>
+      </sampler>
+      <channel source="#{name}-sampler" target="{name}/transform"/>
+    </animation>"##
+            );
+        }
+        text.push_str(
+            r#"  </library_animations>
+  <library_visual_scenes>
+    <visual_scene id="Scene" name="Scene">
+"#,
+        );
+
+        let mut stack = vec![(rig.root, 3usize, false)];
+        while let Some((index, depth, close)) = stack.pop() {
+            let pad = "  ".repeat(depth);
+            if close {
+                let _ = writeln!(text, "{pad}</node>");
+                continue;
+            }
+            let name = escape(&joints[index].name);
+            let _ = writeln!(
+                text,
+                r#"{pad}<node id="{name}" name="{name}" sid="{name}" type="JOINT">"#
+            );
+            let _ = writeln!(
+                text,
+                r#"{pad}  <matrix sid="transform">{}</matrix>"#,
+                matrix(transform(index, None))
+            );
+            stack.push((index, depth, true));
+            for &child in rig.children[index].iter().rev() {
+                stack.push((child, depth + 1, false));
+            }
+        }
+        text.push_str(
+            r##"    </visual_scene>
+  </library_visual_scenes>
+  <scene>
+    <instance_visual_scene url="#Scene"/>
+  </scene>
+</COLLADA>
+"##,
+        );
+        writer.write_all(text.as_bytes()).map_err(AnimError::Io)
+    }
+}
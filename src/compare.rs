@@ -0,0 +1,362 @@
+//! Compare animations by how they move rather than by their keys.
+
+use crate::{AnimError, Animation, JointData, PositionKey, Result, RotationKey, WrapMode, math};
+use binrw::{BinRead, BinWrite, binrw};
+use glam::{Quat, Vec3};
+use std::io::Cursor;
+
+/// Per-sample error across joints.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ErrorCurve {
+    /// Largest error of any joint at each sample.
+    pub max: Vec<f32>,
+    /// Average error over the compared joints at each sample.
+    pub mean: Vec<f32>,
+}
+
+impl ErrorCurve {
+    /// Largest error anywhere on the curve, `0.0` if empty.
+    pub fn peak(&self) -> f32 {
+        self.max.iter().copied().fold(0.0, f32::max)
+    }
+
+    /// Time-average of the per-sample mean, `0.0` if empty.
+    pub fn average(&self) -> f32 {
+        if self.mean.is_empty() {
+            0.0
+        } else {
+            self.mean.iter().sum::<f32>() / self.mean.len() as f32
+        }
+    }
+}
+
+/// Result of [`sampled_distance`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SampledDistance {
+    /// Sample times in seconds.
+    pub times: Vec<f32>,
+    /// Rotation error in radians (geodesic angle between the two poses of a joint).
+    pub rotation: ErrorCurve,
+    /// Position error in meters.
+    pub position: ErrorCurve,
+}
+
+fn curve(samples: impl Iterator<Item = Vec<f32>>) -> ErrorCurve {
+    let mut out = ErrorCurve::default();
+    for errors in samples {
+        out.max.push(errors.iter().copied().fold(0.0, f32::max));
+        out.mean.push(if errors.is_empty() {
+            0.0
+        } else {
+            errors.iter().sum::<f32>() / errors.len() as f32
+        });
+    }
+    out
+}
+
+/// Most samples taken of one animation; past one per tick they can't tell more apart.
+const MAX_SAMPLES: usize = 1 << 16;
+
+/// Sample times in seconds covering `[0, duration]` at `fps`, ending exactly on the duration.
+///
+/// Beyond [`MAX_SAMPLES`] the samples are spread evenly instead. A duration that is not a
+/// number is sampled once, at 0, which is where sampling such an animation lands anyway.
+fn sample_times(duration: f32, fps: f32) -> Vec<f32> {
+    let duration = if duration.is_finite() {
+        duration.max(0.0)
+    } else {
+        0.0
+    };
+    let span = (duration * fps).ceil();
+    if span >= MAX_SAMPLES as f32 {
+        let last = (MAX_SAMPLES - 1) as f32;
+        return (0..MAX_SAMPLES)
+            .map(|i| duration * i as f32 / last)
+            .collect();
+    }
+    (0..=span as usize)
+        .map(|i| (i as f32 / fps).min(duration))
+        .collect()
+}
+
+fn check_rate(fps: f32) -> Result<()> {
+    if fps.is_finite() && fps > 0.0 {
+        Ok(())
+    } else {
+        Err(AnimError::InvalidStructure(format!(
+            "Comparison needs a positive sample rate, got {fps}"
+        )))
+    }
+}
+
+/// Distance between the poses of `a` and `b` over time, sampled `fps` times per second.
+///
+/// Both are sampled in seconds, so differing key times, key counts or durations don't matter;
+/// the shorter animation holds its last pose. Every joint present in either animation is
+/// compared; a missing rotation counts as identity and a missing position as no offset, so a
+/// joint only one side animates shows up as error.
+///
+/// # Example
+///
+/// ```rust
+/// use avatar_anim::compare::sampled_distance;
+/// use avatar_anim::{Animation, JointData, RotationKey};
+/// use glam::Quat;
+///
+/// let mut a = Animation::new();
+/// a.header.duration = 1.0;
+/// a.joints.push(JointData {
+///     name: "mHead".into(),
+///     rotation_keys: vec![
+///         RotationKey { time: 0, rot: Quat::IDENTITY },
+///         RotationKey { time: 65535, rot: Quat::from_rotation_z(0.4) },
+///     ],
+///     ..Default::default()
+/// });
+/// let mut b = a.clone();
+/// b.reduce_keys(0.01);
+/// let distance = sampled_distance(&a, &b, 30.0)?;
+/// assert!(distance.rotation.peak() < 0.01);
+/// # Ok::<(), avatar_anim::AnimError>(())
+/// ```
+///
+/// # Errors
+///
+/// Fails if `fps` is not positive.
+pub fn sampled_distance(a: &Animation, b: &Animation, fps: f32) -> Result<SampledDistance> {
+    check_rate(fps)?;
+    let mut names: Vec<&str> = a.joints.iter().map(|j| j.name.as_str()).collect();
+    for joint in &b.joints {
+        if !names.contains(&joint.name.as_str()) {
+            names.push(&joint.name);
+        }
+    }
+    let has = |anim: &Animation, name: &str, rotation: bool| {
+        anim.joint(name).is_some_and(|j| {
+            if rotation {
+                !j.rotation_keys.is_empty()
+            } else {
+                !j.position_keys.is_empty()
+            }
+        })
+    };
+    let rotated: Vec<&str> = names
+        .iter()
+        .copied()
+        .filter(|n| has(a, n, true) || has(b, n, true))
+        .collect();
+    let moved: Vec<&str> = names
+        .iter()
+        .copied()
+        .filter(|n| has(a, n, false) || has(b, n, false))
+        .collect();
+
+    let longest = [a.header.duration, b.header.duration]
+        .into_iter()
+        .filter(|d| d.is_finite())
+        .fold(0.0, f32::max);
+    let times = sample_times(longest, fps);
+    let sample = |anim: &Animation, name: &str, t: f32| {
+        anim.sample_joint(name, t, WrapMode::Clamp)
+            .unwrap_or_default()
+    };
+
+    let rotation = curve(times.iter().map(|&t| {
+        rotated
+            .iter()
+            .map(|name| {
+                let ra = sample(a, name, t).rotation.unwrap_or(Quat::IDENTITY);
+                let rb = sample(b, name, t).rotation.unwrap_or(Quat::IDENTITY);
+                math::geodesic_distance(ra, rb)
+            })
+            .collect()
+    }));
+    let position = curve(times.iter().map(|&t| {
+        moved
+            .iter()
+            .map(|name| {
+                let pa = sample(a, name, t).position.unwrap_or(Vec3::ZERO);
+                let pb = sample(b, name, t).position.unwrap_or(Vec3::ZERO);
+                pa.distance(pb)
+            })
+            .collect()
+    }));
+    Ok(SampledDistance {
+        times,
+        rotation,
+        position,
+    })
+}
+
+/// Largest acceptable deviation from a [`Baseline`].
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Tolerance {
+    /// Radians.
+    pub rotation: f32,
+    /// Meters.
+    pub position: f32,
+}
+
+impl Default for Tolerance {
+    /// Half a degree and two millimeters, well above `.anim` quantization.
+    fn default() -> Self {
+        Self {
+            rotation: 0.5f32.to_radians(),
+            position: 0.002,
+        }
+    }
+}
+
+/// Outcome of [`Baseline::check`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct BaselineCheck {
+    pub distance: SampledDistance,
+    /// Whether the durations agree to within one sample.
+    pub duration_matches: bool,
+    /// Whether the animation stayed within the baseline's tolerance.
+    pub passed: bool,
+}
+
+#[binrw]
+#[brw(little, magic = b"AABL")]
+struct BaselineFile {
+    #[br(assert(version == 1, "unsupported baseline version {}", version))]
+    version: u16,
+    sample_rate: f32,
+    rotation_tolerance: f32,
+    position_tolerance: f32,
+    poses: Animation,
+}
+
+/// Approved motion of an animation, recorded as poses sampled at a fixed rate.
+///
+/// Store it next to the asset (e.g. `walk.anim.baseline`) and [`Baseline::check`] later
+/// exports against it: any change in motion beyond the tolerance fails, however the keys are
+/// laid out. The sidecar holds the poses in `.anim` quantization, one key per sample.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use avatar_anim::Animation;
+/// use avatar_anim::compare::Baseline;
+///
+/// # fn main() -> avatar_anim::Result<()> {
+/// let approved = Animation::from_file("walk.anim")?;
+/// Baseline::capture(&approved).to_file("walk.anim.baseline")?;
+///
+/// let baseline = Baseline::from_file("walk.anim.baseline")?;
+/// let check = baseline.check(&Animation::from_file("walk_reexport.anim")?);
+/// if !check.passed {
+///     eprintln!("motion changed by up to {} rad", check.distance.rotation.peak());
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Baseline {
+    /// Samples per second.
+    pub sample_rate: f32,
+    pub tolerance: Tolerance,
+    poses: Animation,
+}
+
+impl Baseline {
+    pub const DEFAULT_SAMPLE_RATE: f32 = 30.0;
+
+    /// Record `anim` at [`Baseline::DEFAULT_SAMPLE_RATE`] with the default tolerance.
+    pub fn capture(anim: &Animation) -> Self {
+        Self::capture_with(anim, Self::DEFAULT_SAMPLE_RATE, Tolerance::default())
+            .expect("default sample rate is positive")
+    }
+
+    /// Record `anim` at `sample_rate` samples per second.
+    ///
+    /// # Errors
+    ///
+    /// Fails if `sample_rate` is not positive.
+    pub fn capture_with(anim: &Animation, sample_rate: f32, tolerance: Tolerance) -> Result<Self> {
+        check_rate(sample_rate)?;
+        let times = sample_times(anim.header.duration, sample_rate);
+        let ticks: Vec<f32> = times.iter().map(|&t| anim.seconds_to_tick(t)).collect();
+        let mut poses = Animation {
+            header: anim.header.clone(),
+            ..Default::default()
+        };
+        for joint in &anim.joints {
+            let mut keys = JointData {
+                name: joint.name.clone(),
+                priority: joint.priority,
+                ..Default::default()
+            };
+            for &tick in &ticks {
+                let time = tick.round() as u16;
+                if let Some(rot) = joint.rotation_at(tick) {
+                    keys.rotation_keys.push(RotationKey { time, rot });
+                }
+                if let Some(pos) = joint.position_at(tick) {
+                    keys.position_keys.push(PositionKey { time, pos });
+                }
+            }
+            keys.rotation_keys.dedup_by_key(|k| k.time);
+            keys.position_keys.dedup_by_key(|k| k.time);
+            poses.joints.push(keys);
+        }
+        Ok(Self {
+            sample_rate,
+            tolerance,
+            poses,
+        })
+    }
+
+    /// Compare `anim` with the recorded motion at the baseline's sample rate.
+    pub fn check(&self, anim: &Animation) -> BaselineCheck {
+        let distance = sampled_distance(&self.poses, anim, self.sample_rate)
+            .expect("baseline sample rate was validated");
+        let duration_matches =
+            (self.poses.header.duration - anim.header.duration).abs() <= 1.0 / self.sample_rate;
+        let passed = duration_matches
+            && distance.rotation.peak() <= self.tolerance.rotation
+            && distance.position.peak() <= self.tolerance.position;
+        BaselineCheck {
+            distance,
+            duration_matches,
+            passed,
+        }
+    }
+
+    /// Encode as a baseline sidecar.
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let file = BaselineFile {
+            version: 1,
+            sample_rate: self.sample_rate,
+            rotation_tolerance: self.tolerance.rotation,
+            position_tolerance: self.tolerance.position,
+            poses: self.poses.clone(),
+        };
+        let mut bytes = Cursor::new(Vec::new());
+        file.write(&mut bytes).map_err(AnimError::BinRw)?;
+        Ok(bytes.into_inner())
+    }
+
+    /// Decode a baseline sidecar.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let file = BaselineFile::read(&mut Cursor::new(bytes)).map_err(AnimError::BinRw)?;
+        check_rate(file.sample_rate)?;
+        Ok(Self {
+            sample_rate: file.sample_rate,
+            tolerance: Tolerance {
+                rotation: file.rotation_tolerance,
+                position: file.position_tolerance,
+            },
+            poses: file.poses,
+        })
+    }
+
+    pub fn to_file<P: AsRef<std::path::Path>>(&self, path: P) -> Result<()> {
+        std::fs::write(path, self.to_bytes()?).map_err(AnimError::Io)
+    }
+
+    pub fn from_file<P: AsRef<std::path::Path>>(path: P) -> Result<Self> {
+        Self::from_bytes(&std::fs::read(path).map_err(AnimError::Io)?)
+    }
+}
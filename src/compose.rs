@@ -0,0 +1,434 @@
+use crate::time::{MAX_TICK, to_tick};
+use crate::track::{Keyframe, interpolate};
+use crate::{
+    AnimError, Animation, Constraint, DuplicateKeyStrategy, EditOperation, JointData, PositionKey,
+    Result, RotationKey, Track,
+};
+use glam::Quat;
+
+/// How [`Animation::merge`] settles a channel (rotation or position) keyed in both
+/// animations.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum MergeStrategy {
+    /// This animation's keys win.
+    KeepSelf,
+    /// The other animation's keys win.
+    KeepOther,
+    /// The keys of the joint with the higher priority win, the other animation's on a tie.
+    HigherPriority,
+    /// Both are sampled at the keys of either and mixed, `0.0` giving this animation's
+    /// values and `1.0` the other's.
+    Blend(f32),
+}
+
+/// Both channels sampled at the ticks of either and mixed by `weight`.
+fn blend_channel<K: Keyframe>(
+    a: &[K],
+    b: &[K],
+    weight: f32,
+    key: impl Fn(u16, K::Value) -> K,
+) -> Vec<K> {
+    let mut a = a.to_vec();
+    a.sort_by_key(K::time);
+    let mut b = b.to_vec();
+    b.sort_by_key(K::time);
+    let mut ticks: Vec<u16> = a.iter().chain(&b).map(K::time).collect();
+    ticks.sort_unstable();
+    ticks.dedup();
+    ticks
+        .into_iter()
+        .filter_map(|tick| {
+            let from = interpolate(&a, tick as f32)?;
+            let to = interpolate(&b, tick as f32)?;
+            Some(key(tick, K::mix(from, to, weight)))
+        })
+        .collect()
+}
+
+/// Samples per second taken across the overlap of [`Animation::crossfade`].
+const CROSSFADE_RATE: f32 = 30.0;
+
+/// Timing of a crossfade, in seconds.
+struct Fade {
+    first: f32,
+    second: f32,
+    /// Where the second clip starts in the result.
+    start: f32,
+    overlap: f32,
+    duration: f32,
+}
+
+impl Fade {
+    /// Weight of the second clip at `t`, easing in and out of the overlap (smoothstep).
+    fn weight(&self, t: f32) -> f32 {
+        if self.overlap <= 0.0 {
+            return 1.0;
+        }
+        let w = ((t - self.start) / self.overlap).clamp(0.0, 1.0);
+        w * w * (3.0 - 2.0 * w)
+    }
+
+    /// One channel of the result from the same channel of both clips, either of which may be
+    /// empty.
+    fn channel<K: Keyframe>(&self, a: &[K], b: &[K], key: impl Fn(u16, K::Value) -> K) -> Vec<K> {
+        let mut a = a.to_vec();
+        a.sort_by_key(K::time);
+        let mut b = b.to_vec();
+        b.sort_by_key(K::time);
+        let a_time = |k: &K| k.time() as f32 / MAX_TICK * self.first;
+        let b_time = |k: &K| self.start + k.time() as f32 / MAX_TICK * self.second;
+
+        let mut out: Vec<K> = a
+            .iter()
+            .filter(|k| a_time(k) < self.start)
+            .map(|k| key(to_tick(a_time(k), self.duration), k.value()))
+            .collect();
+        // Past one sample per tick, more steps only land on the same ticks.
+        let steps = (self.overlap * CROSSFADE_RATE).ceil().clamp(1.0, MAX_TICK);
+        let samples = (0..=steps as u32)
+            .map(|i| self.start + self.overlap * i as f32 / steps)
+            .chain(a.iter().map(a_time).filter(|&t| t >= self.start))
+            .chain(b.iter().map(b_time).filter(|&t| t <= self.first));
+        for t in samples {
+            let from = interpolate(&a, t / self.first * MAX_TICK);
+            let to = interpolate(&b, (t - self.start) / self.second * MAX_TICK);
+            let value = match (from, to) {
+                (Some(from), Some(to)) => K::mix(from, to, self.weight(t)),
+                (from, to) => match from.or(to) {
+                    Some(value) => value,
+                    None => continue,
+                },
+            };
+            out.push(key(to_tick(t, self.duration), value));
+        }
+        out.extend(
+            b.iter()
+                .filter(|k| b_time(k) > self.first)
+                .map(|k| key(to_tick(b_time(k), self.duration), k.value())),
+        );
+        Track::from_keys(&out, DuplicateKeyStrategy::KeepLast).into_vec()
+    }
+}
+
+impl Animation {
+    /// A copy of this animation with `other` layered on top. The copy keeps this animation's
+    /// edit log, as do the other copies below, with an entry for the step.
+    ///
+    /// Joints only in `other` are added. For joints in both, each channel (rotation,
+    /// position) that has keys in `other` replaces this animation's channel, and the joint takes
+    /// `other`'s priority. The header stays this animation's.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use avatar_anim::Animation;
+    /// use avatar_anim::joints::UPPER_BODY;
+    ///
+    /// let base = Animation::new();
+    /// let hands = Animation::new();
+    /// let combined = base.overlay(&hands).masked(UPPER_BODY);
+    /// ```
+    pub fn overlay(&self, other: &Animation) -> Animation {
+        let mut out = self.clone();
+        let mark = out.edit_mark();
+        for top in &other.joints {
+            let Some(joint) = out.joint_mut(&top.name) else {
+                out.joints.push(top.clone());
+                continue;
+            };
+            joint.priority = top.priority;
+            if !top.rotation_keys.is_empty() {
+                joint.rotation_keys = top.rotation_keys.clone();
+            }
+            if !top.position_keys.is_empty() {
+                joint.position_keys = top.position_keys.clone();
+            }
+        }
+        out.log_edit(
+            mark,
+            EditOperation::Overlay,
+            &[("joints", &other.joints.len())],
+        );
+        out
+    }
+
+    /// Layer `other` into this animation, e.g. a hands pose onto a body animation, settling
+    /// conflicts with `strategy`.
+    ///
+    /// Joints only in `other` are added. For joints in both, a channel (rotation, position)
+    /// keyed in only one of them is taken from it, and a channel keyed in both is resolved
+    /// by `strategy`. The joint keeps the priority of the side that won, the higher of the
+    /// two when blending. Keys are matched by tick, as in [`Animation::overlay`]; the header
+    /// stays this animation's. Constraints of `other` this animation lacks are appended.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use avatar_anim::{Animation, MergeStrategy};
+    ///
+    /// # fn main() -> avatar_anim::Result<()> {
+    /// let mut dance = Animation::from_file("dance.anim")?;
+    /// let fists = Animation::from_file("fists.anim")?;
+    /// dance.merge(&fists, MergeStrategy::HigherPriority);
+    /// dance.to_file("dance_fists.anim")?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn merge(&mut self, other: &Animation, strategy: MergeStrategy) -> &mut Self {
+        let mark = self.edit_mark();
+        for top in &other.joints {
+            let Some(joint) = self.joint_mut(&top.name) else {
+                self.joints.push(top.clone());
+                continue;
+            };
+            let conflict = (!joint.rotation_keys.is_empty() && !top.rotation_keys.is_empty())
+                || (!joint.position_keys.is_empty() && !top.position_keys.is_empty());
+            let take_other = match strategy {
+                MergeStrategy::KeepSelf => false,
+                MergeStrategy::KeepOther => true,
+                MergeStrategy::HigherPriority => top.priority >= joint.priority,
+                MergeStrategy::Blend(weight) => {
+                    if !top.rotation_keys.is_empty() && !joint.rotation_keys.is_empty() {
+                        joint.rotation_keys = blend_channel(
+                            &joint.rotation_keys,
+                            &top.rotation_keys,
+                            weight,
+                            |time, rot| RotationKey {
+                                time,
+                                rot: rot.normalize(),
+                            },
+                        );
+                    }
+                    if !top.position_keys.is_empty() && !joint.position_keys.is_empty() {
+                        joint.position_keys = blend_channel(
+                            &joint.position_keys,
+                            &top.position_keys,
+                            weight,
+                            |time, pos| PositionKey { time, pos },
+                        );
+                    }
+                    joint.priority = joint.priority.max(top.priority);
+                    false
+                }
+            };
+            if joint.rotation_keys.is_empty() || take_other && !top.rotation_keys.is_empty() {
+                joint.rotation_keys = top.rotation_keys.clone();
+            }
+            if joint.position_keys.is_empty() || take_other && !top.position_keys.is_empty() {
+                joint.position_keys = top.position_keys.clone();
+            }
+            if take_other && conflict {
+                joint.priority = top.priority;
+            }
+        }
+        for constraint in &other.constraints {
+            if !self.constraints.contains(constraint) {
+                self.constraints.push(constraint.clone());
+            }
+        }
+        self.log_edit(
+            mark,
+            EditOperation::Merge,
+            &[
+                ("joints", &other.joints.len()),
+                ("strategy", &format_args!("{strategy:?}")),
+            ],
+        );
+        self
+    }
+
+    /// A copy of this animation with only the named joints.
+    pub fn masked<I, S>(&self, joints: I) -> Animation
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let keep: Vec<S> = joints.into_iter().collect();
+        let mut out = self.clone();
+        let mark = out.edit_mark();
+        out.joints
+            .retain(|j| keep.iter().any(|name| name.as_ref() == j.name));
+        let names: Vec<&str> = keep.iter().map(AsRef::as_ref).collect();
+        out.log_edit(mark, EditOperation::Masked, &[("joints", &names.join(","))]);
+        out
+    }
+
+    /// A copy of this animation with its motion scaled by `weight` relative to the rest pose.
+    ///
+    /// Rotations are slerped from identity and position offsets scaled toward zero, so `0.0`
+    /// gives the rest pose, `1.0` the original and values above `1.0` exaggerate.
+    pub fn scaled(&self, weight: f32) -> Animation {
+        let mut out = self.clone();
+        let mark = out.edit_mark();
+        for joint in &mut out.joints {
+            for key in &mut joint.rotation_keys {
+                key.rot = Quat::IDENTITY.slerp(key.rot, weight).normalize();
+            }
+            for key in &mut joint.position_keys {
+                key.pos *= weight;
+            }
+        }
+        out.log_edit(mark, EditOperation::Scaled, &[("weight", &weight)]);
+        out
+    }
+
+    /// A priority-0 copy meant to play underneath everything else, e.g. an AO's base stand.
+    ///
+    /// The loop region (or the whole animation if it doesn't loop) becomes the entire
+    /// animation, looping from start to end, with every channel keyed at both ends so nothing
+    /// drifts at the boundaries (see [`Animation::loop_subset`]). All priorities are set to 0.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use avatar_anim::Animation;
+    ///
+    /// # fn main() -> avatar_anim::Result<()> {
+    /// let stand = Animation::from_file("stand.anim")?;
+    /// stand.to_underlay().to_file("stand_underlay.anim")?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn to_underlay(&self) -> Animation {
+        let mut out = self.clone();
+        let mark = out.edit_mark();
+        out.unlogged(|out| {
+            let header = &out.header;
+            let duration = header.duration;
+            if duration.is_finite() && duration > 0.0 {
+                let start = header.loop_in_point.max(0.0).min(duration);
+                let end = header.loop_out_point.max(start).min(duration);
+                let region = if header.looped != 0 && end > start {
+                    start..end
+                } else {
+                    0.0..duration
+                };
+                out.loop_subset(|_| true, region)
+                    .expect("region lies inside the animation");
+            } else {
+                out.header.looped = 1;
+                out.header.loop_in_point = 0.0;
+                out.header.loop_out_point = duration.max(0.0);
+            }
+            out.set_priority(0);
+        });
+        out.log_edit(mark, EditOperation::ToUnderlay, &[]);
+        out
+    }
+
+    /// `a` followed by `b`, blended over the last `overlap_seconds` of `a` and the first of
+    /// `b` into one animation, e.g. a seamless transition from a walk into a run.
+    ///
+    /// Across the overlap both clips are sampled at 30 fps and at their own keys, and each
+    /// channel is slerped (rotations) or lerped (positions) from `a` to `b` with a smoothstep
+    /// weight. A channel only one clip has keeps that clip's motion. Outside the overlap the
+    /// keys are copied, so the result lasts the two durations minus the overlap.
+    ///
+    /// The header, priorities and ease in come from `a`, the ease out from `b`, and `b`'s
+    /// constraints are appended with shifted ease times. If `b` loops its shifted loop region
+    /// becomes the loop, as in [`Animation::append`]; otherwise the result plays once. The
+    /// result keeps `a`'s edit log.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use avatar_anim::Animation;
+    ///
+    /// # fn main() -> avatar_anim::Result<()> {
+    /// let walk = Animation::from_file("walk.anim")?;
+    /// let run = Animation::from_file("run.anim")?;
+    /// Animation::crossfade(&walk, &run, 0.5)?.to_file("walk_to_run.anim")?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Fails unless both durations are positive and `overlap_seconds` is between zero and the
+    /// shorter of them.
+    pub fn crossfade(a: &Animation, b: &Animation, overlap_seconds: f32) -> Result<Animation> {
+        let (first, second) = (a.header.duration, b.header.duration);
+        let positive = |d: f32| d.is_finite() && d > 0.0;
+        if !(positive(first) && positive(second)) {
+            return Err(AnimError::InvalidStructure(format!(
+                "Cannot crossfade a {first}s animation into a {second}s one"
+            )));
+        }
+        if !(overlap_seconds >= 0.0 && overlap_seconds <= first.min(second)) {
+            return Err(AnimError::InvalidStructure(format!(
+                "Crossfade overlap of {overlap_seconds}s does not fit {first}s and {second}s animations"
+            )));
+        }
+        let start = first - overlap_seconds;
+        let fade = Fade {
+            first,
+            second,
+            start,
+            overlap: overlap_seconds,
+            duration: start + second,
+        };
+
+        let mut out = a.clone();
+        let mark = out.edit_mark();
+        let empty = JointData {
+            name: String::new(),
+            priority: 0,
+            rotation_keys: Vec::new(),
+            position_keys: Vec::new(),
+        };
+        let names = a.joints.iter().map(|j| &j.name).chain(
+            b.joints
+                .iter()
+                .map(|j| &j.name)
+                .filter(|name| a.joint(name).is_none()),
+        );
+        out.joints = names
+            .map(|name| {
+                let from = a.joint(name).unwrap_or(&empty);
+                let to = b.joint(name).unwrap_or(&empty);
+                JointData {
+                    name: name.clone(),
+                    priority: a.joint(name).unwrap_or(to).priority,
+                    rotation_keys: fade.channel(
+                        &from.rotation_keys,
+                        &to.rotation_keys,
+                        |time, rot| RotationKey { time, rot },
+                    ),
+                    position_keys: fade.channel(
+                        &from.position_keys,
+                        &to.position_keys,
+                        |time, pos| PositionKey { time, pos },
+                    ),
+                }
+            })
+            .collect();
+        out.constraints
+            .extend(b.constraints.iter().map(|constraint| Constraint {
+                ease_in_start: constraint.ease_in_start + start,
+                ease_in_stop: constraint.ease_in_stop + start,
+                ease_out_start: constraint.ease_out_start + start,
+                ease_out_stop: constraint.ease_out_stop + start,
+                ..constraint.clone()
+            }));
+
+        let duration = fade.duration;
+        let header = &mut out.header;
+        header.duration = duration;
+        if b.header.looped != 0 {
+            header.looped = 1;
+            header.loop_in_point = (start + b.header.loop_in_point).clamp(0.0, duration);
+            header.loop_out_point = (start + b.header.loop_out_point).clamp(0.0, duration);
+        } else {
+            header.looped = 0;
+            header.loop_in_point = 0.0;
+            header.loop_out_point = duration;
+        }
+        header.ease_out_duration = b.header.ease_out_duration;
+        out.log_edit(
+            mark,
+            EditOperation::Crossfade,
+            &[("duration", &second), ("overlap_seconds", &overlap_seconds)],
+        );
+        Ok(out)
+    }
+}
@@ -0,0 +1,74 @@
+//! The writing half of the conversion matrix; reading is [`crate::open_as`].
+
+use crate::open::unsupported;
+use crate::{Animation, Result, SourceFormat};
+
+impl Animation {
+    /// Encode in `format`, as [`crate::open_as`] reads it back (except for the export-only
+    /// COLLADA).
+    ///
+    /// BVH, glTF and COLLADA are baked onto [`Skeleton::second_life`](crate::Skeleton::second_life)
+    /// with default export options; pose formats hold the final frame only (see
+    /// [`Animation::to_llsd`]).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use avatar_anim::{Animation, SourceFormat, open_as};
+    ///
+    /// # fn main() -> avatar_anim::Result<()> {
+    /// let anim = Animation::new();
+    /// let bytes = anim.to_format(SourceFormat::Anim)?;
+    /// assert_eq!(open_as(&bytes, SourceFormat::Anim)?, anim);
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Fails if the format's feature is disabled or its encoder rejects the animation.
+    pub fn to_format(&self, format: SourceFormat) -> Result<Vec<u8>> {
+        match format {
+            SourceFormat::Anim => self.to_bytes(),
+            #[cfg(feature = "llsd")]
+            SourceFormat::LlsdXml => llsd_rs::xml::to_pretty_string(&self.to_llsd())
+                .map(String::into_bytes)
+                .map_err(|e| crate::AnimError::Llsd(e.to_string())),
+            #[cfg(feature = "llsd")]
+            SourceFormat::LlsdNotation => {
+                let context = llsd_rs::notation::FormatterContext::new();
+                llsd_rs::notation::to_vec(&self.to_llsd(), &context).map_err(crate::AnimError::Io)
+            }
+            #[cfg(feature = "llsd")]
+            SourceFormat::LlsdBinary => self.to_llsd_binary(),
+            #[cfg(feature = "bvh")]
+            SourceFormat::Bvh => {
+                let mut bytes = Vec::new();
+                self.to_bvh_writer(
+                    &mut bytes,
+                    &crate::Skeleton::second_life(),
+                    &Default::default(),
+                )?;
+                Ok(bytes)
+            }
+            #[cfg(feature = "gltf")]
+            SourceFormat::Gltf => self
+                .to_gltf(&crate::Skeleton::second_life())
+                .map(String::into_bytes),
+            #[cfg(feature = "serde")]
+            SourceFormat::Json => self.to_json().map(String::into_bytes),
+            #[cfg(feature = "collada")]
+            SourceFormat::Collada => {
+                let mut bytes = Vec::new();
+                self.to_collada_writer(
+                    &mut bytes,
+                    &crate::Skeleton::second_life(),
+                    &Default::default(),
+                )?;
+                Ok(bytes)
+            }
+            #[allow(unreachable_patterns)]
+            other => Err(unsupported(other, "output")),
+        }
+    }
+}
@@ -0,0 +1,352 @@
+//! Library-wide statistics for a periodic health overview of an animation catalog.
+//!
+//! [`LibraryStats`] is built up one animation at a time with [`LibraryStats::add`], so only
+//! the running totals are kept however large the catalog is. [`LibraryStats::from_dir`] walks
+//! a directory tree of `.anim` files, and [`LibraryStats::from_dir_with_cache`] reuses an
+//! [`AnimCache`] so periodic rescans skip unchanged files; the totals render as text, JSON
+//! or a standalone HTML page.
+//!
+//! ```rust,no_run
+//! use avatar_anim::dashboard::LibraryStats;
+//!
+//! # fn main() -> avatar_anim::Result<()> {
+//! let stats = LibraryStats::from_dir("anims/")?;
+//! println!("{}", stats.to_text(10));
+//! std::fs::write("dashboard.html", stats.to_html(10))?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::cache::AnimCache;
+use crate::manifest::json_string;
+use crate::{AnimError, Animation, Result};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write as _;
+use std::path::Path;
+
+/// Upper bounds in seconds of the duration histogram buckets; a last bucket holds everything
+/// longer, and durations that are not numbers.
+pub const DURATION_BUCKETS: [f32; 6] = [1.0, 2.0, 5.0, 10.0, 30.0, 60.0];
+
+/// How much of a catalog animates one joint.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct JointUsage {
+    pub name: String,
+    /// Animations with the joint.
+    pub animations: usize,
+    /// Rotation and position keys of the joint across all animations.
+    pub keys: usize,
+}
+
+/// Running totals over a catalog of animations.
+#[derive(Clone, Debug, Default)]
+pub struct LibraryStats {
+    /// Animations added, not counting failures.
+    pub animations: usize,
+    /// Encoded size of the added animations.
+    pub bytes: u64,
+    /// Rotation and position keys of all added animations.
+    pub keys: usize,
+    pub looped: usize,
+    /// Number of animations per base priority.
+    pub priorities: BTreeMap<i32, usize>,
+    /// Number of animations per [`DURATION_BUCKETS`] bucket, plus one for longer ones.
+    pub durations: [usize; DURATION_BUCKETS.len() + 1],
+    /// Files that could not be read, with the reason.
+    pub failures: Vec<(String, String)>,
+    joints: HashMap<String, JointUsage>,
+    fingerprints: HashMap<u64, Vec<String>>,
+}
+
+fn duration_bucket(duration: f32) -> usize {
+    if duration.is_nan() {
+        return DURATION_BUCKETS.len();
+    }
+    DURATION_BUCKETS.partition_point(|&bound| bound <= duration)
+}
+
+/// Label of duration bucket `i`, e.g. `"2-5s"`.
+fn bucket_label(i: usize) -> String {
+    match i {
+        0 => format!("<{}s", DURATION_BUCKETS[0]),
+        i if i == DURATION_BUCKETS.len() => format!(">={}s", DURATION_BUCKETS[i - 1]),
+        i => format!("{}-{}s", DURATION_BUCKETS[i - 1], DURATION_BUCKETS[i]),
+    }
+}
+
+fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+impl LibraryStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Statistics of the `.anim` files in `dir` and its subdirectories, named by their path
+    /// relative to `dir`. Files that fail to read are recorded in
+    /// [`LibraryStats::failures`] rather than failing the whole scan.
+    ///
+    /// Every file is parsed; use [`LibraryStats::from_dir_with_cache`] to rescan a catalog
+    /// periodically.
+    ///
+    /// # Errors
+    ///
+    /// Fails with [`AnimError::Io`] if a directory can't be listed.
+    pub fn from_dir<P: AsRef<Path>>(dir: P) -> Result<Self> {
+        Self::from_dir_with_cache(dir, &AnimCache::new(0))
+    }
+
+    /// Like [`LibraryStats::from_dir`], but files are opened through `cache`, so a rescan with
+    /// the same cache only parses the files that changed since. The totals are still
+    /// recomputed over the whole catalog.
+    ///
+    /// # Errors
+    ///
+    /// Fails with [`AnimError::Io`] if a directory can't be listed.
+    pub fn from_dir_with_cache<P: AsRef<Path>>(dir: P, cache: &AnimCache) -> Result<Self> {
+        let dir = dir.as_ref();
+        let files = crate::cache::files_below(dir, |path| {
+            path.extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("anim"))
+        })?;
+        let mut stats = Self::new();
+        for path in files {
+            let name = path
+                .strip_prefix(dir)
+                .unwrap_or(&path)
+                .to_string_lossy()
+                .into_owned();
+            let read = std::fs::metadata(&path)
+                .map_err(AnimError::Io)
+                .and_then(|meta| Ok((meta.len(), cache.open(&path)?)));
+            match read {
+                Ok((size, anim)) => stats.add(name, size, &anim),
+                Err(error) => stats.add_failure(name, &error),
+            }
+        }
+        Ok(stats)
+    }
+
+    /// Count `anim`, stored as `size` bytes, under `name`.
+    pub fn add(&mut self, name: impl Into<String>, size: u64, anim: &Animation) {
+        let header = &anim.header;
+        self.animations += 1;
+        self.bytes += size;
+        self.looped += usize::from(header.looped != 0);
+        *self.priorities.entry(header.base_priority).or_default() += 1;
+        self.durations[duration_bucket(header.duration)] += 1;
+        for joint in &anim.joints {
+            let keys = joint.rotation_keys.len() + joint.position_keys.len();
+            self.keys += keys;
+            let usage = self
+                .joints
+                .entry(joint.name.clone())
+                .or_insert_with(|| JointUsage {
+                    name: joint.name.clone(),
+                    ..Default::default()
+                });
+            usage.animations += 1;
+            usage.keys += keys;
+        }
+        self.fingerprints
+            .entry(anim.fingerprint())
+            .or_default()
+            .push(name.into());
+    }
+
+    /// Record that `name` could not be read.
+    pub fn add_failure(&mut self, name: impl Into<String>, error: &AnimError) {
+        self.failures.push((name.into(), error.to_string()));
+    }
+
+    /// The `limit` joints with the most keys across the catalog, most first.
+    pub fn most_keyed_joints(&self, limit: usize) -> Vec<JointUsage> {
+        let mut joints: Vec<JointUsage> = self.joints.values().cloned().collect();
+        joints.sort_by(|a, b| b.keys.cmp(&a.keys).then_with(|| a.name.cmp(&b.name)));
+        joints.truncate(limit);
+        joints
+    }
+
+    /// Groups of names added with the same [`Animation::fingerprint`], i.e. identical
+    /// content, sorted by their first name.
+    pub fn duplicates(&self) -> Vec<Vec<String>> {
+        let mut groups: Vec<Vec<String>> = self
+            .fingerprints
+            .values()
+            .filter(|names| names.len() > 1)
+            .cloned()
+            .collect();
+        groups.sort();
+        groups
+    }
+
+    /// A plain-text report listing the `top` most keyed joints.
+    pub fn to_text(&self, top: usize) -> String {
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "Animations: {} ({} bytes, {} keys), {} looped, {} unreadable",
+            self.animations,
+            self.bytes,
+            self.keys,
+            self.looped,
+            self.failures.len()
+        );
+        out.push_str("Priorities:\n");
+        for (priority, count) in &self.priorities {
+            let _ = writeln!(out, "  {priority:>3}: {count}");
+        }
+        out.push_str("Durations:\n");
+        let widest = self.durations.iter().copied().max().unwrap_or(0).max(1);
+        for (i, count) in self.durations.iter().enumerate() {
+            let bar = "#".repeat((count * 40).div_ceil(widest));
+            let _ = writeln!(out, "  {:>8}: {count:>5} {bar}", bucket_label(i));
+        }
+        out.push_str("Most keyed joints:\n");
+        for joint in self.most_keyed_joints(top) {
+            let _ = writeln!(
+                out,
+                "  {}: {} keys in {} animations",
+                joint.name, joint.keys, joint.animations
+            );
+        }
+        let duplicates = self.duplicates();
+        let _ = writeln!(out, "Duplicates: {}", duplicates.len());
+        for group in &duplicates {
+            let _ = writeln!(out, "  {}", group.join(", "));
+        }
+        for (name, error) in &self.failures {
+            let _ = writeln!(out, "Unreadable: {name}: {error}");
+        }
+        out
+    }
+
+    /// A JSON object with the totals, `priorities` and `durations` as objects of counts, the
+    /// `top` most keyed joints, `duplicates` as arrays of names and `failures`.
+    pub fn to_json(&self, top: usize) -> String {
+        let mut out = String::from("{\n");
+        let _ = writeln!(
+            out,
+            "  \"animations\": {}, \"bytes\": {}, \"keys\": {}, \"looped\": {},",
+            self.animations, self.bytes, self.keys, self.looped
+        );
+        out.push_str("  \"priorities\": {");
+        for (i, (priority, count)) in self.priorities.iter().enumerate() {
+            let separator = if i == 0 { "" } else { ", " };
+            let _ = write!(out, "{separator}\"{priority}\": {count}");
+        }
+        out.push_str("},\n  \"durations\": {");
+        for (i, count) in self.durations.iter().enumerate() {
+            let separator = if i == 0 { "" } else { ", " };
+            out.push_str(separator);
+            json_string(&mut out, &bucket_label(i));
+            let _ = write!(out, ": {count}");
+        }
+        out.push_str("},\n  \"most_keyed_joints\": [");
+        for (i, joint) in self.most_keyed_joints(top).iter().enumerate() {
+            out.push_str(if i == 0 { "" } else { ", " });
+            out.push_str("{\"name\": ");
+            json_string(&mut out, &joint.name);
+            let _ = write!(
+                out,
+                ", \"keys\": {}, \"animations\": {}}}",
+                joint.keys, joint.animations
+            );
+        }
+        out.push_str("],\n  \"duplicates\": [");
+        for (i, group) in self.duplicates().iter().enumerate() {
+            out.push_str(if i == 0 { "[" } else { ", [" });
+            for (j, name) in group.iter().enumerate() {
+                if j > 0 {
+                    out.push_str(", ");
+                }
+                json_string(&mut out, name);
+            }
+            out.push(']');
+        }
+        out.push_str("],\n  \"failures\": [");
+        for (i, (name, error)) in self.failures.iter().enumerate() {
+            out.push_str(if i == 0 { "" } else { ", " });
+            out.push_str("{\"name\": ");
+            json_string(&mut out, name);
+            out.push_str(", \"error\": ");
+            json_string(&mut out, error);
+            out.push('}');
+        }
+        out.push_str("]\n}\n");
+        out
+    }
+
+    /// A standalone HTML page with the same content as [`LibraryStats::to_text`], the
+    /// histograms drawn as bars.
+    pub fn to_html(&self, top: usize) -> String {
+        let mut out = String::from(
+            "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>Animation library</title>\n\
+             <style>body{font-family:sans-serif}td{padding:0 .5em}\
+             .bar{background:#4a7;height:1em}</style></head><body>\n",
+        );
+        let _ = writeln!(
+            out,
+            "<h1>Animation library</h1>\n<p>{} animations, {} bytes, {} keys, {} looped, {} unreadable</p>",
+            self.animations,
+            self.bytes,
+            self.keys,
+            self.looped,
+            self.failures.len()
+        );
+        let bars = |out: &mut String, title: &str, rows: &[(String, usize)]| {
+            let widest = rows.iter().map(|row| row.1).max().unwrap_or(0).max(1);
+            let _ = writeln!(out, "<h2>{title}</h2>\n<table>");
+            for (label, count) in rows {
+                let _ = writeln!(
+                    out,
+                    "<tr><td>{}</td><td>{count}</td><td><div class=\"bar\" style=\"width:{}px\"></div></td></tr>",
+                    html_escape(label),
+                    count * 300 / widest
+                );
+            }
+            out.push_str("</table>\n");
+        };
+        let priorities: Vec<_> = self
+            .priorities
+            .iter()
+            .map(|(priority, &count)| (priority.to_string(), count))
+            .collect();
+        bars(&mut out, "Priorities", &priorities);
+        let durations: Vec<_> = (0..self.durations.len())
+            .map(|i| (bucket_label(i), self.durations[i]))
+            .collect();
+        bars(&mut out, "Durations", &durations);
+        let joints: Vec<_> = self
+            .most_keyed_joints(top)
+            .into_iter()
+            .map(|joint| (joint.name, joint.keys))
+            .collect();
+        bars(&mut out, "Most keyed joints", &joints);
+        let duplicates = self.duplicates();
+        let _ = writeln!(out, "<h2>Duplicates ({})</h2>\n<ul>", duplicates.len());
+        for group in &duplicates {
+            let names: Vec<String> = group.iter().map(|name| html_escape(name)).collect();
+            let _ = writeln!(out, "<li>{}</li>", names.join(", "));
+        }
+        out.push_str("</ul>\n");
+        if !self.failures.is_empty() {
+            out.push_str("<h2>Unreadable</h2>\n<ul>\n");
+            for (name, error) in &self.failures {
+                let _ = writeln!(
+                    out,
+                    "<li>{}: {}</li>",
+                    html_escape(name),
+                    html_escape(error)
+                );
+            }
+            out.push_str("</ul>\n");
+        }
+        out.push_str("</body></html>\n");
+        out
+    }
+}
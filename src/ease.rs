@@ -0,0 +1,227 @@
+use crate::io::u16_to_time;
+use crate::math::geodesic_distance;
+use crate::{Animation, EditOperation, JointData};
+
+/// Rotations closer than this (radians) count as the same pose.
+const STILL_ROTATION: f32 = 1e-3;
+/// Positions closer than this (meters) count as the same pose.
+const STILL_POSITION: f32 = 1e-4;
+
+/// A header whose ease windows play differently than they read, found by
+/// [`Animation::check_ease`]. Times are in seconds.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum EaseProblem {
+    /// A non-looped animation's ease in and ease out together are longer than it, so it never
+    /// plays at full strength. Looped animations ease in and out when started and stopped,
+    /// however long they run, so this doesn't apply to them.
+    Overlap {
+        ease_in: f32,
+        ease_out: f32,
+        duration: f32,
+    },
+    /// A looped animation with an intro before its loop in point is still easing in when the
+    /// first cycle reaches the loop out point, so neither the intro nor the first pass through
+    /// the loop plays at full strength.
+    SwallowsLoop { ease_in: f32, loop_out: f32 },
+    /// A non-looped animation starts easing out before its last motion, fading out movement
+    /// that should be seen instead of holding the final pose.
+    CutsMotion {
+        ease_out_start: f32,
+        motion_end: f32,
+    },
+}
+
+impl std::fmt::Display for EaseProblem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EaseProblem::Overlap {
+                ease_in,
+                ease_out,
+                duration,
+            } => write!(
+                f,
+                "ease in {ease_in:.3}s and ease out {ease_out:.3}s overlap within the {duration:.3}s duration; the animation never reaches full strength"
+            ),
+            EaseProblem::SwallowsLoop { ease_in, loop_out } => write!(
+                f,
+                "ease in {ease_in:.3}s runs past the loop out point {loop_out:.3}s; the first cycle plays blended"
+            ),
+            EaseProblem::CutsMotion {
+                ease_out_start,
+                motion_end,
+            } => write!(
+                f,
+                "ease out starts at {ease_out_start:.3}s, before the motion ends at {motion_end:.3}s; the end of the movement fades out"
+            ),
+        }
+    }
+}
+
+/// What [`Animation::fix_ease_windows`] changed. Windows are `(ease_in, ease_out)` in
+/// seconds.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EaseFix {
+    pub before: (f32, f32),
+    pub after: (f32, f32),
+    /// The problems found before fixing.
+    pub problems: Vec<EaseProblem>,
+}
+
+impl EaseFix {
+    /// Whether either window changed.
+    pub fn changed(&self) -> bool {
+        self.before != self.after
+    }
+}
+
+/// Latest time in seconds at which a track of `joint` still moves, i.e. of the last key that
+/// differs from the key before it.
+fn motion_end(joint: &JointData, duration: f32) -> Option<f32> {
+    let mut rotations: Vec<_> = joint.rotation_keys.iter().collect();
+    rotations.sort_by_key(|key| key.time);
+    let rotation = rotations
+        .windows(2)
+        .filter(|pair| geodesic_distance(pair[0].rot, pair[1].rot) > STILL_ROTATION)
+        .map(|pair| pair[1].time)
+        .max();
+    let mut positions: Vec<_> = joint.position_keys.iter().collect();
+    positions.sort_by_key(|key| key.time);
+    let position = positions
+        .windows(2)
+        .filter(|pair| pair[0].pos.distance(pair[1].pos) > STILL_POSITION)
+        .map(|pair| pair[1].time)
+        .max();
+    rotation
+        .max(position)
+        .map(|tick| u16_to_time(tick, duration))
+}
+
+impl Animation {
+    /// Time in seconds at which the last joint stops moving, `None` for a still animation.
+    fn motion_end(&self) -> Option<f32> {
+        self.joints
+            .iter()
+            .filter_map(|joint| motion_end(joint, self.header.duration))
+            .max_by(f32::total_cmp)
+    }
+
+    /// Ease windows that will play wrong: see [`EaseProblem`].
+    ///
+    /// Windows that are negative or not numbers are reported by [`Animation::validate`]
+    /// instead and skipped here.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use avatar_anim::{Animation, EaseProblem};
+    ///
+    /// let mut anim = Animation::new();
+    /// anim.header.duration = 1.0;
+    /// anim.header.looped = 0;
+    /// anim.header.ease_in_duration = 0.8;
+    /// anim.header.ease_out_duration = 0.8;
+    /// assert!(matches!(anim.check_ease()[..], [EaseProblem::Overlap { .. }]));
+    /// ```
+    pub fn check_ease(&self) -> Vec<EaseProblem> {
+        let header = &self.header;
+        let (ease_in, ease_out, duration) = (
+            header.ease_in_duration,
+            header.ease_out_duration,
+            header.duration,
+        );
+        let usable = |t: f32| t.is_finite() && t >= 0.0;
+        if !(usable(ease_in) && usable(ease_out) && usable(duration)) {
+            return Vec::new();
+        }
+
+        let mut problems = Vec::new();
+        if header.looped != 0 {
+            let loop_out = header.loop_out_point;
+            if header.loop_in_point > 0.0 && loop_out.is_finite() && ease_in > loop_out {
+                problems.push(EaseProblem::SwallowsLoop { ease_in, loop_out });
+            }
+            return problems;
+        }
+        if ease_in + ease_out > duration {
+            problems.push(EaseProblem::Overlap {
+                ease_in,
+                ease_out,
+                duration,
+            });
+        }
+        if let Some(motion_end) = self.motion_end() {
+            let ease_out_start = duration - ease_out;
+            if ease_out > 0.0 && ease_out_start < motion_end {
+                problems.push(EaseProblem::CutsMotion {
+                    ease_out_start,
+                    motion_end,
+                });
+            }
+        }
+        problems
+    }
+
+    /// Rebalance the ease windows so none of the [`EaseProblem`]s remain.
+    ///
+    /// Negative or non-number windows become 0. Looped animations with an intro ease in no
+    /// longer than up to the loop out point. Non-looped ones ease out only over the final held
+    /// pose, and windows still longer than the duration together are then scaled down
+    /// proportionally.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use avatar_anim::Animation;
+    ///
+    /// let mut anim = Animation::new();
+    /// anim.header.duration = 1.0;
+    /// anim.header.looped = 0;
+    /// anim.header.ease_in_duration = 1.5;
+    /// anim.header.ease_out_duration = 0.5;
+    /// let fix = anim.fix_ease_windows();
+    /// assert_eq!(fix.after, (0.75, 0.25));
+    /// assert!(anim.check_ease().is_empty());
+    /// ```
+    pub fn fix_ease_windows(&mut self) -> EaseFix {
+        let mark = self.edit_mark();
+        let problems = self.check_ease();
+        let motion_end = self.motion_end();
+        let header = &mut self.header;
+        let before = (header.ease_in_duration, header.ease_out_duration);
+        let duration = if header.duration.is_finite() {
+            header.duration.max(0.0)
+        } else {
+            0.0
+        };
+        let sane = |t: f32| if t.is_finite() { t.max(0.0) } else { 0.0 };
+        let (mut ease_in, mut ease_out) = (sane(before.0), sane(before.1));
+
+        if header.looped != 0 {
+            let loop_out = header.loop_out_point;
+            if header.loop_in_point > 0.0 && loop_out.is_finite() {
+                ease_in = ease_in.min(loop_out.max(0.0));
+            }
+        } else {
+            if let Some(motion_end) = motion_end {
+                ease_out = ease_out.min((duration - motion_end).max(0.0));
+            }
+            let total = ease_in + ease_out;
+            if total > duration {
+                let scale = duration / total;
+                ease_in *= scale;
+                ease_out *= scale;
+            }
+        }
+
+        header.ease_in_duration = ease_in;
+        header.ease_out_duration = ease_out;
+        if let Some(entry) = self.log_edit(mark, EditOperation::FixEaseWindows, &[]) {
+            entry.warnings = problems.iter().map(ToString::to_string).collect();
+        }
+        EaseFix {
+            before,
+            after: (ease_in, ease_out),
+            problems,
+        }
+    }
+}
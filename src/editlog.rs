@@ -0,0 +1,314 @@
+//! Provenance of the edits applied to an animation.
+//!
+//! An [`EditLog`] attached with [`Animation::start_edit_log`] receives one [`EditEntry`] per
+//! call to the crate's editing methods (priorities, key stripping and cleanup, reduction,
+//! retiming, mirroring, retargeting, ease and loop fixes): the operation, its parameters, the
+//! joint, key and constraint counts before and after, and any warnings. Animations without a
+//! log pay nothing. Assigning to the public fields directly is not recorded.
+//!
+//! ```rust
+//! use avatar_anim::{Animation, EditOperation};
+//!
+//! let mut anim = Animation::new();
+//! anim.start_edit_log();
+//! anim.set_priority(4).drop_position_keys();
+//! let log = anim.edit_log.as_ref().unwrap();
+//! assert_eq!(log.entries[0].operation, EditOperation::SetPriority);
+//! assert_eq!(log.entries[0].parameters, [("priority".to_string(), "4".to_string())]);
+//! println!("{log}");
+//! ```
+
+use crate::Animation;
+use crate::manifest::json_string;
+use std::fmt::{Display, Write};
+
+/// Size of an animation at one point of an [`EditLog`].
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct EditCounts {
+    pub joints: usize,
+    pub rotation_keys: usize,
+    pub position_keys: usize,
+    pub constraints: usize,
+}
+
+impl EditCounts {
+    pub fn of(anim: &Animation) -> Self {
+        Self {
+            joints: anim.joints.len(),
+            rotation_keys: anim.joints.iter().map(|j| j.rotation_keys.len()).sum(),
+            position_keys: anim.joints.iter().map(|j| j.position_keys.len()).sum(),
+            constraints: anim.constraints.len(),
+        }
+    }
+}
+
+/// The editing method behind an [`EditEntry`]. [`EditOperation::name`] is the method's name,
+/// as shown by [`EditLog`]'s [`Display`] and [`EditLog::to_json`].
+#[non_exhaustive]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum EditOperation {
+    Append,
+    ApplyLoopSuggestion,
+    CleanupKeysWith,
+    Crossfade,
+    DropEmptyJoints,
+    DropJoints,
+    DropPosition,
+    DropPositionKeys,
+    DropRotation,
+    DropRotationKeys,
+    DropSubtree,
+    EnforceKeyBudget,
+    FixEaseWindows,
+    FixLoopPoints,
+    Insert,
+    KeepSubtree,
+    LodVariants,
+    LoopSubset,
+    Masked,
+    Merge,
+    Mirror,
+    MirrorJoint,
+    Overlay,
+    PruneRedundantKeys,
+    RecomputeDuration,
+    ReduceKeysWith,
+    RemoveRootYawDrift,
+    Resample,
+    RetainChain,
+    Retarget,
+    Reverse,
+    ScalePositions,
+    Scaled,
+    SetJointPriority,
+    SetPriority,
+    SyncToBeats,
+    TimeWarp,
+    ToUnderlay,
+    VisitKeysMut,
+}
+
+impl EditOperation {
+    /// Stable snake_case name, e.g. `"reduce_keys_with"`.
+    pub fn name(self) -> &'static str {
+        match self {
+            EditOperation::Append => "append",
+            EditOperation::ApplyLoopSuggestion => "apply_loop_suggestion",
+            EditOperation::CleanupKeysWith => "cleanup_keys_with",
+            EditOperation::Crossfade => "crossfade",
+            EditOperation::DropEmptyJoints => "drop_empty_joints",
+            EditOperation::DropJoints => "drop_joints",
+            EditOperation::DropPosition => "drop_position",
+            EditOperation::DropPositionKeys => "drop_position_keys",
+            EditOperation::DropRotation => "drop_rotation",
+            EditOperation::DropRotationKeys => "drop_rotation_keys",
+            EditOperation::DropSubtree => "drop_subtree",
+            EditOperation::EnforceKeyBudget => "enforce_key_budget",
+            EditOperation::FixEaseWindows => "fix_ease_windows",
+            EditOperation::FixLoopPoints => "fix_loop_points",
+            EditOperation::Insert => "insert",
+            EditOperation::KeepSubtree => "keep_subtree",
+            EditOperation::LodVariants => "lod_variants",
+            EditOperation::LoopSubset => "loop_subset",
+            EditOperation::Masked => "masked",
+            EditOperation::Merge => "merge",
+            EditOperation::Mirror => "mirror",
+            EditOperation::MirrorJoint => "mirror_joint",
+            EditOperation::Overlay => "overlay",
+            EditOperation::PruneRedundantKeys => "prune_redundant_keys",
+            EditOperation::RecomputeDuration => "recompute_duration",
+            EditOperation::ReduceKeysWith => "reduce_keys_with",
+            EditOperation::RemoveRootYawDrift => "remove_root_yaw_drift",
+            EditOperation::Resample => "resample",
+            EditOperation::RetainChain => "retain_chain",
+            EditOperation::Retarget => "retarget",
+            EditOperation::Reverse => "reverse",
+            EditOperation::ScalePositions => "scale_positions",
+            EditOperation::Scaled => "scaled",
+            EditOperation::SetJointPriority => "set_joint_priority",
+            EditOperation::SetPriority => "set_priority",
+            EditOperation::SyncToBeats => "sync_to_beats",
+            EditOperation::TimeWarp => "time_warp",
+            EditOperation::ToUnderlay => "to_underlay",
+            EditOperation::VisitKeysMut => "visit_keys_mut",
+        }
+    }
+}
+
+impl Display for EditOperation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// One operation recorded in an [`EditLog`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct EditEntry {
+    /// The method that made the edit.
+    pub operation: EditOperation,
+    /// Arguments as `(name, value)`, formatted for reading.
+    pub parameters: Vec<(String, String)>,
+    pub before: EditCounts,
+    pub after: EditCounts,
+    /// Things the operation adjusted or could not do, e.g. clamped constraint windows.
+    pub warnings: Vec<String>,
+}
+
+impl Display for EditEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.operation.name())?;
+        for (i, (name, value)) in self.parameters.iter().enumerate() {
+            let separator = if i == 0 { " " } else { ", " };
+            write!(f, "{separator}{name}={value}")?;
+        }
+        let (before, after) = (&self.before, &self.after);
+        write!(
+            f,
+            ": joints {} -> {}, keys {} -> {}",
+            before.joints,
+            after.joints,
+            before.rotation_keys + before.position_keys,
+            after.rotation_keys + after.position_keys
+        )?;
+        if before.constraints != after.constraints {
+            write!(
+                f,
+                ", constraints {} -> {}",
+                before.constraints, after.constraints
+            )?;
+        }
+        for warning in &self.warnings {
+            write!(f, "\n  warning: {warning}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Ordered record of the edits applied to an animation. [`Display`] prints one line per
+/// entry; [`EditLog::to_json`] exports it.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct EditLog {
+    pub entries: Vec<EditEntry>,
+}
+
+impl EditLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Render the entries as a JSON array of objects with `operation`, `parameters` (an object
+    /// of strings), `before` and `after` counts and `warnings`.
+    pub fn to_json(&self) -> String {
+        let counts = |out: &mut String, counts: &EditCounts| {
+            let _ = write!(
+                out,
+                "{{\"joints\": {}, \"rotation_keys\": {}, \"position_keys\": {}, \"constraints\": {}}}",
+                counts.joints, counts.rotation_keys, counts.position_keys, counts.constraints
+            );
+        };
+        let mut out = String::from("[\n");
+        for (i, entry) in self.entries.iter().enumerate() {
+            out.push_str("  {\"operation\": ");
+            json_string(&mut out, entry.operation.name());
+            out.push_str(", \"parameters\": {");
+            for (j, (name, value)) in entry.parameters.iter().enumerate() {
+                if j > 0 {
+                    out.push_str(", ");
+                }
+                json_string(&mut out, name);
+                out.push_str(": ");
+                json_string(&mut out, value);
+            }
+            out.push_str("}, \"before\": ");
+            counts(&mut out, &entry.before);
+            out.push_str(", \"after\": ");
+            counts(&mut out, &entry.after);
+            out.push_str(", \"warnings\": [");
+            for (j, warning) in entry.warnings.iter().enumerate() {
+                if j > 0 {
+                    out.push_str(", ");
+                }
+                json_string(&mut out, warning);
+            }
+            out.push_str("]}");
+            out.push_str(if i + 1 < self.entries.len() {
+                ",\n"
+            } else {
+                "\n"
+            });
+        }
+        out.push(']');
+        out.push('\n');
+        out
+    }
+}
+
+impl Display for EditLog {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, entry) in self.entries.iter().enumerate() {
+            writeln!(f, "{}. {entry}", i + 1)?;
+        }
+        Ok(())
+    }
+}
+
+impl Animation {
+    /// Attach an empty [`EditLog`] that the editing methods append to; an existing log is
+    /// kept.
+    pub fn start_edit_log(&mut self) -> &mut Self {
+        self.edit_log.get_or_insert_with(EditLog::new);
+        self
+    }
+
+    /// Detach and return the edit log, leaving edits unrecorded from here on.
+    pub fn take_edit_log(&mut self) -> Option<EditLog> {
+        self.edit_log.take()
+    }
+
+    /// Counts to pass to [`Animation::log_edit`] once the edit is done, `None` when no log is
+    /// attached.
+    pub(crate) fn edit_mark(&self) -> Option<EditCounts> {
+        self.edit_log.as_ref().map(|_| EditCounts::of(self))
+    }
+
+    /// Append an entry for `operation` if `mark` came from a logged animation. Parameters are
+    /// only formatted then; the entry is returned so warnings can be added.
+    pub(crate) fn log_edit(
+        &mut self,
+        mark: Option<EditCounts>,
+        operation: EditOperation,
+        parameters: &[(&str, &dyn Display)],
+    ) -> Option<&mut EditEntry> {
+        let before = mark?;
+        let after = EditCounts::of(self);
+        let log = self.edit_log.as_mut()?;
+        log.entries.push(EditEntry {
+            operation,
+            parameters: parameters
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+            before,
+            after,
+            warnings: Vec::new(),
+        });
+        log.entries.last_mut()
+    }
+
+    /// Run `edit` with the log detached, for operations built on other logged ones that
+    /// record a single entry of their own.
+    pub(crate) fn unlogged<R>(&mut self, edit: impl FnOnce(&mut Self) -> R) -> R {
+        let log = self.edit_log.take();
+        let result = edit(self);
+        self.edit_log = log;
+        result
+    }
+}
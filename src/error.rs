@@ -0,0 +1,243 @@
+use crate::limits::Limit;
+use crate::validate::{Issue, Severity};
+use binrw::error::BacktraceFrame;
+use std::string::FromUtf8Error;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum AnimError {
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Binary parsing error: {0}")]
+    BinRw(#[from] binrw::Error),
+    #[error("Invalid structure: {0}")]
+    InvalidStructure(String),
+    #[error("LLSD parse error: {0}")]
+    Llsd(String),
+    #[error("Unexpected LLSD layout: {0}")]
+    LlsdShape(String),
+    #[error("Read limit exceeded: {limit} {found} is over the maximum of {max}")]
+    LimitExceeded {
+        limit: Limit,
+        max: usize,
+        found: usize,
+    },
+    #[error("{count} trailing bytes after the constraint block")]
+    TrailingBytes { count: usize },
+    #[error(
+        "Failed to decode {field} at byte {offset}{}: {source}",
+        joint_context(*.joint, .joint_name.as_deref())
+    )]
+    Decode {
+        /// Absolute offset of the field that failed.
+        offset: usize,
+        /// Index of the joint being decoded, inside the joint table.
+        joint: Option<usize>,
+        /// Name of that joint, once it was read.
+        joint_name: Option<String>,
+        /// What was being decoded, e.g. `"rotation keys"`.
+        field: &'static str,
+        source: Box<AnimError>,
+    },
+    #[error("Refusing to write: {}", blockers(.issues))]
+    ValidationFailed {
+        /// Every issue found, blockers first.
+        issues: Vec<Issue>,
+    },
+}
+
+fn blockers(issues: &[Issue]) -> String {
+    let messages: Vec<&str> = issues
+        .iter()
+        .filter(|issue| issue.severity == Severity::Blocker)
+        .map(|issue| issue.message.as_str())
+        .collect();
+    messages.join("; ")
+}
+
+fn joint_context(joint: Option<usize>, name: Option<&str>) -> String {
+    match (joint, name) {
+        (Some(index), Some(name)) => format!(" in joint {index} ({name})"),
+        (Some(index), None) => format!(" in joint {index}"),
+        _ => String::new(),
+    }
+}
+
+/// Stable category of an [`AnimError`], for mapping errors to user-facing messages.
+///
+/// Codes are never renamed or repurposed; new codes may be added in minor releases, so
+/// matches should keep a wildcard arm.
+#[non_exhaustive]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum ErrorCode {
+    /// A file could not be opened or created (missing, permission denied).
+    IoOpen,
+    /// Any other I/O failure.
+    Io,
+    /// The data ended inside the animation header.
+    TruncatedHeader,
+    /// The data ended inside the joint table.
+    TruncatedJointTable,
+    /// The data ended inside the constraint table.
+    TruncatedConstraints,
+    /// A declared element count is negative or implausibly large.
+    CountOverflow,
+    /// A joint name, emote name or volume name is not valid UTF-8.
+    InvalidUtf8Name,
+    /// Any other malformed binary data.
+    MalformedBinary,
+    /// The data is well-formed but structurally inconsistent.
+    InvalidStructure,
+    /// LLSD input could not be parsed.
+    LlsdParse,
+    /// LLSD parsed, but not into the layout a pose file has.
+    LlsdShape,
+    /// The data exceeds a [`ReadOptions`](crate::ReadOptions) limit.
+    LimitExceeded,
+    /// Bytes follow the constraint block and the read was strict.
+    TrailingBytes,
+    /// A strict write found upload blockers.
+    ValidationFailed,
+}
+
+impl ErrorCode {
+    /// Stable snake_case identifier, e.g. `"truncated_joint_table"`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ErrorCode::IoOpen => "io_open",
+            ErrorCode::Io => "io",
+            ErrorCode::TruncatedHeader => "truncated_header",
+            ErrorCode::TruncatedJointTable => "truncated_joint_table",
+            ErrorCode::TruncatedConstraints => "truncated_constraints",
+            ErrorCode::CountOverflow => "count_overflow",
+            ErrorCode::InvalidUtf8Name => "invalid_utf8_name",
+            ErrorCode::MalformedBinary => "malformed_binary",
+            ErrorCode::InvalidStructure => "invalid_structure",
+            ErrorCode::LlsdParse => "llsd_parse",
+            ErrorCode::LlsdShape => "llsd_shape",
+            ErrorCode::LimitExceeded => "limit_exceeded",
+            ErrorCode::TrailingBytes => "trailing_bytes",
+            ErrorCode::ValidationFailed => "validation_failed",
+        }
+    }
+}
+
+impl std::fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+fn io_code(err: &std::io::Error) -> ErrorCode {
+    use std::io::ErrorKind;
+    match err.kind() {
+        ErrorKind::NotFound | ErrorKind::PermissionDenied | ErrorKind::IsADirectory => {
+            ErrorCode::IoOpen
+        }
+        _ => ErrorCode::Io,
+    }
+}
+
+/// Top-level table of an `.anim` file, attached to binrw errors as `err_context` so
+/// [`AnimError::code`] can tell where a read stopped.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub(crate) enum Section {
+    JointTable,
+    Constraints,
+}
+
+impl std::fmt::Display for Section {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Section::JointTable => "While parsing the joint table",
+            Section::Constraints => "While parsing the constraint table",
+        })
+    }
+}
+
+/// A key or constraint count below zero.
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct NegativeCount(pub(crate) i32);
+
+impl std::fmt::Display for NegativeCount {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "count {} is negative", self.0)
+    }
+}
+
+/// A null-terminated name that is not valid UTF-8.
+#[derive(Debug)]
+pub(crate) struct InvalidName(pub(crate) FromUtf8Error);
+
+impl std::fmt::Display for InvalidName {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Invalid UTF-8 in null-terminated string: {}", self.0)
+    }
+}
+
+/// Which top-level section of an `.anim` file the binrw backtrace points into.
+fn truncated_section(err: &binrw::Error) -> ErrorCode {
+    let binrw::Error::Backtrace(bt) = err else {
+        return ErrorCode::TruncatedHeader;
+    };
+    let section = bt.frames.iter().find_map(|frame| match frame {
+        BacktraceFrame::Custom(context) => context.downcast_ref::<Section>(),
+        _ => None,
+    });
+    match section {
+        Some(Section::JointTable) => ErrorCode::TruncatedJointTable,
+        Some(Section::Constraints) => ErrorCode::TruncatedConstraints,
+        None => ErrorCode::TruncatedHeader,
+    }
+}
+
+impl AnimError {
+    /// Stable category of this error.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use avatar_anim::{Animation, ErrorCode};
+    ///
+    /// let err = Animation::from_file("does/not/exist.anim").unwrap_err();
+    /// assert_eq!(err.code(), ErrorCode::IoOpen);
+    /// ```
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            AnimError::Io(e) => io_code(e),
+            AnimError::BinRw(e) => match e.root_cause() {
+                _ if e.is_eof() => truncated_section(e),
+                binrw::Error::Io(io) => io_code(io),
+                _ if e.custom_err::<InvalidName>().is_some() => ErrorCode::InvalidUtf8Name,
+                _ if e.custom_err::<NegativeCount>().is_some() => ErrorCode::CountOverflow,
+                _ => ErrorCode::MalformedBinary,
+            },
+            AnimError::InvalidStructure(_) => ErrorCode::InvalidStructure,
+            AnimError::Llsd(_) => ErrorCode::LlsdParse,
+            AnimError::LlsdShape(_) => ErrorCode::LlsdShape,
+            AnimError::LimitExceeded { .. } => ErrorCode::LimitExceeded,
+            AnimError::TrailingBytes { .. } => ErrorCode::TrailingBytes,
+            AnimError::Decode { source, .. } => source.code(),
+            AnimError::ValidationFailed { .. } => ErrorCode::ValidationFailed,
+        }
+    }
+
+    /// Attribute a [`AnimError::Decode`] to the joint at `index`; other errors are unchanged.
+    pub(crate) fn in_joint(self, index: usize, name: Option<&str>) -> Self {
+        match self {
+            AnimError::Decode {
+                offset,
+                field,
+                source,
+                ..
+            } => AnimError::Decode {
+                offset,
+                joint: Some(index),
+                joint_name: name.map(str::to_string),
+                field,
+                source,
+            },
+            other => other,
+        }
+    }
+}
@@ -0,0 +1,170 @@
+//! Conversions between Second Life's joint frame and other tools' conventions.
+//!
+//! SL is right-handed and Z-up with the avatar facing +X, so +Y is the avatar's left. Every
+//! [`Frame`] here is right-handed as well, so converting is a change of basis: vectors are
+//! rotated, rotations are conjugated. No handedness flips and no unit scaling happen here.
+//!
+//! | Frame                  | Up | Avatar faces | Avatar's left |
+//! |------------------------|----|--------------|---------------|
+//! | [`Frame::SecondLife`]  | +Z | +X           | +Y            |
+//! | [`Frame::BlenderZUp`]  | +Z | −Y           | +X            |
+//! | [`Frame::MayaYUp`]     | +Y | +Z           | +X            |
+//!
+//! BVH files are conventionally authored in [`Frame::MayaYUp`]; their per-joint Euler channel
+//! order is handled by [`RotationOrder`].
+
+use glam::{EulerRot, Mat3, Quat, Vec3};
+
+/// An axis convention for positions and rotations.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub enum Frame {
+    #[default]
+    SecondLife,
+    BlenderZUp,
+    MayaYUp,
+}
+
+impl Frame {
+    /// Change of basis taking SL coordinates into this frame.
+    pub fn basis_matrix(self) -> Mat3 {
+        match self {
+            Frame::SecondLife => Mat3::IDENTITY,
+            Frame::BlenderZUp => Mat3::from_cols(Vec3::NEG_Y, Vec3::X, Vec3::Z),
+            Frame::MayaYUp => Mat3::from_cols(Vec3::Z, Vec3::X, Vec3::Y),
+        }
+    }
+
+    fn basis(self) -> Quat {
+        Quat::from_mat3(&self.basis_matrix())
+    }
+
+    /// Express an SL position or direction in this frame.
+    pub fn vec_from_sl(self, v: Vec3) -> Vec3 {
+        self.basis() * v
+    }
+
+    /// Express a position or direction given in this frame in SL coordinates.
+    pub fn vec_to_sl(self, v: Vec3) -> Vec3 {
+        self.basis().conjugate() * v
+    }
+
+    /// Express an SL rotation in this frame.
+    pub fn rot_from_sl(self, q: Quat) -> Quat {
+        let b = self.basis();
+        (b * q * b.conjugate()).normalize()
+    }
+
+    /// Express a rotation given in this frame in SL coordinates.
+    pub fn rot_to_sl(self, q: Quat) -> Quat {
+        let b = self.basis();
+        (b.conjugate() * q * b).normalize()
+    }
+}
+
+/// Convert a position or direction between frames.
+pub fn convert_vec(v: Vec3, from: Frame, to: Frame) -> Vec3 {
+    to.vec_from_sl(from.vec_to_sl(v))
+}
+
+/// Convert a rotation between frames.
+///
+/// # Example
+///
+/// ```rust
+/// use avatar_anim::frame::{Frame, convert_rot};
+/// use glam::Quat;
+///
+/// // Turning about SL's up axis is turning about Maya's up axis.
+/// let yaw = Quat::from_rotation_z(0.5);
+/// let maya = convert_rot(yaw, Frame::SecondLife, Frame::MayaYUp);
+/// assert!(maya.abs_diff_eq(Quat::from_rotation_y(0.5), 1e-6));
+/// ```
+pub fn convert_rot(q: Quat, from: Frame, to: Frame) -> Quat {
+    to.rot_from_sl(from.rot_to_sl(q))
+}
+
+/// Euler channel order, as in a BVH `CHANNELS` line.
+///
+/// Angles are given in the listed order and composed intrinsically: `Zxy` with angles
+/// `[z, x, y]` is `Rz(z) * Rx(x) * Ry(y)`, which is how BVH readers interpret
+/// `Zrotation Xrotation Yrotation`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum RotationOrder {
+    Xyz,
+    Xzy,
+    Yxz,
+    Yzx,
+    Zxy,
+    Zyx,
+}
+
+impl RotationOrder {
+    /// Read the order from BVH channel names, ignoring position channels.
+    ///
+    /// Returns `None` unless exactly one each of `Xrotation`, `Yrotation` and `Zrotation`
+    /// (case-insensitive) are present.
+    pub fn from_bvh_channels<S: AsRef<str>>(channels: &[S]) -> Option<Self> {
+        let axes: Vec<char> = channels
+            .iter()
+            .filter_map(|c| {
+                let c = c.as_ref().to_ascii_lowercase();
+                let axis = c.strip_suffix("rotation")?;
+                (axis.len() == 1).then(|| axis.chars().next()).flatten()
+            })
+            .collect();
+        match axes.as_slice() {
+            ['x', 'y', 'z'] => Some(RotationOrder::Xyz),
+            ['x', 'z', 'y'] => Some(RotationOrder::Xzy),
+            ['y', 'x', 'z'] => Some(RotationOrder::Yxz),
+            ['y', 'z', 'x'] => Some(RotationOrder::Yzx),
+            ['z', 'x', 'y'] => Some(RotationOrder::Zxy),
+            ['z', 'y', 'x'] => Some(RotationOrder::Zyx),
+            _ => None,
+        }
+    }
+
+    /// BVH channel names in this order.
+    pub fn bvh_channels(self) -> [&'static str; 3] {
+        let name = |axis| match axis {
+            'X' => "Xrotation",
+            'Y' => "Yrotation",
+            _ => "Zrotation",
+        };
+        let [a, b, c] = self.axes();
+        [name(a), name(b), name(c)]
+    }
+
+    fn axes(self) -> [char; 3] {
+        match self {
+            RotationOrder::Xyz => ['X', 'Y', 'Z'],
+            RotationOrder::Xzy => ['X', 'Z', 'Y'],
+            RotationOrder::Yxz => ['Y', 'X', 'Z'],
+            RotationOrder::Yzx => ['Y', 'Z', 'X'],
+            RotationOrder::Zxy => ['Z', 'X', 'Y'],
+            RotationOrder::Zyx => ['Z', 'Y', 'X'],
+        }
+    }
+
+    fn euler_rot(self) -> EulerRot {
+        match self {
+            RotationOrder::Xyz => EulerRot::XYZ,
+            RotationOrder::Xzy => EulerRot::XZY,
+            RotationOrder::Yxz => EulerRot::YXZ,
+            RotationOrder::Yzx => EulerRot::YZX,
+            RotationOrder::Zxy => EulerRot::ZXY,
+            RotationOrder::Zyx => EulerRot::ZYX,
+        }
+    }
+
+    /// Build a rotation from angles in degrees, listed in channel order.
+    pub fn compose(self, degrees: [f32; 3]) -> Quat {
+        let [a, b, c] = degrees.map(f32::to_radians);
+        Quat::from_euler(self.euler_rot(), a, b, c).normalize()
+    }
+
+    /// Decompose a rotation into angles in degrees, listed in channel order.
+    pub fn decompose(self, q: Quat) -> [f32; 3] {
+        let (a, b, c) = q.to_euler(self.euler_rot());
+        [a, b, c].map(f32::to_degrees)
+    }
+}
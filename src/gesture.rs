@@ -0,0 +1,347 @@
+//! Second Life gesture assets and their references to animations.
+//!
+//! A gesture is a text asset the viewer plays step by step: start or stop an animation, play
+//! a sound, say something in chat, or wait. Animations are referenced by asset UUID, so a
+//! gesture silently breaks when the animation it points at is replaced or deleted.
+//! [`check`] compares a gesture against a [`Ledger`] of known animations.
+//!
+//! The format (version 2) has one value per line:
+//!
+//! ```text
+//! 2
+//! 0            key
+//! 0            mask
+//! /wave        trigger
+//!              replacement text
+//! 2            step count
+//! 0            step type (0 animation, 1 sound, 2 chat, 3 wait)
+//! Wave         animation name
+//! c541c47f-e0c0-058b-ad1a-d6ae3a4584d9
+//! 0            flags (1 = stop)
+//! 3
+//! 1.5          wait seconds
+//! 1            flags (1 = time, 2 = until animations finish)
+//! ```
+//!
+//! Trailing annotations like the ones above are not part of the format.
+
+use crate::manifest::ManifestEntry;
+use crate::{AnimError, Result};
+use std::collections::BTreeMap;
+
+/// The only gesture serialization version the viewer writes.
+pub const GESTURE_VERSION: i32 = 2;
+
+/// Animation step flag: stop the animation instead of starting it.
+pub const ANIM_FLAG_STOP: u32 = 0x01;
+/// Wait step flag: wait for the step's `seconds`.
+pub const WAIT_FLAG_TIME: u32 = 0x01;
+/// Wait step flag: wait until every animation the gesture started has finished.
+pub const WAIT_FLAG_ALL_ANIM: u32 = 0x02;
+
+/// One step of a [`Gesture`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum Step {
+    Animation {
+        name: String,
+        /// Lowercase asset UUID.
+        asset_id: String,
+        flags: u32,
+    },
+    Sound {
+        name: String,
+        /// Lowercase asset UUID.
+        asset_id: String,
+        flags: u32,
+    },
+    Chat {
+        text: String,
+        flags: u32,
+    },
+    Wait {
+        seconds: f32,
+        flags: u32,
+    },
+}
+
+/// A parsed gesture asset.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Gesture {
+    /// Shortcut key code, `0` for none.
+    pub key: u8,
+    /// Modifier mask for `key`.
+    pub mask: u32,
+    /// Chat text that triggers the gesture, e.g. `/wave`.
+    pub trigger: String,
+    /// Text said in place of the trigger.
+    pub replace_text: String,
+    pub steps: Vec<Step>,
+}
+
+fn invalid(message: impl std::fmt::Display) -> AnimError {
+    AnimError::InvalidStructure(format!("Invalid gesture: {message}"))
+}
+
+/// Whether `text` is a UUID in the usual hyphenated 8-4-4-4-12 form.
+pub(crate) fn is_uuid(text: &str) -> bool {
+    text.len() == 36
+        && text.char_indices().all(|(i, c)| match i {
+            8 | 13 | 18 | 23 => c == '-',
+            _ => c.is_ascii_hexdigit(),
+        })
+}
+
+struct Lines<'a> {
+    lines: std::str::Lines<'a>,
+    line: usize,
+}
+
+impl<'a> Lines<'a> {
+    fn text(&mut self, what: &str) -> Result<&'a str> {
+        self.line += 1;
+        let line = self
+            .lines
+            .next()
+            .ok_or_else(|| invalid(format!("ended before {what} (line {})", self.line)))?;
+        Ok(line.strip_suffix('\r').unwrap_or(line))
+    }
+
+    fn number<T: std::str::FromStr>(&mut self, what: &str) -> Result<T> {
+        let line = self.line + 1;
+        let text = self.text(what)?.trim();
+        text.parse()
+            .map_err(|_| invalid(format!("bad {what} {text:?} (line {line})")))
+    }
+
+    fn uuid(&mut self) -> Result<String> {
+        let line = self.line + 1;
+        let text = self.text("asset id")?.trim();
+        if is_uuid(text) {
+            Ok(text.to_ascii_lowercase())
+        } else {
+            Err(invalid(format!("bad asset id {text:?} (line {line})")))
+        }
+    }
+}
+
+impl Gesture {
+    /// Parse a gesture asset's text.
+    ///
+    /// # Errors
+    ///
+    /// Fails on another version than [`GESTURE_VERSION`], an unknown step type, a malformed
+    /// number or UUID, or text that ends before the declared step count.
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut lines = Lines {
+            lines: text.lines(),
+            line: 0,
+        };
+        let version: i32 = lines.number("version")?;
+        if version != GESTURE_VERSION {
+            return Err(invalid(format!("unsupported version {version}")));
+        }
+        let key = lines.number("key")?;
+        let mask = lines.number("mask")?;
+        let trigger = lines.text("trigger")?.to_string();
+        let replace_text = lines.text("replacement text")?.to_string();
+        let count: usize = lines.number("step count")?;
+        let mut steps = Vec::with_capacity(count.min(256));
+        for _ in 0..count {
+            let step = match lines.number::<i32>("step type")? {
+                0 => Step::Animation {
+                    name: lines.text("animation name")?.to_string(),
+                    asset_id: lines.uuid()?,
+                    flags: lines.number("flags")?,
+                },
+                1 => Step::Sound {
+                    name: lines.text("sound name")?.to_string(),
+                    asset_id: lines.uuid()?,
+                    flags: lines.number("flags")?,
+                },
+                2 => Step::Chat {
+                    text: lines.text("chat text")?.to_string(),
+                    flags: lines.number("flags")?,
+                },
+                3 => Step::Wait {
+                    seconds: lines.number("wait seconds")?,
+                    flags: lines.number("flags")?,
+                },
+                other => {
+                    return Err(invalid(format!(
+                        "unknown step type {other} (line {})",
+                        lines.line
+                    )));
+                }
+            };
+            steps.push(step);
+        }
+        Ok(Self {
+            key,
+            mask,
+            trigger,
+            replace_text,
+            steps,
+        })
+    }
+
+    pub fn from_file<P: AsRef<std::path::Path>>(path: P) -> Result<Self> {
+        Self::parse(&std::fs::read_to_string(path).map_err(AnimError::Io)?)
+    }
+}
+
+/// The animations of a library, by asset UUID.
+///
+/// # Example
+///
+/// ```rust
+/// use avatar_anim::Animation;
+/// use avatar_anim::gesture::{self, Gesture, Ledger};
+/// use avatar_anim::manifest::ManifestEntry;
+///
+/// # fn main() -> avatar_anim::Result<()> {
+/// let mut ledger = Ledger::new();
+/// ledger.insert(
+///     "c541c47f-e0c0-058b-ad1a-d6ae3a4584d9",
+///     ManifestEntry::new("Wave", &Animation::new()),
+/// );
+/// let gesture = Gesture::parse(
+///     "2\n0\n0\n/wave\n\n1\n0\nWave\nc541c47f-e0c0-058b-ad1a-d6ae3a4584d9\n0\n",
+/// )?;
+/// assert!(gesture::check(&gesture, &ledger).is_empty());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Ledger {
+    entries: BTreeMap<String, ManifestEntry>,
+}
+
+impl Ledger {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the animation uploaded as `asset_id`, replacing any previous entry.
+    pub fn insert(&mut self, asset_id: &str, entry: ManifestEntry) {
+        self.entries
+            .insert(asset_id.trim().to_ascii_lowercase(), entry);
+    }
+
+    /// The animation uploaded as `asset_id`, case-insensitively.
+    pub fn get(&self, asset_id: &str) -> Option<&ManifestEntry> {
+        self.entries.get(&asset_id.trim().to_ascii_lowercase())
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// A problem [`check`] found in a gesture. Step numbers index [`Gesture::steps`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum Issue {
+    /// The animation step references an asset the ledger doesn't have.
+    MissingAnimation { step: usize, asset_id: String },
+    /// The ledger knows the asset under another name, so the gesture predates a rename or
+    /// points at the wrong upload.
+    NameMismatch {
+        step: usize,
+        gesture_name: String,
+        ledger_name: String,
+    },
+    /// Animation `second` starts while `first` is still playing and both drive `joints`; the
+    /// higher priority one (or the later one, on a tie) hides the other on those joints.
+    Overlap {
+        first: usize,
+        second: usize,
+        /// Seconds both play at once; infinite when both loop and neither is stopped.
+        seconds: f32,
+        joints: Vec<String>,
+    },
+}
+
+/// Cross-check `gesture` against `ledger`.
+///
+/// Steps are played on a timeline the way the viewer does: animation, sound and chat steps
+/// take no time, [`WAIT_FLAG_TIME`] advances by the wait and [`WAIT_FLAG_ALL_ANIM`] until the
+/// started non-looping animations end. Durations come from the ledger; an animation it
+/// doesn't know is reported as missing and otherwise ignored.
+pub fn check(gesture: &Gesture, ledger: &Ledger) -> Vec<Issue> {
+    let mut issues = Vec::new();
+    // (step, asset id, end time) of the animations currently playing.
+    let mut playing: Vec<(usize, &str, f32)> = Vec::new();
+    let mut now = 0.0f32;
+    for (index, step) in gesture.steps.iter().enumerate() {
+        match step {
+            Step::Animation {
+                name,
+                asset_id,
+                flags,
+            } => {
+                if flags & ANIM_FLAG_STOP != 0 {
+                    playing.retain(|&(_, id, _)| id != asset_id);
+                    continue;
+                }
+                let Some(entry) = ledger.get(asset_id) else {
+                    issues.push(Issue::MissingAnimation {
+                        step: index,
+                        asset_id: asset_id.clone(),
+                    });
+                    continue;
+                };
+                if !entry.name.eq_ignore_ascii_case(name) {
+                    issues.push(Issue::NameMismatch {
+                        step: index,
+                        gesture_name: name.clone(),
+                        ledger_name: entry.name.clone(),
+                    });
+                }
+                // Restarting an animation replaces it rather than overlapping itself.
+                playing.retain(|&(_, id, _)| id != asset_id);
+                let length = if entry.looped {
+                    f32::INFINITY
+                } else {
+                    entry.duration.max(0.0)
+                };
+                for &(first, id, end) in &playing {
+                    let other = ledger.get(id).expect("only known animations play");
+                    let joints: Vec<String> = entry
+                        .joints
+                        .iter()
+                        .filter(|j| other.joints.contains(j))
+                        .cloned()
+                        .collect();
+                    if !joints.is_empty() {
+                        issues.push(Issue::Overlap {
+                            first,
+                            second: index,
+                            seconds: (end - now).min(length),
+                            joints,
+                        });
+                    }
+                }
+                playing.push((index, asset_id, now + length));
+            }
+            Step::Wait { seconds, flags } => {
+                if flags & WAIT_FLAG_TIME != 0 && seconds.is_finite() {
+                    now += seconds.max(0.0);
+                }
+                if flags & WAIT_FLAG_ALL_ANIM != 0 {
+                    let last = playing
+                        .iter()
+                        .map(|&(_, _, end)| end)
+                        .filter(|end| end.is_finite())
+                        .fold(now, f32::max);
+                    now = last;
+                }
+                playing.retain(|&(_, _, end)| end > now);
+            }
+            Step::Sound { .. } | Step::Chat { .. } => {}
+        }
+    }
+    issues
+}
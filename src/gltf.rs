@@ -0,0 +1,522 @@
+//! glTF 2.0 animation import and export.
+//!
+//! Rotation and translation channels of one glTF animation become joint keys. Node names are
+//! mapped to SL joints through [`GltfOptions::joint_names`]; glTF is Y-up and faces +Z, like
+//! [`Frame::MayaYUp`], and already uses meters.
+//!
+//! Rotations are taken relative to each node's rest pose, so rigs whose bones carry rest
+//! orientations (as Blender exports do) still produce SL keys. Only the node mapped to
+//! `mPelvis` keeps its translation, as an offset from its rest position.
+//!
+//! [`Animation::to_gltf`] writes the reverse: a node tree for a [`Skeleton`] with one
+//! linearly interpolated sampler per channel, for previewing in any glTF viewer.
+
+use crate::frame::Frame;
+use crate::manifest::json_string;
+use crate::skeleton::Skeleton;
+use crate::{AnimError, Animation, AnimationHeader, JointData, PositionKey, Result, RotationKey};
+use ::gltf::animation::Interpolation;
+use ::gltf::animation::util::ReadOutputs;
+use ::gltf::buffer::Source;
+use glam::{Quat, Vec3};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::path::Path;
+
+/// Options for [`Animation::from_gltf_slice`] and [`Animation::from_gltf_file`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct GltfOptions {
+    /// glTF node name to SL joint name. Nodes not listed keep their name if it already looks
+    /// like an SL joint (`mPelvis`, `mFaceJaw`, ...); anything else is skipped.
+    pub joint_names: BTreeMap<String, String>,
+    /// Index of the animation to import.
+    pub animation: usize,
+}
+
+impl GltfOptions {
+    fn sl_name<'a>(&'a self, node: &'a str) -> Option<&'a str> {
+        if let Some(name) = self.joint_names.get(node) {
+            return Some(name);
+        }
+        let mut chars = node.chars();
+        (chars.next() == Some('m') && chars.next().is_some_and(|c| c.is_ascii_uppercase()))
+            .then_some(node)
+    }
+}
+
+fn error(message: impl std::fmt::Display) -> AnimError {
+    AnimError::InvalidStructure(format!("glTF: {message}"))
+}
+
+fn decode_base64(text: &str) -> Result<Vec<u8>> {
+    let mut out = Vec::with_capacity(text.len() / 4 * 3);
+    let mut bits = 0u32;
+    let mut count = 0;
+    for c in text
+        .bytes()
+        .filter(|c| !c.is_ascii_whitespace() && *c != b'=')
+    {
+        let value = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' | b'-' => 62,
+            b'/' | b'_' => 63,
+            _ => return Err(error("invalid base64 in data URI")),
+        };
+        bits = bits << 6 | u32::from(value);
+        count += 6;
+        if count >= 8 {
+            count -= 8;
+            out.push((bits >> count) as u8);
+        }
+    }
+    Ok(out)
+}
+
+fn encode_base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let bits = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |bits, (i, &b)| bits | u32::from(b) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(bits >> (18 - 6 * i) & 63) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+fn json_floats(values: &[f32]) -> String {
+    let items: Vec<String> = values.iter().map(f32::to_string).collect();
+    format!("[{}]", items.join(","))
+}
+
+/// Binary payload and the buffer views and accessors describing it, as JSON objects.
+#[derive(Default)]
+struct Payload {
+    bin: Vec<u8>,
+    views: Vec<String>,
+    accessors: Vec<String>,
+}
+
+impl Payload {
+    /// Append float data with `width` components per element; returns the accessor index.
+    fn push(&mut self, values: &[f32], width: usize) -> usize {
+        let offset = self.bin.len();
+        self.bin.extend(values.iter().flat_map(|v| v.to_le_bytes()));
+        self.views.push(format!(
+            r#"{{"buffer":0,"byteOffset":{offset},"byteLength":{}}}"#,
+            values.len() * 4
+        ));
+        let kind = match width {
+            1 => "SCALAR",
+            3 => "VEC3",
+            _ => "VEC4",
+        };
+        let mut accessor = format!(
+            r#"{{"bufferView":{},"componentType":5126,"count":{},"type":"{kind}""#,
+            self.views.len() - 1,
+            values.len() / width
+        );
+        if width == 1 {
+            let min = values.iter().copied().fold(f32::INFINITY, f32::min);
+            let max = values.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+            let _ = write!(accessor, r#","min":[{min}],"max":[{max}]"#);
+        }
+        accessor.push('}');
+        self.accessors.push(accessor);
+        self.accessors.len() - 1
+    }
+}
+
+/// Load every buffer of the document; external files are resolved against `base`.
+fn load_buffers(gltf: &::gltf::Gltf, base: Option<&Path>) -> Result<Vec<Vec<u8>>> {
+    gltf.document
+        .buffers()
+        .map(|buffer| {
+            let data = match buffer.source() {
+                Source::Bin => gltf
+                    .blob
+                    .clone()
+                    .ok_or_else(|| error("buffer refers to a missing GLB chunk"))?,
+                Source::Uri(uri) if uri.starts_with("data:") => {
+                    let (_, payload) = uri
+                        .split_once(";base64,")
+                        .ok_or_else(|| error("only base64 data URIs are supported"))?;
+                    decode_base64(payload)?
+                }
+                Source::Uri(uri) => {
+                    let base = base.ok_or_else(|| {
+                        error(format!("external buffer '{uri}' needs a file path"))
+                    })?;
+                    std::fs::read(base.join(uri)).map_err(AnimError::Io)?
+                }
+            };
+            if data.len() < buffer.length() {
+                return Err(error(format!("buffer {} is truncated", buffer.index())));
+            }
+            Ok(data)
+        })
+        .collect()
+}
+
+fn tick(t: f32, start: f32, duration: f32) -> u16 {
+    if duration > 0.0 {
+        (((t - start) / duration).clamp(0.0, 1.0) * u16::MAX as f32).round() as u16
+    } else {
+        0
+    }
+}
+
+/// Keyframes of one channel as `(time, value)`, dropping cubic spline tangents.
+fn keyframes<T: Copy>(
+    times: &[f32],
+    values: Vec<T>,
+    interpolation: Interpolation,
+) -> Vec<(f32, T)> {
+    let values = match interpolation {
+        Interpolation::CubicSpline => values.chunks(3).filter_map(|v| v.get(1).copied()).collect(),
+        _ => values,
+    };
+    times.iter().copied().zip(values).collect()
+}
+
+/// Quantize keyframes to ticks; step interpolation is spelled out by holding each value until
+/// the tick before the next key. Of keys sharing a tick the last one wins.
+fn ticked<T: Copy>(keys: &[(f32, T)], step: bool, start: f32, duration: f32) -> Vec<(u16, T)> {
+    let mut out: Vec<(u16, T)> = Vec::with_capacity(keys.len() * 2);
+    for (i, &(t, value)) in keys.iter().enumerate() {
+        let time = tick(t, start, duration);
+        if step && i > 0 {
+            let previous = out[out.len() - 1];
+            if time > previous.0.saturating_add(1) {
+                out.push((time - 1, previous.1));
+            }
+        }
+        match out.last_mut() {
+            Some(last) if last.0 >= time => *last = (last.0, value),
+            _ => out.push((time, value)),
+        }
+    }
+    out
+}
+
+impl Animation {
+    /// Parse a `.glb` file, or a `.gltf` whose buffers are embedded as data URIs.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use avatar_anim::Animation;
+    /// use avatar_anim::gltf::GltfOptions;
+    ///
+    /// # fn main() -> avatar_anim::Result<()> {
+    /// let mut options = GltfOptions::default();
+    /// options.joint_names.insert("Hips".into(), "mPelvis".into());
+    /// options.joint_names.insert("Head".into(), "mHead".into());
+    /// let bytes = std::fs::read("nod.glb").map_err(avatar_anim::AnimError::Io)?;
+    /// Animation::from_gltf_slice(&bytes, &options)?.to_file("nod.anim")?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn from_gltf_slice(bytes: &[u8], options: &GltfOptions) -> Result<Self> {
+        let gltf = ::gltf::Gltf::from_slice(bytes).map_err(error)?;
+        let buffers = load_buffers(&gltf, None)?;
+        Self::from_gltf_document(&gltf.document, &buffers, options)
+    }
+
+    /// Load a `.glb` or `.gltf` file; external buffers are read relative to it.
+    pub fn from_gltf_file<P: AsRef<Path>>(path: P, options: &GltfOptions) -> Result<Self> {
+        let path = path.as_ref();
+        let bytes = std::fs::read(path).map_err(AnimError::Io)?;
+        let gltf = ::gltf::Gltf::from_slice(&bytes).map_err(error)?;
+        let buffers = load_buffers(&gltf, path.parent())?;
+        Self::from_gltf_document(&gltf.document, &buffers, options)
+    }
+
+    fn from_gltf_document(
+        document: &::gltf::Document,
+        buffers: &[Vec<u8>],
+        options: &GltfOptions,
+    ) -> Result<Self> {
+        let animation = document
+            .animations()
+            .nth(options.animation)
+            .ok_or_else(|| error(format!("no animation {}", options.animation)))?;
+
+        let mut parents = vec![None; document.nodes().len()];
+        for node in document.nodes() {
+            for child in node.children() {
+                parents[child.index()] = Some(node.index());
+            }
+        }
+        let rest = |index: usize| {
+            let (t, r, _) = document
+                .nodes()
+                .nth(index)
+                .unwrap()
+                .transform()
+                .decomposed();
+            (Vec3::from_array(t), Quat::from_array(r).normalize())
+        };
+        // Rest orientation of a node's parent in scene space.
+        let parent_rest = |index: usize| {
+            let mut world = Quat::IDENTITY;
+            let mut parent = parents[index];
+            // Bounded in case a malformed file has a cycle.
+            for _ in 0..parents.len() {
+                let Some(p) = parent else { break };
+                world = rest(p).1 * world;
+                parent = parents[p];
+            }
+            world
+        };
+
+        #[derive(Default)]
+        struct Channel {
+            joint: String,
+            times: Vec<f32>,
+            rotations: Vec<(f32, Quat)>,
+            positions: Vec<(f32, Vec3)>,
+            rotation_step: bool,
+            position_step: bool,
+        }
+        let mut channels: Vec<Channel> = Vec::new();
+        for channel in animation.channels() {
+            let node = channel.target().node();
+            let Some(joint) = node.name().and_then(|n| options.sl_name(n)) else {
+                continue;
+            };
+            let reader = channel.reader(|b| buffers.get(b.index()).map(Vec::as_slice));
+            let times: Vec<f32> = reader
+                .read_inputs()
+                .ok_or_else(|| error("channel without input times"))?
+                .collect();
+            let interpolation = channel.sampler().interpolation();
+            let (rest_translation, rest_rotation) = rest(node.index());
+            let parent = parent_rest(node.index());
+            let index = match channels.iter().position(|c| c.joint == joint) {
+                Some(index) => index,
+                None => {
+                    channels.push(Channel {
+                        joint: joint.to_string(),
+                        ..Default::default()
+                    });
+                    channels.len() - 1
+                }
+            };
+            let entry = &mut channels[index];
+            match reader.read_outputs() {
+                Some(ReadOutputs::Rotations(rotations)) => {
+                    let values = rotations
+                        .into_f32()
+                        .map(|r| {
+                            let delta = Quat::from_array(r).normalize() * rest_rotation.conjugate();
+                            Frame::MayaYUp.rot_to_sl(parent * delta * parent.conjugate())
+                        })
+                        .collect();
+                    entry.rotations = keyframes(&times, values, interpolation);
+                    entry.rotation_step = interpolation == Interpolation::Step;
+                }
+                Some(ReadOutputs::Translations(translations)) if joint == "mPelvis" => {
+                    let values = translations
+                        .map(|t| {
+                            let offset = parent * (Vec3::from_array(t) - rest_translation);
+                            Frame::MayaYUp.vec_to_sl(offset)
+                        })
+                        .collect();
+                    entry.positions = keyframes(&times, values, interpolation);
+                    entry.position_step = interpolation == Interpolation::Step;
+                }
+                _ => continue,
+            }
+            entry.times.extend(&times);
+        }
+
+        let all_times = channels.iter().flat_map(|c| c.times.iter().copied());
+        let start = all_times.clone().fold(f32::INFINITY, f32::min);
+        let end = all_times.fold(f32::NEG_INFINITY, f32::max);
+        let duration = if end > start { end - start } else { 0.0 };
+
+        let mut anim = Animation {
+            header: AnimationHeader {
+                base_priority: 3,
+                duration,
+                loop_out_point: duration,
+                looped: 0,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        for channel in channels {
+            anim.joints.push(JointData {
+                name: channel.joint,
+                priority: anim.header.base_priority,
+                rotation_keys: ticked(&channel.rotations, channel.rotation_step, start, duration)
+                    .into_iter()
+                    .map(|(time, rot)| RotationKey { time, rot })
+                    .collect(),
+                position_keys: ticked(&channel.positions, channel.position_step, start, duration)
+                    .into_iter()
+                    .map(|(time, pos)| PositionKey { time, pos })
+                    .collect(),
+            });
+        }
+        Ok(anim)
+    }
+
+    /// Write the animation as a self-contained glTF 2.0 document (JSON, with the binary data
+    /// embedded as a base64 data URI).
+    ///
+    /// Every skeleton joint becomes a node at its bind translation, so the joint tree shows
+    /// up in viewers such as the Babylon.js sandbox. Each rotation track becomes a linear
+    /// sampler on its node; position keys are only written for the skeleton's root, added to
+    /// its bind translation. Joints missing from the skeleton are skipped.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use avatar_anim::{Animation, Skeleton};
+    ///
+    /// # fn main() -> avatar_anim::Result<()> {
+    /// let anim = Animation::from_file("wave.anim")?;
+    /// let gltf = anim.to_gltf(&Skeleton::second_life())?;
+    /// std::fs::write("wave.gltf", gltf).map_err(avatar_anim::AnimError::Io)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Fails on an empty skeleton, non-finite key values or a non-finite duration.
+    pub fn to_gltf(&self, skeleton: &Skeleton) -> Result<String> {
+        let joints = skeleton.joints();
+        if joints.is_empty() {
+            return Err(error("export needs a non-empty skeleton"));
+        }
+        let finite = self.joints.iter().all(|j| {
+            j.rotation_keys.iter().all(|k| k.rot.is_finite())
+                && j.position_keys.iter().all(|k| k.pos.is_finite())
+        });
+        if !finite {
+            return Err(error("cannot export non-finite key values"));
+        }
+        if !self.header.duration.is_finite() {
+            return Err(error("cannot export a non-finite duration"));
+        }
+
+        let nodes: Vec<String> = joints
+            .iter()
+            .enumerate()
+            .map(|(i, joint)| {
+                let translation = Frame::MayaYUp.vec_from_sl(joint.position).to_array();
+                let mut node = String::from(r#"{"name":"#);
+                json_string(&mut node, &joint.name);
+                let _ = write!(node, r#","translation":{}"#, json_floats(&translation));
+                let children: Vec<String> = joints
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, j)| j.parent == Some(i))
+                    .map(|(c, _)| c.to_string())
+                    .collect();
+                if !children.is_empty() {
+                    let _ = write!(node, r#","children":[{}]"#, children.join(","));
+                }
+                node.push('}');
+                node
+            })
+            .collect();
+        let roots: Vec<String> = (0..joints.len())
+            .filter(|&i| joints[i].parent.is_none())
+            .map(|i| i.to_string())
+            .collect();
+
+        let duration = self.header.duration.max(0.0);
+        let seconds = |tick: u16| tick as f32 / u16::MAX as f32 * duration;
+        let mut payload = Payload::default();
+        let mut samplers = Vec::new();
+        let mut channels = Vec::new();
+        let mut add = |node: usize, path: &str, times: Vec<f32>, values: Vec<f32>, width| {
+            let input = payload.push(&times, 1);
+            let output = payload.push(&values, width);
+            samplers.push(format!(
+                r#"{{"input":{input},"output":{output},"interpolation":"LINEAR"}}"#
+            ));
+            channels.push(format!(
+                r#"{{"sampler":{},"target":{{"node":{node},"path":"{path}"}}}}"#,
+                samplers.len() - 1
+            ));
+        };
+        for joint in &self.joints {
+            let Some(node) = skeleton.index_of(&joint.name) else {
+                continue;
+            };
+            let mut rotation_keys = joint.rotation_keys.clone();
+            rotation_keys.sort_by_key(|k| k.time);
+            rotation_keys.dedup_by_key(|k| k.time);
+            if !rotation_keys.is_empty() {
+                add(
+                    node,
+                    "rotation",
+                    rotation_keys.iter().map(|k| seconds(k.time)).collect(),
+                    rotation_keys
+                        .iter()
+                        .flat_map(|k| Frame::MayaYUp.rot_from_sl(k.rot).to_array())
+                        .collect(),
+                    4,
+                );
+            }
+            let mut position_keys = joint.position_keys.clone();
+            position_keys.sort_by_key(|k| k.time);
+            position_keys.dedup_by_key(|k| k.time);
+            if joints[node].parent.is_none() && !position_keys.is_empty() {
+                let bind = joints[node].position;
+                add(
+                    node,
+                    "translation",
+                    position_keys.iter().map(|k| seconds(k.time)).collect(),
+                    position_keys
+                        .iter()
+                        .flat_map(|k| Frame::MayaYUp.vec_from_sl(bind + k.pos).to_array())
+                        .collect(),
+                    3,
+                );
+            }
+        }
+
+        let mut json = String::from(r#"{"asset":{"version":"2.0","generator":"avatar-anim"}"#);
+        let _ = write!(
+            json,
+            r#","scene":0,"scenes":[{{"nodes":[{}]}}],"nodes":[{}]"#,
+            roots.join(","),
+            nodes.join(",")
+        );
+        if !channels.is_empty() {
+            let mut name = String::new();
+            json_string(&mut name, &self.header.emote_name);
+            let _ = write!(
+                json,
+                r#","animations":[{{"name":{name},"channels":[{}],"samplers":[{}]}}]"#,
+                channels.join(","),
+                samplers.join(",")
+            );
+            let _ = write!(
+                json,
+                r#","buffers":[{{"byteLength":{},"uri":"data:application/octet-stream;base64,{}"}}],"bufferViews":[{}],"accessors":[{}]"#,
+                payload.bin.len(),
+                encode_base64(&payload.bin),
+                payload.views.join(","),
+                payload.accessors.join(",")
+            );
+        }
+        json.push('}');
+        Ok(json)
+    }
+}
@@ -0,0 +1,238 @@
+//! Inventory listings, for naming animations that are only known by asset UUID.
+//!
+//! Reads the LLSD the viewer uses for inventory: its inventory cache (one LLSD-XML document
+//! per line), AIS responses, or an exported listing as a single XML or binary LLSD document.
+//! Items are maps with `item_id`, `asset_id`, `name`, `parent_id` and `type`; folders are
+//! maps with `cat_id` (or `category_id`), `name` and `parent_id`. They may be nested in
+//! arrays and maps at any depth; everything else is ignored.
+
+use crate::gesture::{Ledger, is_uuid};
+use crate::manifest::ManifestEntry;
+use crate::open::{self, SourceFormat};
+use crate::{AnimError, Animation, Result};
+use llsd_rs::Llsd;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+/// `LLAssetType::AT_ANIMATION`, the numeric form of the `animation` asset type.
+pub const ASSET_TYPE_ANIMATION: i32 = 20;
+
+/// An inventory entry.
+#[derive(Clone, Debug, PartialEq)]
+pub struct InventoryItem {
+    /// Lowercase inventory item UUID.
+    pub item_id: String,
+    /// Lowercase UUID of the asset the item points at.
+    pub asset_id: String,
+    pub name: String,
+    /// Names of the enclosing folders, outermost first. Folders missing from the listing end
+    /// the path.
+    pub folder: Vec<String>,
+    /// Whether the item is an animation.
+    pub is_animation: bool,
+}
+
+/// Items of an inventory listing.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Inventory {
+    /// Sorted by folder, then name.
+    pub items: Vec<InventoryItem>,
+}
+
+struct Folder {
+    name: String,
+    parent: Option<String>,
+}
+
+struct Item {
+    item_id: String,
+    asset_id: String,
+    name: String,
+    parent: Option<String>,
+    is_animation: bool,
+}
+
+fn uuid(value: Option<&Llsd>) -> Option<String> {
+    let text = match value? {
+        Llsd::Uuid(id) => id.to_string(),
+        Llsd::String(text) => text.trim().to_string(),
+        _ => return None,
+    };
+    is_uuid(&text).then(|| text.to_ascii_lowercase())
+}
+
+fn string(value: Option<&Llsd>) -> String {
+    match value {
+        Some(Llsd::String(text)) => text.clone(),
+        _ => String::new(),
+    }
+}
+
+fn collect(value: &Llsd, folders: &mut HashMap<String, Folder>, items: &mut Vec<Item>) {
+    match value {
+        Llsd::Array(values) => {
+            for value in values {
+                collect(value, folders, items);
+            }
+        }
+        Llsd::Map(map) => {
+            let parent = uuid(map.get("parent_id"));
+            if let (Some(item_id), Some(asset_id)) =
+                (uuid(map.get("item_id")), uuid(map.get("asset_id")))
+            {
+                let is_animation = match map.get("type") {
+                    Some(Llsd::Integer(kind)) => *kind == ASSET_TYPE_ANIMATION,
+                    Some(Llsd::String(kind)) => kind == "animation" || kind == "animatn",
+                    _ => false,
+                };
+                items.push(Item {
+                    item_id,
+                    asset_id,
+                    name: string(map.get("name")),
+                    parent,
+                    is_animation,
+                });
+            } else if let Some(id) =
+                uuid(map.get("cat_id")).or_else(|| uuid(map.get("category_id")))
+            {
+                folders.insert(
+                    id,
+                    Folder {
+                        name: string(map.get("name")),
+                        parent,
+                    },
+                );
+            }
+            for value in map.values() {
+                collect(value, folders, items);
+            }
+        }
+        _ => {}
+    }
+}
+
+impl Inventory {
+    /// Parse a listing as LLSD-XML (one or more documents) or binary LLSD.
+    ///
+    /// # Errors
+    ///
+    /// Fails if a document is not valid LLSD.
+    pub fn parse(bytes: &[u8]) -> Result<Self> {
+        let mut values = Vec::new();
+        match open::sniff(bytes)? {
+            SourceFormat::LlsdBinary => {
+                values.push(open::decode_llsd(bytes, SourceFormat::LlsdBinary)?)
+            }
+            _ => {
+                // The inventory cache holds one document per line.
+                let text = String::from_utf8_lossy(bytes);
+                for document in text.split_inclusive("</llsd>") {
+                    if document.contains("<llsd") {
+                        values.push(open::decode_llsd(
+                            document.trim().as_bytes(),
+                            SourceFormat::LlsdXml,
+                        )?);
+                    }
+                }
+            }
+        }
+
+        let mut folders = HashMap::new();
+        let mut found = Vec::new();
+        for value in &values {
+            collect(value, &mut folders, &mut found);
+        }
+        let mut items: Vec<InventoryItem> = found
+            .into_iter()
+            .map(|item| {
+                let mut folder = Vec::new();
+                let mut parent = item.parent;
+                // Bounded in case the listing has a parent cycle.
+                while let Some(entry) = parent.as_ref().and_then(|id| folders.get(id)) {
+                    if folder.len() > folders.len() {
+                        break;
+                    }
+                    folder.push(entry.name.clone());
+                    parent = entry.parent.clone();
+                }
+                folder.reverse();
+                InventoryItem {
+                    item_id: item.item_id,
+                    asset_id: item.asset_id,
+                    name: item.name,
+                    folder,
+                    is_animation: item.is_animation,
+                }
+            })
+            .collect();
+        items.sort_by(|a, b| {
+            (&a.folder, &a.name, &a.item_id).cmp(&(&b.folder, &b.name, &b.item_id))
+        });
+        // Listings can repeat an item anywhere; keep its first entry in sorted order.
+        let mut seen = HashSet::new();
+        items.retain(|item| seen.insert(item.item_id.clone()));
+        Ok(Self { items })
+    }
+
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::parse(&std::fs::read(path).map_err(AnimError::Io)?)
+    }
+
+    /// Animation items, in [`Inventory::items`] order.
+    pub fn animations(&self) -> impl Iterator<Item = &InventoryItem> {
+        self.items.iter().filter(|item| item.is_animation)
+    }
+
+    /// The first animation item pointing at `asset_id`, case-insensitively.
+    pub fn animation(&self, asset_id: &str) -> Option<&InventoryItem> {
+        let asset_id = asset_id.trim();
+        self.animations()
+            .find(|item| item.asset_id.eq_ignore_ascii_case(asset_id))
+    }
+
+    /// Build a [`Ledger`] from animations extracted from the viewer cache.
+    ///
+    /// Every file under `dir` (recursively) whose name contains the asset UUID of an
+    /// inventory animation is loaded with [`Animation::from_file`] and recorded under that
+    /// item's name. Files that are not animations of this inventory, or don't parse, are
+    /// skipped.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use avatar_anim::gesture::{self, Gesture};
+    /// use avatar_anim::inventory::Inventory;
+    ///
+    /// # fn main() -> avatar_anim::Result<()> {
+    /// let inventory = Inventory::from_file("inventory.xml")?;
+    /// let ledger = inventory.ledger_from_cache("cache/assets")?;
+    /// let gesture = Gesture::from_file("wave.gesture")?;
+    /// for issue in gesture::check(&gesture, &ledger) {
+    ///     println!("{issue:?}");
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Fails if a directory can't be read.
+    pub fn ledger_from_cache<P: AsRef<Path>>(&self, dir: P) -> Result<Ledger> {
+        let mut ledger = Ledger::new();
+        for path in crate::cache::files_below(dir.as_ref(), |_| true)? {
+            let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+                continue;
+            };
+            let item = (0..name.len().saturating_sub(35))
+                .filter_map(|i| name.get(i..i + 36))
+                .find(|text| is_uuid(text))
+                .and_then(|asset_id| self.animation(asset_id));
+            if let Some(item) = item
+                && let Ok(anim) = Animation::from_file(&path)
+            {
+                ledger.insert(&item.asset_id, ManifestEntry::new(item.name.clone(), &anim));
+            }
+        }
+        Ok(ledger)
+    }
+}
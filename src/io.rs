@@ -1,3 +1,8 @@
+use crate::error::InvalidName;
+/// Fake [`Seek`] for streams that can't seek, so binrw can write to them. None of the
+/// `.anim` writers seek, so wrapping a socket or compressor in it is all
+/// [`crate::Animation::to_stream`] needs.
+pub use binrw::io::NoSeek;
 use binrw::{
     BinResult, Endian, NamedArgs,
     io::{Read, Seek, Write},
@@ -18,13 +23,23 @@ fn f32_to_u16(value: f32, lower: f32, upper: f32) -> u16 {
     (val * u16::MAX as f32).floor() as u16
 }
 
-fn u16_to_f32(value: u16, lower: f32, upper: f32) -> f32 {
+/// Quantization steps around zero that decode as exactly `0.0`, as in the viewer's
+/// `U16_to_F32`. Zero itself has no exact encoding, so this makes it round trip.
+pub(crate) const VIEWER_ZERO_SNAP: u16 = 1;
+
+pub(crate) fn u16_to_f32(value: u16, lower: f32, upper: f32) -> f32 {
+    u16_to_f32_snapped(value, lower, upper, VIEWER_ZERO_SNAP)
+}
+
+/// Like [`u16_to_f32`], snapping values within `snap_quanta` quantization steps of zero
+/// instead of one; `0` snaps nothing.
+pub(crate) fn u16_to_f32_snapped(value: u16, lower: f32, upper: f32, snap_quanta: u16) -> f32 {
     let mut val = value as f32 * OOU16MAX;
     let delta = upper - lower;
     val *= delta;
     val += lower;
 
-    let max_error = delta * OOU16MAX;
+    let max_error = delta * OOU16MAX * snap_quanta as f32;
     if val.abs() < max_error {
         val = 0.0;
     }
@@ -32,6 +47,58 @@ fn u16_to_f32(value: u16, lower: f32, upper: f32) -> f32 {
     val
 }
 
+/// Whether `value` decodes to `0.0` only because of the snap, i.e. it isn't the code zero
+/// itself is written as.
+pub(crate) fn snaps_to_zero(value: u16, lower: f32, upper: f32, snap_quanta: u16) -> bool {
+    value != f32_to_u16(0.0, lower, upper)
+        && u16_to_f32_snapped(value, lower, upper, snap_quanta) == 0.0
+        && u16_to_f32_snapped(value, lower, upper, 0) != 0.0
+}
+
+/// Key time in ticks for `seconds` into an animation of `duration` seconds, rounded the way
+/// the viewer does when it writes an animation (`F32_to_U16`): clamped to `0..=duration`,
+/// scaled to `0..=65535` and floored.
+///
+/// `0` and anything earlier map to tick 0, `duration` and anything later to 65535. A
+/// non-positive or non-finite `duration` maps every time to 0. Because of the floor, this is
+/// not an exact inverse of [`u16_to_time`]: a tick's time can come back one tick low, so keep
+/// ticks rather than seconds when editing decoded keys.
+///
+/// ```rust
+/// use avatar_anim::io::{time_to_u16, u16_to_time};
+///
+/// assert_eq!(time_to_u16(0.0, 2.0), 0);
+/// assert_eq!(time_to_u16(1.0, 2.0), 32767);
+/// assert_eq!(time_to_u16(5.0, 2.0), u16::MAX);
+/// assert_eq!(u16_to_time(u16::MAX, 2.0), 2.0);
+/// ```
+pub fn time_to_u16(seconds: f32, duration: f32) -> u16 {
+    if !(duration.is_finite() && duration > 0.0) || seconds.is_nan() {
+        return 0;
+    }
+    f32_to_u16(seconds, 0.0, duration)
+}
+
+/// Time in seconds the viewer decodes for key tick `tick` of an animation lasting `duration`
+/// seconds (`U16_to_F32`).
+///
+/// Tick 0 is exactly `0.0` and tick 65535 is `duration` up to `f32` rounding. Like the viewer,
+/// results within one tick of zero snap to `0.0`, which only matters for a negative
+/// `duration`.
+pub fn u16_to_time(tick: u16, duration: f32) -> f32 {
+    u16_to_f32(tick, 0.0, duration)
+}
+
+/// `BinWrite` for `u16` needs `Seek`; the writers here only ever append.
+fn write_u16<W: Write>(writer: &mut W, value: u16, e: Endian) -> BinResult<()> {
+    let bytes = match e {
+        Endian::Big => value.to_be_bytes(),
+        Endian::Little => value.to_le_bytes(),
+    };
+    writer.write_all(&bytes)?;
+    Ok(())
+}
+
 pub fn read_null_terminated_string<R: Read + Seek>(
     r: &mut R,
     _: Endian,
@@ -46,13 +113,13 @@ pub fn read_null_terminated_string<R: Read + Seek>(
         }
         buf.push(byte[0]);
     }
-    String::from_utf8(buf).map_err(|e: FromUtf8Error| binrw::Error::AssertFail {
+    String::from_utf8(buf).map_err(|e: FromUtf8Error| binrw::Error::Custom {
         pos: 0,
-        message: format!("Invalid UTF-8 in null-terminated string: {e}"),
+        err: Box::new(InvalidName(e)),
     })
 }
 
-pub fn write_null_terminated_string<W: Write + Seek>(
+pub fn write_null_terminated_string<W: Write>(
     data: &String,
     w: &mut W,
     _: Endian,
@@ -82,7 +149,7 @@ pub fn read_fixed_length_string<R: Read + Seek>(
     Ok(String::from_utf8(buf).unwrap_or_default())
 }
 
-pub fn write_fixed_length_string<W: Write + Seek>(
+pub fn write_fixed_length_string<W: Write>(
     data: &String,
     w: &mut W,
     _: Endian,
@@ -97,9 +164,14 @@ pub fn write_fixed_length_string<W: Write + Seek>(
 
 pub fn read_rot_quat<R: Read + Seek>(reader: &mut R, e: Endian, _: ()) -> BinResult<Quat> {
     use binrw::BinRead;
-    let x: f32 = u16_to_f32(u16::read_options(reader, e, ())?, -1.0, 1.0);
-    let y: f32 = u16_to_f32(u16::read_options(reader, e, ())?, -1.0, 1.0);
-    let z: f32 = u16_to_f32(u16::read_options(reader, e, ())?, -1.0, 1.0);
+    let stored = <[u16; 3]>::read_options(reader, e, ())?;
+    Ok(decode_rotation(stored, VIEWER_ZERO_SNAP))
+}
+
+/// Rotation from its stored `x, y, z` codes, snapping components within `snap_quanta` steps
+/// of zero.
+pub(crate) fn decode_rotation(stored: [u16; 3], snap_quanta: u16) -> Quat {
+    let [x, y, z] = stored.map(|c| u16_to_f32_snapped(c, -1.0, 1.0, snap_quanta));
     let sum = x * x + y * y + z * z;
     let w = if sum <= 1.0 { (1.0 - sum).sqrt() } else { 0.0 };
     let mut q = Quat::from_xyzw(x, y, z, w);
@@ -109,16 +181,10 @@ pub fn read_rot_quat<R: Read + Seek>(reader: &mut R, e: Endian, _: ()) -> BinRes
     if q.w < 0.0 {
         q = Quat::from_xyzw(-q.x, -q.y, -q.z, -q.w);
     }
-    Ok(q)
+    q
 }
 
-pub fn write_rot_quat<W: Write + Seek>(
-    value: &Quat,
-    writer: &mut W,
-    e: Endian,
-    _: (),
-) -> BinResult<()> {
-    use binrw::BinWrite;
+pub fn write_rot_quat<W: Write>(value: &Quat, writer: &mut W, e: Endian, _: ()) -> BinResult<()> {
     let mut q = if value.length_squared() > 0.0 {
         value.normalize()
     } else {
@@ -128,29 +194,26 @@ pub fn write_rot_quat<W: Write + Seek>(
     if q.w < 0.0 {
         q = Quat::from_xyzw(-q.x, -q.y, -q.z, -q.w);
     }
-    f32_to_u16(q.x, -1.0, 1.0).write_options(writer, e, ())?;
-    f32_to_u16(q.y, -1.0, 1.0).write_options(writer, e, ())?;
-    f32_to_u16(q.z, -1.0, 1.0).write_options(writer, e, ())
+    write_u16(writer, f32_to_u16(q.x, -1.0, 1.0), e)?;
+    write_u16(writer, f32_to_u16(q.y, -1.0, 1.0), e)?;
+    write_u16(writer, f32_to_u16(q.z, -1.0, 1.0), e)
 }
 
 pub fn read_pos_vec3<R: Read + Seek>(reader: &mut R, e: Endian, _: ()) -> BinResult<Vec3> {
     use binrw::BinRead;
-    let x = u16_to_f32(u16::read_options(reader, e, ())?, -5.0f32, 5.0f32);
-    let y = u16_to_f32(u16::read_options(reader, e, ())?, -5.0f32, 5.0f32);
-    let z = u16_to_f32(u16::read_options(reader, e, ())?, -5.0f32, 5.0f32);
-    Ok(Vec3::new(x, y, z))
+    let stored = <[u16; 3]>::read_options(reader, e, ())?;
+    Ok(decode_position(stored, VIEWER_ZERO_SNAP))
 }
 
-pub fn write_pos_vec3<W: Write + Seek>(
-    value: &Vec3,
-    writer: &mut W,
-    e: Endian,
-    _: (),
-) -> BinResult<()> {
-    use binrw::BinWrite;
-    f32_to_u16(value.x, -5.0f32, 5.0f32).write_options(writer, e, ())?;
-    f32_to_u16(value.y, -5.0f32, 5.0f32).write_options(writer, e, ())?;
-    f32_to_u16(value.z, -5.0f32, 5.0f32).write_options(writer, e, ())
+/// Position from its stored codes, snapping components within `snap_quanta` steps of zero.
+pub(crate) fn decode_position(stored: [u16; 3], snap_quanta: u16) -> Vec3 {
+    Vec3::from_array(stored.map(|c| u16_to_f32_snapped(c, -5.0f32, 5.0f32, snap_quanta)))
+}
+
+pub fn write_pos_vec3<W: Write>(value: &Vec3, writer: &mut W, e: Endian, _: ()) -> BinResult<()> {
+    write_u16(writer, f32_to_u16(value.x, -5.0f32, 5.0f32), e)?;
+    write_u16(writer, f32_to_u16(value.y, -5.0f32, 5.0f32), e)?;
+    write_u16(writer, f32_to_u16(value.z, -5.0f32, 5.0f32), e)
 }
 
 // Quantization helper docs:
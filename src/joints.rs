@@ -0,0 +1,505 @@
+use crate::{AnimError, Result};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Language for [`display_name`].
+#[non_exhaustive]
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub enum Locale {
+    #[default]
+    En,
+    De,
+    Fr,
+    Es,
+}
+
+impl Locale {
+    /// Parse a language tag such as `"de"`, `"fr-CA"` or `"es_ES"`; only the language part is used.
+    pub fn from_tag(tag: &str) -> Option<Self> {
+        let lang = tag.split(['-', '_']).next()?.to_ascii_lowercase();
+        match lang.as_str() {
+            "en" => Some(Locale::En),
+            "de" => Some(Locale::De),
+            "fr" => Some(Locale::Fr),
+            "es" => Some(Locale::Es),
+            _ => None,
+        }
+    }
+
+    fn index(self) -> usize {
+        match self {
+            Locale::En => 0,
+            Locale::De => 1,
+            Locale::Fr => 2,
+            Locale::Es => 3,
+        }
+    }
+}
+
+/// Legacy joints above the pelvis: spine, head, eyes and arms.
+pub const UPPER_BODY: &[&str] = &[
+    "mTorso",
+    "mChest",
+    "mNeck",
+    "mHead",
+    "mSkull",
+    "mEyeLeft",
+    "mEyeRight",
+    "mCollarLeft",
+    "mShoulderLeft",
+    "mElbowLeft",
+    "mWristLeft",
+    "mCollarRight",
+    "mShoulderRight",
+    "mElbowRight",
+    "mWristRight",
+];
+
+/// Legacy joints from the pelvis down.
+pub const LOWER_BODY: &[&str] = &[
+    "mPelvis",
+    "mHipLeft",
+    "mKneeLeft",
+    "mAnkleLeft",
+    "mFootLeft",
+    "mToeLeft",
+    "mHipRight",
+    "mKneeRight",
+    "mAnkleRight",
+    "mFootRight",
+    "mToeRight",
+];
+
+/// Labels follow the viewer's attachment point names, e.g. `mCollarLeft` is the left shoulder.
+const DISPLAY_NAMES: &[(&str, [&str; 4])] = &[
+    ("mPelvis", ["Pelvis", "Becken", "Bassin", "Pelvis"]),
+    ("mTorso", ["Stomach", "Bauch", "Ventre", "Vientre"]),
+    ("mChest", ["Chest", "Brust", "Poitrine", "Pecho"]),
+    ("mNeck", ["Neck", "Hals", "Cou", "Cuello"]),
+    ("mHead", ["Head", "Kopf", "Tête", "Cabeza"]),
+    ("mSkull", ["Skull", "Schädel", "Crâne", "Cráneo"]),
+    (
+        "mEyeLeft",
+        ["Left Eye", "Linkes Auge", "Œil gauche", "Ojo izquierdo"],
+    ),
+    (
+        "mEyeRight",
+        ["Right Eye", "Rechtes Auge", "Œil droit", "Ojo derecho"],
+    ),
+    (
+        "mCollarLeft",
+        [
+            "Left Shoulder",
+            "Linke Schulter",
+            "Épaule gauche",
+            "Hombro izquierdo",
+        ],
+    ),
+    (
+        "mCollarRight",
+        [
+            "Right Shoulder",
+            "Rechte Schulter",
+            "Épaule droite",
+            "Hombro derecho",
+        ],
+    ),
+    (
+        "mShoulderLeft",
+        [
+            "Left Upper Arm",
+            "Linker Oberarm",
+            "Haut du bras gauche",
+            "Brazo superior izquierdo",
+        ],
+    ),
+    (
+        "mShoulderRight",
+        [
+            "Right Upper Arm",
+            "Rechter Oberarm",
+            "Haut du bras droit",
+            "Brazo superior derecho",
+        ],
+    ),
+    (
+        "mElbowLeft",
+        [
+            "Left Forearm",
+            "Linker Unterarm",
+            "Avant-bras gauche",
+            "Antebrazo izquierdo",
+        ],
+    ),
+    (
+        "mElbowRight",
+        [
+            "Right Forearm",
+            "Rechter Unterarm",
+            "Avant-bras droit",
+            "Antebrazo derecho",
+        ],
+    ),
+    (
+        "mWristLeft",
+        ["Left Hand", "Linke Hand", "Main gauche", "Mano izquierda"],
+    ),
+    (
+        "mWristRight",
+        ["Right Hand", "Rechte Hand", "Main droite", "Mano derecha"],
+    ),
+    (
+        "mHipLeft",
+        [
+            "Left Upper Leg",
+            "Linker Oberschenkel",
+            "Cuisse gauche",
+            "Muslo izquierdo",
+        ],
+    ),
+    (
+        "mHipRight",
+        [
+            "Right Upper Leg",
+            "Rechter Oberschenkel",
+            "Cuisse droite",
+            "Muslo derecho",
+        ],
+    ),
+    (
+        "mKneeLeft",
+        [
+            "Left Lower Leg",
+            "Linker Unterschenkel",
+            "Jambe gauche",
+            "Pierna izquierda",
+        ],
+    ),
+    (
+        "mKneeRight",
+        [
+            "Right Lower Leg",
+            "Rechter Unterschenkel",
+            "Jambe droite",
+            "Pierna derecha",
+        ],
+    ),
+    (
+        "mAnkleLeft",
+        ["Left Foot", "Linker Fuß", "Pied gauche", "Pie izquierdo"],
+    ),
+    (
+        "mAnkleRight",
+        ["Right Foot", "Rechter Fuß", "Pied droit", "Pie derecho"],
+    ),
+    (
+        "mFootLeft",
+        [
+            "Left Ball of Foot",
+            "Linker Fußballen",
+            "Avant-pied gauche",
+            "Antepié izquierdo",
+        ],
+    ),
+    (
+        "mFootRight",
+        [
+            "Right Ball of Foot",
+            "Rechter Fußballen",
+            "Avant-pied droit",
+            "Antepié derecho",
+        ],
+    ),
+    (
+        "mToeLeft",
+        [
+            "Left Toes",
+            "Linke Zehen",
+            "Orteils gauches",
+            "Dedos del pie izquierdo",
+        ],
+    ),
+    (
+        "mToeRight",
+        [
+            "Right Toes",
+            "Rechte Zehen",
+            "Orteils droits",
+            "Dedos del pie derecho",
+        ],
+    ),
+];
+
+/// Split a viewer joint name into words: `mHandThumb1Left` becomes `Left Hand Thumb 1`.
+fn humanize(joint: &str) -> String {
+    let base = joint.strip_prefix('m').unwrap_or(joint);
+    let mut words: Vec<String> = Vec::new();
+    for c in base.chars() {
+        let boundary = match words.last().and_then(|w| w.chars().last()) {
+            None => true,
+            Some(prev) => {
+                (c.is_ascii_uppercase() && !prev.is_ascii_uppercase())
+                    || (c.is_ascii_digit() != prev.is_ascii_digit())
+            }
+        };
+        if boundary || c == '_' {
+            words.push(String::new());
+        }
+        if c != '_' {
+            words.last_mut().expect("word pushed above").push(c);
+        }
+    }
+    words.retain(|w| !w.is_empty());
+    if let Some(side) = words
+        .iter()
+        .position(|w| w == "Left" || w == "Right")
+        .filter(|&i| i == words.len() - 1)
+    {
+        let side = words.remove(side);
+        words.insert(0, side);
+    }
+    words.join(" ")
+}
+
+/// Friendly label for a joint, e.g. `"Left Shoulder"` for `mCollarLeft`.
+///
+/// Joints outside the table (Bento bones, collision volumes) fall back to their name split
+/// into words.
+///
+/// # Example
+///
+/// ```rust
+/// use avatar_anim::joints::{Locale, display_name};
+///
+/// assert_eq!(display_name("mCollarLeft", Locale::En), "Left Shoulder");
+/// assert_eq!(display_name("mHead", Locale::De), "Kopf");
+/// assert_eq!(display_name("mHandThumb1Left", Locale::En), "Left Hand Thumb 1");
+/// ```
+pub fn display_name(joint: &str, locale: Locale) -> Cow<'static, str> {
+    match DISPLAY_NAMES.iter().find(|(name, _)| *name == joint) {
+        Some((_, labels)) => Cow::Borrowed(labels[locale.index()]),
+        None => Cow::Owned(humanize(joint)),
+    }
+}
+
+/// The joint on the other side of the body, e.g. `mShoulderRight` for `mShoulderLeft`, or
+/// `None` for joints on the center line.
+///
+/// Sided SL joints, Bento bones included, end in `Left` or `Right`, so the pair is found by
+/// swapping the suffix.
+///
+/// # Example
+///
+/// ```rust
+/// use avatar_anim::joints::opposite_joint;
+///
+/// assert_eq!(opposite_joint("mShoulderLeft").as_deref(), Some("mShoulderRight"));
+/// assert_eq!(opposite_joint("mHandThumb2Right").as_deref(), Some("mHandThumb2Left"));
+/// assert_eq!(opposite_joint("mHead"), None);
+/// ```
+pub fn opposite_joint(joint: &str) -> Option<String> {
+    if let Some(base) = joint.strip_suffix("Left") {
+        Some(format!("{base}Right"))
+    } else {
+        joint
+            .strip_suffix("Right")
+            .map(|base| format!("{base}Left"))
+    }
+}
+
+/// The collision volume on the other side of the body, e.g. `R_HAND` for `L_HAND` or
+/// `RIGHT_PEC` for `LEFT_PEC`, or `None` for volumes on the center line.
+///
+/// # Example
+///
+/// ```rust
+/// use avatar_anim::joints::opposite_volume;
+///
+/// assert_eq!(opposite_volume("L_UPPER_ARM").as_deref(), Some("R_UPPER_ARM"));
+/// assert_eq!(opposite_volume("RIGHT_HANDLE").as_deref(), Some("LEFT_HANDLE"));
+/// assert_eq!(opposite_volume("PELVIS"), None);
+/// ```
+pub fn opposite_volume(volume: &str) -> Option<String> {
+    const SIDES: [(&str, &str); 4] = [
+        ("L_", "R_"),
+        ("R_", "L_"),
+        ("LEFT_", "RIGHT_"),
+        ("RIGHT_", "LEFT_"),
+    ];
+    SIDES.iter().find_map(|(side, other)| {
+        volume
+            .strip_prefix(side)
+            .map(|rest| format!("{other}{rest}"))
+    })
+}
+
+/// Alternate spellings of SL joints, keyed by [`alias_key`].
+const ALIASES: &[(&[&str], &str)] = &[
+    (&["hip", "hips", "pelvis"], "mPelvis"),
+    (&["abdomen", "abdomenlower", "spine", "lowerback"], "mTorso"),
+    (&["chest", "chestlower", "spine1", "upperback"], "mChest"),
+    (&["neck", "necklower"], "mNeck"),
+    (&["head"], "mHead"),
+    (&["lcollar", "leftshoulder", "leftcollar"], "mCollarLeft"),
+    (
+        &["lshldr", "lshldrbend", "leftarm", "leftupperarm"],
+        "mShoulderLeft",
+    ),
+    (
+        &["lforearm", "lforearmbend", "leftforearm", "leftlowerarm"],
+        "mElbowLeft",
+    ),
+    (&["lhand", "lefthand"], "mWristLeft"),
+    (&["rcollar", "rightshoulder", "rightcollar"], "mCollarRight"),
+    (
+        &["rshldr", "rshldrbend", "rightarm", "rightupperarm"],
+        "mShoulderRight",
+    ),
+    (
+        &["rforearm", "rforearmbend", "rightforearm", "rightlowerarm"],
+        "mElbowRight",
+    ),
+    (&["rhand", "righthand"], "mWristRight"),
+    (
+        &["lthigh", "lthighbend", "leftupleg", "leftthigh"],
+        "mHipLeft",
+    ),
+    (&["lshin", "leftleg", "leftshin"], "mKneeLeft"),
+    (&["lfoot", "leftfoot"], "mAnkleLeft"),
+    (&["ltoe", "lefttoebase", "lefttoe"], "mFootLeft"),
+    (
+        &["rthigh", "rthighbend", "rightupleg", "rightthigh"],
+        "mHipRight",
+    ),
+    (&["rshin", "rightleg", "rightshin"], "mKneeRight"),
+    (&["rfoot", "rightfoot"], "mAnkleRight"),
+    (&["rtoe", "righttoebase", "righttoe"], "mFootRight"),
+];
+
+/// Lookup form of a joint name: lowercase, without `_`, `-`, spaces or a `mixamorig:`-style
+/// namespace.
+fn alias_key(name: &str) -> String {
+    let base = name.rsplit(':').next().unwrap_or(name);
+    base.chars()
+        .filter(|c| !matches!(c, '_' | '-' | ' '))
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+/// Whether `name` is spelled like an SL joint: `m` followed by an uppercase letter.
+fn is_sl_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next() == Some('m') && chars.next().is_some_and(|c| c.is_ascii_uppercase())
+}
+
+/// SL joint for a name from another tool, or `None` if it has no counterpart.
+///
+/// Covers QAvimator, Poser and DAZ (`hip`, `abdomen`, `lShldrBend`, ...), Mixamo and the CMU
+/// rigs. Names are compared case-insensitively, ignoring `_`, `-`, spaces and a
+/// `mixamorig:`-style namespace. Names that already look like SL joints (`mPelvis`,
+/// `mFaceJaw`, ...) are kept. Helper bones without a counterpart, such as Poser's
+/// `figureHair` root or DAZ twist bones, resolve to `None`. [`JointAliases`] adds spellings
+/// of your own.
+///
+/// # Example
+///
+/// ```rust
+/// use avatar_anim::joints::resolve_alias;
+///
+/// assert_eq!(resolve_alias("abdomen"), Some("mTorso"));
+/// assert_eq!(resolve_alias("mixamorig:LeftForeArm"), Some("mElbowLeft"));
+/// assert_eq!(resolve_alias("figureHair"), None);
+/// ```
+pub fn resolve_alias(name: &str) -> Option<&str> {
+    if is_sl_name(name) {
+        return Some(name);
+    }
+    let key = alias_key(name);
+    ALIASES
+        .iter()
+        .find(|(aliases, _)| aliases.contains(&key.as_str()))
+        .map(|(_, joint)| *joint)
+}
+
+/// Joint aliases on top of the built-in table of [`resolve_alias`].
+///
+/// An alias file has one `alias = joint` per line; `#` starts a comment. A joint of `-` marks
+/// the alias as having no SL counterpart, overriding the built-in table:
+///
+/// ```text
+/// # rig from the studio's exporter
+/// Bip01_Pelvis = mPelvis
+/// Bip01_Spine  = mTorso
+/// pelvis       = -   # DAZ's pelvis sits below hip, which already maps to mPelvis
+/// ```
+///
+/// # Example
+///
+/// ```rust
+/// use avatar_anim::joints::JointAliases;
+///
+/// let aliases = JointAliases::parse("Bip01_Spine = mTorso\nhip = -").unwrap();
+/// assert_eq!(aliases.resolve("bip01 spine"), Some("mTorso"));
+/// assert_eq!(aliases.resolve("hip"), None);
+/// assert_eq!(aliases.resolve("abdomen"), Some("mTorso"));
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct JointAliases {
+    /// Joint for each alias in lookup form, `None` for aliases without a counterpart.
+    extra: HashMap<String, Option<String>>,
+}
+
+impl JointAliases {
+    /// Parse an alias file.
+    ///
+    /// # Errors
+    ///
+    /// Fails with [`AnimError::InvalidStructure`] naming the first line that is not
+    /// `alias = joint`.
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut aliases = Self::default();
+        for (index, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let invalid = || {
+                AnimError::InvalidStructure(format!(
+                    "alias file line {}: expected `alias = joint`, found {line:?}",
+                    index + 1
+                ))
+            };
+            let (alias, joint) = line.split_once('=').ok_or_else(invalid)?;
+            let (alias, joint) = (alias.trim(), joint.trim());
+            if alias.is_empty() || joint.is_empty() {
+                return Err(invalid());
+            }
+            aliases.insert(alias, (joint != "-").then_some(joint));
+        }
+        Ok(aliases)
+    }
+
+    /// Read an alias file.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::parse(&std::fs::read_to_string(path).map_err(AnimError::Io)?)
+    }
+
+    /// Map `alias` to `joint`, or to no joint at all, replacing any earlier entry.
+    pub fn insert(&mut self, alias: &str, joint: Option<&str>) -> &mut Self {
+        self.extra
+            .insert(alias_key(alias), joint.map(str::to_string));
+        self
+    }
+
+    /// Like [`resolve_alias`], checking the aliases added here first.
+    pub fn resolve<'a>(&'a self, name: &'a str) -> Option<&'a str> {
+        if is_sl_name(name) {
+            return Some(name);
+        }
+        match self.extra.get(&alias_key(name)) {
+            Some(joint) => joint.as_deref(),
+            None => resolve_alias(name),
+        }
+    }
+}
@@ -0,0 +1,366 @@
+//! Human-readable JSON interchange format.
+//!
+//! Key times are in seconds and values are plain floats, so scripts and web tools can edit
+//! animations without knowing the `.anim` quantization:
+//!
+//! ```json
+//! {
+//!   "version": 1,
+//!   "sub_version": 0,
+//!   "base_priority": 3,
+//!   "duration": 2.0,
+//!   "emote_name": "",
+//!   "loop_in_point": 0.0,
+//!   "loop_out_point": 2.0,
+//!   "looped": true,
+//!   "ease_in_duration": 0.3,
+//!   "ease_out_duration": 0.3,
+//!   "hand_pose": 1,
+//!   "joints": [
+//!     {
+//!       "name": "mHead",
+//!       "priority": 3,
+//!       "rotations": [
+//!         { "time": 0.0, "tick": 0, "quat": [0.0, 0.0, 0.0, 1.0] },
+//!         { "time": 1.0, "tick": 32768, "euler": [0.0, 20.0, 0.0] }
+//!       ],
+//!       "positions": []
+//!     }
+//!   ]
+//! }
+//! ```
+//!
+//! Key times are given as `time` in seconds, `tick` in the file's own `0..=65535` units, or
+//! both; a `tick` is exact and wins when both are present. [`TimeFormat`] picks which ones
+//! are written.
+//!
+//! A rotation is either `quat` (`[x, y, z, w]`) or `euler`: degrees `[x, y, z]` composed as
+//! `Rx * Ry * Rz`, like BVH's `Xrotation Yrotation Zrotation`. Positions are `pos` in meters.
+//! `constraints` may be omitted when there are none.
+
+use crate::frame::RotationOrder;
+use crate::{
+    AnimError, Animation, AnimationHeader, Constraint, JointData, PositionKey, Result, RotationKey,
+};
+use glam::{Quat, Vec3};
+use serde::{Deserialize, Serialize};
+
+/// How [`Animation::to_json_with`] writes rotations.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum RotationFormat {
+    /// `quat: [x, y, z, w]`, exact.
+    #[default]
+    Quat,
+    /// `euler: [x, y, z]` in degrees, easier to edit by hand.
+    Euler,
+}
+
+/// Which key times [`Animation::to_json_with_options`] writes.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum TimeFormat {
+    /// `time` in seconds, for people reading the file.
+    Seconds,
+    /// `tick` in `0..=65535`, which re-imports without rounding.
+    Ticks,
+    /// Both `time` and `tick`.
+    #[default]
+    Both,
+}
+
+/// How [`Animation::to_json_with_options`] writes keys.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct JsonOptions {
+    pub rotations: RotationFormat,
+    pub times: TimeFormat,
+}
+
+#[derive(Serialize, Deserialize)]
+struct AnimationJson {
+    version: u16,
+    sub_version: u16,
+    base_priority: i32,
+    duration: f32,
+    #[serde(default)]
+    emote_name: String,
+    loop_in_point: f32,
+    loop_out_point: f32,
+    looped: bool,
+    ease_in_duration: f32,
+    ease_out_duration: f32,
+    hand_pose: u32,
+    joints: Vec<JointJson>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    constraints: Vec<ConstraintJson>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct JointJson {
+    name: String,
+    priority: i32,
+    #[serde(default)]
+    rotations: Vec<RotationJson>,
+    #[serde(default)]
+    positions: Vec<PositionJson>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct RotationJson {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    time: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    tick: Option<u16>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    quat: Option<[f32; 4]>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    euler: Option<[f32; 3]>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct PositionJson {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    time: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    tick: Option<u16>,
+    pos: [f32; 3],
+}
+
+#[derive(Serialize, Deserialize)]
+struct ConstraintJson {
+    chain_length: u8,
+    constraint_type: u8,
+    source_volume: String,
+    source_offset: [f32; 3],
+    target_volume: String,
+    target_offset: [f32; 3],
+    target_dir: [f32; 3],
+    ease_in_start: f32,
+    ease_in_stop: f32,
+    ease_out_start: f32,
+    ease_out_stop: f32,
+}
+
+fn invalid(message: impl std::fmt::Display) -> AnimError {
+    AnimError::InvalidStructure(format!("Invalid animation JSON: {message}"))
+}
+
+impl Animation {
+    /// Serialize to pretty-printed JSON with quaternion rotations and key times in both
+    /// seconds and ticks.
+    pub fn to_json(&self) -> Result<String> {
+        self.to_json_with_options(&JsonOptions::default())
+    }
+
+    /// Serialize to pretty-printed JSON, writing rotations as `format`.
+    pub fn to_json_with(&self, format: RotationFormat) -> Result<String> {
+        self.to_json_with_options(&JsonOptions {
+            rotations: format,
+            ..Default::default()
+        })
+    }
+
+    /// Serialize to pretty-printed JSON as chosen by `options`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use avatar_anim::json::{JsonOptions, TimeFormat};
+    /// use avatar_anim::{Animation, JointData, PositionKey};
+    ///
+    /// # fn main() -> avatar_anim::Result<()> {
+    /// let mut anim = Animation::new();
+    /// anim.joints.push(JointData {
+    ///     name: "mPelvis".into(),
+    ///     position_keys: vec![PositionKey::default()],
+    ///     ..Default::default()
+    /// });
+    /// let ticks = JsonOptions { times: TimeFormat::Ticks, ..Default::default() };
+    /// let text = anim.to_json_with_options(&ticks)?;
+    /// assert!(text.contains("\"tick\": 0") && !text.contains("\"time\""));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn to_json_with_options(&self, options: &JsonOptions) -> Result<String> {
+        let header = &self.header;
+        let format = options.rotations;
+        let seconds = |tick: u16| {
+            (options.times != TimeFormat::Ticks)
+                .then(|| tick as f32 / u16::MAX as f32 * header.duration)
+        };
+        let ticks = |tick: u16| (options.times != TimeFormat::Seconds).then_some(tick);
+        let json = AnimationJson {
+            version: header.version,
+            sub_version: header.sub_version,
+            base_priority: header.base_priority,
+            duration: header.duration,
+            emote_name: header.emote_name.clone(),
+            loop_in_point: header.loop_in_point,
+            loop_out_point: header.loop_out_point,
+            looped: header.looped != 0,
+            ease_in_duration: header.ease_in_duration,
+            ease_out_duration: header.ease_out_duration,
+            hand_pose: header.hand_pose,
+            joints: self
+                .joints
+                .iter()
+                .map(|joint| JointJson {
+                    name: joint.name.clone(),
+                    priority: joint.priority,
+                    rotations: joint
+                        .rotation_keys
+                        .iter()
+                        .map(|k| RotationJson {
+                            time: seconds(k.time),
+                            tick: ticks(k.time),
+                            quat: (format == RotationFormat::Quat).then(|| k.rot.to_array()),
+                            euler: (format == RotationFormat::Euler)
+                                .then(|| RotationOrder::Xyz.decompose(k.rot)),
+                        })
+                        .collect(),
+                    positions: joint
+                        .position_keys
+                        .iter()
+                        .map(|k| PositionJson {
+                            time: seconds(k.time),
+                            tick: ticks(k.time),
+                            pos: k.pos.to_array(),
+                        })
+                        .collect(),
+                })
+                .collect(),
+            constraints: self
+                .constraints
+                .iter()
+                .map(|c| ConstraintJson {
+                    chain_length: c.chain_length,
+                    constraint_type: c.constraint_type,
+                    source_volume: c.source_volume.clone(),
+                    source_offset: c.source_offset,
+                    target_volume: c.target_volume.clone(),
+                    target_offset: c.target_offset,
+                    target_dir: c.target_dir,
+                    ease_in_start: c.ease_in_start,
+                    ease_in_stop: c.ease_in_stop,
+                    ease_out_start: c.ease_out_start,
+                    ease_out_stop: c.ease_out_stop,
+                })
+                .collect(),
+        };
+        serde_json::to_string_pretty(&json).map_err(invalid)
+    }
+
+    /// Parse the JSON format described in the [module docs](crate::json).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use avatar_anim::Animation;
+    ///
+    /// # fn main() -> avatar_anim::Result<()> {
+    /// let mut anim = Animation::new();
+    /// anim.header.duration = 1.5;
+    /// let text = anim.to_json()?;
+    /// assert_eq!(Animation::from_json(&text)?, anim);
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Fails on malformed JSON, a key without `time` or `tick`, a key time outside
+    /// `0..=duration`, or a rotation that doesn't give exactly one of `quat` and `euler`.
+    pub fn from_json(text: &str) -> Result<Self> {
+        let json: AnimationJson = serde_json::from_str(text).map_err(invalid)?;
+        let duration = json.duration;
+        let tick = |tick: Option<u16>, t: Option<f32>| -> Result<u16> {
+            if let Some(tick) = tick {
+                return Ok(tick);
+            }
+            let Some(t) = t else {
+                return Err(invalid("key without a time or tick"));
+            };
+            // Half a tick of slack for times that went through float formatting.
+            let slack = duration / u16::MAX as f32 / 2.0;
+            if !(t.is_finite() && t >= -slack && t <= duration + slack) {
+                return Err(invalid(format!("key time {t} outside 0..={duration}")));
+            }
+            Ok(if duration > 0.0 {
+                ((t / duration).clamp(0.0, 1.0) * u16::MAX as f32).round() as u16
+            } else {
+                0
+            })
+        };
+
+        let mut joints = Vec::with_capacity(json.joints.len());
+        for joint in json.joints {
+            let mut data = JointData {
+                name: joint.name,
+                priority: joint.priority,
+                ..Default::default()
+            };
+            for key in joint.rotations {
+                let rot = match (key.quat, key.euler) {
+                    (Some(q), None) => Quat::from_array(q).normalize(),
+                    (None, Some(degrees)) => RotationOrder::Xyz.compose(degrees),
+                    _ => {
+                        let at = match (key.tick, key.time) {
+                            (Some(tick), _) => format!("tick {tick}"),
+                            (None, Some(t)) => format!("{t}s"),
+                            (None, None) => "no time".into(),
+                        };
+                        return Err(invalid(format!(
+                            "rotation of {} at {at} needs exactly one of quat and euler",
+                            data.name
+                        )));
+                    }
+                };
+                data.rotation_keys.push(RotationKey {
+                    time: tick(key.tick, key.time)?,
+                    rot,
+                });
+            }
+            for key in joint.positions {
+                data.position_keys.push(PositionKey {
+                    time: tick(key.tick, key.time)?,
+                    pos: Vec3::from_array(key.pos),
+                });
+            }
+            joints.push(data);
+        }
+
+        Ok(Animation {
+            header: AnimationHeader {
+                version: json.version,
+                sub_version: json.sub_version,
+                base_priority: json.base_priority,
+                duration,
+                emote_name: json.emote_name,
+                loop_in_point: json.loop_in_point,
+                loop_out_point: json.loop_out_point,
+                looped: i32::from(json.looped),
+                ease_in_duration: json.ease_in_duration,
+                ease_out_duration: json.ease_out_duration,
+                hand_pose: json.hand_pose,
+            },
+            joints,
+            constraints: json
+                .constraints
+                .into_iter()
+                .map(|c| Constraint {
+                    chain_length: c.chain_length,
+                    constraint_type: c.constraint_type,
+                    source_volume: c.source_volume,
+                    source_offset: c.source_offset,
+                    target_volume: c.target_volume,
+                    target_offset: c.target_offset,
+                    target_dir: c.target_dir,
+                    ease_in_start: c.ease_in_start,
+                    ease_in_stop: c.ease_in_stop,
+                    ease_out_start: c.ease_out_start,
+                    ease_out_stop: c.ease_out_stop,
+                })
+                .collect(),
+            trailing_data: Vec::new(),
+            edit_log: None,
+        })
+    }
+}
@@ -0,0 +1,223 @@
+//! Tolerant reading of animations exported before the 1.0 layout settled.
+//!
+//! The viewer still accepts version 0.1 files, whose keys hold an `f32` time in seconds and
+//! three `f32` components: Euler angles in degrees for rotations, meters for positions, 16
+//! bytes per key instead of 8. Some early exports also end right after the joint table,
+//! without a constraint count. [`Animation::read_legacy`] reads either, reports which quirks
+//! it found, and returns an ordinary [`Animation`] that writes in the modern layout.
+//!
+//! ```rust,no_run
+//! use avatar_anim::Animation;
+//!
+//! # fn main() -> avatar_anim::Result<()> {
+//! let legacy = Animation::read_legacy(&std::fs::read("old_wave.anim")?)?;
+//! for quirk in &legacy.quirks {
+//!     println!("{quirk}");
+//! }
+//! legacy.animation.to_file("wave.anim")?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::math;
+use crate::view::{Bytes, CONSTRAINT_SIZE};
+use crate::{
+    AnimError, Animation, AnimationHeader, Constraint, JointData, PositionKey, Result, RotationKey,
+};
+use binrw::BinRead;
+use glam::Vec3;
+
+/// Version of the float-key layout.
+const FLOAT_KEY_VERSION: (u16, u16) = (0, 1);
+
+/// A deviation from the 1.0 layout found by [`Animation::read_legacy`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum LegacyQuirk {
+    /// Version 0.1 float keys: times in seconds, rotations as Euler degrees. They were
+    /// quantized into the modern encoding.
+    FloatKeys,
+    /// The file ends after the joint table; read as having no constraints.
+    MissingConstraintCount,
+    /// Float key times outside `0..=duration`, clamped to the nearest end.
+    KeyTimesOutOfRange { count: usize },
+}
+
+impl std::fmt::Display for LegacyQuirk {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LegacyQuirk::FloatKeys => {
+                f.write_str("version 0.1 float keys (seconds and Euler degrees)")
+            }
+            LegacyQuirk::MissingConstraintCount => {
+                f.write_str("no constraint count after the joint table")
+            }
+            LegacyQuirk::KeyTimesOutOfRange { count } => {
+                write!(f, "{count} key times outside the duration were clamped")
+            }
+        }
+    }
+}
+
+/// Result of [`Animation::read_legacy`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct LegacyRead {
+    /// The animation, with the header version set to 1.0.
+    pub animation: Animation,
+    /// Version and sub-version stored in the file.
+    pub version: (u16, u16),
+    /// Empty for a file in the modern layout.
+    pub quirks: Vec<LegacyQuirk>,
+}
+
+impl LegacyRead {
+    /// Whether the file needed any legacy handling.
+    pub fn is_legacy(&self) -> bool {
+        !self.quirks.is_empty()
+    }
+}
+
+struct Reader<'a> {
+    r: Bytes<'a>,
+    duration: f32,
+    out_of_range: usize,
+}
+
+impl Reader<'_> {
+    fn vec3(&mut self, what: &'static str) -> Result<Vec3> {
+        Ok(Vec3::new(
+            self.r.f32(what)?,
+            self.r.f32(what)?,
+            self.r.f32(what)?,
+        ))
+    }
+
+    fn tick(&mut self, what: &'static str) -> Result<u16> {
+        let t = self.r.f32(what)?;
+        if !(0.0..=self.duration).contains(&t) {
+            self.out_of_range += 1;
+        }
+        Ok(if self.duration > 0.0 && t.is_finite() {
+            ((t / self.duration).clamp(0.0, 1.0) * u16::MAX as f32).round() as u16
+        } else {
+            0
+        })
+    }
+
+    fn joint(&mut self, float_keys: bool) -> Result<JointData> {
+        let mut joint = JointData {
+            name: self.r.str("a joint name")?.to_string(),
+            priority: self.r.i32("a joint priority")?,
+            ..Default::default()
+        };
+        let rotations = self.r.count("a rotation key count")?;
+        for _ in 0..rotations {
+            joint.rotation_keys.push(if float_keys {
+                let time = self.tick("a rotation key")?;
+                // The viewer decodes these with `mayaQ(x, y, z, ZYX)`.
+                let degrees = self.vec3("a rotation key")?;
+                RotationKey {
+                    time,
+                    rot: math::quat_from_viewer_euler(degrees),
+                }
+            } else {
+                crate::view::rotation_key(self.r.take(crate::view::KEY_SIZE, "a rotation key")?)
+            });
+        }
+        let positions = self.r.count("a position key count")?;
+        for _ in 0..positions {
+            joint.position_keys.push(if float_keys {
+                PositionKey {
+                    time: self.tick("a position key")?,
+                    pos: self.vec3("a position key")?,
+                }
+            } else {
+                crate::view::position_key(self.r.take(crate::view::KEY_SIZE, "a position key")?)
+            });
+        }
+        Ok(joint)
+    }
+}
+
+impl Animation {
+    /// Read `.anim` bytes in the 1.0 layout or one of the older ones described in the
+    /// [module docs](crate::legacy), reporting what was old about them.
+    ///
+    /// # Errors
+    ///
+    /// Fails with [`AnimError::Decode`] where the data ends early or holds a negative count,
+    /// and with [`AnimError::InvalidStructure`] for versions other than 1.0 and 0.1.
+    pub fn read_legacy(bytes: &[u8]) -> Result<LegacyRead> {
+        const HEADER: &str = "the header";
+        let mut r = Bytes::new(bytes);
+        let version = (r.u16(HEADER)?, r.u16(HEADER)?);
+        let float_keys = version == FLOAT_KEY_VERSION;
+        if !float_keys && version != (1, 0) {
+            return Err(AnimError::InvalidStructure(format!(
+                "Unknown animation version {}.{}",
+                version.0, version.1
+            )));
+        }
+        let header = AnimationHeader {
+            version: 1,
+            sub_version: 0,
+            base_priority: r.i32(HEADER)?,
+            duration: r.f32(HEADER)?,
+            emote_name: r.str("the emote name")?.to_string(),
+            loop_in_point: r.f32(HEADER)?,
+            loop_out_point: r.f32(HEADER)?,
+            looped: r.i32(HEADER)?,
+            ease_in_duration: r.f32(HEADER)?,
+            ease_out_duration: r.f32(HEADER)?,
+            hand_pose: r.u32(HEADER)?,
+        };
+        let joint_count = r.u32("the joint count")? as usize;
+        let mut reader = Reader {
+            r,
+            duration: header.duration,
+            out_of_range: 0,
+        };
+        let mut joints = Vec::with_capacity(joint_count.min(512));
+        for index in 0..joint_count {
+            joints.push(
+                reader
+                    .joint(float_keys)
+                    .map_err(|err| err.in_joint(index, None))?,
+            );
+        }
+
+        let mut quirks = Vec::new();
+        if float_keys {
+            quirks.push(LegacyQuirk::FloatKeys);
+        }
+        if reader.out_of_range > 0 {
+            quirks.push(LegacyQuirk::KeyTimesOutOfRange {
+                count: reader.out_of_range,
+            });
+        }
+        let mut r = reader.r;
+        let mut constraints = Vec::new();
+        if r.offset() == bytes.len() {
+            quirks.push(LegacyQuirk::MissingConstraintCount);
+        } else {
+            for _ in 0..r.count("the constraint count")? {
+                let at = r.offset();
+                let raw = r.take(CONSTRAINT_SIZE, "a constraint")?;
+                let constraint = Constraint::read(&mut std::io::Cursor::new(raw))
+                    .map_err(|err| crate::recover::locate(&bytes[..at + raw.len()], err))?;
+                constraints.push(constraint);
+            }
+        }
+
+        Ok(LegacyRead {
+            animation: Animation {
+                header,
+                joints,
+                constraints,
+                trailing_data: Vec::new(),
+                edit_log: None,
+            },
+            version,
+            quirks,
+        })
+    }
+}
@@ -1,28 +1,86 @@
 use binrw::binrw;
-use glam::{EulerRot, Quat, Vec3};
+use glam::{Quat, Vec3};
+#[cfg(feature = "llsd")]
 use llsd_rs::Llsd;
-use std::collections::HashSet;
-use thiserror::Error;
 
+pub mod analysis;
+#[cfg(feature = "tokio")]
+mod async_io;
+#[cfg(any(feature = "bvh", feature = "collada"))]
+mod bake;
+pub mod batch;
+#[cfg(feature = "bvh")]
+pub mod bvh;
+pub mod cache;
+mod capabilities;
+#[cfg(feature = "cli")]
+pub mod cli;
+#[cfg(feature = "collada")]
+pub mod collada;
+pub mod compare;
+mod compose;
+mod convert;
+pub mod dashboard;
+mod ease;
+mod editlog;
+mod error;
+pub mod frame;
+pub mod gesture;
+#[cfg(feature = "gltf")]
+pub mod gltf;
+#[cfg(feature = "llsd")]
+pub mod inventory;
 pub mod io;
-
+pub mod joints;
+#[cfg(feature = "serde")]
+pub mod json;
+pub mod legacy;
+mod limits;
+#[cfg(feature = "llsd")]
+mod llsd_binary;
+pub mod manifest;
+pub mod math;
+mod mirror;
+mod open;
+pub mod pose;
+pub mod prelude;
+mod recover;
+mod reduce;
+mod report;
+mod retarget;
+mod rewrite;
+pub mod sample;
+#[cfg(feature = "serde")]
+pub mod service;
+pub mod skeleton;
+mod time;
+pub mod track;
+mod twist;
+mod validate;
+pub mod view;
+
+use crate::error::{NegativeCount, Section};
 use crate::io::*;
 
 pub use AnimError as Error;
+pub use capabilities::{Capabilities, capabilities};
+pub use compose::MergeStrategy;
+pub use ease::{EaseFix, EaseProblem};
+pub use editlog::{EditCounts, EditEntry, EditLog, EditOperation};
+pub use error::{AnimError, ErrorCode};
+pub use limits::{Limit, ReadOptions};
+pub use open::{AnimInput, SourceFormat, open_any, open_as};
+pub use pose::Pose;
+pub use recover::PartialRead;
+pub use reduce::ReductionMode;
+pub use report::{ParseReport, ParseWarning};
+pub use sample::{JointSample, WrapMode};
+pub use skeleton::Skeleton;
+pub use time::LoopFix;
+pub use track::Track;
+pub use validate::{HeaderViolation, Issue, Severity, Target, ValidationReport, WriteOptions};
 pub type Result<T> = std::result::Result<T, AnimError>;
 
-#[derive(Debug, Error)]
-pub enum AnimError {
-    #[error("I/O error: {0}")]
-    Io(#[from] std::io::Error),
-    #[error("Binary parsing error: {0}")]
-    BinRw(#[from] binrw::Error),
-    #[error("Invalid structure: {0}")]
-    InvalidStructure(String),
-    #[error("LLSD parse error: {0}")]
-    Llsd(String),
-}
-
 #[binrw]
 #[brw(little)]
 #[derive(Clone, Debug, PartialEq)]
@@ -60,6 +118,30 @@ impl Default for AnimationHeader {
     }
 }
 
+impl AnimationHeader {
+    /// Viewer name of `hand_pose` (e.g. `"Fist"` for 3), or `None` for values the viewer
+    /// doesn't define.
+    pub fn hand_pose_name(&self) -> Option<&'static str> {
+        const HAND_POSES: [&str; 14] = [
+            "Spread",
+            "Relaxed",
+            "Point",
+            "Fist",
+            "Relaxed Left",
+            "Point Left",
+            "Fist Left",
+            "Relaxed Right",
+            "Point Right",
+            "Fist Right",
+            "Salute Right",
+            "Typing",
+            "Peace Right",
+            "Palm Right",
+        ];
+        HAND_POSES.get(self.hand_pose as usize).copied()
+    }
+}
+
 #[binrw]
 #[brw(little)]
 #[derive(Clone, Debug, Default, PartialEq)]
@@ -101,13 +183,13 @@ pub struct JointData {
     pub name: String,
     pub priority: i32,
 
-    #[br(temp)]
+    #[br(temp, assert(num_rot_keys >= 0, NegativeCount(num_rot_keys)))]
     #[bw(calc = rotation_keys.len() as i32)]
     num_rot_keys: i32,
     #[br(count = num_rot_keys)]
     pub rotation_keys: Vec<RotationKey>,
 
-    #[br(temp)]
+    #[br(temp, assert(num_pos_keys >= 0, NegativeCount(num_pos_keys)))]
     #[bw(calc = position_keys.len() as i32)]
     num_pos_keys: i32,
     #[br(count = num_pos_keys)]
@@ -123,14 +205,14 @@ pub struct Constraint {
 
     #[br(count = 16)]
     #[br(parse_with = read_fixed_length_string)]
-    #[bw(write_with = write_fixed_length_string)]
+    #[bw(write_with = write_fixed_length_string, args { count: 16 })]
     pub source_volume: String,
 
     pub source_offset: [f32; 3],
 
     #[br(count = 16)]
     #[br(parse_with = read_fixed_length_string)]
-    #[bw(write_with = write_fixed_length_string)]
+    #[bw(write_with = write_fixed_length_string, args { count: 16 })]
     pub target_volume: String,
 
     pub target_offset: [f32; 3],
@@ -143,21 +225,42 @@ pub struct Constraint {
 
 #[binrw]
 #[brw(little)]
-#[derive(Clone, Debug, Default, PartialEq)]
+#[derive(Clone, Debug, Default)]
+#[non_exhaustive]
 pub struct Animation {
     pub header: AnimationHeader,
 
-    #[br(temp)]
+    #[br(temp, err_context(Section::JointTable))]
     #[bw(calc = joints.len() as u32)]
     num_joints: u32,
-    #[br(count = num_joints)]
+    #[br(count = num_joints, err_context(Section::JointTable))]
     pub joints: Vec<JointData>,
 
-    #[br(temp)]
+    #[br(temp, err_context(Section::Constraints))]
+    #[br(assert(num_constraints >= 0, NegativeCount(num_constraints)))]
     #[bw(calc = constraints.len() as i32)]
     num_constraints: i32,
-    #[br(count = num_constraints)]
+    #[br(count = num_constraints, err_context(Section::Constraints))]
     pub constraints: Vec<Constraint>,
+
+    /// Unrecognized bytes after the constraint block, written back verbatim. Only filled in
+    /// when reading with [`ReadOptions::keep_trailing_bytes`].
+    #[br(ignore)]
+    pub trailing_data: Vec<u8>,
+
+    /// Record of the edits made since [`Animation::start_edit_log`]; never read or written.
+    #[brw(ignore)]
+    pub edit_log: Option<EditLog>,
+}
+
+/// Compares the animation data only; the [`Animation::edit_log`] is provenance, not content.
+impl PartialEq for Animation {
+    fn eq(&self, other: &Self) -> bool {
+        self.header == other.header
+            && self.joints == other.joints
+            && self.constraints == other.constraints
+            && self.trailing_data == other.trailing_data
+    }
 }
 
 /// Strategy for handling duplicate keyframe times when cleaning up keys.
@@ -167,62 +270,35 @@ pub enum DuplicateKeyStrategy {
     KeepFirst,
     /// Keep the last encountered key.
     KeepLast,
-    /// Average all keys with the same timestamp (rotation via progressive slerp, position via arithmetic mean).
+    /// Average all keys with the same timestamp (rotation via [`math::average_quats`], position
+    /// via arithmetic mean).
     Average,
 }
 
-fn group_average_rot(keys: &[RotationKey]) -> Vec<RotationKey> {
-    if keys.is_empty() {
-        return Vec::new();
-    }
-    let mut out = Vec::new();
-    let mut i = 0usize;
-    while i < keys.len() {
-        let t = keys[i].time;
-        let mut acc = glam::Quat::IDENTITY;
-        let mut count = 0f32;
-        let mut j = i;
-        while j < keys.len() && keys[j].time == t {
-            acc = if count == 0.0 {
-                keys[j].rot
-            } else {
-                acc.slerp(keys[j].rot, 1.0 / (count + 1.0))
-            };
-            count += 1.0;
-            j += 1;
-        }
-        out.push(RotationKey {
-            time: t,
-            rot: acc.normalize(),
-        });
-        i = j;
+/// Result of [`Animation::to_file_if_changed`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum WriteOutcome {
+    /// The file was missing or differed and has been (re)written.
+    Written,
+    /// The file already held exactly these bytes and was not touched.
+    Unchanged,
+}
+
+struct Fnv1a(u64);
+
+impl Default for Fnv1a {
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
     }
-    out
 }
 
-fn group_average_pos(keys: &[PositionKey]) -> Vec<PositionKey> {
-    if keys.is_empty() {
-        return Vec::new();
-    }
-    let mut out = Vec::new();
-    let mut i = 0usize;
-    while i < keys.len() {
-        let t = keys[i].time;
-        let mut acc = glam::Vec3::ZERO;
-        let mut count = 0.0f32;
-        let mut j = i;
-        while j < keys.len() && keys[j].time == t {
-            acc += keys[j].pos;
-            count += 1.0;
-            j += 1;
+impl Fnv1a {
+    fn write(&mut self, bytes: &[u8]) {
+        for b in bytes {
+            self.0 ^= *b as u64;
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
         }
-        out.push(PositionKey {
-            time: t,
-            pos: acc / count,
-        });
-        i = j;
     }
-    out
 }
 
 impl Animation {
@@ -230,109 +306,198 @@ impl Animation {
         Self::default()
     }
 
+    /// Build an animation from its parts, with no trailing data.
+    pub fn from_parts(
+        header: AnimationHeader,
+        joints: Vec<JointData>,
+        constraints: Vec<Constraint>,
+    ) -> Self {
+        Self {
+            header,
+            joints,
+            constraints,
+            ..Self::default()
+        }
+    }
+
     pub fn set_priority(&mut self, priority: i32) -> &mut Self {
+        let mark = self.edit_mark();
         self.header.base_priority = priority;
         for joint in &mut self.joints {
             joint.priority = priority;
         }
+        self.log_edit(mark, EditOperation::SetPriority, &[("priority", &priority)]);
         self
     }
 
     pub fn set_joint_priority(&mut self, priority: i32) -> &mut Self {
+        let mark = self.edit_mark();
         for joint in &mut self.joints {
             joint.priority = priority;
         }
+        self.log_edit(
+            mark,
+            EditOperation::SetJointPriority,
+            &[("priority", &priority)],
+        );
         self
     }
 
     pub fn drop_empty_joints(&mut self) -> &mut Self {
+        let mark = self.edit_mark();
         self.joints
             .retain(|joint| !joint.position_keys.is_empty() || !joint.rotation_keys.is_empty());
+        self.log_edit(mark, EditOperation::DropEmptyJoints, &[]);
         self
     }
 
     pub fn drop_position_keys(&mut self) -> &mut Self {
+        let mark = self.edit_mark();
         for joint in &mut self.joints {
             joint.position_keys.clear();
         }
+        self.log_edit(mark, EditOperation::DropPositionKeys, &[]);
         self
     }
 
     pub fn drop_position(&mut self, joints: impl Fn(&JointData) -> bool) -> &mut Self {
+        let mark = self.edit_mark();
+        let mut dropped = Vec::new();
         for joint in &mut self.joints {
             if joints(joint) {
                 joint.position_keys.clear();
+                dropped.push(joint.name.as_str());
             }
         }
+        let dropped = dropped.join(",");
+        self.log_edit(mark, EditOperation::DropPosition, &[("joints", &dropped)]);
         self
     }
 
     pub fn drop_rotation_keys(&mut self) -> &mut Self {
+        let mark = self.edit_mark();
         for joint in &mut self.joints {
             joint.rotation_keys.clear();
         }
+        self.log_edit(mark, EditOperation::DropRotationKeys, &[]);
         self
     }
 
     pub fn drop_rotation(&mut self, joints: impl Fn(&JointData) -> bool) -> &mut Self {
+        let mark = self.edit_mark();
+        let mut dropped = Vec::new();
         for joint in &mut self.joints {
             if joints(joint) {
                 joint.rotation_keys.clear();
+                dropped.push(joint.name.as_str());
             }
         }
+        let dropped = dropped.join(",");
+        self.log_edit(mark, EditOperation::DropRotation, &[("joints", &dropped)]);
         self
     }
 
+    /// Keep only the joints on the kinematic chain from `from` down to `to` (see
+    /// [`Skeleton::chain`]), e.g. `mChest` to `mWristLeft` for an arm overlay.
+    ///
+    /// # Errors
+    ///
+    /// Fails without modifying the animation if `skeleton` has no such chain.
+    pub fn retain_chain(&mut self, skeleton: &Skeleton, from: &str, to: &str) -> Result<&mut Self> {
+        let Some(chain) = skeleton.chain(from, to) else {
+            return Err(AnimError::InvalidStructure(format!(
+                "No joint chain from '{from}' to '{to}'"
+            )));
+        };
+        let mark = self.edit_mark();
+        self.joints
+            .retain(|joint| chain.contains(&joint.name.as_str()));
+        self.log_edit(
+            mark,
+            EditOperation::RetainChain,
+            &[("from", &from), ("to", &to)],
+        );
+        Ok(self)
+    }
+
+    /// Joints of the subtree rooted at `root`, `root` included.
+    fn subtree<'a>(skeleton: &'a Skeleton, root: &'a str) -> Result<Vec<&'a str>> {
+        let Some(below) = skeleton.joints_below(root) else {
+            return Err(AnimError::InvalidStructure(format!(
+                "Unknown joint '{root}'"
+            )));
+        };
+        let mut subtree = vec![root];
+        subtree.extend(below);
+        Ok(subtree)
+    }
+
+    /// Remove `root` and every joint below it in `skeleton`, e.g. `mCollarLeft` for the
+    /// whole left arm or `mFaceRoot` for the Bento face.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use avatar_anim::{Animation, JointData, Skeleton};
+    ///
+    /// # fn main() -> avatar_anim::Result<()> {
+    /// let mut anim = Animation::new();
+    /// for name in ["mChest", "mCollarLeft", "mElbowLeft", "mHandIndex2Left", "mCollarRight"] {
+    ///     anim.joints.push(JointData { name: name.into(), ..Default::default() });
+    /// }
+    /// anim.drop_subtree(&Skeleton::bento(), "mCollarLeft")?;
+    /// let names: Vec<&str> = anim.joints.iter().map(|j| j.name.as_str()).collect();
+    /// assert_eq!(names, ["mChest", "mCollarRight"]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Fails without modifying the animation if `skeleton` has no joint `root`.
+    pub fn drop_subtree(&mut self, skeleton: &Skeleton, root: &str) -> Result<&mut Self> {
+        let subtree = Self::subtree(skeleton, root)?;
+        let mark = self.edit_mark();
+        self.joints
+            .retain(|joint| !subtree.contains(&joint.name.as_str()));
+        self.log_edit(mark, EditOperation::DropSubtree, &[("root", &root)]);
+        Ok(self)
+    }
+
+    /// Keep only `root` and the joints below it in `skeleton`, e.g. `mFaceRoot` for a facial
+    /// expression overlay.
+    ///
+    /// # Errors
+    ///
+    /// Fails without modifying the animation if `skeleton` has no joint `root`.
+    pub fn keep_subtree(&mut self, skeleton: &Skeleton, root: &str) -> Result<&mut Self> {
+        let subtree = Self::subtree(skeleton, root)?;
+        let mark = self.edit_mark();
+        self.joints
+            .retain(|joint| subtree.contains(&joint.name.as_str()));
+        self.log_edit(mark, EditOperation::KeepSubtree, &[("root", &root)]);
+        Ok(self)
+    }
+
+    /// Sort keys by time and keep the last of each duplicate time.
     pub fn cleanup_keys(&mut self) -> &mut Self {
-        for joint in &mut self.joints {
-            let mut seen_times = HashSet::new();
-            joint.rotation_keys.reverse();
-            joint
-                .rotation_keys
-                .retain(|key| seen_times.insert(key.time));
-            joint.rotation_keys.sort_by_key(|key| key.time);
-            seen_times.clear();
-            joint.position_keys.reverse();
-            joint
-                .position_keys
-                .retain(|key| seen_times.insert(key.time));
-            joint.position_keys.sort_by_key(|key| key.time);
-        }
-        self
+        self.cleanup_keys_with(DuplicateKeyStrategy::KeepLast)
     }
 
     /// Cleanup duplicate keyframe times with a customizable strategy.
+    ///
+    /// Keys need not be sorted beforehand; see [`Track::from_keys`].
     pub fn cleanup_keys_with(&mut self, strategy: DuplicateKeyStrategy) -> &mut Self {
+        let mark = self.edit_mark();
         for joint in &mut self.joints {
-            match strategy {
-                DuplicateKeyStrategy::KeepFirst => {
-                    let mut seen = HashSet::new();
-                    joint.rotation_keys.retain(|k| seen.insert(k.time));
-                    seen.clear();
-                    joint.position_keys.retain(|k| seen.insert(k.time));
-                }
-                DuplicateKeyStrategy::KeepLast => {
-                    // Retain last: iterate reverse, keep first occurrence in reverse order.
-                    let mut seen = HashSet::new();
-                    joint.rotation_keys.reverse();
-                    joint.rotation_keys.retain(|k| seen.insert(k.time));
-                    joint.rotation_keys.reverse();
-                    seen.clear();
-                    joint.position_keys.reverse();
-                    joint.position_keys.retain(|k| seen.insert(k.time));
-                    joint.position_keys.reverse();
-                }
-                DuplicateKeyStrategy::Average => {
-                    // Group by time then average.
-                    joint.rotation_keys.sort_by_key(|k| k.time);
-                    joint.position_keys.sort_by_key(|k| k.time);
-                    joint.rotation_keys = group_average_rot(&joint.rotation_keys);
-                    joint.position_keys = group_average_pos(&joint.position_keys);
-                }
-            }
-            joint.rotation_keys.sort_by_key(|k| k.time);
-            joint.position_keys.sort_by_key(|k| k.time);
+            joint.rotation_keys = Track::from_keys(&joint.rotation_keys, strategy).into_vec();
+            joint.position_keys = Track::from_keys(&joint.position_keys, strategy).into_vec();
         }
+        self.log_edit(
+            mark,
+            EditOperation::CleanupKeysWith,
+            &[("strategy", &format_args!("{strategy:?}"))],
+        );
         self
     }
 
@@ -344,6 +509,89 @@ impl Animation {
         self.joints.iter_mut().find(|joint| joint.name == name)
     }
 
+    /// The first joint named `name`, appended without keys if there is none.
+    pub fn joint_or_insert(&mut self, name: &str) -> &mut JointData {
+        let index = match self.joints.iter().position(|joint| joint.name == name) {
+            Some(index) => index,
+            None => {
+                self.joints.push(JointData {
+                    name: name.to_string(),
+                    priority: self.header.base_priority,
+                    ..Default::default()
+                });
+                self.joints.len() - 1
+            }
+        };
+        &mut self.joints[index]
+    }
+
+    /// Stable 64-bit content fingerprint (FNV-1a) over the data as it would be written to disk.
+    ///
+    /// Keys are hashed in their quantized form, so two animations that serialize to the same
+    /// `.anim` bytes share a fingerprint. [`Animation::trailing_data`] is hashed last, so
+    /// files differing only in a vendor trailer don't.
+    pub fn fingerprint(&self) -> u64 {
+        let mut h = Fnv1a::default();
+        let header = &self.header;
+        h.write(&header.version.to_le_bytes());
+        h.write(&header.sub_version.to_le_bytes());
+        h.write(&header.base_priority.to_le_bytes());
+        h.write(&header.duration.to_le_bytes());
+        h.write(header.emote_name.as_bytes());
+        h.write(&[0]);
+        h.write(&header.loop_in_point.to_le_bytes());
+        h.write(&header.loop_out_point.to_le_bytes());
+        h.write(&header.looped.to_le_bytes());
+        h.write(&header.ease_in_duration.to_le_bytes());
+        h.write(&header.ease_out_duration.to_le_bytes());
+        h.write(&header.hand_pose.to_le_bytes());
+        h.write(&(self.joints.len() as u32).to_le_bytes());
+        for joint in &self.joints {
+            h.write(joint.name.as_bytes());
+            h.write(&[0]);
+            h.write(&joint.priority.to_le_bytes());
+            h.write(&(joint.rotation_keys.len() as u32).to_le_bytes());
+            for key in &joint.rotation_keys {
+                let (x, y, z) = quantize_rotation(key.rot);
+                for v in [key.time, x, y, z] {
+                    h.write(&v.to_le_bytes());
+                }
+            }
+            h.write(&(joint.position_keys.len() as u32).to_le_bytes());
+            for key in &joint.position_keys {
+                let (x, y, z) = quantize_position(key.pos);
+                for v in [key.time, x, y, z] {
+                    h.write(&v.to_le_bytes());
+                }
+            }
+        }
+        h.write(&(self.constraints.len() as u32).to_le_bytes());
+        for c in &self.constraints {
+            h.write(&[c.chain_length, c.constraint_type]);
+            h.write(c.source_volume.as_bytes());
+            h.write(&[0]);
+            h.write(c.target_volume.as_bytes());
+            h.write(&[0]);
+            let eases = [
+                c.ease_in_start,
+                c.ease_in_stop,
+                c.ease_out_start,
+                c.ease_out_stop,
+            ];
+            let floats = c
+                .source_offset
+                .iter()
+                .chain(&c.target_offset)
+                .chain(&c.target_dir)
+                .chain(&eases);
+            for f in floats {
+                h.write(&f.to_le_bytes());
+            }
+        }
+        h.write(&self.trailing_data);
+        h.0
+    }
+
     /// Creates an animation from LLSD data, typically from Firestorm poser files.
     ///
     /// This function parses LLSD-XML data exported by Firestorm's poser system and converts
@@ -379,9 +627,10 @@ impl Animation {
     /// # Ok(())
     /// # }
     /// ```
+    #[cfg(feature = "llsd")]
     pub fn from_llsd(llsd: &Llsd, check_enabled: bool) -> Result<Self> {
         let Some(joints) = llsd.as_map() else {
-            return Err(AnimError::InvalidStructure("LLSD must be a map".into()));
+            return Err(AnimError::LlsdShape("LLSD must be a map".into()));
         };
         let mut animation = Self::default();
         for (key, value) in joints {
@@ -406,7 +655,7 @@ impl Animation {
             };
             let rotation = extract_key("rotation").map(|(roll, pitch, yaw)| RotationKey {
                 time: u16::MAX,
-                rot: Quat::from_euler(EulerRot::XYZ, roll, pitch, yaw).normalize(),
+                rot: math::from_viewer_euler(Vec3::new(roll, pitch, yaw)),
             });
             let position = extract_key("position").map(|(x, y, z)| PositionKey {
                 time: u16::MAX,
@@ -435,14 +684,41 @@ impl Animation {
     /// # }
     /// ```
     pub fn from_file<P: AsRef<std::path::Path>>(path: P) -> Result<Self> {
+        Self::from_bytes(&std::fs::read(path).map_err(AnimError::Io)?)
+    }
+
+    /// Load an animation from any seekable source, such as an in-memory cache entry or a
+    /// file inside an archive.
+    ///
+    /// Reading stops at the end of the animation; the source is left positioned there. No
+    /// [`ReadOptions`] limits apply, so use [`Animation::read_with_options`] for untrusted data.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use avatar_anim::Animation;
+    /// use std::io::Cursor;
+    ///
+    /// # fn main() -> avatar_anim::Result<()> {
+    /// let mut buffer = Cursor::new(Vec::new());
+    /// Animation::new().to_writer(&mut buffer)?;
+    /// buffer.set_position(0);
+    /// assert_eq!(Animation::from_reader(buffer)?, Animation::new());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn from_reader<R: std::io::Read + std::io::Seek>(mut reader: R) -> Result<Self> {
         use binrw::BinRead;
-        use std::fs::File;
-        use std::io::BufReader;
-        let file = File::open(path).map_err(AnimError::Io)?;
-        let mut reader = BufReader::new(file);
         Self::read(&mut reader).map_err(AnimError::BinRw)
     }
 
+    /// Load an animation from a source that can't seek, like a network stream or a
+    /// decompressor, by buffering it to the end first. At most
+    /// [`ReadOptions::max_bytes`] are buffered, as in [`Animation::read_with_options`].
+    pub fn from_stream<R: std::io::Read>(reader: R) -> Result<Self> {
+        Self::read_with_options(reader, &ReadOptions::default())
+    }
+
     /// Save an animation to a .anim file
     ///
     /// # Example
@@ -457,15 +733,112 @@ impl Animation {
     /// # }
     /// ```
     pub fn to_file<P: AsRef<std::path::Path>>(&self, path: P) -> Result<()> {
+        use std::io::Write;
+        let file = std::fs::File::create(path).map_err(AnimError::Io)?;
+        let mut writer = std::io::BufWriter::new(file);
+        self.to_writer(&mut writer)?;
+        writer.flush().map_err(AnimError::Io)
+    }
+
+    /// Write the `.anim` encoding to any seekable sink; see [`Animation::to_stream`] for
+    /// sinks that can't seek.
+    pub fn to_writer<W: std::io::Write + std::io::Seek>(&self, mut writer: W) -> Result<()> {
         use binrw::BinWrite;
-        use std::fs::File;
-        use std::io::BufWriter;
-        let file = File::create(path).map_err(AnimError::Io)?;
-        let mut writer = BufWriter::new(file);
         self.write(&mut writer).map_err(AnimError::BinRw)
     }
 
-    /// Load LLSD-XML data from a Firestorm pose file
+    /// Write the `.anim` encoding to a sink that can't seek, like a socket, a compressor or
+    /// a zip entry.
+    ///
+    /// The encoding is streamed through a small buffer (wrapped in [`io::NoSeek`]) instead of
+    /// being built in memory first; the sink is flushed at the end.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use avatar_anim::Animation;
+    ///
+    /// # fn main() -> avatar_anim::Result<()> {
+    /// let mut sink = Vec::new();
+    /// Animation::new().to_stream(&mut sink)?;
+    /// assert_eq!(sink, Animation::new().to_bytes()?);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn to_stream<W: std::io::Write>(&self, writer: W) -> Result<()> {
+        use binrw::BinWrite;
+        use std::io::Write;
+        let mut writer = io::NoSeek::new(std::io::BufWriter::new(writer));
+        self.write(&mut writer).map_err(AnimError::BinRw)?;
+        writer.flush().map_err(AnimError::Io)
+    }
+
+    /// Size of the `.anim` encoding in bytes, without encoding it.
+    pub fn encoded_len(&self) -> usize {
+        // Fixed header fields plus the joint and constraint counts.
+        const HEADER: usize = 2 + 2 + 4 + 4 + 4 * 6 + 4 + 4;
+        const KEY: usize = 8;
+        const JOINT: usize = 4 + 4 + 4;
+        const CONSTRAINT: usize = 2 + 16 + 12 + 16 + 12 + 12 + 16;
+        HEADER
+            + self.header.emote_name.len()
+            + 1
+            + self
+                .joints
+                .iter()
+                .map(|joint| {
+                    JOINT
+                        + joint.name.len()
+                        + 1
+                        + KEY * (joint.rotation_keys.len() + joint.position_keys.len())
+                })
+                .sum::<usize>()
+            + CONSTRAINT * self.constraints.len()
+            + self.trailing_data.len()
+    }
+
+    /// Decode an `.anim` asset blob within the [`ReadOptions::default`] limits.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use avatar_anim::Animation;
+    ///
+    /// # fn main() -> avatar_anim::Result<()> {
+    /// let bytes = Animation::new().to_bytes()?;
+    /// assert_eq!(Animation::from_bytes(&bytes)?, Animation::new());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        Self::from_bytes_with_options(bytes, &ReadOptions::default())
+    }
+
+    /// Encode as an `.anim` asset blob, allocating [`Animation::encoded_len`] bytes once.
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let mut bytes = std::io::Cursor::new(Vec::with_capacity(self.encoded_len()));
+        self.to_writer(&mut bytes)?;
+        Ok(bytes.into_inner())
+    }
+
+    /// Save to a .anim file unless it already contains exactly the same encoding.
+    ///
+    /// Leaving identical files alone keeps their modification times, so watch modes and
+    /// incremental asset builds don't re-trigger on no-op conversions.
+    pub fn to_file_if_changed<P: AsRef<std::path::Path>>(&self, path: P) -> Result<WriteOutcome> {
+        let bytes = self.to_bytes()?;
+        let path = path.as_ref();
+        if std::fs::read(path).is_ok_and(|existing| existing == bytes) {
+            return Ok(WriteOutcome::Unchanged);
+        }
+        std::fs::write(path, bytes).map_err(AnimError::Io)?;
+        Ok(WriteOutcome::Written)
+    }
+
+    /// Load a Firestorm pose file, as LLSD-XML or binary LLSD
+    ///
+    /// Binary LLSD is recognized by its `<? LLSD/Binary ?>` header or, without one, by a
+    /// leading binary map; anything else is parsed as XML.
     ///
     /// # Example
     ///
@@ -477,12 +850,77 @@ impl Animation {
     /// # Ok(())
     /// # }
     /// ```
+    #[cfg(feature = "llsd")]
     pub fn from_llsd_file<P: AsRef<std::path::Path>>(path: P, check_enabled: bool) -> Result<Self> {
-        use std::fs::File;
-        use std::io::BufReader;
-        let file = File::open(path).map_err(AnimError::Io)?;
-        let reader = BufReader::new(file);
-        let llsd = llsd_rs::xml::from_reader(reader).map_err(|e| AnimError::Llsd(e.to_string()))?;
+        let bytes = std::fs::read(path).map_err(AnimError::Io)?;
+        let format = match open::sniff(&bytes)? {
+            SourceFormat::LlsdBinary => SourceFormat::LlsdBinary,
+            _ => SourceFormat::LlsdXml,
+        };
+        let llsd = open::decode_llsd(&bytes, format)?;
         Self::from_llsd(&llsd, check_enabled)
     }
+
+    /// Convert to a Firestorm poser LLSD map, the inverse of [`Animation::from_llsd`].
+    ///
+    /// A pose file holds a single pose, so each joint's final pose (its last key) is written,
+    /// with `enabled` set. Rotations become the poser's Euler angles in radians (see
+    /// [`math::euler_for_viewer`] for the order); joints
+    /// without position keys get no `position` entry.
+    #[cfg(feature = "llsd")]
+    pub fn to_llsd(&self) -> Llsd {
+        let triple =
+            |values: [f32; 3]| Llsd::Array(values.iter().map(|&v| Llsd::Real(v as f64)).collect());
+        let mut joints = std::collections::HashMap::new();
+        for joint in &self.joints {
+            let mut entry = std::collections::HashMap::new();
+            entry.insert("enabled".to_string(), Llsd::Boolean(true));
+            if let Some(rot) = joint.rotation_at(u16::MAX as f32) {
+                let euler = math::viewer_euler(rot);
+                entry.insert("rotation".to_string(), triple(euler.to_array()));
+            }
+            if let Some(pos) = joint.position_at(u16::MAX as f32) {
+                entry.insert("position".to_string(), triple(pos.to_array()));
+            }
+            joints.insert(joint.name.clone(), Llsd::Map(entry));
+        }
+        Llsd::Map(joints)
+    }
+
+    /// Save as a Firestorm poser LLSD-XML file (see [`Animation::to_llsd`]).
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use avatar_anim::Animation;
+    ///
+    /// # fn main() -> avatar_anim::Result<()> {
+    /// let animation = Animation::from_file("pose.anim")?;
+    /// animation.to_llsd_file("pose.xml")?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "llsd")]
+    pub fn to_llsd_file<P: AsRef<std::path::Path>>(&self, path: P) -> Result<()> {
+        let xml = llsd_rs::xml::to_pretty_string(&self.to_llsd())
+            .map_err(|e| AnimError::Llsd(e.to_string()))?;
+        std::fs::write(path, xml).map_err(AnimError::Io)
+    }
+
+    /// Encode [`Animation::to_llsd`] as binary LLSD, with the `<? LLSD/Binary ?>` header
+    /// viewers write in front of it.
+    #[cfg(feature = "llsd")]
+    pub fn to_llsd_binary(&self) -> Result<Vec<u8>> {
+        let mut bytes = open::LLSD_BINARY_HEADER.to_vec();
+        bytes.push(b'\n');
+        llsd_rs::binary::write(&self.to_llsd(), &mut bytes)
+            .map_err(|e| AnimError::Llsd(e.to_string()))?;
+        Ok(bytes)
+    }
+
+    /// Save as a binary LLSD pose file (see [`Animation::to_llsd_binary`]).
+    #[cfg(feature = "llsd")]
+    pub fn to_llsd_binary_file<P: AsRef<std::path::Path>>(&self, path: P) -> Result<()> {
+        std::fs::write(path, self.to_llsd_binary()?).map_err(AnimError::Io)
+    }
 }
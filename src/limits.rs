@@ -0,0 +1,242 @@
+use crate::io::{VIEWER_ZERO_SNAP, decode_position, decode_rotation};
+use crate::view::{AnimView, Bytes, CONSTRAINT_SIZE, KEY_SIZE};
+use crate::{AnimError, Animation, Result};
+use std::io::Read;
+
+/// A bound enforced by [`ReadOptions`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum Limit {
+    Joints,
+    /// Rotation or position keys of a single joint.
+    KeysPerJoint,
+    Constraints,
+    /// Bytes in the emote name or a joint name.
+    StringLength,
+    /// Bytes of the whole input.
+    Size,
+}
+
+impl std::fmt::Display for Limit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Limit::Joints => "joint count",
+            Limit::KeysPerJoint => "keys per joint",
+            Limit::Constraints => "constraint count",
+            Limit::StringLength => "string length",
+            Limit::Size => "size in bytes",
+        })
+    }
+}
+
+/// Bounds on what a parse may allocate, for reading untrusted `.anim` data.
+///
+/// A declared count is checked before anything is allocated for it, so a crafted file that
+/// claims `u32::MAX` joints fails fast with [`AnimError::LimitExceeded`] instead of exhausting
+/// memory. The defaults are well above anything the viewer produces.
+///
+/// # Example
+///
+/// ```rust
+/// use avatar_anim::{Animation, ErrorCode, ReadOptions};
+///
+/// let mut bytes = Animation::new().to_bytes().unwrap();
+/// bytes.extend_from_slice(b"junk");
+/// assert!(Animation::from_bytes(&bytes).is_ok());
+///
+/// let strict = ReadOptions { reject_trailing_bytes: true, ..Default::default() };
+/// let err = Animation::from_bytes_with_options(&bytes, &strict).unwrap_err();
+/// assert_eq!(err.code(), ErrorCode::TrailingBytes);
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ReadOptions {
+    /// Largest input in bytes. Default 64 MiB; a reader is not read past it.
+    pub max_bytes: usize,
+    /// Default 512; the viewer skeleton with Bento and collision volumes has fewer than 200.
+    pub max_joints: usize,
+    /// Rotation keys, and separately position keys, per joint. Default 65 536, one per tick.
+    pub max_keys_per_joint: usize,
+    /// Default 256; the viewer plays at most 10.
+    pub max_constraints: usize,
+    /// Longest emote or joint name in bytes. Default 255.
+    pub max_string_length: usize,
+    /// Fail with [`AnimError::TrailingBytes`] when data follows the constraint block, which
+    /// the viewer ignores but an upload validator should not. Default `false`.
+    pub reject_trailing_bytes: bool,
+    /// Keep data after the constraint block in [`Animation::trailing_data`], which some
+    /// tools use for vendor data, so that writing reproduces the input byte for byte.
+    /// Ignored when `reject_trailing_bytes` is set. Default `false`.
+    pub keep_trailing_bytes: bool,
+    /// Key components within this many quantization steps of zero are read as exactly `0.0`.
+    /// Default 1, what the viewer does, so that zero (which has no exact encoding) round
+    /// trips. It also zeroes tiny deliberate values, such as a pelvis offset under 0.2 mm;
+    /// 0 keeps every component as stored. [`Animation::read_with_report`] tells when the
+    /// snap zeroed something.
+    pub zero_snap_quanta: u16,
+    /// Replace header times that are not numbers or implausible (negative ease windows,
+    /// anything over an hour) with usable values, so that a NaN duration doesn't turn every
+    /// computation in seconds into NaN. Default `false`, which reads the header as stored;
+    /// [`Animation::read_with_report`] reports such values either way.
+    pub sanitize_header: bool,
+}
+
+impl Default for ReadOptions {
+    fn default() -> Self {
+        Self {
+            max_bytes: 64 << 20,
+            max_joints: 512,
+            max_keys_per_joint: 65_536,
+            max_constraints: 256,
+            max_string_length: 255,
+            reject_trailing_bytes: false,
+            keep_trailing_bytes: false,
+            zero_snap_quanta: VIEWER_ZERO_SNAP,
+            sanitize_header: false,
+        }
+    }
+}
+
+fn check(limit: Limit, max: usize, found: usize) -> Result<()> {
+    if found > max {
+        Err(AnimError::LimitExceeded { limit, max, found })
+    } else {
+        Ok(())
+    }
+}
+
+impl ReadOptions {
+    /// Walk the counts and names of `bytes`, failing on the first one over a limit, and on
+    /// trailing bytes if those are rejected.
+    ///
+    /// Malformed data is not an error here: the walk just stops, and the full decode that
+    /// follows reports it. Any count the decoder reaches has been checked by then.
+    pub(crate) fn check(&self, bytes: &[u8]) -> Result<()> {
+        check(Limit::Size, self.max_bytes, bytes.len())?;
+        let mut r = Bytes::new(bytes);
+        match self.walk(&mut r) {
+            Err(err @ AnimError::LimitExceeded { .. }) => Err(err),
+            Ok(()) if self.reject_trailing_bytes && r.offset() < bytes.len() => {
+                Err(AnimError::TrailingBytes {
+                    count: bytes.len() - r.offset(),
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn walk(&self, r: &mut Bytes) -> Result<()> {
+        const HEADER: &str = "the header";
+        r.skip(12, HEADER)?;
+        check(
+            Limit::StringLength,
+            self.max_string_length,
+            r.str_len("the emote name")?,
+        )?;
+        r.skip(24, HEADER)?;
+        let joints = r.u32("the joint count")? as usize;
+        check(Limit::Joints, self.max_joints, joints)?;
+        for _ in 0..joints {
+            check(
+                Limit::StringLength,
+                self.max_string_length,
+                r.str_len("a joint name")?,
+            )?;
+            r.skip(4, "a joint priority")?;
+            for _ in 0..2 {
+                let keys = r.count("a key count")?;
+                check(Limit::KeysPerJoint, self.max_keys_per_joint, keys)?;
+                r.skip(keys * KEY_SIZE, "keys")?;
+            }
+        }
+        let constraints = r.count("the constraint count")?;
+        check(Limit::Constraints, self.max_constraints, constraints)?;
+        r.skip(constraints * CONSTRAINT_SIZE, "constraints")
+    }
+}
+
+impl Animation {
+    /// Read an animation from untrusted data, enforcing `options` before allocating.
+    ///
+    /// The source is read to the end first, but no further than
+    /// [`ReadOptions::max_bytes`]; [`Animation::from_bytes`] and [`Animation::from_file`]
+    /// apply [`ReadOptions::default`] the same way.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use avatar_anim::{AnimError, Animation, Limit, ReadOptions};
+    ///
+    /// let bytes = Animation::new().to_bytes().unwrap();
+    /// let strict = ReadOptions { max_joints: 0, ..Default::default() };
+    /// assert!(Animation::read_with_options(&bytes[..], &strict).is_ok());
+    ///
+    /// let mut hostile = bytes.clone();
+    /// let count_at = hostile.len() - 8; // joint count of an animation without joints
+    /// hostile[count_at..count_at + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+    /// let err = Animation::read_with_options(&hostile[..], &ReadOptions::default()).unwrap_err();
+    /// assert!(matches!(err, AnimError::LimitExceeded { limit: Limit::Joints, .. }));
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Fails with [`AnimError::LimitExceeded`] when a limit is exceeded, otherwise like
+    /// [`Animation::from_bytes`]. For a source longer than `max_bytes`, `found` is
+    /// `max_bytes + 1`, as far as it was read.
+    pub fn read_with_options<R: Read>(reader: R, options: &ReadOptions) -> Result<Self> {
+        let mut bytes = Vec::new();
+        let bound = (options.max_bytes as u64).saturating_add(1);
+        reader
+            .take(bound)
+            .read_to_end(&mut bytes)
+            .map_err(AnimError::Io)?;
+        Self::from_bytes_with_options(&bytes, options)
+    }
+
+    /// Decode an `.anim` blob, enforcing `options` before allocating.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use avatar_anim::{Animation, ReadOptions};
+    ///
+    /// let mut bytes = Animation::new().to_bytes().unwrap();
+    /// bytes.extend_from_slice(b"vendor data");
+    /// let keep = ReadOptions { keep_trailing_bytes: true, ..Default::default() };
+    /// let anim = Animation::from_bytes_with_options(&bytes, &keep).unwrap();
+    /// assert_eq!(anim.trailing_data, b"vendor data");
+    /// assert_eq!(anim.to_bytes().unwrap(), bytes);
+    /// ```
+    pub fn from_bytes_with_options(bytes: &[u8], options: &ReadOptions) -> Result<Self> {
+        options.check(bytes)?;
+        let mut cursor = std::io::Cursor::new(bytes);
+        let mut anim = <Self as binrw::BinRead>::read(&mut cursor)
+            .map_err(|err| crate::recover::locate(bytes, err))?;
+        if options.keep_trailing_bytes {
+            anim.trailing_data = bytes[cursor.position() as usize..].to_vec();
+        }
+        if options.zero_snap_quanta != VIEWER_ZERO_SNAP {
+            anim.decode_keys_with_snap(bytes, options.zero_snap_quanta)?;
+        }
+        if options.sanitize_header {
+            crate::report::sanitize_header_times(&mut anim.header);
+        }
+        Ok(anim)
+    }
+
+    /// Decode every key value again from `bytes`, which `self` was read from, with a
+    /// different zero snap.
+    fn decode_keys_with_snap(&mut self, bytes: &[u8], snap_quanta: u16) -> Result<()> {
+        let codes = |key: &[u8]| [2, 4, 6].map(|at| u16::from_le_bytes([key[at], key[at + 1]]));
+        for (joint, view) in self.joints.iter_mut().zip(AnimView::parse(bytes)?.joints()) {
+            let view = view?;
+            let stored = view.encoded_rotations().chunks_exact(KEY_SIZE);
+            for (key, stored) in joint.rotation_keys.iter_mut().zip(stored) {
+                key.rot = decode_rotation(codes(stored), snap_quanta);
+            }
+            let stored = view.encoded_positions().chunks_exact(KEY_SIZE);
+            for (key, stored) in joint.position_keys.iter_mut().zip(stored) {
+                key.pos = decode_position(codes(stored), snap_quanta);
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,82 @@
+//! Binary LLSD reader.
+//!
+//! `llsd_rs::binary` writes arrays as `[` count items `]` but its reader never consumes the
+//! closing `]`, so any map following an array fails to parse. Containers are read here and
+//! scalars are left to `llsd_rs`.
+
+use llsd_rs::Llsd;
+use std::collections::HashMap;
+use std::io::{Cursor, Read};
+
+type Reader<'a> = Cursor<&'a [u8]>;
+
+/// Nesting limit; poses are two levels deep.
+const MAX_DEPTH: usize = 64;
+
+fn read_u8(r: &mut Reader) -> Result<u8, String> {
+    let mut b = [0u8; 1];
+    r.read_exact(&mut b).map_err(|e| e.to_string())?;
+    Ok(b[0])
+}
+
+fn read_len(r: &mut Reader) -> Result<usize, String> {
+    let mut b = [0u8; 4];
+    r.read_exact(&mut b).map_err(|e| e.to_string())?;
+    Ok(u32::from_be_bytes(b) as usize)
+}
+
+fn expect(r: &mut Reader, byte: u8) -> Result<(), String> {
+    match read_u8(r)? {
+        b if b == byte => Ok(()),
+        b => Err(format!(
+            "expected '{}' at offset {}, found 0x{b:02x}",
+            byte as char,
+            r.position() - 1
+        )),
+    }
+}
+
+fn read_value(r: &mut Reader, depth: usize) -> Result<Llsd, String> {
+    if depth == 0 {
+        return Err("binary LLSD nested too deeply".into());
+    }
+    // Reserve no more than the remaining input could possibly hold.
+    let remaining = |r: &Reader| r.get_ref().len().saturating_sub(r.position() as usize);
+    match read_u8(r)? {
+        b'[' => {
+            let len = read_len(r)?;
+            let mut items = Vec::with_capacity(len.min(remaining(r)));
+            for _ in 0..len {
+                items.push(read_value(r, depth - 1)?);
+            }
+            expect(r, b']')?;
+            Ok(Llsd::Array(items))
+        }
+        b'{' => {
+            let len = read_len(r)?;
+            let mut map = HashMap::with_capacity(len.min(remaining(r)));
+            for _ in 0..len {
+                expect(r, b'k')?;
+                let key_len = read_len(r)?;
+                let mut key = vec![0u8; key_len.min(remaining(r))];
+                r.read_exact(&mut key).map_err(|e| e.to_string())?;
+                if key.len() != key_len {
+                    return Err("binary LLSD key runs past the end of the input".into());
+                }
+                let key = String::from_utf8(key).map_err(|e| e.to_string())?;
+                map.insert(key, read_value(r, depth - 1)?);
+            }
+            expect(r, b'}')?;
+            Ok(Llsd::Map(map))
+        }
+        _ => {
+            r.set_position(r.position() - 1);
+            llsd_rs::binary::from_reader(r).map_err(|e| e.to_string())
+        }
+    }
+}
+
+/// Parse one binary LLSD value (without the `<? LLSD/Binary ?>` header).
+pub(crate) fn from_slice(bytes: &[u8]) -> Result<Llsd, String> {
+    read_value(&mut Cursor::new(bytes), MAX_DEPTH)
+}
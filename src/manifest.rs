@@ -0,0 +1,323 @@
+use crate::Animation;
+use std::fmt::Write as _;
+
+/// Animation override slot an animation most likely belongs to.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum Slot {
+    Stand,
+    Walk,
+    Run,
+    Sit,
+    GroundSit,
+    Crouch,
+    CrouchWalk,
+    Jump,
+    Fall,
+    Land,
+    Fly,
+    Hover,
+    Turn,
+    Dance,
+    Gesture,
+    Other,
+}
+
+impl Slot {
+    pub fn name(self) -> &'static str {
+        match self {
+            Slot::Stand => "stand",
+            Slot::Walk => "walk",
+            Slot::Run => "run",
+            Slot::Sit => "sit",
+            Slot::GroundSit => "ground_sit",
+            Slot::Crouch => "crouch",
+            Slot::CrouchWalk => "crouch_walk",
+            Slot::Jump => "jump",
+            Slot::Fall => "fall",
+            Slot::Land => "land",
+            Slot::Fly => "fly",
+            Slot::Hover => "hover",
+            Slot::Turn => "turn",
+            Slot::Dance => "dance",
+            Slot::Gesture => "gesture",
+            Slot::Other => "other",
+        }
+    }
+
+    /// Guess the slot from the animation's name, falling back to its loop flag.
+    ///
+    /// The name is split into words at `_`, `-`, spaces, digits and camelCase humps, and a
+    /// keyword must be a whole word or run of adjacent words (`"ground_sit"`, `"GroundSit"`),
+    /// optionally inflected (`"walking"`, `"sits"`, `"dancer"`), so `"position"` or `"prune"`
+    /// don't match. Matching is case-insensitive; the more specific keyword wins
+    /// (`"gsit"`/`"groundsit"` before `"sit"`, `"crouchwalk"` before `"walk"`).
+    pub fn suggest(name: &str, anim: &Animation) -> Self {
+        const KEYWORDS: &[(&str, Slot)] = &[
+            ("groundsit", Slot::GroundSit),
+            ("gsit", Slot::GroundSit),
+            ("crouchwalk", Slot::CrouchWalk),
+            ("crouch", Slot::Crouch),
+            ("walk", Slot::Walk),
+            ("run", Slot::Run),
+            ("sit", Slot::Sit),
+            ("jump", Slot::Jump),
+            ("fall", Slot::Fall),
+            ("land", Slot::Land),
+            ("hover", Slot::Hover),
+            ("fly", Slot::Fly),
+            ("turn", Slot::Turn),
+            ("dance", Slot::Dance),
+            ("stand", Slot::Stand),
+            ("idle", Slot::Stand),
+        ];
+        let words = words(name);
+        let phrases: Vec<String> = (0..words.len())
+            .flat_map(|start| (start + 1..=words.len()).map(move |end| (start, end)))
+            .map(|(start, end)| words[start..end].concat())
+            .collect();
+        let found = KEYWORDS.iter().find(|(keyword, _)| {
+            phrases
+                .iter()
+                .any(|phrase| is_inflection_of(phrase, keyword))
+        });
+        if let Some((_, slot)) = found {
+            return *slot;
+        }
+        if anim.header.looped == 0 {
+            Slot::Gesture
+        } else {
+            Slot::Other
+        }
+    }
+}
+
+/// Lowercase words of `name`, split at non-letters and where a lowercase letter is followed
+/// by an uppercase one.
+fn words(name: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut previous_lower = false;
+    for c in name.chars() {
+        let boundary = !c.is_alphabetic() || (previous_lower && c.is_uppercase());
+        if boundary && !word.is_empty() {
+            words.push(std::mem::take(&mut word));
+        }
+        if c.is_alphabetic() {
+            word.extend(c.to_lowercase());
+        }
+        previous_lower = c.is_lowercase();
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    words
+}
+
+/// Whether `word` is `keyword` or a regular inflection of it: `-s`, `-ing`, `-er` or `-ed`,
+/// dropping a final `e` or doubling a final consonant as English does.
+fn is_inflection_of(word: &str, keyword: &str) -> bool {
+    const SUFFIXES: [&str; 5] = ["", "s", "ing", "er", "ed"];
+    let doubled = keyword
+        .chars()
+        .last()
+        .map(|last| format!("{keyword}{last}"));
+    let stems = [
+        Some(keyword.to_string()),
+        keyword.strip_suffix('e').map(str::to_string),
+        doubled,
+    ];
+    stems.iter().flatten().any(|stem| {
+        word.strip_prefix(stem.as_str())
+            .is_some_and(|rest| SUFFIXES.contains(&rest))
+    })
+}
+
+/// Metadata for one animation of a set, as consumed by HUD and AO scripts.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ManifestEntry {
+    /// Inventory name, usually the file stem.
+    pub name: String,
+    pub duration: f32,
+    pub priority: i32,
+    pub looped: bool,
+    pub joints: Vec<String>,
+    pub fingerprint: u64,
+    pub slot: Slot,
+}
+
+impl ManifestEntry {
+    pub fn new(name: impl Into<String>, anim: &Animation) -> Self {
+        let name = name.into();
+        Self {
+            slot: Slot::suggest(&name, anim),
+            duration: anim.header.duration,
+            priority: anim.header.base_priority,
+            looped: anim.header.looped != 0,
+            joints: anim.joints.iter().map(|j| j.name.clone()).collect(),
+            fingerprint: anim.fingerprint(),
+            name,
+        }
+    }
+}
+
+pub(crate) fn json_string(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+fn json_number(out: &mut String, value: f32) {
+    if value.is_finite() {
+        let _ = write!(out, "{value}");
+    } else {
+        out.push_str("null");
+    }
+}
+
+/// Render entries as a JSON array of objects.
+pub fn to_json(entries: &[ManifestEntry]) -> String {
+    let mut out = String::from("[\n");
+    for (i, entry) in entries.iter().enumerate() {
+        out.push_str("  {\"name\": ");
+        json_string(&mut out, &entry.name);
+        out.push_str(", \"duration\": ");
+        json_number(&mut out, entry.duration);
+        let _ = write!(
+            out,
+            ", \"priority\": {}, \"looped\": {}, \"slot\": ",
+            entry.priority, entry.looped
+        );
+        json_string(&mut out, entry.slot.name());
+        let _ = write!(
+            out,
+            ", \"fingerprint\": \"{:016x}\", \"joints\": [",
+            entry.fingerprint
+        );
+        for (j, joint) in entry.joints.iter().enumerate() {
+            if j > 0 {
+                out.push_str(", ");
+            }
+            json_string(&mut out, joint);
+        }
+        out.push_str("]}");
+        out.push_str(if i + 1 < entries.len() { ",\n" } else { "\n" });
+    }
+    out.push(']');
+    out.push('\n');
+    out
+}
+
+/// Render entries as notecard lines for `llParseString2List(line, ["|"], [])`.
+///
+/// Each line is `name|duration|priority|looped|slot|fingerprint`; lines starting with `#` are
+/// comments. Durations use four decimals, `looped` is `1`/`0` like LSL booleans.
+pub fn to_notecard(entries: &[ManifestEntry]) -> String {
+    let mut out = String::from("# name|duration|priority|looped|slot|fingerprint\n");
+    for entry in entries {
+        let _ = writeln!(
+            out,
+            "{}|{:.4}|{}|{}|{}|{:016x}",
+            entry.name.replace('|', "_"),
+            entry.duration,
+            entry.priority,
+            entry.looped as u8,
+            entry.slot.name(),
+            entry.fingerprint
+        );
+    }
+    out
+}
+
+/// Shape of the LSL generated by [`to_lsl`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum LslTemplate {
+    /// One strided `list <SLOT>_ANIMS = [...]` initializer of `name, duration, looped`
+    /// triples per suggested slot, for AO scripts.
+    Ao,
+    /// A single strided `DANCES` list of `name, duration, looped` triples for dance HUDs.
+    DanceHud,
+}
+
+fn lsl_string(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+/// The body of a strided list initializer: one `name, duration, looped` line per entry.
+///
+/// LSL has no float literal for NaN or infinity, so a non-finite duration is written as
+/// `0.0` with a comment naming the bad value.
+fn lsl_triples(out: &mut String, entries: &[&ManifestEntry]) {
+    for (i, entry) in entries.iter().enumerate() {
+        out.push_str("    ");
+        lsl_string(out, &entry.name);
+        let duration = if entry.duration.is_finite() {
+            entry.duration
+        } else {
+            0.0
+        };
+        let _ = write!(
+            out,
+            ", {:.4}, {}{}",
+            duration,
+            if entry.looped { "TRUE" } else { "FALSE" },
+            if i + 1 < entries.len() { "," } else { "" }
+        );
+        if !entry.duration.is_finite() {
+            let _ = write!(out, " // invalid duration {}", entry.duration);
+        }
+        out.push('\n');
+    }
+}
+
+/// Render entries as LSL global declarations ready to paste into a script.
+///
+/// Both templates list `name, duration, looped` triples, with the stride declared as
+/// `ANIM_STRIDE` or `DANCE_STRIDE`.
+pub fn to_lsl(entries: &[ManifestEntry], template: LslTemplate) -> String {
+    let mut out = String::from("// Generated from the animation files; do not edit by hand.\n");
+    match template {
+        LslTemplate::Ao => {
+            let mut slots: Vec<Slot> = Vec::new();
+            for entry in entries {
+                if !slots.contains(&entry.slot) {
+                    slots.push(entry.slot);
+                }
+            }
+            out.push_str("integer ANIM_STRIDE = 3;\n");
+            for slot in slots {
+                let _ = writeln!(out, "list {}_ANIMS = [", slot.name().to_ascii_uppercase());
+                let in_slot: Vec<&ManifestEntry> =
+                    entries.iter().filter(|e| e.slot == slot).collect();
+                lsl_triples(&mut out, &in_slot);
+                out.push_str("];\n");
+            }
+        }
+        LslTemplate::DanceHud => {
+            out.push_str("integer DANCE_STRIDE = 3;\nlist DANCES = [\n");
+            lsl_triples(&mut out, &entries.iter().collect::<Vec<_>>());
+            out.push_str("];\n");
+        }
+    }
+    out
+}
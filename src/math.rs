@@ -0,0 +1,189 @@
+//! Quaternion helpers shared by reduction, editing and analysis.
+//!
+//! All functions expect unit quaternions. `q` and `-q` describe the same rotation; the helpers
+//! here pick the short way round so callers don't have to.
+
+use glam::{EulerRot, Mat4, Quat, Vec3, Vec4};
+
+/// Return `q` or `-q`, whichever lies in the same hemisphere as `reference`.
+///
+/// Chain this over a track before interpolating or differencing it to avoid 360° detours.
+pub fn same_hemisphere(reference: Quat, q: Quat) -> Quat {
+    if reference.dot(q) < 0.0 { -q } else { q }
+}
+
+/// Quaternion logarithm: the rotation axis scaled by *half* the rotation angle.
+///
+/// The result is taken for the representative with a non-negative `w`, so `q` and `-q` map to
+/// the same vector.
+pub fn log(q: Quat) -> Vec3 {
+    let q = if q.w < 0.0 { -q } else { q };
+    let v = Vec3::new(q.x, q.y, q.z);
+    let len = v.length();
+    if len <= f32::EPSILON {
+        return Vec3::ZERO;
+    }
+    v * (len.atan2(q.w) / len)
+}
+
+/// Inverse of [`log`].
+pub fn exp(v: Vec3) -> Quat {
+    let half = v.length();
+    if half <= f32::EPSILON {
+        return Quat::IDENTITY;
+    }
+    let (sin, cos) = half.sin_cos();
+    let axis = v * (sin / half);
+    Quat::from_xyzw(axis.x, axis.y, axis.z, cos)
+}
+
+/// Euler angles in radians as the viewer computes them (`LLQuaternion::getEulerAngles`):
+/// `[roll, pitch, yaw]` about X, Y and Z, applied in that order about the fixed axes.
+///
+/// With SL's X forward, Y left and Z up, roll tilts about the forward axis, pitch nods and yaw
+/// turns. This is the order the Firestorm poser stores; it is not glam's `EulerRot::XYZ`,
+/// which applies Z first.
+pub(crate) fn viewer_euler(q: Quat) -> Vec3 {
+    let (yaw, pitch, roll) = q.normalize().to_euler(EulerRot::ZYX);
+    Vec3::new(roll, pitch, yaw)
+}
+
+/// Inverse of [`viewer_euler`].
+pub(crate) fn from_viewer_euler(radians: Vec3) -> Quat {
+    Quat::from_euler(EulerRot::ZYX, radians.z, radians.y, radians.x).normalize()
+}
+
+/// The rotation as the degrees the Firestorm poser panel displays: `[roll, pitch, yaw]`
+/// about the joint's X (forward), Y (left) and Z (up) axes, roll applied first.
+///
+/// Pitch stays within ±90°; roll and yaw within ±180°.
+///
+/// # Example
+///
+/// ```rust
+/// use avatar_anim::math::{euler_for_viewer, quat_from_viewer_euler};
+/// use glam::{Quat, Vec3};
+///
+/// let nod = Quat::from_rotation_y(20f32.to_radians());
+/// assert!(euler_for_viewer(nod).abs_diff_eq(Vec3::new(0.0, 20.0, 0.0), 1e-4));
+///
+/// let q = quat_from_viewer_euler(Vec3::new(10.0, -30.0, 45.0));
+/// assert!(euler_for_viewer(q).abs_diff_eq(Vec3::new(10.0, -30.0, 45.0), 1e-3));
+/// ```
+pub fn euler_for_viewer(q: Quat) -> Vec3 {
+    let radians = viewer_euler(q);
+    Vec3::new(
+        radians.x.to_degrees(),
+        radians.y.to_degrees(),
+        radians.z.to_degrees(),
+    )
+}
+
+/// Inverse of [`euler_for_viewer`]: the rotation for poser panel degrees
+/// `[roll, pitch, yaw]`.
+pub fn quat_from_viewer_euler(degrees: Vec3) -> Quat {
+    from_viewer_euler(Vec3::new(
+        degrees.x.to_radians(),
+        degrees.y.to_radians(),
+        degrees.z.to_radians(),
+    ))
+}
+
+/// Mirror a joint-local rotation across the avatar's left/right (XZ) plane.
+///
+/// Rotations about X (roll) and Z (yaw) change direction, rotations about Y (pitch) don't.
+pub fn mirror_rotation(q: Quat) -> Quat {
+    Quat::from_xyzw(-q.x, q.y, -q.z, q.w)
+}
+
+/// Mirror a position or offset across the avatar's left/right (XZ) plane.
+pub fn mirror_position(v: Vec3) -> Vec3 {
+    Vec3::new(v.x, -v.y, v.z)
+}
+
+/// Angle in radians of the shortest rotation taking `a` to `b`, in `0..=π`.
+///
+/// Numerically stable for nearly identical rotations, where `acos` of the dot product is not.
+pub fn geodesic_distance(a: Quat, b: Quat) -> f32 {
+    let d = a.conjugate() * b;
+    let v = Vec3::new(d.x, d.y, d.z).length();
+    2.0 * v.atan2(d.w.abs())
+}
+
+/// World-space angular velocity (axis times radians per second) turning `from` into `to` over
+/// `dt` seconds. Returns zero for a non-positive `dt`.
+pub fn angular_velocity(from: Quat, to: Quat, dt: f32) -> Vec3 {
+    if dt <= 0.0 {
+        return Vec3::ZERO;
+    }
+    log(to * from.conjugate()) * (2.0 / dt)
+}
+
+/// Mean rotation of `quats`, independent of their order and of the sign of each one.
+///
+/// This is the eigenvector of `Σ q qᵀ` with the largest eigenvalue (Markley et al.), found by
+/// power iteration from the sign-aligned sum. Progressive slerping instead depends on input
+/// order and goes wrong when inputs alternate between `q` and `-q`. The result lies in the
+/// hemisphere of the first input; an empty slice averages to identity.
+///
+/// # Example
+///
+/// ```rust
+/// use avatar_anim::math::average_quats;
+/// use glam::Quat;
+///
+/// let a = Quat::from_rotation_z(0.2);
+/// let b = Quat::from_rotation_z(0.6);
+/// let mean = average_quats(&[a, -b]);
+/// assert!(mean.abs_diff_eq(Quat::from_rotation_z(0.4), 1e-5));
+/// ```
+pub fn average_quats(quats: &[Quat]) -> Quat {
+    let Some(&first) = quats.first() else {
+        return Quat::IDENTITY;
+    };
+    let mut m = Mat4::ZERO;
+    let mut sum = Vec4::ZERO;
+    for &q in quats {
+        let v = Vec4::from(q);
+        m += Mat4::from_cols(v * v.x, v * v.y, v * v.z, v * v.w);
+        sum += Vec4::from(same_hemisphere(first, q));
+    }
+    let mut v = sum.try_normalize().unwrap_or(Vec4::from(first));
+    for _ in 0..32 {
+        match (m * v).try_normalize() {
+            Some(next) => v = next,
+            None => break,
+        }
+    }
+    same_hemisphere(first, Quat::from_vec4(v))
+}
+
+/// Split `q` into `(swing, twist)` with `q = swing * twist`, where `twist` rotates about `axis`
+/// and `swing` rotates about an axis perpendicular to it.
+///
+/// `axis` need not be normalized. When `q` turns `axis` by exactly 180° the twist is undefined
+/// and identity is returned for it.
+///
+/// # Example
+///
+/// ```rust
+/// use avatar_anim::math::swing_twist;
+/// use glam::{Quat, Vec3};
+///
+/// let q = Quat::from_rotation_z(0.4) * Quat::from_rotation_x(0.9);
+/// let (swing, twist) = swing_twist(q, Vec3::X);
+/// assert!(twist.abs_diff_eq(Quat::from_rotation_x(0.9), 1e-5));
+/// assert!((swing * twist).abs_diff_eq(q, 1e-5));
+/// ```
+pub fn swing_twist(q: Quat, axis: Vec3) -> (Quat, Quat) {
+    let axis = axis.normalize_or_zero();
+    let v = Vec3::new(q.x, q.y, q.z);
+    let p = axis * v.dot(axis);
+    let twist = Quat::from_xyzw(p.x, p.y, p.z, q.w);
+    let twist = if twist.length_squared() <= f32::EPSILON {
+        Quat::IDENTITY
+    } else {
+        twist.normalize()
+    };
+    (q * twist.conjugate(), twist)
+}
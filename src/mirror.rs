@@ -0,0 +1,158 @@
+use crate::joints::{opposite_joint, opposite_volume};
+use crate::math::{mirror_position, mirror_rotation};
+use crate::skeleton::Skeleton;
+use crate::{AnimError, Animation, EditOperation, JointData, PositionKey, Result, RotationKey};
+
+/// `hand_pose` values of the viewer's one-sided poses and their counterparts: relaxed,
+/// point and fist. The other right-hand poses have no left version.
+const HAND_POSE_PAIRS: [(u32, u32); 3] = [(4, 7), (5, 8), (6, 9)];
+
+fn mirror_keys(joint: &JointData) -> (Vec<RotationKey>, Vec<PositionKey>) {
+    let rotation_keys = joint
+        .rotation_keys
+        .iter()
+        .map(|key| RotationKey {
+            time: key.time,
+            rot: mirror_rotation(key.rot),
+        })
+        .collect();
+    let position_keys = joint
+        .position_keys
+        .iter()
+        .map(|key| PositionKey {
+            time: key.time,
+            pos: mirror_position(key.pos),
+        })
+        .collect();
+    (rotation_keys, position_keys)
+}
+
+fn mirror_array([x, y, z]: [f32; 3]) -> [f32; 3] {
+    [x, -y, z]
+}
+
+impl Animation {
+    /// Mirror the whole animation left to right, e.g. to turn a left-handed wave into a
+    /// right-handed one.
+    ///
+    /// Tracks of sided joints move to their counterpart (`mCollarLeft` to `mCollarRight`,
+    /// Bento bones included) and every rotation and position is reflected across the avatar's
+    /// left/right plane. A joint `skeleton` knows whose counterpart it doesn't know is
+    /// reflected in place. Constraints swap their `L_`/`R_` and `LEFT_`/`RIGHT_` collision
+    /// volumes and reflect their offsets and direction, and a one-sided hand pose switches
+    /// hands.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use avatar_anim::{Animation, JointData, RotationKey, Skeleton};
+    /// use glam::Quat;
+    ///
+    /// let mut anim = Animation::new();
+    /// anim.joints.push(JointData {
+    ///     name: "mShoulderLeft".into(),
+    ///     rotation_keys: vec![RotationKey { time: 0, rot: Quat::from_rotation_x(0.5) }],
+    ///     ..Default::default()
+    /// });
+    /// anim.mirror(&Skeleton::bento());
+    /// assert_eq!(anim.joints[0].name, "mShoulderRight");
+    /// let rot = anim.joints[0].rotation_keys[0].rot;
+    /// assert!(rot.abs_diff_eq(Quat::from_rotation_x(-0.5), 1e-6));
+    /// ```
+    pub fn mirror(&mut self, skeleton: &Skeleton) -> &mut Self {
+        let mark = self.edit_mark();
+        for joint in &mut self.joints {
+            let (rotation_keys, position_keys) = mirror_keys(joint);
+            joint.rotation_keys = rotation_keys;
+            joint.position_keys = position_keys;
+            if let Some(opposite) = opposite_joint(&joint.name) {
+                let known = skeleton.index_of(&joint.name).is_some();
+                if !known || skeleton.index_of(&opposite).is_some() {
+                    joint.name = opposite;
+                }
+            }
+        }
+
+        for constraint in &mut self.constraints {
+            for volume in [&mut constraint.source_volume, &mut constraint.target_volume] {
+                if let Some(opposite) = opposite_volume(volume) {
+                    *volume = opposite;
+                }
+            }
+            constraint.source_offset = mirror_array(constraint.source_offset);
+            constraint.target_offset = mirror_array(constraint.target_offset);
+            constraint.target_dir = mirror_array(constraint.target_dir);
+        }
+
+        let hand_pose = &mut self.header.hand_pose;
+        if let Some(&(left, right)) = HAND_POSE_PAIRS
+            .iter()
+            .find(|(left, right)| *hand_pose == *left || *hand_pose == *right)
+        {
+            *hand_pose = if *hand_pose == left { right } else { left };
+        }
+        self.log_edit(mark, EditOperation::Mirror, &[]);
+        self
+    }
+
+    /// Replace the keys of joint `to` with the keys of `from`, mirrored across the avatar's
+    /// left/right plane, e.g. to give the right arm the pose of the left one. `from` keeps its
+    /// keys; `to` is added with the priority of `from` if the animation doesn't have it yet.
+    ///
+    /// [`crate::joints::opposite_joint`] names the usual counterpart; [`Animation::mirror`]
+    /// mirrors every joint at once.
+    ///
+    /// # Errors
+    ///
+    /// Fails with [`AnimError::InvalidStructure`] if `from` is not animated or names the same
+    /// joint as `to`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use avatar_anim::{Animation, JointData, RotationKey};
+    /// use glam::Quat;
+    ///
+    /// # fn main() -> avatar_anim::Result<()> {
+    /// let mut anim = Animation::new();
+    /// anim.joints.push(JointData {
+    ///     name: "mShoulderLeft".into(),
+    ///     rotation_keys: vec![RotationKey { time: 0, rot: Quat::from_rotation_z(0.5) }],
+    ///     ..Default::default()
+    /// });
+    /// anim.mirror_joint("mShoulderLeft", "mShoulderRight")?;
+    /// let right = anim.joint("mShoulderRight").unwrap();
+    /// assert!(right.rotation_keys[0].rot.abs_diff_eq(Quat::from_rotation_z(-0.5), 1e-6));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn mirror_joint(&mut self, from: &str, to: &str) -> Result<&mut Self> {
+        if from == to {
+            return Err(AnimError::InvalidStructure(format!(
+                "Cannot mirror '{from}' onto itself"
+            )));
+        }
+        let Some(source) = self.joint(from) else {
+            return Err(AnimError::InvalidStructure(format!(
+                "Joint '{from}' is not animated"
+            )));
+        };
+        let priority = source.priority;
+        let (rotation_keys, position_keys) = mirror_keys(source);
+
+        let mark = self.edit_mark();
+        let is_new = self.joint(to).is_none();
+        let target = self.joint_or_insert(to);
+        if is_new {
+            target.priority = priority;
+        }
+        target.rotation_keys = rotation_keys;
+        target.position_keys = position_keys;
+        self.log_edit(
+            mark,
+            EditOperation::MirrorJoint,
+            &[("from", &from), ("to", &to)],
+        );
+        Ok(self)
+    }
+}
@@ -0,0 +1,427 @@
+use crate::{AnimError, Animation, ReadOptions, Result};
+use std::path::{Path, PathBuf};
+
+/// Header some writers put in front of binary LLSD.
+pub(crate) const LLSD_BINARY_HEADER: &[u8] = b"<? LLSD/Binary ?>";
+/// Nesting limit for LLSD notation; poses are two levels deep.
+#[cfg(feature = "llsd")]
+const LLSD_NOTATION_MAX_DEPTH: usize = 64;
+
+/// Where [`open_any`] reads from.
+#[derive(Copy, Clone, Debug)]
+pub enum AnimInput<'a> {
+    Path(&'a Path),
+    Bytes(&'a [u8]),
+}
+
+impl<'a> From<&'a Path> for AnimInput<'a> {
+    fn from(path: &'a Path) -> Self {
+        AnimInput::Path(path)
+    }
+}
+
+impl<'a> From<&'a PathBuf> for AnimInput<'a> {
+    fn from(path: &'a PathBuf) -> Self {
+        AnimInput::Path(path)
+    }
+}
+
+impl<'a> From<&'a str> for AnimInput<'a> {
+    fn from(path: &'a str) -> Self {
+        AnimInput::Path(Path::new(path))
+    }
+}
+
+impl<'a> From<&'a [u8]> for AnimInput<'a> {
+    fn from(bytes: &'a [u8]) -> Self {
+        AnimInput::Bytes(bytes)
+    }
+}
+
+impl<'a> From<&'a Vec<u8>> for AnimInput<'a> {
+    fn from(bytes: &'a Vec<u8>) -> Self {
+        AnimInput::Bytes(bytes)
+    }
+}
+
+/// Encoding [`open_any`] recognized. Gzip compression is undone transparently and not reported.
+#[non_exhaustive]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum SourceFormat {
+    /// Binary `.anim`.
+    Anim,
+    /// Firestorm poser LLSD in XML.
+    LlsdXml,
+    /// LLSD notation (`{'mPelvis':{...}}`).
+    LlsdNotation,
+    /// Binary LLSD, with or without the `<? LLSD/Binary ?>` header.
+    LlsdBinary,
+    /// Biovision BVH motion capture.
+    Bvh,
+    /// glTF 2.0, as `.glb` or `.gltf`. Only binary `.glb` is detected by [`open_any`].
+    Gltf,
+    /// The [JSON interchange format](crate::json). Never detected; use [`open_as`].
+    Json,
+    /// COLLADA `.dae`, written by [`Animation::to_collada_writer`]. Export only: see
+    /// [`SourceFormat::is_export_only`].
+    Collada,
+}
+
+impl SourceFormat {
+    /// Every format, in the order their names are listed to users.
+    pub const ALL: [SourceFormat; 8] = [
+        SourceFormat::Anim,
+        SourceFormat::LlsdXml,
+        SourceFormat::LlsdNotation,
+        SourceFormat::LlsdBinary,
+        SourceFormat::Bvh,
+        SourceFormat::Gltf,
+        SourceFormat::Json,
+        SourceFormat::Collada,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            SourceFormat::Anim => "anim",
+            SourceFormat::LlsdXml => "llsd-xml",
+            SourceFormat::LlsdNotation => "llsd-notation",
+            SourceFormat::LlsdBinary => "llsd-binary",
+            SourceFormat::Bvh => "bvh",
+            SourceFormat::Gltf => "gltf",
+            SourceFormat::Json => "json",
+            SourceFormat::Collada => "collada",
+        }
+    }
+
+    /// The format called `name`, as returned by [`SourceFormat::name`].
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|format| format.name() == name)
+    }
+
+    /// The format a file extension usually means: `.anim`, `.xml`, `.llsd` (binary), `.bvh`,
+    /// `.gltf`/`.glb`, `.json` or `.dae`. A trailing `.gz` is skipped.
+    pub fn from_extension(path: &Path) -> Option<Self> {
+        let path = match path.extension() {
+            Some(ext) if ext.eq_ignore_ascii_case("gz") => Path::new(path.file_stem()?),
+            _ => path,
+        };
+        let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+        Some(match ext.as_str() {
+            "anim" => SourceFormat::Anim,
+            "xml" => SourceFormat::LlsdXml,
+            "llsd" => SourceFormat::LlsdBinary,
+            "bvh" => SourceFormat::Bvh,
+            "gltf" | "glb" => SourceFormat::Gltf,
+            "json" => SourceFormat::Json,
+            "dae" => SourceFormat::Collada,
+            _ => return None,
+        })
+    }
+
+    /// The Cargo feature this format needs, if any.
+    pub(crate) fn feature(self) -> Option<&'static str> {
+        match self {
+            SourceFormat::Anim => None,
+            SourceFormat::LlsdXml | SourceFormat::LlsdNotation | SourceFormat::LlsdBinary => {
+                Some("llsd")
+            }
+            SourceFormat::Bvh => Some("bvh"),
+            SourceFormat::Gltf => Some("gltf"),
+            SourceFormat::Json => Some("serde"),
+            SourceFormat::Collada => Some("collada"),
+        }
+    }
+
+    /// Whether the format can only be written; [`open_as`] rejects it as input.
+    pub fn is_export_only(self) -> bool {
+        self == SourceFormat::Collada
+    }
+
+    /// Whether this build can write the format, and read it unless it is
+    /// [export only](SourceFormat::is_export_only). Every other format is supported in both
+    /// directions once its feature is enabled.
+    pub fn is_supported(self) -> bool {
+        match self {
+            SourceFormat::Anim => true,
+            SourceFormat::LlsdXml | SourceFormat::LlsdNotation | SourceFormat::LlsdBinary => {
+                cfg!(feature = "llsd")
+            }
+            SourceFormat::Bvh => cfg!(feature = "bvh"),
+            SourceFormat::Gltf => cfg!(feature = "gltf"),
+            SourceFormat::Json => cfg!(feature = "serde"),
+            SourceFormat::Collada => cfg!(feature = "collada"),
+        }
+    }
+}
+
+/// Serialized as its [`SourceFormat::name`].
+#[cfg(feature = "serde")]
+impl serde::Serialize for SourceFormat {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.name())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for SourceFormat {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        SourceFormat::from_name(&name)
+            .ok_or_else(|| serde::de::Error::custom(format!("unknown format {name:?}")))
+    }
+}
+
+/// The error for a format whose feature is disabled, or for reading an export-only one;
+/// `direction` is "input" or "output".
+pub(crate) fn unsupported(format: SourceFormat, direction: &str) -> AnimError {
+    if format.is_export_only() && direction == "input" {
+        return AnimError::InvalidStructure(format!(
+            "{} input is not supported; the format is export only",
+            format.name()
+        ));
+    }
+    AnimError::InvalidStructure(format!(
+        "{} {direction} requires the `{}` feature",
+        format.name(),
+        format.feature().unwrap_or_default()
+    ))
+}
+
+fn trim_start(bytes: &[u8]) -> &[u8] {
+    let bytes = bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(bytes);
+    let start = bytes
+        .iter()
+        .position(|b| !b.is_ascii_whitespace())
+        .unwrap_or(bytes.len());
+    &bytes[start..]
+}
+
+pub(crate) fn sniff(bytes: &[u8]) -> Result<SourceFormat> {
+    let text = trim_start(bytes);
+    if text.starts_with(LLSD_BINARY_HEADER) {
+        return Ok(SourceFormat::LlsdBinary);
+    }
+    if text.starts_with(b"HIERARCHY") {
+        return Ok(SourceFormat::Bvh);
+    }
+    if bytes.starts_with(b"glTF") {
+        return Ok(SourceFormat::Gltf);
+    }
+    match bytes.first() {
+        // A binary map or array is followed by a big-endian count, whose high byte is zero
+        // for anything that fits in memory.
+        Some(b'{' | b'[') if bytes.get(1) == Some(&0) => Ok(SourceFormat::LlsdBinary),
+        _ => match text.first() {
+            Some(b'<') => Ok(SourceFormat::LlsdXml),
+            Some(b'{') => Ok(SourceFormat::LlsdNotation),
+            _ => Ok(SourceFormat::Anim),
+        },
+    }
+}
+
+/// Decode LLSD in any of its three encodings. `format` must be one of the LLSD formats.
+#[cfg(feature = "llsd")]
+pub(crate) fn decode_llsd(bytes: &[u8], format: SourceFormat) -> Result<llsd_rs::Llsd> {
+    match format {
+        SourceFormat::LlsdXml => llsd_rs::xml::from_slice(bytes).map_err(|e| e.to_string()),
+        SourceFormat::LlsdNotation => {
+            llsd_rs::notation::from_bytes(trim_start(bytes), LLSD_NOTATION_MAX_DEPTH)
+                .map_err(|e| e.to_string())
+        }
+        _ => {
+            let body = trim_start(bytes);
+            let body = body
+                .strip_prefix(LLSD_BINARY_HEADER)
+                .map(trim_start)
+                .unwrap_or(bytes);
+            crate::llsd_binary::from_slice(body)
+        }
+    }
+    .map_err(AnimError::Llsd)
+}
+
+#[cfg(feature = "llsd")]
+fn parse_llsd(bytes: &[u8], format: SourceFormat) -> Result<Animation> {
+    Animation::from_llsd(&decode_llsd(bytes, format)?, true)
+}
+
+#[cfg(not(feature = "llsd"))]
+fn parse_llsd(_: &[u8], format: SourceFormat) -> Result<Animation> {
+    Err(unsupported(format, "input"))
+}
+
+#[cfg(feature = "bvh")]
+fn parse_bvh(bytes: &[u8]) -> Result<Animation> {
+    Animation::from_bvh_reader(trim_start(bytes))
+}
+
+#[cfg(not(feature = "bvh"))]
+fn parse_bvh(_: &[u8]) -> Result<Animation> {
+    Err(unsupported(SourceFormat::Bvh, "input"))
+}
+
+#[cfg(feature = "gltf")]
+fn parse_gltf(bytes: &[u8]) -> Result<Animation> {
+    Animation::from_gltf_slice(bytes, &Default::default())
+}
+
+#[cfg(not(feature = "gltf"))]
+fn parse_gltf(_: &[u8]) -> Result<Animation> {
+    Err(unsupported(SourceFormat::Gltf, "input"))
+}
+
+#[cfg(feature = "serde")]
+fn parse_json(bytes: &[u8]) -> Result<Animation> {
+    let text = std::str::from_utf8(bytes)
+        .map_err(|_| AnimError::InvalidStructure("Animation JSON is not UTF-8".into()))?;
+    Animation::from_json(text)
+}
+
+#[cfg(not(feature = "serde"))]
+fn parse_json(_: &[u8]) -> Result<Animation> {
+    Err(unsupported(SourceFormat::Json, "input"))
+}
+
+fn parse(bytes: &[u8], format: SourceFormat) -> Result<Animation> {
+    match format {
+        SourceFormat::Anim => Animation::from_bytes(bytes),
+        SourceFormat::Bvh => parse_bvh(bytes),
+        SourceFormat::Gltf => parse_gltf(bytes),
+        SourceFormat::Json => parse_json(bytes),
+        SourceFormat::Collada => Err(unsupported(format, "input")),
+        _ => parse_llsd(bytes, format),
+    }
+}
+
+/// Decompress gzip `bytes`, failing once the output grows past `max_bytes`.
+#[cfg(feature = "gzip")]
+fn gunzip(bytes: &[u8], max_bytes: usize) -> Result<Vec<u8>> {
+    use std::io::Read;
+    let mut out = Vec::new();
+    flate2::read::MultiGzDecoder::new(bytes)
+        .take((max_bytes as u64).saturating_add(1))
+        .read_to_end(&mut out)
+        .map_err(AnimError::Io)?;
+    if out.len() > max_bytes {
+        return Err(AnimError::LimitExceeded {
+            limit: crate::Limit::Size,
+            max: max_bytes,
+            found: out.len(),
+        });
+    }
+    Ok(out)
+}
+
+#[cfg(not(feature = "gzip"))]
+fn gunzip(_: &[u8], _: usize) -> Result<Vec<u8>> {
+    Err(AnimError::InvalidStructure(
+        "gzip input requires the `gzip` feature".into(),
+    ))
+}
+
+/// Load an animation from a file or buffer in any supported encoding.
+///
+/// The format is detected from the content, not the file name: binary `.anim`, LLSD pose
+/// data as XML, notation or binary (`llsd` feature), BVH (`bvh` feature) and binary glTF
+/// (`gltf` feature), each optionally
+/// gzip-compressed (`gzip` feature). Pose joints that are not enabled are skipped, as with `Animation::from_llsd_file`.
+/// Recognized input whose feature is disabled is reported as an error.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use avatar_anim::{SourceFormat, open_any};
+///
+/// # fn main() -> avatar_anim::Result<()> {
+/// let (anim, format) = open_any("pose.xml")?;
+/// assert_eq!(format, SourceFormat::LlsdXml);
+///
+/// let bytes = std::fs::read("walk.anim.gz").unwrap();
+/// let (anim, format) = open_any(&bytes)?;
+/// # Ok(())
+/// # }
+/// ```
+pub fn open_any<'a>(input: impl Into<AnimInput<'a>>) -> Result<(Animation, SourceFormat)> {
+    with_bytes(input.into(), |bytes| {
+        let format = sniff(bytes)?;
+        Ok((parse(bytes, format)?, format))
+    })
+}
+
+/// Load an animation in a known format, skipping detection. Gzip is still undone.
+///
+/// This is the reading half of the conversion matrix; [`Animation::to_format`] is the
+/// writing half. A `.gltf` path has its external buffers resolved next to it; glTF joints
+/// are mapped with the default [`GltfOptions`](crate::gltf::GltfOptions).
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use avatar_anim::{SourceFormat, open_as};
+///
+/// # fn main() -> avatar_anim::Result<()> {
+/// let anim = open_as("walk.json", SourceFormat::Json)?;
+/// std::fs::write("walk.bvh", anim.to_format(SourceFormat::Bvh)?)?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// Fails if the data isn't valid `format`, or the format's feature is disabled.
+pub fn open_as<'a>(input: impl Into<AnimInput<'a>>, format: SourceFormat) -> Result<Animation> {
+    let input = input.into();
+    #[cfg(feature = "gltf")]
+    if let (AnimInput::Path(path), SourceFormat::Gltf) = (input, format) {
+        return Animation::from_gltf_file(path, &Default::default());
+    }
+    with_bytes(input, |bytes| parse(bytes, format))
+}
+
+/// [`open_as`], or [`open_any`] without a format, for untrusted bytes: gzip output is bounded
+/// by [`ReadOptions::max_bytes`] and `.anim` data is read with `options`.
+#[cfg(feature = "serde")]
+pub(crate) fn open_untrusted(
+    bytes: &[u8],
+    format: Option<SourceFormat>,
+    options: &ReadOptions,
+) -> Result<(Animation, SourceFormat)> {
+    let decompressed;
+    let bytes = if bytes.starts_with(&[0x1f, 0x8b]) {
+        decompressed = gunzip(bytes, options.max_bytes)?;
+        &decompressed[..]
+    } else {
+        bytes
+    };
+    let format = match format {
+        Some(format) => format,
+        None => sniff(bytes)?,
+    };
+    let anim = match format {
+        SourceFormat::Anim => Animation::from_bytes_with_options(bytes, options)?,
+        _ => parse(bytes, format)?,
+    };
+    Ok((anim, format))
+}
+
+fn with_bytes<T>(input: AnimInput, f: impl FnOnce(&[u8]) -> Result<T>) -> Result<T> {
+    let owned;
+    let mut bytes = match input {
+        AnimInput::Path(path) => {
+            owned = std::fs::read(path).map_err(AnimError::Io)?;
+            &owned[..]
+        }
+        AnimInput::Bytes(bytes) => bytes,
+    };
+    let decompressed;
+    if bytes.starts_with(&[0x1f, 0x8b]) {
+        decompressed = gunzip(bytes, ReadOptions::default().max_bytes)?;
+        bytes = &decompressed;
+    }
+    f(bytes)
+}
@@ -0,0 +1,193 @@
+//! Static poses: the state of every joint at one instant.
+//!
+//! A [`Pose`] is what a Firestorm pose file holds and what [`Animation::sample`] returns.
+//! Poses combine like transforms: [`Pose::compose`] applies a delta on top of a pose,
+//! [`Pose::difference`] extracts the delta between two poses and [`Pose::inverse`] undoes one.
+//!
+//! ```rust
+//! use avatar_anim::Pose;
+//! use glam::Quat;
+//!
+//! let mut base = Pose::new();
+//! base.set_rotation("mHead", Quat::from_rotation_z(0.3));
+//! let mut tilted = Pose::new();
+//! tilted.set_rotation("mHead", Quat::from_rotation_z(0.3) * Quat::from_rotation_x(0.2));
+//!
+//! let delta = tilted.difference(&base);
+//! let head = base.compose(&delta).rotation("mHead").unwrap();
+//! assert!(head.abs_diff_eq(tilted.rotation("mHead").unwrap(), 1e-5));
+//! ```
+
+use crate::sample::JointSample;
+use crate::{Animation, JointData, PositionKey, RotationKey};
+use glam::{Quat, Vec3};
+use std::collections::BTreeMap;
+
+/// Rotation and position of each joint at one instant, as returned by
+/// [`Animation::sample`](crate::Animation::sample).
+///
+/// A joint is absent when the animation doesn't drive it, and a channel is `None` when the
+/// joint has no keys for it; either way the viewer leaves that part of the skeleton to
+/// lower-priority motions.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Pose {
+    pub joints: BTreeMap<String, JointSample>,
+}
+
+impl Pose {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, joint: &str) -> Option<&JointSample> {
+        self.joints.get(joint)
+    }
+
+    /// Local rotation of `joint`, if the pose drives it.
+    pub fn rotation(&self, joint: &str) -> Option<Quat> {
+        self.get(joint)?.rotation
+    }
+
+    /// Local position of `joint`, if the pose drives it.
+    pub fn position(&self, joint: &str) -> Option<Vec3> {
+        self.get(joint)?.position
+    }
+
+    pub fn set_rotation(&mut self, joint: impl Into<String>, rotation: Quat) -> &mut Self {
+        self.joints.entry(joint.into()).or_default().rotation = Some(rotation);
+        self
+    }
+
+    pub fn set_position(&mut self, joint: impl Into<String>, position: Vec3) -> &mut Self {
+        self.joints.entry(joint.into()).or_default().position = Some(position);
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.joints.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.joints.is_empty()
+    }
+
+    /// The final frame of `anim`, the frame a pose file stores (see [`Animation::to_llsd`]).
+    pub fn from_animation(anim: &Animation) -> Self {
+        let mut pose = Pose::new();
+        for joint in &anim.joints {
+            pose.joints
+                .entry(joint.name.clone())
+                .or_insert_with(|| joint.sample_at(u16::MAX as f32));
+        }
+        pose
+    }
+
+    /// A still animation holding this pose: one key at time 0 per channel, with the default
+    /// header.
+    pub fn to_animation(&self) -> Animation {
+        let mut anim = Animation::new();
+        anim.joints = self
+            .joints
+            .iter()
+            .map(|(name, sample)| JointData {
+                name: name.clone(),
+                priority: anim.header.base_priority,
+                rotation_keys: sample
+                    .rotation
+                    .map(|rot| RotationKey { time: 0, rot })
+                    .into_iter()
+                    .collect(),
+                position_keys: sample
+                    .position
+                    .map(|pos| PositionKey { time: 0, pos })
+                    .into_iter()
+                    .collect(),
+            })
+            .collect();
+        anim
+    }
+
+    /// Read a Firestorm pose, like [`Animation::from_llsd`].
+    #[cfg(feature = "llsd")]
+    pub fn from_llsd(llsd: &llsd_rs::Llsd, check_enabled: bool) -> crate::Result<Self> {
+        Animation::from_llsd(llsd, check_enabled).map(|anim| Self::from_animation(&anim))
+    }
+
+    /// Write as a Firestorm pose, like [`Animation::to_llsd`].
+    #[cfg(feature = "llsd")]
+    pub fn to_llsd(&self) -> llsd_rs::Llsd {
+        self.to_animation().to_llsd()
+    }
+
+    /// Apply `delta` on top of this pose: rotations multiply (`self * delta`, so the delta is
+    /// in the joint's local frame) and positions add. A channel missing on one side counts as
+    /// identity.
+    pub fn compose(&self, delta: &Pose) -> Pose {
+        self.combine(
+            delta,
+            |a, b| (a * b).normalize(),
+            |a, b| a + b,
+            |a| a,
+            |b| b,
+        )
+    }
+
+    /// The pose that undoes this one under [`Pose::compose`].
+    pub fn inverse(&self) -> Pose {
+        Pose {
+            joints: self
+                .joints
+                .iter()
+                .map(|(name, sample)| {
+                    let inverse = JointSample {
+                        rotation: sample.rotation.map(Quat::inverse),
+                        position: sample.position.map(|p| -p),
+                    };
+                    (name.clone(), inverse)
+                })
+                .collect(),
+        }
+    }
+
+    /// The delta taking `base` to this pose, so that `base.compose(&self.difference(base))`
+    /// reproduces `self`. A channel missing on one side counts as identity.
+    pub fn difference(&self, base: &Pose) -> Pose {
+        self.combine(
+            base,
+            |a, b| (b.inverse() * a).normalize(),
+            |a, b| a - b,
+            |a| a,
+            Quat::inverse,
+        )
+    }
+
+    fn combine(
+        &self,
+        other: &Pose,
+        both: impl Fn(Quat, Quat) -> Quat,
+        add: impl Fn(Vec3, Vec3) -> Vec3,
+        only_self: impl Fn(Quat) -> Quat,
+        only_other: impl Fn(Quat) -> Quat,
+    ) -> Pose {
+        let mut joints = BTreeMap::new();
+        for name in self.joints.keys().chain(other.joints.keys()) {
+            if joints.contains_key(name) {
+                continue;
+            }
+            let a = self.get(name).copied().unwrap_or_default();
+            let b = other.get(name).copied().unwrap_or_default();
+            let rotation = match (a.rotation, b.rotation) {
+                (Some(a), Some(b)) => Some(both(a, b)),
+                (Some(a), None) => Some(only_self(a)),
+                (None, Some(b)) => Some(only_other(b)),
+                (None, None) => None,
+            };
+            let position = match (a.position, b.position) {
+                (None, None) => None,
+                (a, b) => Some(add(a.unwrap_or_default(), b.unwrap_or_default())),
+            };
+            joints.insert(name.clone(), JointSample { rotation, position });
+        }
+        Pose { joints }
+    }
+}
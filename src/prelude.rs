@@ -0,0 +1,24 @@
+//! The types most code needs, for a single glob import.
+//!
+//! ```rust
+//! use avatar_anim::prelude::*;
+//!
+//! let mut anim = Animation::new();
+//! anim.set_priority(4).cleanup_keys_with(DuplicateKeyStrategy::KeepLast);
+//! ```
+//!
+//! Items are only ever added here, so glob-importing the prelude is safe across upgrades
+//! (short of name clashes with your own types).
+
+pub use crate::compose::MergeStrategy;
+pub use crate::error::{AnimError, ErrorCode};
+pub use crate::limits::ReadOptions;
+pub use crate::open::{SourceFormat, open_any, open_as};
+pub use crate::pose::Pose;
+pub use crate::reduce::ReductionMode;
+pub use crate::sample::{JointSample, WrapMode};
+pub use crate::skeleton::Skeleton;
+pub use crate::{
+    Animation, AnimationHeader, Constraint, DuplicateKeyStrategy, JointData, PositionKey, Result,
+    RotationKey, WriteOutcome,
+};
@@ -0,0 +1,137 @@
+//! Salvaging what can be decoded from damaged `.anim` data.
+
+use crate::view::{AnimView, Bytes, CONSTRAINT_SIZE, KEY_SIZE, position_key, rotation_key};
+use crate::{AnimError, Animation, Constraint, JointData, Result};
+use binrw::BinRead;
+use std::io::Cursor;
+
+/// What [`Animation::read_partial`] recovered.
+#[derive(Debug)]
+pub struct PartialRead {
+    /// The header and every joint, key and constraint decoded before parsing stopped. A joint
+    /// cut off inside its keys is kept with the keys that were complete.
+    pub animation: Animation,
+    /// Why parsing stopped early, or `None` when the data decoded to the end.
+    pub error: Option<AnimError>,
+    /// Byte offset where parsing stopped.
+    pub offset: usize,
+}
+
+impl PartialRead {
+    /// Whether the whole animation was decoded.
+    pub fn is_complete(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+impl Animation {
+    /// Read as much of a truncated or damaged animation as possible.
+    ///
+    /// Decoding runs in file order and stops at the first thing that can't be read, such as
+    /// data ending inside a key or a negative key count; see [`PartialRead`] for what is kept.
+    /// Declared counts are never used to reserve memory, so hostile counts just run out of
+    /// data. Bytes after the constraints are ignored.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use avatar_anim::Animation;
+    ///
+    /// # fn main() -> avatar_anim::Result<()> {
+    /// let bytes = Animation::new().to_bytes()?;
+    /// let partial = Animation::read_partial(&bytes[..bytes.len() - 2])?;
+    /// assert!(!partial.is_complete());
+    /// println!("stopped at byte {}: {:?}", partial.offset, partial.error);
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Fails only if the source can't be read or the header itself is incomplete.
+    pub fn read_partial<R: std::io::Read>(mut reader: R) -> Result<PartialRead> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).map_err(AnimError::Io)?;
+        let view = AnimView::parse(&bytes)?;
+        let mut animation = Animation {
+            header: view.header(),
+            joints: Vec::new(),
+            constraints: Vec::new(),
+            trailing_data: Vec::new(),
+            edit_log: None,
+        };
+        let mut r = Bytes::starting_at(&bytes, view.joints_offset());
+        let error = salvage(&mut r, view.joint_count(), &mut animation).err();
+        Ok(PartialRead {
+            animation,
+            error,
+            offset: r.offset(),
+        })
+    }
+}
+
+fn salvage(r: &mut Bytes, joints: usize, animation: &mut Animation) -> Result<()> {
+    for index in 0..joints {
+        salvage_joint(r, animation).map_err(|err| {
+            let name = animation.joints.get(index).map(|joint| joint.name.as_str());
+            err.in_joint(index, name)
+        })?;
+    }
+    for _ in 0..r.count("the constraint count")? {
+        let constraint = r.take(CONSTRAINT_SIZE, "constraints")?;
+        animation
+            .constraints
+            .push(Constraint::read(&mut Cursor::new(constraint))?);
+    }
+    Ok(())
+}
+
+fn salvage_joint(r: &mut Bytes, animation: &mut Animation) -> Result<()> {
+    let name = r.str("a joint name")?.to_string();
+    animation.joints.push(JointData {
+        name,
+        ..Default::default()
+    });
+    let joint = animation.joints.last_mut().expect("just pushed");
+    joint.priority = r.i32("a joint priority")?;
+    for _ in 0..r.count("a rotation key count")? {
+        let key = r.take(KEY_SIZE, "rotation keys")?;
+        joint.rotation_keys.push(rotation_key(key));
+    }
+    for _ in 0..r.count("a position key count")? {
+        let key = r.take(KEY_SIZE, "position keys")?;
+        joint.position_keys.push(position_key(key));
+    }
+    Ok(())
+}
+
+/// Put `source`, a failed decode of `bytes`, in context: the offset, joint and field where
+/// decoding fails. Falls back to `source` alone if the walk finds nothing wrong.
+pub(crate) fn locate(bytes: &[u8], source: binrw::Error) -> AnimError {
+    let stop = match AnimView::parse(bytes) {
+        Err(err) => err,
+        Ok(view) => {
+            let mut r = Bytes::starting_at(bytes, view.joints_offset());
+            match salvage(&mut r, view.joint_count(), &mut Animation::default()) {
+                Err(err) => err,
+                Ok(()) => return AnimError::BinRw(source),
+            }
+        }
+    };
+    match stop {
+        AnimError::Decode {
+            offset,
+            joint,
+            joint_name,
+            field,
+            ..
+        } => AnimError::Decode {
+            offset,
+            joint,
+            joint_name,
+            field,
+            source: Box::new(AnimError::BinRw(source)),
+        },
+        _ => AnimError::BinRw(source),
+    }
+}
@@ -0,0 +1,395 @@
+use crate::math;
+use crate::{Animation, EditOperation, JointData, PositionKey, RotationKey};
+use glam::{Quat, Vec3};
+
+/// How [`JointData::reduce_keys_with`] decides which keys matter.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum ReductionMode {
+    /// The same tolerance everywhere.
+    #[default]
+    Fixed,
+    /// Scale the tolerance per key by local curvature (angular acceleration on quaternion logs
+    /// for rotations, acceleration for positions). The sharpest turn of each channel keeps the
+    /// given tolerance while near-linear stretches get up to twice as much, so files come out
+    /// smaller than with [`ReductionMode::Fixed`] without losing detail where motion changes.
+    Adaptive,
+}
+
+/// How strongly [`ReductionMode::Adaptive`] reacts to curvature.
+const ADAPTIVE_STRENGTH: f32 = 1.0;
+
+/// Douglas-Peucker over a sorted track: marks the keys needed so that interpolating between
+/// kept neighbours reproduces every dropped key within its tolerance.
+fn keep_mask<K>(
+    keys: &[K],
+    tolerance: impl Fn(usize) -> f32,
+    error: impl Fn(&K, &K, &K) -> f32,
+) -> Vec<bool> {
+    let mut keep = vec![false; keys.len()];
+    if keys.len() <= 2 {
+        keep.fill(true);
+        return keep;
+    }
+    keep[0] = true;
+    keep[keys.len() - 1] = true;
+    let mut stack = vec![(0usize, keys.len() - 1)];
+    while let Some((a, b)) = stack.pop() {
+        let mut worst = (0.0f32, 0usize);
+        for i in a + 1..b {
+            let e = error(&keys[a], &keys[b], &keys[i]) / tolerance(i);
+            if e > worst.0 {
+                worst = (e, i);
+            }
+        }
+        if worst.0 > 1.0 {
+            keep[worst.1] = true;
+            stack.push((a, worst.1));
+            stack.push((worst.1, b));
+        }
+    }
+    keep
+}
+
+/// Single forward pass: a key is dropped when the segment from the last kept key to the key
+/// after it reproduces it, and every key already dropped since, within `epsilon`.
+fn prune_mask<K>(keys: &[K], epsilon: f32, error: impl Fn(&K, &K, &K) -> f32) -> Vec<bool> {
+    let mut keep = vec![true; keys.len()];
+    let mut last = 0;
+    for i in 1..keys.len().saturating_sub(1) {
+        let (a, b) = (&keys[last], &keys[i + 1]);
+        if (last + 1..=i).all(|j| error(a, b, &keys[j]) <= epsilon) {
+            keep[i] = false;
+        } else {
+            last = i;
+        }
+    }
+    keep
+}
+
+/// The Douglas-Peucker recursion of [`keep_mask`] run to the end once, recording for each key
+/// the largest tolerance that still drops it: a key survives `keep_mask` with a fixed
+/// tolerance exactly when its importance is above it. First and last keys are infinite.
+fn importance<K>(keys: &[K], error: impl Fn(&K, &K, &K) -> f32) -> Vec<f32> {
+    let mut importance = vec![0.0; keys.len()];
+    if let Some(last) = keys.len().checked_sub(1) {
+        importance[0] = f32::INFINITY;
+        importance[last] = f32::INFINITY;
+    }
+    let mut stack = vec![(0usize, keys.len().saturating_sub(1), f32::INFINITY)];
+    while let Some((a, b, limit)) = stack.pop() {
+        if b - a < 2 {
+            continue;
+        }
+        let mut worst = (0.0f32, a + 1);
+        for i in a + 1..b {
+            let e = error(&keys[a], &keys[b], &keys[i]);
+            if e > worst.0 {
+                worst = (e, i);
+            }
+        }
+        // A key is only reached while every split above it is kept.
+        let value = worst.0.min(limit);
+        importance[worst.1] = value;
+        stack.push((a, worst.1, value));
+        stack.push((worst.1, b, value));
+    }
+    importance
+}
+
+fn retain_mask<K>(keys: &mut Vec<K>, keep: &[bool]) {
+    let mut it = keep.iter();
+    keys.retain(|_| *it.next().unwrap_or(&true));
+}
+
+fn fraction(a: u16, b: u16, t: u16) -> f32 {
+    if b > a {
+        (t - a) as f32 / (b - a) as f32
+    } else {
+        0.0
+    }
+}
+
+fn rotation_error(a: &RotationKey, b: &RotationKey, k: &RotationKey) -> f32 {
+    let s = fraction(a.time, b.time, k.time);
+    math::geodesic_distance(a.rot.slerp(b.rot, s), k.rot)
+}
+
+fn position_error(a: &PositionKey, b: &PositionKey, k: &PositionKey) -> f32 {
+    let s = fraction(a.time, b.time, k.time);
+    a.pos.lerp(b.pos, s).distance(k.pos)
+}
+
+/// Relative curvature of a sampled curve: second differences scaled into `0..=1`.
+fn curvature_weights(times: &[u16], values: &[Vec3]) -> Vec<f32> {
+    let mut weights = vec![0.0; values.len()];
+    for i in 1..values.len().saturating_sub(1) {
+        let dt0 = (times[i] - times[i - 1]).max(1) as f32;
+        let dt1 = (times[i + 1] - times[i]).max(1) as f32;
+        let v0 = (values[i] - values[i - 1]) / dt0;
+        let v1 = (values[i + 1] - values[i]) / dt1;
+        weights[i] = (v1 - v0).length() / (0.5 * (dt0 + dt1));
+    }
+    let max = weights.iter().copied().fold(0.0f32, f32::max);
+    if max > 0.0 {
+        for w in &mut weights {
+            *w /= max;
+        }
+    }
+    weights
+}
+
+fn adaptive_tolerance(tolerance: f32, weight: f32) -> f32 {
+    tolerance * (1.0 + ADAPTIVE_STRENGTH) / (1.0 + ADAPTIVE_STRENGTH * weight)
+}
+
+/// Keep `count` evenly spaced keys, always including the first and last.
+fn decimate<K>(keys: &mut Vec<K>, count: usize) {
+    let len = keys.len();
+    if len <= count || len <= 2 {
+        return;
+    }
+    let count = count.max(2);
+    let mut keep = vec![false; len];
+    for i in 0..count {
+        keep[i * (len - 1) / (count - 1)] = true;
+    }
+    retain_mask(keys, &keep);
+}
+
+impl JointData {
+    pub fn key_count(&self) -> usize {
+        self.rotation_keys.len() + self.position_keys.len()
+    }
+
+    /// Drop keys that interpolation between the remaining keys reproduces within `tolerance`
+    /// (radians for rotations, meters for positions). First and last keys are always kept.
+    ///
+    /// Keys are sorted by time first. Returns the number of keys removed.
+    pub fn reduce_keys(&mut self, tolerance: f32) -> usize {
+        self.reduce_keys_with(tolerance, ReductionMode::Fixed)
+    }
+
+    /// Drop keys that lie within `epsilon` (radians for rotations, meters for positions) of
+    /// the interpolation between their neighbours, like the runs of collinear keys a fixed
+    /// rate bake produces. First and last keys are always kept.
+    ///
+    /// Cheaper than [`JointData::reduce_keys`], which searches for the fewest keys: this is a
+    /// single pass, so it only removes keys that are redundant locally. Keys are sorted by
+    /// time first. Returns the number of keys removed.
+    pub fn prune_redundant_keys(&mut self, epsilon: f32) -> usize {
+        let before = self.key_count();
+        self.rotation_keys.sort_by_key(|k| k.time);
+        self.position_keys.sort_by_key(|k| k.time);
+        let keep = prune_mask(&self.rotation_keys, epsilon, rotation_error);
+        retain_mask(&mut self.rotation_keys, &keep);
+        let keep = prune_mask(&self.position_keys, epsilon, position_error);
+        retain_mask(&mut self.position_keys, &keep);
+        before - self.key_count()
+    }
+
+    /// Like [`JointData::reduce_keys`], choosing how the tolerance is applied with `mode`.
+    pub fn reduce_keys_with(&mut self, tolerance: f32, mode: ReductionMode) -> usize {
+        let before = self.key_count();
+        self.rotation_keys.sort_by_key(|k| k.time);
+        self.position_keys.sort_by_key(|k| k.time);
+        let (rot_weights, pos_weights) = match mode {
+            ReductionMode::Fixed => (
+                vec![1.0; self.rotation_keys.len()],
+                vec![1.0; self.position_keys.len()],
+            ),
+            ReductionMode::Adaptive => {
+                // Accumulate the logs of key-to-key deltas so the curve stays continuous even
+                // where the absolute rotation wraps past 180°.
+                let mut prev: Option<Quat> = None;
+                let mut acc = Vec3::ZERO;
+                let logs: Vec<Vec3> = self
+                    .rotation_keys
+                    .iter()
+                    .map(|k| {
+                        if let Some(p) = prev {
+                            acc += math::log(k.rot * p.conjugate());
+                        }
+                        prev = Some(k.rot);
+                        acc
+                    })
+                    .collect();
+                let times: Vec<u16> = self.rotation_keys.iter().map(|k| k.time).collect();
+                let rot = curvature_weights(&times, &logs);
+                let positions: Vec<Vec3> = self.position_keys.iter().map(|k| k.pos).collect();
+                let times: Vec<u16> = self.position_keys.iter().map(|k| k.time).collect();
+                (rot, curvature_weights(&times, &positions))
+            }
+        };
+        let keep = keep_mask(
+            &self.rotation_keys,
+            |i| adaptive_tolerance(tolerance, rot_weights[i]),
+            rotation_error,
+        );
+        retain_mask(&mut self.rotation_keys, &keep);
+        let keep = keep_mask(
+            &self.position_keys,
+            |i| adaptive_tolerance(tolerance, pos_weights[i]),
+            position_error,
+        );
+        retain_mask(&mut self.position_keys, &keep);
+        before - self.key_count()
+    }
+}
+
+impl Animation {
+    /// Reduce every joint with [`JointData::reduce_keys`].
+    pub fn reduce_keys(&mut self, tolerance: f32) -> &mut Self {
+        self.reduce_keys_with(tolerance, ReductionMode::Fixed)
+    }
+
+    /// Prune every joint with [`JointData::prune_redundant_keys`].
+    pub fn prune_redundant_keys(&mut self, epsilon: f32) -> &mut Self {
+        let mark = self.edit_mark();
+        for joint in &mut self.joints {
+            joint.prune_redundant_keys(epsilon);
+        }
+        self.log_edit(
+            mark,
+            EditOperation::PruneRedundantKeys,
+            &[("epsilon", &epsilon)],
+        );
+        self
+    }
+
+    /// Reduce every joint with [`JointData::reduce_keys_with`].
+    pub fn reduce_keys_with(&mut self, tolerance: f32, mode: ReductionMode) -> &mut Self {
+        let mark = self.edit_mark();
+        for joint in &mut self.joints {
+            joint.reduce_keys_with(tolerance, mode);
+        }
+        self.log_edit(
+            mark,
+            EditOperation::ReduceKeysWith,
+            &[
+                ("tolerance", &tolerance),
+                ("mode", &format_args!("{mode:?}")),
+            ],
+        );
+        self
+    }
+
+    /// Bring every joint down to at most `max_keys_per_joint` keys (both channels together).
+    ///
+    /// Joints already within budget are left untouched, so the most dynamic joints keep their
+    /// fidelity. Over-budget joints are reduced starting at `reduction_tolerance`, doubling the
+    /// tolerance until the budget is met; if even that fails, keys are evenly decimated with the
+    /// budget split between channels by their key counts. Each channel keeps at least its first
+    /// and last key.
+    pub fn enforce_key_budget(
+        &mut self,
+        max_keys_per_joint: usize,
+        reduction_tolerance: f32,
+    ) -> &mut Self {
+        const MAX_ROUNDS: usize = 24;
+        let mark = self.edit_mark();
+        let mut decimated = Vec::new();
+        let mut over: Vec<usize> = (0..self.joints.len())
+            .filter(|&i| self.joints[i].key_count() > max_keys_per_joint)
+            .collect();
+        // Densest tracks first.
+        over.sort_by_key(|&i| std::cmp::Reverse(self.joints[i].key_count()));
+        for index in over {
+            let original = self.joints[index].clone();
+            let mut tolerance = reduction_tolerance.max(f32::EPSILON);
+            let mut reduced = original.clone();
+            for _ in 0..MAX_ROUNDS {
+                reduced = original.clone();
+                reduced.reduce_keys(tolerance);
+                if reduced.key_count() <= max_keys_per_joint {
+                    break;
+                }
+                tolerance *= 2.0;
+            }
+            if reduced.key_count() > max_keys_per_joint {
+                let total = reduced.key_count();
+                let rot = reduced.rotation_keys.len();
+                let rot_budget = if reduced.position_keys.is_empty() {
+                    max_keys_per_joint
+                } else {
+                    (max_keys_per_joint * rot).div_ceil(total)
+                };
+                let pos_budget = max_keys_per_joint.saturating_sub(rot_budget);
+                decimate(&mut reduced.rotation_keys, rot_budget);
+                decimate(&mut reduced.position_keys, pos_budget);
+                decimated.push(reduced.name.clone());
+            }
+            self.joints[index] = reduced;
+        }
+        if let Some(entry) = self.log_edit(
+            mark,
+            EditOperation::EnforceKeyBudget,
+            &[
+                ("max_keys_per_joint", &max_keys_per_joint),
+                ("reduction_tolerance", &reduction_tolerance),
+            ],
+        ) && !decimated.is_empty()
+        {
+            entry.warnings.push(format!(
+                "{} decimated evenly: reduction alone could not meet the budget",
+                decimated.join(", ")
+            ));
+        }
+        self
+    }
+
+    /// Reduced copies of this animation, one per tolerance, as [`Animation::reduce_keys`]
+    /// would produce them.
+    ///
+    /// Each channel is analysed once and every variant is cut from that, so asking for a
+    /// whole ladder of detail levels costs little more than the coarsest one. Variants come
+    /// back in the order of `tolerances`.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use avatar_anim::Animation;
+    ///
+    /// # fn main() -> avatar_anim::Result<()> {
+    /// let dance = Animation::from_file("dance.anim")?;
+    /// let names = ["dance_full.anim", "dance_half.anim", "dance_quarter.anim"];
+    /// for (variant, name) in dance.lod_variants(&[0.001, 0.01, 0.04]).iter().zip(names) {
+    ///     variant.to_file(name)?;
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn lod_variants(&self, tolerances: &[f32]) -> Vec<Animation> {
+        let mut sorted = self.clone();
+        let ranked: Vec<(Vec<f32>, Vec<f32>)> = sorted
+            .joints
+            .iter_mut()
+            .map(|joint| {
+                joint.rotation_keys.sort_by_key(|k| k.time);
+                joint.position_keys.sort_by_key(|k| k.time);
+                (
+                    importance(&joint.rotation_keys, rotation_error),
+                    importance(&joint.position_keys, position_error),
+                )
+            })
+            .collect();
+        let mark = sorted.edit_mark();
+        tolerances
+            .iter()
+            .map(|&tolerance| {
+                let mut variant = sorted.clone();
+                for (joint, (rotations, positions)) in variant.joints.iter_mut().zip(&ranked) {
+                    let keep: Vec<bool> = rotations.iter().map(|&i| i > tolerance).collect();
+                    retain_mask(&mut joint.rotation_keys, &keep);
+                    let keep: Vec<bool> = positions.iter().map(|&i| i > tolerance).collect();
+                    retain_mask(&mut joint.position_keys, &keep);
+                }
+                variant.log_edit(
+                    mark,
+                    EditOperation::LodVariants,
+                    &[("tolerance", &tolerance)],
+                );
+                variant
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,331 @@
+//! Problems noticed while reading that don't stop the read.
+
+use crate::io::{snaps_to_zero, u16_to_f32};
+use crate::limits::ReadOptions;
+use crate::view::{AnimView, KEY_SIZE};
+use crate::{AnimError, Animation, AnimationHeader, Result};
+use std::collections::HashSet;
+use std::io::Read;
+
+/// Stored rotation components longer than this (plus quantization slack) lose their `w`.
+const MAX_ROTATION_LENGTH: f32 = 1.0 + 1e-4;
+/// Header times beyond this many seconds are taken for garbage rather than a long animation.
+pub(crate) const MAX_PLAUSIBLE_SECONDS: f32 = 3600.0;
+
+/// Replace the header times that are not numbers or not plausible with usable ones, returning
+/// `(field, value, replacement)` for each.
+///
+/// A duration past [`MAX_PLAUSIBLE_SECONDS`] is cut to it and one that is not a number becomes
+/// 0; a loop in point that is not a number becomes 0, a loop out point the duration, and
+/// other loop points are clamped into the duration. Ease windows become 0 unless they are a
+/// number of seconds in range, which they are then clamped to. Zero or negative durations
+/// are left to [`ParseWarning::NonPositiveDuration`].
+pub(crate) fn sanitize_header_times(header: &mut AnimationHeader) -> Vec<(&'static str, f32, f32)> {
+    let plausible = |t: f32| t.is_finite() && t.abs() <= MAX_PLAUSIBLE_SECONDS;
+    let mut found = Vec::new();
+    let mut fix = |field, time: &mut f32, ok: bool, replacement: f32| {
+        if !ok {
+            found.push((field, *time, replacement));
+            *time = replacement;
+        }
+    };
+
+    let duration = header.duration;
+    let usable = if duration.is_finite() {
+        duration.min(MAX_PLAUSIBLE_SECONDS)
+    } else {
+        0.0
+    };
+    fix(
+        "duration",
+        &mut header.duration,
+        plausible(duration),
+        usable,
+    );
+    let end = usable.max(0.0);
+    for (field, time, fallback) in [
+        ("loop_in_point", &mut header.loop_in_point, 0.0),
+        ("loop_out_point", &mut header.loop_out_point, end),
+    ] {
+        let replacement = if time.is_finite() {
+            time.clamp(0.0, end)
+        } else {
+            fallback
+        };
+        fix(field, time, plausible(*time), replacement);
+    }
+    for (field, time) in [
+        ("ease_in_duration", &mut header.ease_in_duration),
+        ("ease_out_duration", &mut header.ease_out_duration),
+    ] {
+        let replacement = if time.is_finite() {
+            time.clamp(0.0, MAX_PLAUSIBLE_SECONDS)
+        } else {
+            0.0
+        };
+        fix(field, time, plausible(*time) && *time >= 0.0, replacement);
+    }
+    found
+}
+
+/// One finding of [`ParseReport`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum ParseWarning {
+    /// The stored `x, y, z` of a rotation key are longer than a unit quaternion allows, so `w`
+    /// was taken as zero and the key renormalized; the viewer plays it the same way, but the
+    /// exporter likely wrote garbage.
+    NonNormalizedRotation {
+        joint: String,
+        key: usize,
+        length: f32,
+    },
+    /// The duration is zero or negative.
+    NonPositiveDuration { duration: f32 },
+    /// A header time is not a number, infinite, negative where it can't be, or longer than
+    /// an hour, which points at a broken exporter rather than a long animation. Arithmetic
+    /// on it gives meaningless seconds everywhere downstream; with
+    /// [`ReadOptions::sanitize_header`] it was read as `replaced_with` instead.
+    ImplausibleHeaderTime {
+        field: &'static str,
+        value: f32,
+        replaced_with: Option<f32>,
+    },
+    /// A looping animation's loop points don't satisfy `0 <= in <= out <= duration`.
+    LoopOutsideDuration {
+        loop_in: f32,
+        loop_out: f32,
+        duration: f32,
+    },
+    /// More than one joint has this name; the viewer uses the first.
+    DuplicateJoint { name: String },
+    /// Key times of a joint are not ascending; [`Animation::cleanup_keys`] sorts them.
+    UnsortedKeys { joint: String },
+    /// Bytes follow the constraint block and were ignored.
+    TrailingBytes { count: usize },
+    /// Key components of a joint were stored as tiny non-zero values and read as exactly 0
+    /// by the zero snap (see [`ReadOptions::zero_snap_quanta`]).
+    ZeroSnapped {
+        joint: String,
+        rotation_components: usize,
+        position_components: usize,
+    },
+}
+
+impl std::fmt::Display for ParseWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseWarning::NonNormalizedRotation { joint, key, length } => write!(
+                f,
+                "rotation key {key} of {joint} has stored length {length}, above 1"
+            ),
+            ParseWarning::NonPositiveDuration { duration } => {
+                write!(f, "duration {duration} is not positive")
+            }
+            ParseWarning::ImplausibleHeaderTime {
+                field,
+                value,
+                replaced_with,
+            } => {
+                write!(f, "{field} {value} is not a plausible time in seconds")?;
+                match replaced_with {
+                    Some(replacement) => write!(f, "; read as {replacement}"),
+                    None => Ok(()),
+                }
+            }
+            ParseWarning::LoopOutsideDuration {
+                loop_in,
+                loop_out,
+                duration,
+            } => write!(
+                f,
+                "loop {loop_in}..{loop_out} is outside the duration {duration}"
+            ),
+            ParseWarning::DuplicateJoint { name } => write!(f, "joint {name} appears twice"),
+            ParseWarning::UnsortedKeys { joint } => write!(f, "keys of {joint} are out of order"),
+            ParseWarning::TrailingBytes { count } => {
+                write!(f, "{count} trailing bytes were ignored")
+            }
+            ParseWarning::ZeroSnapped {
+                joint,
+                rotation_components,
+                position_components,
+            } => write!(
+                f,
+                "{rotation_components} rotation and {position_components} position components of {joint} were snapped to zero"
+            ),
+        }
+    }
+}
+
+/// Non-fatal findings of [`Animation::read_with_report`], in file order.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ParseReport {
+    pub warnings: Vec<ParseWarning>,
+}
+
+impl ParseReport {
+    /// Whether nothing was found.
+    pub fn is_clean(&self) -> bool {
+        self.warnings.is_empty()
+    }
+
+    fn collect(bytes: &[u8], anim: &Animation, options: &ReadOptions) -> Result<Self> {
+        let mut warnings = Vec::new();
+        // The header as stored, which `anim` may hold sanitized.
+        let header = &AnimView::parse(bytes)?.header();
+        let implausible = sanitize_header_times(&mut header.clone());
+        if header.duration <= 0.0 {
+            warnings.push(ParseWarning::NonPositiveDuration {
+                duration: header.duration,
+            });
+        }
+        for &(field, value, replacement) in &implausible {
+            warnings.push(ParseWarning::ImplausibleHeaderTime {
+                field,
+                value,
+                replaced_with: options.sanitize_header.then_some(replacement),
+            });
+        }
+        if header.looped != 0
+            && implausible.is_empty()
+            && !(0.0 <= header.loop_in_point
+                && header.loop_in_point <= header.loop_out_point
+                && header.loop_out_point <= header.duration)
+        {
+            warnings.push(ParseWarning::LoopOutsideDuration {
+                loop_in: header.loop_in_point,
+                loop_out: header.loop_out_point,
+                duration: header.duration,
+            });
+        }
+
+        let mut seen = HashSet::new();
+        for (joint, view) in anim.joints.iter().zip(AnimView::parse(bytes)?.joints()) {
+            let view = view?;
+            if !seen.insert(joint.name.as_str()) {
+                warnings.push(ParseWarning::DuplicateJoint {
+                    name: joint.name.clone(),
+                });
+            }
+            for (key, stored) in view.encoded_rotations().chunks_exact(KEY_SIZE).enumerate() {
+                let length = [2, 4, 6]
+                    .map(|at| {
+                        u16_to_f32(u16::from_le_bytes([stored[at], stored[at + 1]]), -1.0, 1.0)
+                    })
+                    .iter()
+                    .map(|c| c * c)
+                    .sum::<f32>()
+                    .sqrt();
+                if length > MAX_ROTATION_LENGTH {
+                    warnings.push(ParseWarning::NonNormalizedRotation {
+                        joint: joint.name.clone(),
+                        key,
+                        length,
+                    });
+                }
+            }
+            let snapped = |keys: &[u8], range: f32| {
+                keys.chunks_exact(KEY_SIZE)
+                    .flat_map(|key| [2, 4, 6].map(|at| u16::from_le_bytes([key[at], key[at + 1]])))
+                    .filter(|&c| snaps_to_zero(c, -range, range, options.zero_snap_quanta))
+                    .count()
+            };
+            let rotation_components = snapped(view.encoded_rotations(), 1.0);
+            let position_components = snapped(view.encoded_positions(), 5.0);
+            if rotation_components + position_components > 0 {
+                warnings.push(ParseWarning::ZeroSnapped {
+                    joint: joint.name.clone(),
+                    rotation_components,
+                    position_components,
+                });
+            }
+            let sorted = joint.rotation_keys.is_sorted_by_key(|k| k.time)
+                && joint.position_keys.is_sorted_by_key(|k| k.time);
+            if !sorted {
+                warnings.push(ParseWarning::UnsortedKeys {
+                    joint: joint.name.clone(),
+                });
+            }
+        }
+
+        let used = anim.encoded_len();
+        if bytes.len() > used {
+            warnings.push(ParseWarning::TrailingBytes {
+                count: bytes.len() - used,
+            });
+        }
+        Ok(Self { warnings })
+    }
+}
+
+impl Animation {
+    /// Read an animation like [`Animation::from_bytes`], also reporting problems that don't
+    /// prevent loading it.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use avatar_anim::{Animation, ParseWarning};
+    ///
+    /// # fn main() -> avatar_anim::Result<()> {
+    /// let mut anim = Animation::new();
+    /// anim.header.duration = -1.0;
+    /// anim.header.looped = 0;
+    /// let (_, report) = Animation::read_with_report(&anim.to_bytes()?[..])?;
+    /// assert_eq!(report.warnings, [ParseWarning::NonPositiveDuration { duration: -1.0 }]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Fails exactly when [`Animation::from_bytes`] does.
+    pub fn read_with_report<R: Read>(reader: R) -> Result<(Self, ParseReport)> {
+        Self::read_with_report_and_options(reader, &ReadOptions::default())
+    }
+
+    /// Like [`Animation::read_with_report`], reading with `options`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use avatar_anim::{Animation, JointData, PositionKey, ReadOptions};
+    /// use glam::Vec3;
+    ///
+    /// # fn main() -> avatar_anim::Result<()> {
+    /// let mut anim = Animation::new();
+    /// anim.joints.push(JointData {
+    ///     name: "mPelvis".into(),
+    ///     position_keys: vec![PositionKey { time: 0, pos: Vec3::new(0.0, 0.0, 0.0002) }],
+    ///     ..Default::default()
+    /// });
+    /// let bytes = anim.to_bytes()?;
+    /// let (viewer, report) = Animation::read_with_report(&bytes[..])?;
+    /// assert_eq!(viewer.joints[0].position_keys[0].pos.z, 0.0);
+    /// assert!(!report.is_clean());
+    ///
+    /// let exact = ReadOptions { zero_snap_quanta: 0, ..Default::default() };
+    /// let (kept, report) = Animation::read_with_report_and_options(&bytes[..], &exact)?;
+    /// assert!(kept.joints[0].position_keys[0].pos.z > 0.0);
+    /// assert!(report.is_clean());
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Fails exactly when [`Animation::from_bytes_with_options`] does.
+    pub fn read_with_report_and_options<R: Read>(
+        reader: R,
+        options: &ReadOptions,
+    ) -> Result<(Self, ParseReport)> {
+        let mut bytes = Vec::new();
+        reader
+            .take((options.max_bytes as u64).saturating_add(1))
+            .read_to_end(&mut bytes)
+            .map_err(AnimError::Io)?;
+        let anim = Self::from_bytes_with_options(&bytes, options)?;
+        let report = ParseReport::collect(&bytes, &anim, options)?;
+        Ok((anim, report))
+    }
+}
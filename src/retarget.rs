@@ -0,0 +1,65 @@
+use crate::skeleton::Skeleton;
+use crate::{Animation, EditOperation};
+
+impl Animation {
+    /// Multiply every position key by `factor`, compensating for a uniformly scaled body.
+    pub fn scale_positions(&mut self, factor: f32) -> &mut Self {
+        let mark = self.edit_mark();
+        for joint in &mut self.joints {
+            for key in &mut joint.position_keys {
+                key.pos *= factor;
+            }
+        }
+        self.log_edit(mark, EditOperation::ScalePositions, &[("factor", &factor)]);
+        self
+    }
+
+    /// Adapt position keys authored for the `from` body to the proportions of `to`.
+    ///
+    /// Each joint's position keys are scaled by the ratio of its bind translation lengths in the
+    /// two skeletons; the pelvis uses its bind height so root motion follows leg length.
+    /// Rotations are left untouched and joints missing from either skeleton are skipped.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use avatar_anim::Animation;
+    /// use avatar_anim::skeleton::{BodyPreset, Skeleton};
+    ///
+    /// let mut anim = Animation::default();
+    /// anim.retarget(&Skeleton::second_life(), &BodyPreset::Tall.skeleton());
+    /// ```
+    pub fn retarget(&mut self, from: &Skeleton, to: &Skeleton) -> &mut Self {
+        let mark = self.edit_mark();
+        let mut skipped = Vec::new();
+        for joint in &mut self.joints {
+            let (Some(src), Some(dst)) = (from.joint(&joint.name), to.joint(&joint.name)) else {
+                if !joint.position_keys.is_empty() {
+                    skipped.push(joint.name.clone());
+                }
+                continue;
+            };
+            let (src_len, dst_len) = if src.parent.is_none() {
+                (src.position.z, dst.position.z)
+            } else {
+                (src.position.length(), dst.position.length())
+            };
+            if src_len.abs() <= f32::EPSILON {
+                continue;
+            }
+            let factor = dst_len / src_len;
+            for key in &mut joint.position_keys {
+                key.pos *= factor;
+            }
+        }
+        if let Some(entry) = self.log_edit(mark, EditOperation::Retarget, &[])
+            && !skipped.is_empty()
+        {
+            entry.warnings.push(format!(
+                "positions of {} kept: not in both skeletons",
+                skipped.join(", ")
+            ));
+        }
+        self
+    }
+}
@@ -0,0 +1,96 @@
+use crate::io::{quantize_position, quantize_rotation};
+use crate::{AnimError, Animation, JointData, Result};
+use binrw::{BinRead, BinWrite};
+use std::io::Cursor;
+
+/// True when both joints encode to the same keys, ignoring priority.
+fn same_keys(a: &JointData, b: &JointData) -> bool {
+    a.name == b.name
+        && a.rotation_keys.len() == b.rotation_keys.len()
+        && a.position_keys.len() == b.position_keys.len()
+        && a.rotation_keys
+            .iter()
+            .zip(&b.rotation_keys)
+            .all(|(x, y)| x.time == y.time && quantize_rotation(x.rot) == quantize_rotation(y.rot))
+        && a.position_keys
+            .iter()
+            .zip(&b.position_keys)
+            .all(|(x, y)| x.time == y.time && quantize_position(x.pos) == quantize_position(y.pos))
+}
+
+/// Byte offsets of each joint's priority field, given the offset where the joint table starts.
+fn priority_offsets(bytes: &[u8], mut at: usize, joints: usize) -> Option<Vec<usize>> {
+    let read_i32 = |at: usize| -> Option<i32> {
+        Some(i32::from_le_bytes(bytes.get(at..at + 4)?.try_into().ok()?))
+    };
+    at += 4; // joint count
+    let mut offsets = Vec::with_capacity(joints);
+    for _ in 0..joints {
+        at += bytes.get(at..)?.iter().position(|&b| b == 0)? + 1;
+        offsets.push(at);
+        at += 4;
+        let rot = usize::try_from(read_i32(at)?).ok()?;
+        at += 4 + rot * 8;
+        let pos = usize::try_from(read_i32(at)?).ok()?;
+        at += 4 + pos * 8;
+    }
+    Some(offsets)
+}
+
+impl Animation {
+    /// Encode this animation, reusing `original_bytes` when only metadata changed.
+    ///
+    /// If the keys and constraints still encode exactly as in `original_bytes` (the file this
+    /// animation was loaded from), the result is the original buffer with the header and joint
+    /// priorities patched, so metadata edits cause no quantization drift and a minimal binary
+    /// diff. Anything else falls back to a full encode. Either way the data after the
+    /// constraints is [`Animation::trailing_data`].
+    ///
+    /// # Errors
+    ///
+    /// Fails only if encoding itself fails; an unreadable `original_bytes` just means a full
+    /// encode.
+    pub fn rewrite_preserving(&self, original_bytes: &[u8]) -> Result<Vec<u8>> {
+        let mut cursor = Cursor::new(original_bytes);
+        let patchable = Animation::read(&mut cursor).ok().and_then(|original| {
+            let unchanged = original.joints.len() == self.joints.len()
+                && original.constraints == self.constraints
+                && original
+                    .joints
+                    .iter()
+                    .zip(&self.joints)
+                    .all(|(a, b)| same_keys(a, b));
+            if !unchanged {
+                return None;
+            }
+            let mut header = Cursor::new(Vec::new());
+            original.header.write(&mut header).ok()?;
+            let table = header.into_inner().len();
+            Some((
+                table,
+                cursor.position() as usize,
+                priority_offsets(original_bytes, table, self.joints.len())?,
+            ))
+        });
+
+        let mut out = Cursor::new(Vec::new());
+        match patchable {
+            Some((table, end, offsets)) => {
+                self.header.write(&mut out).map_err(AnimError::BinRw)?;
+                let shift = out.get_ref().len() as isize - table as isize;
+                let mut out = out.into_inner();
+                out.extend_from_slice(&original_bytes[table..end]);
+                out.extend_from_slice(&self.trailing_data);
+                for (offset, joint) in offsets.into_iter().zip(&self.joints) {
+                    let at = (offset as isize + shift) as usize;
+                    out[at..at + 4].copy_from_slice(&joint.priority.to_le_bytes());
+                }
+                Ok(out)
+            }
+            None => {
+                self.write(&mut out).map_err(AnimError::BinRw)?;
+                Ok(out.into_inner())
+            }
+        }
+    }
+}
@@ -0,0 +1,178 @@
+use crate::track::interpolate;
+use crate::{Animation, JointData, Pose};
+use glam::{Quat, Vec3};
+
+/// How sample times outside the playable range are mapped back into it.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum WrapMode {
+    /// Clamp to `[0, duration]`; one-shot gestures hold their last frame.
+    #[default]
+    Clamp,
+    /// Play up to `loop_out_point` once, then repeat `[loop_in_point, loop_out_point]`.
+    Loop,
+    /// Like [`WrapMode::Loop`], but every pass through the loop region reverses direction.
+    PingPong,
+}
+
+/// Interpolated state of a single joint at one point in time.
+///
+/// A channel is `None` when the joint has no keys for it.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct JointSample {
+    pub rotation: Option<Quat>,
+    pub position: Option<Vec3>,
+}
+
+impl JointData {
+    /// Rotation at `tick` (in key time units, `0..=65535`), slerping between the surrounding keys.
+    ///
+    /// Keys are expected to be sorted by time, as produced by [`Animation::cleanup_keys`];
+    /// [`JointData::normalize`] returns them as [`SortedKeys`](crate::track::SortedKeys), which
+    /// can't be unsorted.
+    pub fn rotation_at(&self, tick: f32) -> Option<Quat> {
+        interpolate(&self.rotation_keys, tick)
+    }
+
+    /// Position at `tick` (in key time units, `0..=65535`), lerping between the surrounding keys.
+    pub fn position_at(&self, tick: f32) -> Option<Vec3> {
+        interpolate(&self.position_keys, tick)
+    }
+
+    /// Sample both channels at `tick`.
+    pub fn sample_at(&self, tick: f32) -> JointSample {
+        JointSample {
+            rotation: self.rotation_at(tick),
+            position: self.position_at(tick),
+        }
+    }
+}
+
+impl Animation {
+    /// Map `t` (seconds) into `[0, duration]` according to `mode`.
+    ///
+    /// Loop-based modes fall back to clamping when the loop region is empty, inverted or not
+    /// made of numbers.
+    pub fn wrap_time(&self, t: f32, mode: WrapMode) -> f32 {
+        let duration = self.header.duration;
+        if !duration.is_finite() || duration <= 0.0 || !t.is_finite() {
+            return 0.0;
+        }
+        let (loop_in, loop_out) = (self.header.loop_in_point, self.header.loop_out_point);
+        if !(loop_in.is_finite() && loop_out.is_finite()) || loop_in > loop_out {
+            return t.clamp(0.0, duration);
+        }
+        let loop_in = loop_in.clamp(0.0, duration);
+        let loop_out = loop_out.clamp(loop_in, duration);
+        let len = loop_out - loop_in;
+        if len <= f32::EPSILON || t < loop_out {
+            return t.clamp(0.0, duration);
+        }
+        match mode {
+            WrapMode::Clamp => t.clamp(0.0, duration),
+            WrapMode::Loop => loop_in + (t - loop_in).rem_euclid(len),
+            WrapMode::PingPong => {
+                let phase = (t - loop_out).rem_euclid(2.0 * len);
+                if phase < len {
+                    loop_out - phase
+                } else {
+                    loop_in + (phase - len)
+                }
+            }
+        }
+    }
+
+    /// Convert a time in seconds to key time units (`0..=65535`), without wrapping.
+    pub fn seconds_to_tick(&self, t: f32) -> f32 {
+        let duration = self.header.duration;
+        if duration > 0.0 {
+            (t / duration).clamp(0.0, 1.0) * u16::MAX as f32
+        } else {
+            0.0
+        }
+    }
+
+    /// Sample the named joint at `t` seconds, wrapping out-of-range times with `mode`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use avatar_anim::{Animation, WrapMode};
+    ///
+    /// let anim = Animation::default();
+    /// assert!(anim.sample_joint("mPelvis", 1.5, WrapMode::Loop).is_none());
+    /// ```
+    pub fn sample_joint(&self, name: &str, t: f32, mode: WrapMode) -> Option<JointSample> {
+        let tick = self.seconds_to_tick(self.wrap_time(t, mode));
+        self.joint(name).map(|joint| joint.sample_at(tick))
+    }
+
+    /// Evaluate every joint at `t` seconds, slerping rotations and lerping positions between
+    /// the surrounding keys.
+    ///
+    /// Looping animations wrap `t` with [`WrapMode::Loop`], others hold their last frame, as
+    /// the viewer plays them. When two joints share a name the first wins, like in the viewer.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use avatar_anim::{Animation, JointData, RotationKey};
+    /// use glam::Quat;
+    ///
+    /// let mut anim = Animation::new();
+    /// anim.header.duration = 2.0;
+    /// anim.header.looped = 0;
+    /// anim.joints.push(JointData {
+    ///     name: "mHead".into(),
+    ///     rotation_keys: vec![
+    ///         RotationKey { time: 0, rot: Quat::IDENTITY },
+    ///         RotationKey { time: u16::MAX, rot: Quat::from_rotation_z(1.0) },
+    ///     ],
+    ///     ..Default::default()
+    /// });
+    /// let pose = anim.sample(1.0);
+    /// let head = pose.rotation("mHead").unwrap();
+    /// assert!(head.angle_between(Quat::from_rotation_z(0.5)) < 1e-3);
+    /// ```
+    pub fn sample(&self, t: f32) -> Pose {
+        let mode = if self.header.looped != 0 {
+            WrapMode::Loop
+        } else {
+            WrapMode::Clamp
+        };
+        let tick = self.seconds_to_tick(self.wrap_time(t, mode));
+        let mut pose = Pose::new();
+        for joint in &self.joints {
+            pose.joints
+                .entry(joint.name.clone())
+                .or_insert_with(|| joint.sample_at(tick));
+        }
+        pose
+    }
+
+    /// Freeze the frame [`Animation::sample`] gives at `t` seconds into a still animation.
+    ///
+    /// This is [`Pose::to_animation`] of that sample, except that joints keep their priority
+    /// and the header is kept as is, so the result can be written as a pose file with
+    /// [`Animation::to_llsd`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use avatar_anim::Animation;
+    ///
+    /// let mut anim = Animation::new();
+    /// anim.header.duration = 2.0;
+    /// let pose = anim.pose_at(0.5);
+    /// assert_eq!(pose.header.duration, 2.0);
+    /// ```
+    pub fn pose_at(&self, t: f32) -> Animation {
+        let mut still = self.sample(t).to_animation();
+        still.header = self.header.clone();
+        for joint in &mut still.joints {
+            if let Some(source) = self.joint(&joint.name) {
+                joint.priority = source.priority;
+            }
+        }
+        still
+    }
+}
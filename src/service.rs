@@ -0,0 +1,218 @@
+//! One well-typed entry point for embedding the toolchain in a long-running service.
+//!
+//! A [`ConversionRequest`] carries the input bytes, an optional input format, edits to apply
+//! and the output format; [`execute`] loads, edits, checks and encodes it in one call. Both
+//! types round-trip through serde, so a web backend can accept requests as JSON:
+//!
+//! ```json
+//! {
+//!   "input": [1, 0, 0, 0, ...],
+//!   "to": "bvh",
+//!   "steps": [
+//!     { "op": "set_priority", "priority": 4 },
+//!     { "op": "reduce_keys", "tolerance": 0.001 }
+//!   ]
+//! }
+//! ```
+//!
+//! Failures are [`AnimError`]s; [`AnimError::code`] gives a stable string to return to clients.
+
+use crate::report::{MAX_PLAUSIBLE_SECONDS, sanitize_header_times};
+use crate::validate::Issue;
+use crate::{
+    AnimError, Animation, DuplicateKeyStrategy, ParseWarning, ReadOptions, Result, SourceFormat,
+};
+use serde::{Deserialize, Serialize};
+
+/// An edit applied by [`execute`], in request order.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum Step {
+    /// [`Animation::set_priority`].
+    SetPriority { priority: i32 },
+    /// Remove the named joints.
+    DropJoints { joints: Vec<String> },
+    /// [`Animation::drop_position_keys`].
+    DropPositions,
+    /// [`Animation::drop_rotation_keys`].
+    DropRotations,
+    /// [`Animation::drop_empty_joints`].
+    DropEmptyJoints,
+    /// [`Animation::cleanup_keys_with`] keeping the last duplicate.
+    CleanupKeys,
+    /// [`Animation::reduce_keys`].
+    ReduceKeys { tolerance: f32 },
+    /// [`Animation::scale_positions`].
+    ScalePositions { factor: f32 },
+    /// [`Animation::remove_root_yaw_drift`].
+    RemoveRootYawDrift,
+    /// Apply [`crate::analysis::suggest_loop`], if it finds a loop.
+    SuggestLoop,
+}
+
+impl Step {
+    fn apply(&self, anim: &mut Animation) {
+        match self {
+            Step::SetPriority { priority } => {
+                anim.set_priority(*priority);
+            }
+            Step::DropJoints { joints } => anim.joints.retain(|j| !joints.contains(&j.name)),
+            Step::DropPositions => {
+                anim.drop_position_keys();
+            }
+            Step::DropRotations => {
+                anim.drop_rotation_keys();
+            }
+            Step::DropEmptyJoints => {
+                anim.drop_empty_joints();
+            }
+            Step::CleanupKeys => {
+                anim.cleanup_keys_with(DuplicateKeyStrategy::KeepLast);
+            }
+            Step::ReduceKeys { tolerance } => {
+                anim.reduce_keys(*tolerance);
+            }
+            Step::ScalePositions { factor } => {
+                anim.scale_positions(*factor);
+            }
+            Step::RemoveRootYawDrift => {
+                anim.remove_root_yaw_drift();
+            }
+            Step::SuggestLoop => {
+                if let Some(suggestion) = crate::analysis::suggest_loop(anim) {
+                    suggestion.apply(anim);
+                }
+            }
+        }
+    }
+}
+
+/// Input of [`execute`].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ConversionRequest {
+    /// The file to convert; gzip is undone when the `gzip` feature is enabled.
+    pub input: Vec<u8>,
+    /// Input format; detected from the content when omitted.
+    #[serde(default)]
+    pub from: Option<SourceFormat>,
+    /// Output format, `anim` by default.
+    #[serde(default = "default_format")]
+    pub to: SourceFormat,
+    #[serde(default)]
+    pub steps: Vec<Step>,
+    /// Reject `.anim` input with bytes after the constraint block.
+    #[serde(default)]
+    pub strict: bool,
+}
+
+fn default_format() -> SourceFormat {
+    SourceFormat::Anim
+}
+
+impl ConversionRequest {
+    /// A request converting `input` to `.anim` without edits.
+    pub fn new(input: impl Into<Vec<u8>>) -> Self {
+        Self {
+            input: input.into(),
+            from: None,
+            to: SourceFormat::Anim,
+            steps: Vec::new(),
+            strict: false,
+        }
+    }
+}
+
+/// Output of [`execute`].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ConversionResponse {
+    /// The encoded result.
+    pub output: Vec<u8>,
+    /// Format of `output`.
+    pub format: SourceFormat,
+    /// Format the input was read as.
+    pub source_format: SourceFormat,
+    /// Duration of the result in seconds.
+    pub duration: f32,
+    /// Joints in the result.
+    pub joints: usize,
+    /// Problems that didn't stop the conversion but will likely misbehave in the viewer: any
+    /// header times that were replaced, then the [`Animation::validate`] issues, each
+    /// formatted as `"warning: ..."` or `"blocker: ..."`.
+    pub warnings: Vec<String>,
+}
+
+/// Load, edit, check and encode `request`.
+///
+/// `.anim` input is read within the default [`ReadOptions`] limits, which also bound gzip
+/// output. Header times that are not numbers or implausible are replaced as
+/// [`ReadOptions::sanitize_header`] would, with a warning each, whatever the input format;
+/// a result still lasting longer than an hour is not exported. An `.anim` result is decoded
+/// again before it is returned, so a response never carries a file the crate itself can't
+/// read.
+///
+/// # Example
+///
+/// ```rust
+/// use avatar_anim::service::{self, ConversionRequest, Step};
+/// use avatar_anim::{Animation, SourceFormat};
+///
+/// # fn main() -> avatar_anim::Result<()> {
+/// let mut request = ConversionRequest::new(Animation::new().to_bytes()?);
+/// request.steps.push(Step::SetPriority { priority: 4 });
+/// request.to = SourceFormat::Json;
+/// let response = service::execute(&request)?;
+/// assert_eq!(response.source_format, SourceFormat::Anim);
+/// assert!(String::from_utf8(response.output).unwrap().contains("\"base_priority\": 4"));
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// Fails if the input can't be read as `from` (or in any format, when detecting), a format's
+/// feature is disabled, or encoding fails.
+pub fn execute(request: &ConversionRequest) -> Result<ConversionResponse> {
+    let options = ReadOptions {
+        reject_trailing_bytes: request.strict,
+        ..Default::default()
+    };
+    let (mut anim, source_format) =
+        crate::open::open_untrusted(&request.input, request.from, &options)?;
+    let sanitized: Vec<String> = sanitize_header_times(&mut anim.header)
+        .into_iter()
+        .map(|(field, value, replacement)| {
+            ParseWarning::ImplausibleHeaderTime {
+                field,
+                value,
+                replaced_with: Some(replacement),
+            }
+            .to_string()
+        })
+        .collect();
+    for step in &request.steps {
+        step.apply(&mut anim);
+    }
+    let duration = anim.header.duration;
+    if !(duration.is_finite() && duration <= MAX_PLAUSIBLE_SECONDS) {
+        return Err(AnimError::InvalidStructure(format!(
+            "Refusing to export an animation lasting {duration}s"
+        )));
+    }
+    let output = anim.to_format(request.to)?;
+    if request.to == SourceFormat::Anim {
+        Animation::from_bytes(&output).map_err(|err| {
+            AnimError::InvalidStructure(format!("The converted animation doesn't read back: {err}"))
+        })?;
+    }
+    Ok(ConversionResponse {
+        output,
+        format: request.to,
+        source_format,
+        duration: anim.header.duration,
+        joints: anim.joints.len(),
+        warnings: sanitized
+            .into_iter()
+            .chain(anim.validate().issues.iter().map(Issue::to_string))
+            .collect(),
+    })
+}
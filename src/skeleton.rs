@@ -0,0 +1,1232 @@
+use crate::{AnimError, Pose, Result};
+use glam::{Mat4, Quat, Vec3};
+#[cfg(feature = "toml")]
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use std::sync::{Arc, OnceLock, PoisonError, RwLock};
+
+/// A single joint of a [`Skeleton`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct SkeletonJoint {
+    pub name: String,
+    /// Index of the parent joint, `None` for the root.
+    pub parent: Option<usize>,
+    /// Bind translation relative to the parent joint, in meters.
+    pub position: Vec3,
+}
+
+/// Placement of a joint in the avatar's frame (Z up, X forward), as computed by
+/// [`Skeleton::world_transforms`].
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct JointTransform {
+    pub rotation: Quat,
+    /// Position in meters.
+    pub translation: Vec3,
+}
+
+impl JointTransform {
+    pub const IDENTITY: Self = Self {
+        rotation: Quat::IDENTITY,
+        translation: Vec3::ZERO,
+    };
+
+    pub fn to_mat4(self) -> Mat4 {
+        Mat4::from_rotation_translation(self.rotation, self.translation)
+    }
+
+    /// Map a point given in the joint's own frame into the avatar's frame.
+    pub fn transform_point(self, point: Vec3) -> Vec3 {
+        self.translation + self.rotation * point
+    }
+}
+
+/// An ellipsoid the viewer attaches to a bone for physics and attachment points.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CollisionVolume {
+    pub name: String,
+    /// Name of the bone the volume moves with.
+    pub bone: String,
+    /// Center relative to the bone, in meters.
+    pub position: Vec3,
+    /// Radii of the ellipsoid along each axis, in meters.
+    pub scale: Vec3,
+}
+
+/// Joint hierarchy with bind translations, used as the reference body for analysis.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Skeleton {
+    joints: Vec<SkeletonJoint>,
+    collision_volumes: Vec<CollisionVolume>,
+}
+
+/// (name, parent, bind translation) of the default Second Life avatar, from `avatar_skeleton.xml`.
+const SECOND_LIFE_JOINTS: &[(&str, Option<&str>, [f32; 3])] = &[
+    ("mPelvis", None, [0.0, 0.0, 1.067]),
+    ("mTorso", Some("mPelvis"), [0.0, 0.0, 0.084]),
+    ("mChest", Some("mTorso"), [-0.015, 0.0, 0.205]),
+    ("mNeck", Some("mChest"), [-0.010, 0.0, 0.251]),
+    ("mHead", Some("mNeck"), [0.0, 0.0, 0.076]),
+    ("mSkull", Some("mHead"), [0.0, 0.0, 0.079]),
+    ("mEyeRight", Some("mHead"), [0.098, -0.036, 0.079]),
+    ("mEyeLeft", Some("mHead"), [0.098, 0.036, 0.079]),
+    ("mCollarLeft", Some("mChest"), [-0.021, 0.085, 0.165]),
+    ("mShoulderLeft", Some("mCollarLeft"), [0.0, 0.079, 0.0]),
+    ("mElbowLeft", Some("mShoulderLeft"), [0.0, 0.248, 0.0]),
+    ("mWristLeft", Some("mElbowLeft"), [0.0, 0.205, 0.0]),
+    ("mCollarRight", Some("mChest"), [-0.021, -0.085, 0.165]),
+    ("mShoulderRight", Some("mCollarRight"), [0.0, -0.079, 0.0]),
+    ("mElbowRight", Some("mShoulderRight"), [0.0, -0.248, 0.0]),
+    ("mWristRight", Some("mElbowRight"), [0.0, -0.205, 0.0]),
+    ("mHipRight", Some("mPelvis"), [0.034, -0.129, -0.041]),
+    ("mKneeRight", Some("mHipRight"), [-0.001, 0.049, -0.491]),
+    ("mAnkleRight", Some("mKneeRight"), [-0.029, 0.0, -0.468]),
+    ("mFootRight", Some("mAnkleRight"), [0.112, 0.0, -0.061]),
+    ("mToeRight", Some("mFootRight"), [0.109, 0.0, 0.0]),
+    ("mHipLeft", Some("mPelvis"), [0.034, 0.127, -0.041]),
+    ("mKneeLeft", Some("mHipLeft"), [-0.001, -0.046, -0.491]),
+    ("mAnkleLeft", Some("mKneeLeft"), [-0.029, 0.001, -0.468]),
+    ("mFootLeft", Some("mAnkleLeft"), [0.112, 0.0, -0.061]),
+    ("mToeLeft", Some("mFootLeft"), [0.109, 0.0, 0.0]),
+];
+
+/// (name, parent, bind translation) of the Bento avatar, all 133 bones of `avatar_skeleton.xml`
+/// version 2.0, parents before children.
+const BENTO_JOINTS: &[(&str, Option<&str>, [f32; 3])] = &[
+    ("mPelvis", None, [0.0, 0.0, 1.067]),
+    ("mSpine1", Some("mPelvis"), [0.0, 0.0, 0.084]),
+    ("mSpine2", Some("mSpine1"), [0.0, 0.0, -0.084]),
+    ("mTorso", Some("mSpine2"), [0.0, 0.0, 0.084]),
+    ("mSpine3", Some("mTorso"), [-0.015, 0.0, 0.205]),
+    ("mSpine4", Some("mSpine3"), [0.015, 0.0, -0.205]),
+    ("mChest", Some("mSpine4"), [-0.015, 0.0, 0.205]),
+    ("mNeck", Some("mChest"), [-0.01, 0.0, 0.251]),
+    ("mHead", Some("mNeck"), [0.0, 0.0, 0.076]),
+    ("mSkull", Some("mHead"), [0.0, 0.0, 0.079]),
+    ("mEyeRight", Some("mHead"), [0.098, -0.036, 0.079]),
+    ("mEyeLeft", Some("mHead"), [0.098, 0.036, 0.079]),
+    ("mFaceRoot", Some("mHead"), [0.025, 0.0, 0.045]),
+    (
+        "mFaceEyeAltRight",
+        Some("mFaceRoot"),
+        [0.073, -0.036, 0.034],
+    ),
+    ("mFaceEyeAltLeft", Some("mFaceRoot"), [0.073, 0.036, 0.034]),
+    (
+        "mFaceForeheadLeft",
+        Some("mFaceRoot"),
+        [0.061, 0.035, 0.083],
+    ),
+    (
+        "mFaceForeheadRight",
+        Some("mFaceRoot"),
+        [0.061, -0.035, 0.083],
+    ),
+    (
+        "mFaceEyebrowOuterLeft",
+        Some("mFaceRoot"),
+        [0.064, 0.051, 0.048],
+    ),
+    (
+        "mFaceEyebrowOuterRight",
+        Some("mFaceRoot"),
+        [0.064, -0.051, 0.048],
+    ),
+    (
+        "mFaceEyebrowCenterLeft",
+        Some("mFaceRoot"),
+        [0.07, 0.043, 0.056],
+    ),
+    (
+        "mFaceEyebrowCenterRight",
+        Some("mFaceRoot"),
+        [0.07, -0.043, 0.056],
+    ),
+    (
+        "mFaceEyebrowInnerLeft",
+        Some("mFaceRoot"),
+        [0.075, 0.022, 0.051],
+    ),
+    (
+        "mFaceEyebrowInnerRight",
+        Some("mFaceRoot"),
+        [0.075, -0.022, 0.051],
+    ),
+    (
+        "mFaceEyeLidUpperLeft",
+        Some("mFaceRoot"),
+        [0.073, 0.036, 0.034],
+    ),
+    (
+        "mFaceEyeLidUpperRight",
+        Some("mFaceRoot"),
+        [0.073, -0.036, 0.034],
+    ),
+    (
+        "mFaceEyeLidLowerLeft",
+        Some("mFaceRoot"),
+        [0.073, 0.036, 0.034],
+    ),
+    (
+        "mFaceEyeLidLowerRight",
+        Some("mFaceRoot"),
+        [0.073, -0.036, 0.034],
+    ),
+    ("mFaceEar1Left", Some("mFaceRoot"), [0.0, 0.08, 0.002]),
+    ("mFaceEar1Right", Some("mFaceRoot"), [0.0, -0.08, 0.002]),
+    (
+        "mFaceEar2Left",
+        Some("mFaceEar1Left"),
+        [-0.019, 0.018, 0.025],
+    ),
+    (
+        "mFaceEar2Right",
+        Some("mFaceEar1Right"),
+        [-0.019, -0.018, 0.025],
+    ),
+    ("mFaceNoseLeft", Some("mFaceRoot"), [0.086, 0.015, -0.004]),
+    ("mFaceNoseCenter", Some("mFaceRoot"), [0.102, 0.0, 0.0]),
+    ("mFaceNoseRight", Some("mFaceRoot"), [0.086, -0.015, -0.004]),
+    (
+        "mFaceCheekLowerLeft",
+        Some("mFaceRoot"),
+        [0.05, 0.034, -0.031],
+    ),
+    (
+        "mFaceCheekLowerRight",
+        Some("mFaceRoot"),
+        [0.05, -0.034, -0.031],
+    ),
+    (
+        "mFaceCheekUpperLeft",
+        Some("mFaceRoot"),
+        [0.07, 0.034, -0.005],
+    ),
+    (
+        "mFaceCheekUpperRight",
+        Some("mFaceRoot"),
+        [0.07, -0.034, -0.005],
+    ),
+    ("mFaceJaw", Some("mFaceRoot"), [-0.001, 0.0, -0.015]),
+    ("mFaceChin", Some("mFaceJaw"), [0.074, 0.0, -0.054]),
+    ("mFaceTeethLower", Some("mFaceJaw"), [0.021, 0.0, -0.039]),
+    (
+        "mFaceLipLowerLeft",
+        Some("mFaceTeethLower"),
+        [0.045, 0.0, 0.0],
+    ),
+    (
+        "mFaceLipLowerRight",
+        Some("mFaceTeethLower"),
+        [0.045, 0.0, 0.0],
+    ),
+    (
+        "mFaceLipLowerCenter",
+        Some("mFaceTeethLower"),
+        [0.045, 0.0, 0.0],
+    ),
+    (
+        "mFaceTongueBase",
+        Some("mFaceTeethLower"),
+        [0.039, 0.0, 0.005],
+    ),
+    (
+        "mFaceTongueTip",
+        Some("mFaceTongueBase"),
+        [0.022, 0.0, 0.007],
+    ),
+    ("mFaceJawShaper", Some("mFaceRoot"), [0.0, 0.0, 0.0]),
+    (
+        "mFaceForeheadCenter",
+        Some("mFaceRoot"),
+        [0.069, 0.0, 0.065],
+    ),
+    ("mFaceNoseBase", Some("mFaceRoot"), [0.094, 0.0, -0.016]),
+    ("mFaceTeethUpper", Some("mFaceRoot"), [0.02, 0.0, -0.03]),
+    (
+        "mFaceLipUpperLeft",
+        Some("mFaceTeethUpper"),
+        [0.045, 0.0, -0.003],
+    ),
+    (
+        "mFaceLipUpperRight",
+        Some("mFaceTeethUpper"),
+        [0.045, 0.0, -0.003],
+    ),
+    (
+        "mFaceLipCornerLeft",
+        Some("mFaceTeethUpper"),
+        [0.028, 0.019, -0.01],
+    ),
+    (
+        "mFaceLipCornerRight",
+        Some("mFaceTeethUpper"),
+        [0.028, -0.019, -0.01],
+    ),
+    (
+        "mFaceLipUpperCenter",
+        Some("mFaceTeethUpper"),
+        [0.045, 0.0, -0.003],
+    ),
+    (
+        "mFaceEyecornerInnerLeft",
+        Some("mFaceRoot"),
+        [0.075, 0.017, 0.032],
+    ),
+    (
+        "mFaceEyecornerInnerRight",
+        Some("mFaceRoot"),
+        [0.075, -0.017, 0.032],
+    ),
+    ("mFaceNoseBridge", Some("mFaceRoot"), [0.091, 0.0, 0.02]),
+    ("mCollarLeft", Some("mChest"), [-0.021, 0.085, 0.165]),
+    ("mShoulderLeft", Some("mCollarLeft"), [0.0, 0.079, 0.0]),
+    ("mElbowLeft", Some("mShoulderLeft"), [0.0, 0.248, 0.0]),
+    ("mWristLeft", Some("mElbowLeft"), [0.0, 0.205, 0.0]),
+    (
+        "mHandMiddle1Left",
+        Some("mWristLeft"),
+        [0.013, 0.101, 0.015],
+    ),
+    (
+        "mHandMiddle2Left",
+        Some("mHandMiddle1Left"),
+        [-0.001, 0.04, -0.006],
+    ),
+    (
+        "mHandMiddle3Left",
+        Some("mHandMiddle2Left"),
+        [-0.001, 0.049, -0.008],
+    ),
+    ("mHandIndex1Left", Some("mWristLeft"), [0.038, 0.097, 0.015]),
+    (
+        "mHandIndex2Left",
+        Some("mHandIndex1Left"),
+        [0.017, 0.036, -0.006],
+    ),
+    (
+        "mHandIndex3Left",
+        Some("mHandIndex2Left"),
+        [0.014, 0.032, -0.006],
+    ),
+    ("mHandRing1Left", Some("mWristLeft"), [-0.01, 0.099, 0.009]),
+    (
+        "mHandRing2Left",
+        Some("mHandRing1Left"),
+        [-0.013, 0.038, -0.008],
+    ),
+    (
+        "mHandRing3Left",
+        Some("mHandRing2Left"),
+        [-0.013, 0.04, -0.009],
+    ),
+    (
+        "mHandPinky1Left",
+        Some("mWristLeft"),
+        [-0.031, 0.095, 0.003],
+    ),
+    (
+        "mHandPinky2Left",
+        Some("mHandPinky1Left"),
+        [-0.024, 0.025, -0.006],
+    ),
+    (
+        "mHandPinky3Left",
+        Some("mHandPinky2Left"),
+        [-0.015, 0.018, -0.004],
+    ),
+    ("mHandThumb1Left", Some("mWristLeft"), [0.031, 0.026, 0.004]),
+    (
+        "mHandThumb2Left",
+        Some("mHandThumb1Left"),
+        [0.028, 0.032, -0.001],
+    ),
+    (
+        "mHandThumb3Left",
+        Some("mHandThumb2Left"),
+        [0.023, 0.031, -0.001],
+    ),
+    ("mCollarRight", Some("mChest"), [-0.021, -0.085, 0.165]),
+    ("mShoulderRight", Some("mCollarRight"), [0.0, -0.079, 0.0]),
+    ("mElbowRight", Some("mShoulderRight"), [0.0, -0.248, 0.0]),
+    ("mWristRight", Some("mElbowRight"), [0.0, -0.205, 0.0]),
+    (
+        "mHandMiddle1Right",
+        Some("mWristRight"),
+        [0.013, -0.101, 0.015],
+    ),
+    (
+        "mHandMiddle2Right",
+        Some("mHandMiddle1Right"),
+        [-0.001, -0.04, -0.006],
+    ),
+    (
+        "mHandMiddle3Right",
+        Some("mHandMiddle2Right"),
+        [-0.001, -0.049, -0.008],
+    ),
+    (
+        "mHandIndex1Right",
+        Some("mWristRight"),
+        [0.038, -0.097, 0.015],
+    ),
+    (
+        "mHandIndex2Right",
+        Some("mHandIndex1Right"),
+        [0.017, -0.036, -0.006],
+    ),
+    (
+        "mHandIndex3Right",
+        Some("mHandIndex2Right"),
+        [0.014, -0.032, -0.006],
+    ),
+    (
+        "mHandRing1Right",
+        Some("mWristRight"),
+        [-0.01, -0.099, 0.009],
+    ),
+    (
+        "mHandRing2Right",
+        Some("mHandRing1Right"),
+        [-0.013, -0.038, -0.008],
+    ),
+    (
+        "mHandRing3Right",
+        Some("mHandRing2Right"),
+        [-0.013, -0.04, -0.009],
+    ),
+    (
+        "mHandPinky1Right",
+        Some("mWristRight"),
+        [-0.031, -0.095, 0.003],
+    ),
+    (
+        "mHandPinky2Right",
+        Some("mHandPinky1Right"),
+        [-0.024, -0.025, -0.006],
+    ),
+    (
+        "mHandPinky3Right",
+        Some("mHandPinky2Right"),
+        [-0.015, -0.018, -0.004],
+    ),
+    (
+        "mHandThumb1Right",
+        Some("mWristRight"),
+        [0.031, -0.026, 0.004],
+    ),
+    (
+        "mHandThumb2Right",
+        Some("mHandThumb1Right"),
+        [0.028, -0.032, -0.001],
+    ),
+    (
+        "mHandThumb3Right",
+        Some("mHandThumb2Right"),
+        [0.023, -0.031, -0.001],
+    ),
+    ("mWingsRoot", Some("mChest"), [-0.014, 0.0, 0.0]),
+    ("mWing1Left", Some("mWingsRoot"), [-0.099, 0.105, 0.181]),
+    ("mWing2Left", Some("mWing1Left"), [-0.168, 0.169, 0.067]),
+    ("mWing3Left", Some("mWing2Left"), [-0.181, 0.183, 0.0]),
+    ("mWing4Left", Some("mWing3Left"), [-0.171, 0.173, 0.0]),
+    ("mWing4FanLeft", Some("mWing3Left"), [-0.171, 0.173, 0.0]),
+    ("mWing1Right", Some("mWingsRoot"), [-0.099, -0.105, 0.181]),
+    ("mWing2Right", Some("mWing1Right"), [-0.168, -0.169, 0.067]),
+    ("mWing3Right", Some("mWing2Right"), [-0.181, -0.183, 0.0]),
+    ("mWing4Right", Some("mWing3Right"), [-0.171, -0.173, 0.0]),
+    ("mWing4FanRight", Some("mWing3Right"), [-0.171, -0.173, 0.0]),
+    ("mHipRight", Some("mPelvis"), [0.034, -0.129, -0.041]),
+    ("mKneeRight", Some("mHipRight"), [-0.001, 0.049, -0.491]),
+    ("mAnkleRight", Some("mKneeRight"), [-0.029, 0.0, -0.468]),
+    ("mFootRight", Some("mAnkleRight"), [0.112, 0.0, -0.061]),
+    ("mToeRight", Some("mFootRight"), [0.109, 0.0, 0.0]),
+    ("mHipLeft", Some("mPelvis"), [0.034, 0.127, -0.041]),
+    ("mKneeLeft", Some("mHipLeft"), [-0.001, -0.046, -0.491]),
+    ("mAnkleLeft", Some("mKneeLeft"), [-0.029, 0.001, -0.468]),
+    ("mFootLeft", Some("mAnkleLeft"), [0.112, 0.0, -0.061]),
+    ("mToeLeft", Some("mFootLeft"), [0.109, 0.0, 0.0]),
+    ("mTail1", Some("mPelvis"), [-0.116, 0.0, 0.047]),
+    ("mTail2", Some("mTail1"), [-0.197, 0.0, 0.0]),
+    ("mTail3", Some("mTail2"), [-0.168, 0.0, 0.0]),
+    ("mTail4", Some("mTail3"), [-0.142, 0.0, 0.0]),
+    ("mTail5", Some("mTail4"), [-0.112, 0.0, 0.0]),
+    ("mTail6", Some("mTail5"), [-0.094, 0.0, 0.0]),
+    ("mGroin", Some("mPelvis"), [0.064, 0.0, -0.097]),
+    ("mHindLimbsRoot", Some("mPelvis"), [-0.2, 0.0, 0.084]),
+    (
+        "mHindLimb1Left",
+        Some("mHindLimbsRoot"),
+        [-0.204, 0.129, -0.125],
+    ),
+    (
+        "mHindLimb2Left",
+        Some("mHindLimb1Left"),
+        [0.002, -0.046, -0.491],
+    ),
+    (
+        "mHindLimb3Left",
+        Some("mHindLimb2Left"),
+        [-0.03, -0.003, -0.468],
+    ),
+    (
+        "mHindLimb4Left",
+        Some("mHindLimb3Left"),
+        [0.112, 0.0, -0.061],
+    ),
+    (
+        "mHindLimb1Right",
+        Some("mHindLimbsRoot"),
+        [-0.204, -0.129, -0.125],
+    ),
+    (
+        "mHindLimb2Right",
+        Some("mHindLimb1Right"),
+        [0.002, 0.046, -0.491],
+    ),
+    (
+        "mHindLimb3Right",
+        Some("mHindLimb2Right"),
+        [-0.03, 0.003, -0.468],
+    ),
+    (
+        "mHindLimb4Right",
+        Some("mHindLimb3Right"),
+        [0.112, 0.0, -0.061],
+    ),
+];
+
+/// (name, bone, position, scale) of the 26 collision volumes of `avatar_skeleton.xml`.
+const COLLISION_VOLUMES: &[(&str, &str, [f32; 3], [f32; 3])] = &[
+    ("PELVIS", "mPelvis", [-0.01, 0.0, -0.02], [0.12, 0.16, 0.17]),
+    ("BUTT", "mPelvis", [-0.06, 0.0, -0.1], [0.1, 0.1, 0.1]),
+    ("BELLY", "mTorso", [0.028, 0.0, 0.04], [0.09, 0.13, 0.15]),
+    (
+        "LOWER_BACK",
+        "mTorso",
+        [0.0, 0.0, 0.023],
+        [0.09, 0.13, 0.15],
+    ),
+    (
+        "LEFT_HANDLE",
+        "mTorso",
+        [0.0, 0.1, 0.058],
+        [0.05, 0.05, 0.05],
+    ),
+    (
+        "RIGHT_HANDLE",
+        "mTorso",
+        [0.0, -0.1, 0.058],
+        [0.05, 0.05, 0.05],
+    ),
+    ("CHEST", "mChest", [0.028, 0.0, 0.07], [0.11, 0.15, 0.2]),
+    (
+        "LEFT_PEC",
+        "mChest",
+        [0.119, 0.082, 0.042],
+        [0.05, 0.05, 0.05],
+    ),
+    (
+        "RIGHT_PEC",
+        "mChest",
+        [0.119, -0.082, 0.042],
+        [0.05, 0.05, 0.05],
+    ),
+    (
+        "UPPER_BACK",
+        "mChest",
+        [0.0, 0.0, 0.017],
+        [0.09, 0.13, 0.15],
+    ),
+    ("NECK", "mNeck", [0.0, 0.0, 0.02], [0.05, 0.06, 0.08]),
+    ("HEAD", "mHead", [0.02, 0.0, 0.07], [0.11, 0.09, 0.12]),
+    (
+        "L_CLAVICLE",
+        "mCollarLeft",
+        [0.02, 0.0, 0.02],
+        [0.07, 0.14, 0.05],
+    ),
+    (
+        "L_UPPER_ARM",
+        "mShoulderLeft",
+        [0.0, 0.12, 0.01],
+        [0.05, 0.17, 0.05],
+    ),
+    (
+        "L_LOWER_ARM",
+        "mElbowLeft",
+        [0.0, 0.1, 0.0],
+        [0.04, 0.14, 0.04],
+    ),
+    (
+        "L_HAND",
+        "mWristLeft",
+        [0.01, 0.05, 0.0],
+        [0.05, 0.08, 0.03],
+    ),
+    (
+        "R_CLAVICLE",
+        "mCollarRight",
+        [0.02, 0.0, 0.02],
+        [0.07, 0.14, 0.05],
+    ),
+    (
+        "R_UPPER_ARM",
+        "mShoulderRight",
+        [0.0, -0.12, 0.01],
+        [0.05, 0.17, 0.05],
+    ),
+    (
+        "R_LOWER_ARM",
+        "mElbowRight",
+        [0.0, -0.1, 0.0],
+        [0.04, 0.14, 0.04],
+    ),
+    (
+        "R_HAND",
+        "mWristRight",
+        [0.01, -0.05, 0.0],
+        [0.05, 0.08, 0.03],
+    ),
+    (
+        "R_UPPER_LEG",
+        "mHipRight",
+        [-0.02, 0.05, -0.22],
+        [0.09, 0.09, 0.32],
+    ),
+    (
+        "R_LOWER_LEG",
+        "mKneeRight",
+        [-0.02, 0.0, -0.2],
+        [0.06, 0.06, 0.25],
+    ),
+    (
+        "R_FOOT",
+        "mAnkleRight",
+        [0.077, 0.0, -0.041],
+        [0.13, 0.05, 0.05],
+    ),
+    (
+        "L_UPPER_LEG",
+        "mHipLeft",
+        [-0.02, -0.05, -0.22],
+        [0.09, 0.09, 0.32],
+    ),
+    (
+        "L_LOWER_LEG",
+        "mKneeLeft",
+        [-0.02, 0.0, -0.2],
+        [0.06, 0.06, 0.25],
+    ),
+    (
+        "L_FOOT",
+        "mAnkleLeft",
+        [0.077, 0.0, -0.041],
+        [0.13, 0.05, 0.05],
+    ),
+];
+
+impl Skeleton {
+    fn from_table(table: &[(&str, Option<&str>, [f32; 3])]) -> Self {
+        let mut skeleton = Self::default();
+        for (name, parent, pos) in table {
+            let parent = parent.and_then(|p| skeleton.index_of(p));
+            skeleton.joints.push(SkeletonJoint {
+                name: (*name).to_string(),
+                parent,
+                position: Vec3::from_array(*pos),
+            });
+        }
+        skeleton
+    }
+
+    /// The default Second Life avatar skeleton: the 26 joints of the legacy system avatar.
+    pub fn second_life() -> Self {
+        Self::from_table(SECOND_LIFE_JOINTS)
+    }
+
+    /// The full Bento skeleton of the default avatar, built in: all 133 bones (spine, face,
+    /// fingers, wings, tail, hind limbs and groin) with their bind translations, plus the
+    /// collision volumes.
+    ///
+    /// The legacy joints keep their names, but the spine is longer: `mTorso` now hangs below
+    /// `mSpine1` and `mSpine2`, and `mChest` below `mSpine3` and `mSpine4`.
+    pub fn bento() -> Self {
+        let mut skeleton = Self::from_table(BENTO_JOINTS);
+        skeleton.collision_volumes = COLLISION_VOLUMES
+            .iter()
+            .map(|(name, bone, position, scale)| CollisionVolume {
+                name: (*name).to_string(),
+                bone: (*bone).to_string(),
+                position: Vec3::from_array(*position),
+                scale: Vec3::from_array(*scale),
+            })
+            .collect();
+        skeleton
+    }
+
+    /// All joints, parents always ordered before their children.
+    pub fn joints(&self) -> &[SkeletonJoint] {
+        &self.joints
+    }
+
+    /// Load the viewer's skeleton definition, `character/avatar_skeleton.xml` in a viewer
+    /// install, e.g. the one of an OpenSim grid with extra or moved bones.
+    ///
+    /// # Errors
+    ///
+    /// Fails with [`AnimError::Io`] if the file can't be read, otherwise like
+    /// [`Skeleton::from_avatar_skeleton_str`].
+    pub fn from_avatar_skeleton_xml<P: AsRef<std::path::Path>>(path: P) -> Result<Self> {
+        let text = std::fs::read_to_string(path).map_err(AnimError::Io)?;
+        Self::from_avatar_skeleton_str(&text)
+    }
+
+    /// Parse the text of an `avatar_skeleton.xml`: every nested `<bone>` becomes a joint with
+    /// its `pos` as bind translation, every `<collision_volume>` a [`CollisionVolume`] of the
+    /// bone around it. Other elements and attributes are ignored.
+    ///
+    /// # Errors
+    ///
+    /// Fails with [`AnimError::InvalidStructure`] on malformed markup, a bone or volume without
+    /// a name or with an unreadable `pos`/`scale`, a repeated bone name, or a file without
+    /// bones.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use avatar_anim::skeleton::Skeleton;
+    ///
+    /// # fn main() -> avatar_anim::Result<()> {
+    /// let skeleton = Skeleton::from_avatar_skeleton_str(
+    ///     r#"<linden_skeleton num_bones="2" num_collision_volumes="1" version="2.0">
+    ///          <bone name="mPelvis" pos="0.000 0.000 1.067">
+    ///            <collision_volume name="PELVIS" pos="-0.01 0.0 -0.02" scale="0.12 0.16 0.17"/>
+    ///            <bone name="mTorso" pos="0.000 0.000 0.084"></bone>
+    ///          </bone>
+    ///        </linden_skeleton>"#,
+    /// )?;
+    /// assert_eq!(skeleton.parent("mTorso").unwrap().name, "mPelvis");
+    /// assert_eq!(skeleton.collision_volume("PELVIS").unwrap().bone, "mPelvis");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn from_avatar_skeleton_str(text: &str) -> Result<Self> {
+        let invalid =
+            |what: String| AnimError::InvalidStructure(format!("Invalid avatar skeleton: {what}"));
+        let mut skeleton = Self::default();
+        // Joint index of each open <bone>.
+        let mut open: Vec<usize> = Vec::new();
+        let mut rest = text;
+        while let Some(start) = rest.find('<') {
+            rest = &rest[start..];
+            if let Some(comment) = rest.strip_prefix("<!--") {
+                let end = comment
+                    .find("-->")
+                    .ok_or_else(|| invalid("unterminated comment".into()))?;
+                rest = &comment[end + 3..];
+                continue;
+            }
+            let end = rest
+                .find('>')
+                .ok_or_else(|| invalid("unterminated tag".into()))?;
+            let tag = &rest[1..end];
+            rest = &rest[end + 1..];
+
+            if let Some(name) = tag.strip_prefix('/') {
+                if name.trim() == "bone" && open.pop().is_none() {
+                    return Err(invalid("</bone> without an open bone".into()));
+                }
+                continue;
+            }
+            let (tag, self_closing) = match tag.strip_suffix('/') {
+                Some(tag) => (tag, true),
+                None => (tag, false),
+            };
+            let element = tag.split_whitespace().next().unwrap_or_default();
+            if !matches!(element, "bone" | "collision_volume") {
+                continue;
+            }
+            let attrs = xml_attributes(&tag[element.len()..])
+                .ok_or_else(|| invalid(format!("malformed attributes in <{element}>")))?;
+            let attr = |key: &str| {
+                attrs
+                    .iter()
+                    .find(|(name, _)| *name == key)
+                    .map(|(_, value)| value.as_str())
+            };
+            let name = attr("name")
+                .ok_or_else(|| invalid(format!("<{element}> without a name")))?
+                .to_string();
+            let vec3 = |key: &str| -> Result<Vec3> {
+                let Some(value) = attr(key) else {
+                    return Ok(Vec3::ZERO);
+                };
+                let parts: Vec<f32> = value
+                    .split_whitespace()
+                    .map(str::parse)
+                    .collect::<std::result::Result<_, _>>()
+                    .map_err(|_| invalid(format!("{key} \"{value}\" of {name}")))?;
+                <[f32; 3]>::try_from(parts)
+                    .map(Vec3::from_array)
+                    .map_err(|_| invalid(format!("{key} \"{value}\" of {name}")))
+            };
+
+            if element == "bone" {
+                if skeleton.index_of(&name).is_some() {
+                    return Err(invalid(format!("bone {name} appears more than once")));
+                }
+                skeleton.joints.push(SkeletonJoint {
+                    position: vec3("pos")?,
+                    parent: open.last().copied(),
+                    name,
+                });
+                if !self_closing {
+                    open.push(skeleton.joints.len() - 1);
+                }
+            } else {
+                let bone = open
+                    .last()
+                    .map(|&index| skeleton.joints[index].name.clone())
+                    .ok_or_else(|| invalid(format!("collision volume {name} outside a bone")))?;
+                skeleton.collision_volumes.push(CollisionVolume {
+                    position: vec3("pos")?,
+                    scale: vec3("scale")?,
+                    name,
+                    bone,
+                });
+            }
+        }
+        if !open.is_empty() {
+            return Err(invalid("unclosed <bone>".into()));
+        }
+        if skeleton.joints.is_empty() {
+            return Err(invalid("no bones".into()));
+        }
+        Ok(skeleton)
+    }
+
+    /// Collision volumes, empty for [`Skeleton::second_life`] and built skeletons.
+    pub fn collision_volumes(&self) -> &[CollisionVolume] {
+        &self.collision_volumes
+    }
+
+    pub fn collision_volume(&self, name: &str) -> Option<&CollisionVolume> {
+        self.collision_volumes
+            .iter()
+            .find(|volume| volume.name == name)
+    }
+
+    pub fn index_of(&self, name: &str) -> Option<usize> {
+        self.joints.iter().position(|joint| joint.name == name)
+    }
+
+    pub fn joint(&self, name: &str) -> Option<&SkeletonJoint> {
+        self.joints.iter().find(|joint| joint.name == name)
+    }
+
+    pub fn parent(&self, name: &str) -> Option<&SkeletonJoint> {
+        self.joint(name)
+            .and_then(|joint| joint.parent)
+            .map(|index| &self.joints[index])
+    }
+
+    /// Every descendant of `name`, in skeleton order, without `name` itself, e.g. the elbow,
+    /// wrist and 15 finger bones for `mShoulderLeft` on [`Skeleton::bento`].
+    ///
+    /// Returns `None` if the joint is unknown.
+    pub fn joints_below(&self, name: &str) -> Option<Vec<&str>> {
+        let root = self.index_of(name)?;
+        let mut inside = vec![false; self.joints.len()];
+        inside[root] = true;
+        let mut below = Vec::new();
+        // Parents come before their children, so one pass sees every ancestor first.
+        for (index, joint) in self.joints.iter().enumerate().skip(root + 1) {
+            if joint.parent.is_some_and(|parent| inside[parent]) {
+                inside[index] = true;
+                below.push(joint.name.as_str());
+            }
+        }
+        Some(below)
+    }
+
+    /// Joints from `from` down to its descendant `to`, both included, e.g. `mChest` to
+    /// `mWristLeft` gives the chest, collar, shoulder, elbow and wrist.
+    ///
+    /// Returns `None` if either joint is unknown or `from` is not an ancestor of (or equal to)
+    /// `to`.
+    pub fn chain(&self, from: &str, to: &str) -> Option<Vec<&str>> {
+        let from = self.index_of(from)?;
+        let mut chain = Vec::new();
+        let mut index = Some(self.index_of(to)?);
+        while let Some(i) = index {
+            chain.push(self.joints[i].name.as_str());
+            if i == from {
+                chain.reverse();
+                return Some(chain);
+            }
+            index = self.joints[i].parent;
+        }
+        None
+    }
+
+    /// Forward kinematics: the placement of every joint in the avatar's frame for `pose`, in
+    /// the order of [`Skeleton::joints`].
+    ///
+    /// Joints the pose doesn't drive keep their bind rotation and translation. Position
+    /// channels of a root are offsets from its bind translation, as in the `.anim` pelvis
+    /// track; on other joints they replace the bind translation. Pose joints the skeleton
+    /// doesn't know are ignored.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use avatar_anim::Pose;
+    /// use avatar_anim::skeleton::Skeleton;
+    /// use glam::{Quat, Vec3};
+    ///
+    /// let skeleton = Skeleton::second_life();
+    /// let mut pose = Pose::new();
+    /// // Raise the left arm straight up.
+    /// pose.set_rotation("mShoulderLeft", Quat::from_rotation_x(std::f32::consts::FRAC_PI_2));
+    /// let world = skeleton.world_transforms(&pose);
+    /// let shoulder = world[skeleton.index_of("mShoulderLeft").unwrap()].translation;
+    /// let wrist = world[skeleton.index_of("mWristLeft").unwrap()].translation;
+    /// assert!((wrist - shoulder).abs_diff_eq(Vec3::new(0.0, 0.0, 0.453), 1e-5));
+    /// ```
+    pub fn world_transforms(&self, pose: &Pose) -> Vec<JointTransform> {
+        let mut world: Vec<JointTransform> = Vec::with_capacity(self.joints.len());
+        for joint in &self.joints {
+            let sample = pose.get(&joint.name);
+            let rotation = sample.and_then(|s| s.rotation).unwrap_or(Quat::IDENTITY);
+            let position = sample.and_then(|s| s.position);
+            let local = match (joint.parent, position) {
+                (None, Some(offset)) => joint.position + offset,
+                (Some(_), Some(position)) => position,
+                (_, None) => joint.position,
+            };
+            let parent = joint
+                .parent
+                .map_or(JointTransform::IDENTITY, |index| world[index]);
+            world.push(JointTransform {
+                rotation: parent.rotation * rotation,
+                translation: parent.transform_point(local),
+            });
+        }
+        world
+    }
+
+    /// Override the bind translation of `name`. Returns `false` if the joint is unknown.
+    pub fn set_position(&mut self, name: &str, position: Vec3) -> bool {
+        match self.joints.iter_mut().find(|joint| joint.name == name) {
+            Some(joint) => {
+                joint.position = position;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Add a joint below `parent` (or as another root), e.g. a custom attachment bone.
+    ///
+    /// Fails without modifying the skeleton if the name is taken or the parent is unknown.
+    pub fn add_joint(
+        &mut self,
+        name: &str,
+        parent: Option<&str>,
+        position: Vec3,
+    ) -> Result<&mut Self> {
+        if self.index_of(name).is_some() {
+            return Err(AnimError::InvalidStructure(format!(
+                "Joint '{name}' is already in the skeleton"
+            )));
+        }
+        let parent = match parent {
+            Some(parent) => Some(self.index_of(parent).ok_or_else(|| {
+                AnimError::InvalidStructure(format!("Unknown parent joint '{parent}'"))
+            })?),
+            None => None,
+        };
+        self.joints.push(SkeletonJoint {
+            name: name.to_string(),
+            parent,
+            position,
+        });
+        Ok(self)
+    }
+
+    /// Apply joint position overrides, e.g. the joint offsets a mesh body ships with.
+    ///
+    /// Fails without modifying the skeleton if any override names an unknown joint.
+    pub fn apply_offsets(&mut self, offsets: &JointOffsets) -> Result<&mut Self> {
+        if let Some(unknown) = offsets
+            .positions
+            .keys()
+            .find(|n| self.index_of(n).is_none())
+        {
+            return Err(AnimError::InvalidStructure(format!(
+                "Joint offset for unknown joint '{unknown}'"
+            )));
+        }
+        for (name, position) in &offsets.positions {
+            self.set_position(name, *position);
+        }
+        Ok(self)
+    }
+}
+
+/// `name="value"` pairs of an XML tag, with the standard entities decoded. `None` if the text
+/// isn't a sequence of such pairs.
+fn xml_attributes(text: &str) -> Option<Vec<(&str, String)>> {
+    let mut attrs = Vec::new();
+    let mut rest = text.trim_start();
+    while !rest.is_empty() {
+        let (name, after) = rest.split_once('=')?;
+        let after = after.trim_start();
+        let quote = after.chars().next().filter(|c| matches!(c, '"' | '\''))?;
+        let (value, after) = after[1..].split_once(quote)?;
+        let value = value
+            .replace("&lt;", "<")
+            .replace("&gt;", ">")
+            .replace("&quot;", "\"")
+            .replace("&apos;", "'")
+            .replace("&amp;", "&");
+        attrs.push((name.trim(), value));
+        rest = after.trim_start();
+    }
+    Some(attrs)
+}
+
+/// Process-wide set of known joints, shared by every thread.
+///
+/// Starts out as [`Skeleton::bento`] on first use. Readers take a cheap snapshot with
+/// [`JointRegistry::skeleton`]; changes replace the snapshot, so threads holding the old one
+/// keep a consistent view. Register custom joints at startup, before worker threads start
+/// validating or remapping.
+///
+/// # Example
+///
+/// ```rust
+/// use avatar_anim::skeleton::registry;
+/// use glam::Vec3;
+///
+/// # fn main() -> avatar_anim::Result<()> {
+/// registry().register("mTailHelper", Some("mPelvis"), Vec3::new(-0.1, 0.0, 0.0))?;
+/// assert!(registry().contains("mTailHelper"));
+/// assert!(registry().skeleton().chain("mPelvis", "mTailHelper").is_some());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct JointRegistry {
+    skeleton: RwLock<Arc<Skeleton>>,
+}
+
+impl JointRegistry {
+    /// The current skeleton.
+    pub fn skeleton(&self) -> Arc<Skeleton> {
+        self.skeleton
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Whether `name` is a known joint.
+    pub fn contains(&self, name: &str) -> bool {
+        self.skeleton().index_of(name).is_some()
+    }
+
+    /// Add a joint; see [`Skeleton::add_joint`].
+    pub fn register(&self, name: &str, parent: Option<&str>, position: Vec3) -> Result<()> {
+        let mut current = self
+            .skeleton
+            .write()
+            .unwrap_or_else(PoisonError::into_inner);
+        let mut skeleton = Skeleton::clone(&current);
+        skeleton.add_joint(name, parent, position)?;
+        *current = Arc::new(skeleton);
+        Ok(())
+    }
+
+    /// Replace the whole skeleton, e.g. with one loaded from a viewer's skeleton file.
+    pub fn replace(&self, skeleton: Skeleton) {
+        *self
+            .skeleton
+            .write()
+            .unwrap_or_else(PoisonError::into_inner) = Arc::new(skeleton);
+    }
+}
+
+/// The process-wide [`JointRegistry`], created on first use.
+pub fn registry() -> &'static JointRegistry {
+    static REGISTRY: OnceLock<JointRegistry> = OnceLock::new();
+    REGISTRY.get_or_init(|| JointRegistry {
+        skeleton: RwLock::new(Arc::new(Skeleton::bento())),
+    })
+}
+
+#[cfg(feature = "toml")]
+#[derive(Deserialize)]
+struct OffsetEntry {
+    position: [f32; 3],
+}
+
+/// Joint position overrides, replacing bind translations the way the viewer's joint offsets do.
+///
+/// The TOML form (with the `toml` feature) has one table per joint:
+///
+/// ```toml
+/// [mPelvis]
+/// position = [0.0, 0.0, 1.12]
+///
+/// [mKneeLeft]
+/// position = [-0.001, -0.046, -0.52]
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct JointOffsets {
+    pub positions: BTreeMap<String, Vec3>,
+}
+
+impl JointOffsets {
+    /// Parse overrides from TOML text.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use avatar_anim::skeleton::{JointOffsets, Skeleton};
+    ///
+    /// # fn main() -> avatar_anim::Result<()> {
+    /// let offsets = JointOffsets::from_toml_str("[mPelvis]\nposition = [0.0, 0.0, 1.12]\n")?;
+    /// let mut skeleton = Skeleton::second_life();
+    /// skeleton.apply_offsets(&offsets)?;
+    /// assert_eq!(skeleton.joint("mPelvis").unwrap().position.z, 1.12);
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "toml")]
+    pub fn from_toml_str(text: &str) -> Result<Self> {
+        let entries: BTreeMap<String, OffsetEntry> = toml::from_str(text)
+            .map_err(|e| AnimError::InvalidStructure(format!("Invalid joint offsets: {e}")))?;
+        Ok(Self {
+            positions: entries
+                .into_iter()
+                .map(|(name, entry)| (name, Vec3::from_array(entry.position)))
+                .collect(),
+        })
+    }
+
+    /// Load overrides from a TOML file.
+    #[cfg(feature = "toml")]
+    pub fn from_toml_file<P: AsRef<std::path::Path>>(path: P) -> Result<Self> {
+        let text = std::fs::read_to_string(path).map_err(AnimError::Io)?;
+        Self::from_toml_str(&text)
+    }
+}
+
+/// Body proportion presets for analysing content made for mesh bodies instead of the system avatar.
+///
+/// Presets are derived from the default skeleton by scaling body regions. They approximate
+/// common proportion families and are not measurements of any particular product; load the
+/// body's own joint offsets with [`Skeleton::apply_offsets`] when exact numbers matter.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum BodyPreset {
+    /// The unmodified system avatar.
+    Legacy,
+    /// Broader shoulders, slightly longer limbs and torso.
+    Athletic,
+    /// Smaller overall with shorter legs.
+    Petite,
+    /// Taller overall with longer legs.
+    Tall,
+}
+
+struct Proportions {
+    overall: f32,
+    spine: f32,
+    shoulders: f32,
+    arms: f32,
+    legs: f32,
+}
+
+impl BodyPreset {
+    pub const ALL: [BodyPreset; 4] = [
+        BodyPreset::Legacy,
+        BodyPreset::Athletic,
+        BodyPreset::Petite,
+        BodyPreset::Tall,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            BodyPreset::Legacy => "legacy",
+            BodyPreset::Athletic => "athletic",
+            BodyPreset::Petite => "petite",
+            BodyPreset::Tall => "tall",
+        }
+    }
+
+    /// Look up a preset by its (case-insensitive) name.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|preset| preset.name().eq_ignore_ascii_case(name.trim()))
+    }
+
+    fn proportions(self) -> Proportions {
+        let (overall, spine, shoulders, arms, legs) = match self {
+            BodyPreset::Legacy => (1.0, 1.0, 1.0, 1.0, 1.0),
+            BodyPreset::Athletic => (1.0, 1.05, 1.12, 1.03, 1.04),
+            BodyPreset::Petite => (0.9, 0.97, 0.95, 0.97, 0.94),
+            BodyPreset::Tall => (1.06, 1.02, 1.0, 1.03, 1.08),
+        };
+        Proportions {
+            overall,
+            spine,
+            shoulders,
+            arms,
+            legs,
+        }
+    }
+
+    /// Build the preset's skeleton.
+    pub fn skeleton(self) -> Skeleton {
+        let p = self.proportions();
+        let mut skeleton = Skeleton::second_life();
+        for joint in &mut skeleton.joints {
+            let name = joint.name.as_str();
+            let scale = if name == "mPelvis" {
+                // Pelvis height is dominated by leg length; keeps the feet on the ground.
+                Vec3::new(1.0, 1.0, p.legs)
+            } else if name.starts_with("mHip")
+                || name.starts_with("mKnee")
+                || name.starts_with("mAnkle")
+                || name.starts_with("mFoot")
+                || name.starts_with("mToe")
+            {
+                Vec3::splat(p.legs)
+            } else if name.starts_with("mShoulder")
+                || name.starts_with("mElbow")
+                || name.starts_with("mWrist")
+            {
+                Vec3::splat(p.arms)
+            } else if name.starts_with("mCollar") {
+                Vec3::new(1.0, p.shoulders, 1.0)
+            } else if matches!(name, "mTorso" | "mChest" | "mNeck") {
+                Vec3::splat(p.spine)
+            } else {
+                Vec3::ONE
+            };
+            joint.position *= scale * p.overall;
+        }
+        skeleton
+    }
+}
+
+impl FromStr for BodyPreset {
+    type Err = AnimError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::from_name(s)
+            .ok_or_else(|| AnimError::InvalidStructure(format!("Unknown body preset '{s}'")))
+    }
+}
@@ -0,0 +1,888 @@
+use crate::io::u16_to_time;
+use crate::{
+    AnimError, Animation, Constraint, DuplicateKeyStrategy, EditCounts, EditOperation, JointData,
+    PositionKey, Result, RotationKey, Track,
+};
+use glam::{Quat, Vec3};
+use std::fmt::Display;
+use std::ops::Range;
+
+pub(crate) const MAX_TICK: f32 = u16::MAX as f32;
+
+/// What [`Animation::fix_loop_points`] changed. Points are `(loop_in, loop_out)` in seconds.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct LoopFix {
+    pub before: (f32, f32),
+    pub after: (f32, f32),
+    /// A point was outside `[0, duration]` or not a number.
+    pub clamped: bool,
+    /// Loop in was after loop out.
+    pub swapped: bool,
+    /// A point moved to the nearest key.
+    pub snapped: bool,
+}
+
+impl LoopFix {
+    /// Whether either loop point changed.
+    pub fn changed(&self) -> bool {
+        self.clamped || self.swapped || self.snapped
+    }
+}
+
+/// Piecewise-linear map through `points`, extending the first and last segments beyond them.
+fn warp(points: &[(f32, f32)], t: f32) -> f32 {
+    let i = points
+        .partition_point(|p| p.0 <= t)
+        .clamp(1, points.len() - 1);
+    let ((x0, y0), (x1, y1)) = (points[i - 1], points[i]);
+    y0 + (t - x0) * (y1 - y0) / (x1 - x0)
+}
+
+pub(crate) fn to_tick(t: f32, duration: f32) -> u16 {
+    ((t / duration).clamp(0.0, 1.0) * MAX_TICK).round() as u16
+}
+
+/// Sorted, deduplicated output ticks for the input ticks of one channel.
+fn warped_ticks(key_ticks: &[u16], breaks: &[f32], map: impl Fn(f32) -> u16) -> Vec<(f32, u16)> {
+    let (Some(&first), Some(&last)) = (key_ticks.first(), key_ticks.last()) else {
+        return Vec::new();
+    };
+    let mut ticks: Vec<(f32, u16)> = key_ticks
+        .iter()
+        .map(|&t| t as f32)
+        .chain(
+            breaks
+                .iter()
+                .copied()
+                .filter(|&b| b > first as f32 && b < last as f32),
+        )
+        .map(|t| (t, map(t)))
+        .collect();
+    ticks.sort_by(|a, b| a.1.cmp(&b.1).then(a.0.total_cmp(&b.0)));
+    ticks.dedup_by_key(|t| t.1);
+    ticks
+}
+
+/// Ticks to sample for one channel cut to `[start, end]`: both ends plus the keys in between.
+fn region_ticks(key_ticks: impl Iterator<Item = u16>, start: f32, end: f32) -> Vec<f32> {
+    let mut ticks = vec![start];
+    ticks.extend(key_ticks.map(f32::from).filter(|&t| t > start && t < end));
+    ticks.push(end);
+    ticks
+}
+
+/// Wrap an angle in radians into `-π..=π`.
+fn wrap_angle(angle: f32) -> f32 {
+    let wrapped = (angle + std::f32::consts::PI).rem_euclid(std::f32::consts::TAU);
+    wrapped - std::f32::consts::PI
+}
+
+/// Heading of a rotation in SL's frame: the angle about Z of where it turns the forward axis.
+fn heading(rot: Quat) -> f32 {
+    let forward = rot * Vec3::X;
+    forward.y.atan2(forward.x)
+}
+
+impl Animation {
+    /// Map the constraint ease times (in seconds) through `map`, the same retiming the keys
+    /// got, and clamp them into the new `0..=duration`. A monotonic `map` keeps every window's
+    /// start before its stop. Returns how many constraints had a time clamped.
+    fn retime_constraints(&mut self, duration: f32, map: impl Fn(f32) -> f32) -> usize {
+        let mut clamped = 0;
+        for constraint in &mut self.constraints {
+            let mut outside = false;
+            for time in [
+                &mut constraint.ease_in_start,
+                &mut constraint.ease_in_stop,
+                &mut constraint.ease_out_start,
+                &mut constraint.ease_out_stop,
+            ] {
+                let mapped = map(*time);
+                *time = mapped.clamp(0.0, duration);
+                outside |= *time != mapped;
+            }
+            clamped += usize::from(outside);
+        }
+        clamped
+    }
+
+    /// Log an edit that may have clamped constraint ease windows.
+    fn log_retime(
+        &mut self,
+        mark: Option<EditCounts>,
+        operation: EditOperation,
+        parameters: &[(&str, &dyn Display)],
+        clamped: usize,
+    ) {
+        if let Some(entry) = self.log_edit(mark, operation, parameters)
+            && clamped > 0
+        {
+            entry.warnings.push(format!(
+                "{clamped} constraint ease windows clamped into the new duration"
+            ));
+        }
+    }
+
+    /// Retime the animation through a monotonic curve of `(input_seconds, output_seconds)`
+    /// points, e.g. `[(0.0, 0.0), (0.5, 1.5), (2.0, 2.0)]` to play the first half second
+    /// three times slower and catch up afterwards.
+    ///
+    /// Between points the mapping is linear; before the first and after the last point the
+    /// outer segments are extended. The duration, loop points and constraint ease times are
+    /// mapped too.
+    ///
+    /// Every channel is re-keyed at its warped key times plus the curve's breakpoints, which
+    /// reproduces the warped motion exactly; keys that land on the same tick after
+    /// quantization are merged.
+    ///
+    /// # Errors
+    ///
+    /// Fails without modifying anything if the curve has fewer than two points, is not
+    /// strictly increasing in both coordinates, or maps the duration to zero or less.
+    pub fn time_warp(&mut self, curve: &[(f32, f32)]) -> Result<&mut Self> {
+        let increasing = curve.windows(2).all(|w| w[1].0 > w[0].0 && w[1].1 > w[0].1);
+        let finite = curve.iter().all(|p| p.0.is_finite() && p.1.is_finite());
+        if curve.len() < 2 || !increasing || !finite {
+            return Err(AnimError::InvalidStructure(
+                "Time warp curve needs at least two strictly increasing points".into(),
+            ));
+        }
+        let old = self.header.duration;
+        let new = warp(curve, old);
+        if new.is_nan() || new <= 0.0 {
+            return Err(AnimError::InvalidStructure(format!(
+                "Time warp maps the {old}s duration to {new}s"
+            )));
+        }
+        let mark = self.edit_mark();
+        let map = |tick: f32| to_tick(warp(curve, tick / MAX_TICK * old), new);
+        let breaks: Vec<f32> = curve
+            .iter()
+            .map(|p| p.0 / old * MAX_TICK)
+            .filter(|b| b.is_finite())
+            .collect();
+
+        for joint in &mut self.joints {
+            joint.rotation_keys.sort_by_key(|k| k.time);
+            joint.position_keys.sort_by_key(|k| k.time);
+            let ticks: Vec<u16> = joint.rotation_keys.iter().map(|k| k.time).collect();
+            let rotation_keys = warped_ticks(&ticks, &breaks, map)
+                .into_iter()
+                .filter_map(|(t, time)| {
+                    Some(RotationKey {
+                        time,
+                        rot: joint.rotation_at(t)?,
+                    })
+                })
+                .collect();
+            let ticks: Vec<u16> = joint.position_keys.iter().map(|k| k.time).collect();
+            let position_keys = warped_ticks(&ticks, &breaks, map)
+                .into_iter()
+                .filter_map(|(t, time)| {
+                    Some(PositionKey {
+                        time,
+                        pos: joint.position_at(t)?,
+                    })
+                })
+                .collect();
+            joint.rotation_keys = rotation_keys;
+            joint.position_keys = position_keys;
+        }
+
+        let clamped = self.retime_constraints(new, |t| warp(curve, t));
+        let header = &mut self.header;
+        header.loop_in_point = warp(curve, header.loop_in_point).clamp(0.0, new);
+        header.loop_out_point = warp(curve, header.loop_out_point).clamp(0.0, new);
+        header.duration = new;
+        let points: Vec<String> = curve.iter().map(|(a, b)| format!("{a}:{b}")).collect();
+        let points = points.join(" ");
+        self.log_retime(
+            mark,
+            EditOperation::TimeWarp,
+            &[("curve", &points)],
+            clamped,
+        );
+        Ok(self)
+    }
+
+    /// Retime the animation so its extreme poses land on the given beat times (in seconds).
+    ///
+    /// Extremes come from [`crate::analysis::find_extremes`]. Each one,
+    /// in order, is pinned to the nearest beat not yet used, and [`Animation::time_warp`]
+    /// stretches the motion in between. The start stays at zero; extremes with no later beat
+    /// left are not pinned.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use avatar_anim::Animation;
+    ///
+    /// # fn main() -> avatar_anim::Result<()> {
+    /// let mut anim = Animation::from_file("dance.anim")?;
+    /// let beat = 60.0 / 128.0;
+    /// let beats: Vec<f32> = (1..64).map(|i| i as f32 * beat).collect();
+    /// anim.sync_to_beats(&beats)?.to_file("dance_128bpm.anim")?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Fails like [`Animation::time_warp`]; with no extremes or usable beats the animation is
+    /// left unchanged.
+    pub fn sync_to_beats(&mut self, beat_times_s: &[f32]) -> Result<&mut Self> {
+        let mut beats: Vec<f32> = beat_times_s
+            .iter()
+            .copied()
+            .filter(|b| b.is_finite() && *b > 0.0)
+            .collect();
+        beats.sort_by(f32::total_cmp);
+        beats.dedup();
+
+        let mut curve = vec![(0.0f32, 0.0f32)];
+        let mut next = 0;
+        for extreme in crate::analysis::find_extremes(self) {
+            let candidates = &beats[next..];
+            let Some(best) = (0..candidates.len()).min_by(|&a, &b| {
+                (candidates[a] - extreme)
+                    .abs()
+                    .total_cmp(&(candidates[b] - extreme).abs())
+            }) else {
+                break;
+            };
+            curve.push((extreme, candidates[best]));
+            next += best + 1;
+        }
+        let mark = self.edit_mark();
+        let pinned = curve.len() - 1;
+        if pinned > 0 {
+            self.unlogged(|anim| anim.time_warp(&curve).map(|_| ()))?;
+        }
+        if let Some(entry) = self.log_edit(
+            mark,
+            EditOperation::SyncToBeats,
+            &[("beats", &beats.len()), ("pinned", &pinned)],
+        ) && pinned == 0
+        {
+            entry
+                .warnings
+                .push("no extreme pose could be pinned to a beat; left unchanged".into());
+        }
+        Ok(self)
+    }
+
+    /// Cut the animation down to `loop_region` (in seconds) and loop it, keeping only the
+    /// joints chosen by `joint_filter` in motion; every other joint is frozen in its pose at
+    /// the start of the region.
+    ///
+    /// Useful to lift e.g. a tail swish out of a full-body capture as a looping overlay. The
+    /// new duration is the region's length, the loop covers all of it and `looped` is set.
+    /// Frozen joints keep a single key per channel; joints without keys stay empty.
+    /// Constraint ease times move with the region and are clamped into it.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use avatar_anim::Animation;
+    ///
+    /// # fn main() -> avatar_anim::Result<()> {
+    /// let mut anim = Animation::from_file("capture.anim")?;
+    /// anim.loop_subset(|j| j.name.starts_with("mTail"), 1.2..2.4)?
+    ///     .to_file("tail_swish.anim")?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Fails without modifying anything unless `0 <= start < end <= duration`.
+    pub fn loop_subset<F>(&mut self, joint_filter: F, loop_region: Range<f32>) -> Result<&mut Self>
+    where
+        F: Fn(&JointData) -> bool,
+    {
+        let Range { start, end } = loop_region;
+        let duration = self.header.duration;
+        if !(start >= 0.0 && start < end && end <= duration) {
+            return Err(AnimError::InvalidStructure(format!(
+                "Loop region {start}..{end} is not inside the {duration}s animation"
+            )));
+        }
+        let mark = self.edit_mark();
+        let length = end - start;
+        let (first, last) = (start / duration * MAX_TICK, end / duration * MAX_TICK);
+        let rebase = |tick: f32| to_tick(tick / MAX_TICK * duration - start, length);
+
+        for joint in &mut self.joints {
+            joint.rotation_keys.sort_by_key(|k| k.time);
+            joint.position_keys.sort_by_key(|k| k.time);
+            let (rotation_ticks, position_ticks) = if joint_filter(joint) {
+                (
+                    region_ticks(joint.rotation_keys.iter().map(|k| k.time), first, last),
+                    region_ticks(joint.position_keys.iter().map(|k| k.time), first, last),
+                )
+            } else {
+                (vec![first], vec![first])
+            };
+            let mut rotation_keys: Vec<RotationKey> = rotation_ticks
+                .into_iter()
+                .filter_map(|t| {
+                    Some(RotationKey {
+                        time: rebase(t),
+                        rot: joint.rotation_at(t)?,
+                    })
+                })
+                .collect();
+            let mut position_keys: Vec<PositionKey> = position_ticks
+                .into_iter()
+                .filter_map(|t| {
+                    Some(PositionKey {
+                        time: rebase(t),
+                        pos: joint.position_at(t)?,
+                    })
+                })
+                .collect();
+            rotation_keys.dedup_by_key(|k| k.time);
+            position_keys.dedup_by_key(|k| k.time);
+            joint.rotation_keys = rotation_keys;
+            joint.position_keys = position_keys;
+        }
+
+        let clamped = self.retime_constraints(length, |t| t - start);
+        let header = &mut self.header;
+        header.duration = length;
+        header.loop_in_point = 0.0;
+        header.loop_out_point = length;
+        header.looped = 1;
+        header.ease_in_duration = header.ease_in_duration.min(length);
+        header.ease_out_duration = header.ease_out_duration.min(length);
+        self.log_retime(
+            mark,
+            EditOperation::LoopSubset,
+            &[("region", &format_args!("{start}..{end}"))],
+            clamped,
+        );
+        Ok(self)
+    }
+
+    /// Replace every channel's keys with samples taken `fps` times per second, plus one at
+    /// the very end when the duration isn't a whole number of frames.
+    ///
+    /// Channels are evaluated the way the viewer plays them (slerp for rotations, lerp for
+    /// positions), so the motion is unchanged at the new keys. Channels without keys stay
+    /// empty. Follow with [`Animation::reduce_keys`] to drop the keys that turn out redundant.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use avatar_anim::Animation;
+    ///
+    /// # fn main() -> avatar_anim::Result<()> {
+    /// let mut anim = Animation::from_file("mocap.anim")?;
+    /// anim.resample(30.0)?.to_file("mocap_30fps.anim")?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Fails without modifying anything unless `fps` is positive and finite and the duration
+    /// is finite.
+    pub fn resample(&mut self, fps: f32) -> Result<&mut Self> {
+        if !(fps.is_finite() && fps > 0.0) {
+            return Err(AnimError::InvalidStructure(format!(
+                "Resampling needs a positive frame rate, got {fps}"
+            )));
+        }
+        let duration = self.header.duration;
+        if !duration.is_finite() {
+            return Err(AnimError::InvalidStructure(format!(
+                "Cannot resample an animation lasting {duration}s"
+            )));
+        }
+        let mark = self.edit_mark();
+        let duration = duration.max(0.0);
+        let span = (duration * fps).ceil();
+        let mut ticks: Vec<f32> = if span >= MAX_TICK {
+            // Frames no further apart than a tick: every tick is a sample.
+            (0..=u16::MAX).map(f32::from).collect()
+        } else {
+            (0..=span as usize)
+                .map(|frame| {
+                    if duration > 0.0 {
+                        (frame as f32 / fps / duration).min(1.0) * MAX_TICK
+                    } else {
+                        0.0
+                    }
+                })
+                .map(f32::round)
+                .collect()
+        };
+        ticks.dedup();
+
+        for joint in &mut self.joints {
+            joint.rotation_keys.sort_by_key(|k| k.time);
+            joint.position_keys.sort_by_key(|k| k.time);
+            let rotation_keys = ticks
+                .iter()
+                .filter_map(|&t| {
+                    Some(RotationKey {
+                        time: t as u16,
+                        rot: joint.rotation_at(t)?,
+                    })
+                })
+                .collect();
+            let position_keys = ticks
+                .iter()
+                .filter_map(|&t| {
+                    Some(PositionKey {
+                        time: t as u16,
+                        pos: joint.position_at(t)?,
+                    })
+                })
+                .collect();
+            joint.rotation_keys = rotation_keys;
+            joint.position_keys = position_keys;
+        }
+        self.log_edit(mark, EditOperation::Resample, &[("fps", &fps)]);
+        Ok(self)
+    }
+
+    /// Shorten the header duration to end at the latest key, rescaling key ticks so every key
+    /// keeps its time in seconds.
+    ///
+    /// Key ticks are fractions of the header duration, so a duration set by hand that runs
+    /// past the motion leaves a frozen tail, and loop points computed from it drift. After
+    /// this the latest key sits at tick 65535. The loop points and constraint ease times are
+    /// clamped into the new duration; with `update_loop_out` the loop out point is set to it.
+    /// Nothing changes when
+    /// there are no keys past tick 0 or the duration is not a positive number.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use avatar_anim::{Animation, JointData, RotationKey};
+    /// use glam::Quat;
+    ///
+    /// let mut anim = Animation::new();
+    /// anim.header.duration = 4.0;
+    /// anim.joints.push(JointData {
+    ///     name: "mHead".into(),
+    ///     rotation_keys: vec![RotationKey { time: 32768, rot: Quat::IDENTITY }],
+    ///     ..Default::default()
+    /// });
+    /// anim.recompute_duration(true);
+    /// assert!((anim.header.duration - 2.0).abs() < 1e-3);
+    /// assert_eq!(anim.header.loop_out_point, anim.header.duration);
+    /// assert_eq!(anim.joints[0].rotation_keys[0].time, u16::MAX);
+    /// ```
+    pub fn recompute_duration(&mut self, update_loop_out: bool) -> &mut Self {
+        let mark = self.edit_mark();
+        let parameters: [(&str, &dyn Display); 1] = [("update_loop_out", &update_loop_out)];
+        let duration = self.header.duration;
+        let last = self
+            .joints
+            .iter()
+            .flat_map(|j| {
+                let rotations = j.rotation_keys.iter().map(|k| k.time);
+                rotations.chain(j.position_keys.iter().map(|k| k.time))
+            })
+            .max()
+            .unwrap_or(0);
+        if last == 0 || !(duration.is_finite() && duration > 0.0) {
+            self.log_edit(mark, EditOperation::RecomputeDuration, &parameters);
+            return self;
+        }
+        let scale = MAX_TICK / last as f32;
+        let rescale = |t: u16| (t as f32 * scale).round().min(MAX_TICK) as u16;
+        for joint in &mut self.joints {
+            for key in &mut joint.rotation_keys {
+                key.time = rescale(key.time);
+            }
+            for key in &mut joint.position_keys {
+                key.time = rescale(key.time);
+            }
+        }
+
+        let new = duration * last as f32 / MAX_TICK;
+        let clamped = self.retime_constraints(new, |t| t);
+        let header = &mut self.header;
+        header.duration = new;
+        header.loop_in_point = header.loop_in_point.min(header.duration);
+        header.loop_out_point = if update_loop_out {
+            header.duration
+        } else {
+            header.loop_out_point.min(header.duration)
+        };
+        self.log_retime(mark, EditOperation::RecomputeDuration, &parameters, clamped);
+        self
+    }
+
+    /// Play `other` after this animation, `gap_seconds` after it ends, e.g. to put an intro
+    /// and a loop from separate files into one sequence.
+    ///
+    /// Keys keep their times in seconds: this animation's at the start, `other`'s shifted by
+    /// this duration plus the gap, which the new duration covers. Tracks of joints in both are
+    /// joined, and the motion blends across the gap; a key of `other` landing on the same tick
+    /// as one of this animation wins. Joints only in one of the two hold their nearest pose
+    /// during the other part, as the viewer plays them. Constraints of `other` are appended
+    /// with their ease times shifted.
+    ///
+    /// If `other` loops, its shifted loop region becomes the loop, so the first part plays
+    /// once as an intro; otherwise the loop region is extended to the new end. The ease in
+    /// stays this animation's and the ease out becomes `other`'s. Joint priorities come from
+    /// this animation where it has the joint.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use avatar_anim::Animation;
+    ///
+    /// # fn main() -> avatar_anim::Result<()> {
+    /// let mut dance = Animation::from_file("dance_intro.anim")?;
+    /// dance.append(&Animation::from_file("dance_loop.anim")?, 0.0)?;
+    /// dance.to_file("dance.anim")?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Fails without modifying anything unless both durations are positive and `gap_seconds`
+    /// is zero or more.
+    pub fn append(&mut self, other: &Animation, gap_seconds: f32) -> Result<&mut Self> {
+        let (first, second) = (self.header.duration, other.header.duration);
+        let positive = |d: f32| d.is_finite() && d > 0.0;
+        if !(positive(first) && positive(second)) {
+            return Err(AnimError::InvalidStructure(format!(
+                "Cannot append a {second}s animation to a {first}s one"
+            )));
+        }
+        if !(gap_seconds.is_finite() && gap_seconds >= 0.0) {
+            return Err(AnimError::InvalidStructure(format!(
+                "Invalid gap of {gap_seconds}s between appended animations"
+            )));
+        }
+        let mark = self.edit_mark();
+        let offset = first + gap_seconds;
+        let duration = offset + second;
+        let head = |tick: u16| to_tick(tick as f32 / MAX_TICK * first, duration);
+        let tail = |tick: u16| to_tick(offset + tick as f32 / MAX_TICK * second, duration);
+
+        for joint in &mut self.joints {
+            for key in &mut joint.rotation_keys {
+                key.time = head(key.time);
+            }
+            for key in &mut joint.position_keys {
+                key.time = head(key.time);
+            }
+        }
+        for source in &other.joints {
+            let rotations = source.rotation_keys.iter().map(|key| RotationKey {
+                time: tail(key.time),
+                rot: key.rot,
+            });
+            let positions = source.position_keys.iter().map(|key| PositionKey {
+                time: tail(key.time),
+                pos: key.pos,
+            });
+            match self.joints.iter_mut().find(|j| j.name == source.name) {
+                Some(joint) => {
+                    joint.rotation_keys.extend(rotations);
+                    joint.position_keys.extend(positions);
+                    let strategy = DuplicateKeyStrategy::KeepLast;
+                    joint.rotation_keys =
+                        Track::from_keys(&joint.rotation_keys, strategy).into_vec();
+                    joint.position_keys =
+                        Track::from_keys(&joint.position_keys, strategy).into_vec();
+                }
+                None => self.joints.push(JointData {
+                    name: source.name.clone(),
+                    priority: source.priority,
+                    rotation_keys: rotations.collect(),
+                    position_keys: positions.collect(),
+                }),
+            }
+        }
+        self.constraints
+            .extend(other.constraints.iter().map(|constraint| Constraint {
+                ease_in_start: constraint.ease_in_start + offset,
+                ease_in_stop: constraint.ease_in_stop + offset,
+                ease_out_start: constraint.ease_out_start + offset,
+                ease_out_stop: constraint.ease_out_stop + offset,
+                ..constraint.clone()
+            }));
+
+        let header = &mut self.header;
+        header.duration = duration;
+        if other.header.looped != 0 {
+            header.looped = 1;
+            header.loop_in_point = (offset + other.header.loop_in_point).clamp(0.0, duration);
+            header.loop_out_point = (offset + other.header.loop_out_point).clamp(0.0, duration);
+        } else {
+            header.loop_out_point = duration;
+        }
+        header.ease_out_duration = other.header.ease_out_duration;
+        self.log_edit(
+            mark,
+            EditOperation::Append,
+            &[("duration", &second), ("gap_seconds", &gap_seconds)],
+        );
+        Ok(self)
+    }
+
+    /// Play the animation backwards, e.g. to turn a stand-up into a sit-down.
+    ///
+    /// Every key moves to the mirrored tick (`65535 - tick`) and each track is re-sorted. The
+    /// loop region is mirrored around the duration, the ease in and ease out windows swap, and
+    /// so do each constraint's ease in and ease out windows.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use avatar_anim::{Animation, JointData, RotationKey};
+    /// use glam::Quat;
+    ///
+    /// let mut anim = Animation::new();
+    /// anim.header.duration = 2.0;
+    /// anim.header.loop_in_point = 0.5;
+    /// anim.header.loop_out_point = 2.0;
+    /// anim.header.ease_in_duration = 0.2;
+    /// anim.header.ease_out_duration = 0.6;
+    /// anim.joints.push(JointData {
+    ///     name: "mPelvis".into(),
+    ///     rotation_keys: vec![
+    ///         RotationKey { time: 0, rot: Quat::IDENTITY },
+    ///         RotationKey { time: 65535, rot: Quat::from_rotation_z(0.5) },
+    ///     ],
+    ///     ..Default::default()
+    /// });
+    /// anim.reverse();
+    /// let keys = &anim.joints[0].rotation_keys;
+    /// assert_eq!((keys[0].time, keys[0].rot), (0, Quat::from_rotation_z(0.5)));
+    /// assert_eq!((anim.header.loop_in_point, anim.header.loop_out_point), (0.0, 1.5));
+    /// assert_eq!((anim.header.ease_in_duration, anim.header.ease_out_duration), (0.6, 0.2));
+    /// ```
+    pub fn reverse(&mut self) -> &mut Self {
+        let mark = self.edit_mark();
+        for joint in &mut self.joints {
+            for key in &mut joint.rotation_keys {
+                key.time = u16::MAX - key.time;
+            }
+            for key in &mut joint.position_keys {
+                key.time = u16::MAX - key.time;
+            }
+            // Reversing first keeps duplicate times in their mirrored order.
+            joint.rotation_keys.reverse();
+            joint.position_keys.reverse();
+            joint.rotation_keys.sort_by_key(|k| k.time);
+            joint.position_keys.sort_by_key(|k| k.time);
+        }
+
+        let duration = self.header.duration;
+        for constraint in &mut self.constraints {
+            (
+                constraint.ease_in_start,
+                constraint.ease_in_stop,
+                constraint.ease_out_start,
+                constraint.ease_out_stop,
+            ) = (
+                duration - constraint.ease_out_stop,
+                duration - constraint.ease_out_start,
+                duration - constraint.ease_in_stop,
+                duration - constraint.ease_in_start,
+            );
+        }
+        let header = &mut self.header;
+        (header.loop_in_point, header.loop_out_point) = (
+            duration - header.loop_out_point,
+            duration - header.loop_in_point,
+        );
+        std::mem::swap(&mut header.ease_in_duration, &mut header.ease_out_duration);
+        self.log_edit(mark, EditOperation::Reverse, &[]);
+        self
+    }
+
+    /// Make the loop points usable: clamp them into `[0, duration]`, swap them if loop in
+    /// comes after loop out, and with `snap_to_keys` move each to the nearest key time.
+    ///
+    /// A loop in that is not a number becomes 0, a loop out that is not a number becomes the
+    /// duration. Snapping considers the keys of every joint; without keys it does nothing.
+    /// The `looped` flag is left alone.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use avatar_anim::Animation;
+    ///
+    /// let mut anim = Animation::new();
+    /// anim.header.duration = 2.0;
+    /// anim.header.loop_in_point = 3.0;
+    /// anim.header.loop_out_point = 0.5;
+    /// let fix = anim.fix_loop_points(false);
+    /// assert!(fix.clamped && fix.swapped);
+    /// assert_eq!(fix.after, (0.5, 2.0));
+    /// assert!(!anim.fix_loop_points(false).changed());
+    /// ```
+    pub fn fix_loop_points(&mut self, snap_to_keys: bool) -> LoopFix {
+        let mark = self.edit_mark();
+        let duration = self.header.duration;
+        let end = if duration.is_finite() {
+            duration.max(0.0)
+        } else {
+            0.0
+        };
+        let before = (self.header.loop_in_point, self.header.loop_out_point);
+        let clamp = |t: f32, fallback: f32| {
+            if t.is_nan() {
+                fallback
+            } else {
+                t.clamp(0.0, end)
+            }
+        };
+        let (mut loop_in, mut loop_out) = (clamp(before.0, 0.0), clamp(before.1, end));
+        let clamped = (loop_in, loop_out) != before;
+        let swapped = loop_in > loop_out;
+        if swapped {
+            std::mem::swap(&mut loop_in, &mut loop_out);
+        }
+
+        let mut snapped = false;
+        if snap_to_keys {
+            let mut ticks: Vec<u16> = self
+                .joints
+                .iter()
+                .flat_map(|j| {
+                    let rotations = j.rotation_keys.iter().map(|k| k.time);
+                    rotations.chain(j.position_keys.iter().map(|k| k.time))
+                })
+                .collect();
+            ticks.sort_unstable();
+            ticks.dedup();
+            let nearest = |t: f32| {
+                ticks
+                    .iter()
+                    .map(|&tick| u16_to_time(tick, end))
+                    .min_by(|a, b| (a - t).abs().total_cmp(&(b - t).abs()))
+                    .unwrap_or(t)
+            };
+            let snapped_points = (nearest(loop_in), nearest(loop_out));
+            snapped = snapped_points != (loop_in, loop_out);
+            (loop_in, loop_out) = snapped_points;
+        }
+
+        self.header.loop_in_point = loop_in;
+        self.header.loop_out_point = loop_out;
+        if let Some(entry) = self.log_edit(
+            mark,
+            EditOperation::FixLoopPoints,
+            &[("snap_to_keys", &snap_to_keys)],
+        ) {
+            let notes = [
+                (clamped, "loop points clamped into the duration"),
+                (swapped, "loop in and out swapped"),
+                (snapped, "loop points snapped to key times"),
+            ];
+            entry.warnings = notes
+                .iter()
+                .filter(|(applies, _)| *applies)
+                .map(|(_, note)| note.to_string())
+                .collect();
+        }
+        LoopFix {
+            before,
+            after: (loop_in, loop_out),
+            clamped,
+            swapped,
+            snapped,
+        }
+    }
+
+    /// Loop region in whole ticks, or the whole animation when it doesn't loop.
+    fn loop_ticks(&self) -> (f32, f32) {
+        if self.header.looped != 0 {
+            let start = self.seconds_to_tick(self.header.loop_in_point).round();
+            let end = self.seconds_to_tick(self.header.loop_out_point).round();
+            if end > start {
+                return (start, end);
+            }
+        }
+        (0.0, u16::MAX as f32)
+    }
+
+    /// Net yaw in radians (about Z, counter-clockwise seen from above) the pelvis turns over
+    /// one pass of the loop, or of the whole animation when it doesn't loop.
+    ///
+    /// Turns are accumulated key by key, so a full spin counts as `2π` rather than zero.
+    pub fn root_yaw_drift(&self) -> f32 {
+        let Some(pelvis) = self.joint("mPelvis") else {
+            return 0.0;
+        };
+        let (start, end) = self.loop_ticks();
+        let ticks = region_ticks(pelvis.rotation_keys.iter().map(|k| k.time), start, end);
+        let headings: Vec<f32> = ticks
+            .iter()
+            .filter_map(|&t| pelvis.rotation_at(t).map(heading))
+            .collect();
+        headings
+            .windows(2)
+            .map(|pair| wrap_angle(pair[1] - pair[0]))
+            .sum()
+    }
+
+    /// Counter-rotate the pelvis so a loop ends facing the way it started.
+    ///
+    /// Converted mocap idles often turn a few degrees per cycle, which slowly spins the avatar
+    /// while the loop repeats. The pelvis is first keyed at the loop in and out ticks, then the
+    /// [`Animation::root_yaw_drift`] is spread linearly over the loop region as a yaw about Z;
+    /// keys after the loop get the full correction. Positions are left alone.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use avatar_anim::{Animation, JointData, RotationKey};
+    /// use glam::Quat;
+    ///
+    /// let mut anim = Animation::new();
+    /// anim.header.duration = 2.0;
+    /// anim.header.loop_out_point = 2.0;
+    /// anim.joints.push(JointData {
+    ///     name: "mPelvis".into(),
+    ///     rotation_keys: vec![
+    ///         RotationKey { time: 0, rot: Quat::IDENTITY },
+    ///         RotationKey { time: 65535, rot: Quat::from_rotation_z(0.2) },
+    ///     ],
+    ///     ..Default::default()
+    /// });
+    /// anim.remove_root_yaw_drift();
+    /// assert!(anim.root_yaw_drift().abs() < 1e-5);
+    /// ```
+    pub fn remove_root_yaw_drift(&mut self) -> &mut Self {
+        let mark = self.edit_mark();
+        let drift = self.root_yaw_drift();
+        let (start, end) = self.loop_ticks();
+        if drift != 0.0
+            && drift.is_finite()
+            && let Some(pelvis) = self.joint_mut("mPelvis")
+        {
+            for tick in [start, end] {
+                let time = tick as u16;
+                let at = pelvis.rotation_keys.partition_point(|k| k.time < time);
+                if pelvis.rotation_keys.get(at).is_none_or(|k| k.time != time)
+                    && let Some(rot) = pelvis.rotation_at(tick)
+                {
+                    pelvis.rotation_keys.insert(at, RotationKey { time, rot });
+                }
+            }
+            for key in &mut pelvis.rotation_keys {
+                let progress = ((f32::from(key.time) - start) / (end - start)).clamp(0.0, 1.0);
+                key.rot = (Quat::from_rotation_z(-drift * progress) * key.rot).normalize();
+            }
+        }
+        self.log_edit(
+            mark,
+            EditOperation::RemoveRootYawDrift,
+            &[("drift_degrees", &drift.to_degrees())],
+        );
+        self
+    }
+}
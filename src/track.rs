@@ -0,0 +1,525 @@
+//! Operations shared by rotation and position key lists.
+//!
+//! Both channels are lists of timed keys, so grouping, deduplication and the like are written
+//! once against [`Keyframe`] instead of once per key type. [`Track`] is a key list kept
+//! sorted with one key per time; [`JointData::edit_rotations`](crate::JointData::edit_rotations)
+//! and [`JointData::edit_positions`](crate::JointData::edit_positions) edit a joint's keys
+//! through one. [`Animation::visit_keys`] and [`Animation::visit_keys_mut`] walk every key of
+//! every joint.
+//!
+//! ```rust
+//! use avatar_anim::track::dedup_by_time;
+//! use avatar_anim::{DuplicateKeyStrategy, PositionKey};
+//! use glam::Vec3;
+//!
+//! let key = |time, x| PositionKey { time, pos: Vec3::new(x, 0.0, 0.0) };
+//! let keys = [key(20, 1.0), key(10, 2.0), key(20, 3.0)];
+//! let dedup = dedup_by_time(&keys, DuplicateKeyStrategy::Average);
+//! assert_eq!(dedup.keys, [key(10, 2.0), key(20, 2.0)]);
+//! assert_eq!(dedup.merged, [vec![0, 2]]);
+//! ```
+
+use crate::{
+    Animation, DuplicateKeyStrategy, EditOperation, JointData, PositionKey, RotationKey, math,
+};
+use glam::{Quat, Vec3};
+use std::ops::{Bound, RangeBounds};
+
+/// The two kinds of key list a joint has.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum Channel {
+    Rotation,
+    Position,
+}
+
+/// The value of a key passed to [`Animation::visit_keys`].
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum KeyValue {
+    Rotation(Quat),
+    Position(Vec3),
+}
+
+/// The value of a key passed to [`Animation::visit_keys_mut`], to be edited in place.
+#[derive(Debug, PartialEq)]
+pub enum KeyValueMut<'a> {
+    Rotation(&'a mut Quat),
+    Position(&'a mut Vec3),
+}
+
+/// A key of a rotation or position channel.
+pub trait Keyframe: Clone {
+    /// What the key holds: a [`Quat`] or a [`Vec3`].
+    type Value: Copy;
+
+    fn time(&self) -> u16;
+
+    fn value(&self) -> Self::Value;
+
+    /// The value a fraction `s` of the way from `a` to `b`, the way the viewer plays it.
+    fn mix(a: Self::Value, b: Self::Value, s: f32) -> Self::Value;
+
+    /// One key standing for `keys`, which share a time; `keys` is never empty.
+    fn average(keys: &[Self]) -> Self;
+}
+
+impl Keyframe for RotationKey {
+    type Value = Quat;
+
+    fn time(&self) -> u16 {
+        self.time
+    }
+
+    fn value(&self) -> Quat {
+        self.rot
+    }
+
+    /// Slerp.
+    fn mix(a: Quat, b: Quat, s: f32) -> Quat {
+        a.slerp(b, s)
+    }
+
+    /// The [`math::average_quats`] mean.
+    fn average(keys: &[Self]) -> Self {
+        let rots: Vec<_> = keys.iter().map(|k| k.rot).collect();
+        RotationKey {
+            time: keys[0].time,
+            rot: math::average_quats(&rots),
+        }
+    }
+}
+
+impl Keyframe for PositionKey {
+    type Value = Vec3;
+
+    fn time(&self) -> u16 {
+        self.time
+    }
+
+    fn value(&self) -> Vec3 {
+        self.pos
+    }
+
+    /// Lerp.
+    fn mix(a: Vec3, b: Vec3, s: f32) -> Vec3 {
+        a.lerp(b, s)
+    }
+
+    /// The arithmetic mean.
+    fn average(keys: &[Self]) -> Self {
+        PositionKey {
+            time: keys[0].time,
+            pos: keys.iter().map(|k| k.pos).sum::<Vec3>() / keys.len() as f32,
+        }
+    }
+}
+
+/// Value of time-sorted `keys` at `tick`, holding the first and last key beyond the ends.
+pub(crate) fn interpolate<K: Keyframe>(keys: &[K], tick: f32) -> Option<K::Value> {
+    let first = keys.first()?;
+    let next = keys.partition_point(|k| (k.time() as f32) <= tick);
+    if next == 0 {
+        return Some(first.value());
+    }
+    if next == keys.len() {
+        return keys.last().map(K::value);
+    }
+    let (a, b) = (&keys[next - 1], &keys[next]);
+    let span = (b.time() - a.time()) as f32;
+    let s = if span > 0.0 {
+        (tick - a.time() as f32) / span
+    } else {
+        0.0
+    };
+    Some(K::mix(a.value(), b.value(), s))
+}
+
+/// Indices of `keys` grouped by time, groups in ascending time and indices in input order
+/// within a group. `keys` need not be sorted.
+pub fn group_by_time<K: Keyframe>(keys: &[K]) -> Vec<Vec<usize>> {
+    let mut order: Vec<usize> = (0..keys.len()).collect();
+    // Stable, so equal times keep their input order.
+    order.sort_by_key(|&i| keys[i].time());
+    order
+        .chunk_by(|&a, &b| keys[a].time() == keys[b].time())
+        .map(<[usize]>::to_vec)
+        .collect()
+}
+
+/// Result of [`dedup_by_time`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Deduplicated<K> {
+    /// One key per distinct time, sorted by time.
+    pub keys: Vec<K>,
+    /// Input indices of every group of two or more keys that became one, in the order of
+    /// [`Deduplicated::keys`]. Empty when the input had no duplicate times.
+    pub merged: Vec<Vec<usize>>,
+}
+
+/// Sort `keys` by time and reduce each run of equal times to one key with `strategy`.
+///
+/// "First" and "last" refer to input order, whether or not the input was sorted.
+pub fn dedup_by_time<K: Keyframe>(keys: &[K], strategy: DuplicateKeyStrategy) -> Deduplicated<K> {
+    let mut out = Deduplicated {
+        keys: Vec::with_capacity(keys.len()),
+        merged: Vec::new(),
+    };
+    for group in group_by_time(keys) {
+        let key = match strategy {
+            DuplicateKeyStrategy::KeepFirst => keys[group[0]].clone(),
+            DuplicateKeyStrategy::KeepLast => keys[group[group.len() - 1]].clone(),
+            DuplicateKeyStrategy::Average if group.len() == 1 => keys[group[0]].clone(),
+            DuplicateKeyStrategy::Average => {
+                K::average(&group.iter().map(|&i| keys[i].clone()).collect::<Vec<_>>())
+            }
+        };
+        out.keys.push(key);
+        if group.len() > 1 {
+            out.merged.push(group);
+        }
+    }
+    out
+}
+
+/// Keys of one channel, sorted by time with at most one key per time.
+///
+/// # Example
+///
+/// ```rust
+/// use avatar_anim::PositionKey;
+/// use avatar_anim::track::Track;
+/// use glam::Vec3;
+///
+/// let mut track = Track::new();
+/// track.insert(PositionKey { time: 300, pos: Vec3::Z });
+/// track.insert(PositionKey { time: 100, pos: Vec3::X });
+/// let replaced = track.insert(PositionKey { time: 300, pos: Vec3::Y });
+/// assert_eq!(replaced.map(|k| k.pos), Some(Vec3::Z));
+/// assert_eq!(track.get(100).map(|k| k.pos), Some(Vec3::X));
+/// assert_eq!(track.range(200..).len(), 1);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Track<K> {
+    keys: Vec<K>,
+}
+
+impl<K> Default for Track<K> {
+    fn default() -> Self {
+        Self { keys: Vec::new() }
+    }
+}
+
+impl<K: Keyframe> Track<K> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sort `keys` and resolve duplicate times with `strategy`.
+    pub fn from_keys(keys: &[K], strategy: DuplicateKeyStrategy) -> Self {
+        Self {
+            keys: dedup_by_time(keys, strategy).keys,
+        }
+    }
+
+    fn search(&self, time: u16) -> std::result::Result<usize, usize> {
+        self.keys.binary_search_by_key(&time, K::time)
+    }
+
+    /// Insert `key` in time order, returning the key it replaces at the same time.
+    pub fn insert(&mut self, key: K) -> Option<K> {
+        match self.search(key.time()) {
+            Ok(index) => Some(std::mem::replace(&mut self.keys[index], key)),
+            Err(index) => {
+                self.keys.insert(index, key);
+                None
+            }
+        }
+    }
+
+    /// The key at exactly `time`.
+    pub fn get(&self, time: u16) -> Option<&K> {
+        self.search(time).ok().map(|index| &self.keys[index])
+    }
+
+    pub fn get_mut(&mut self, time: u16) -> Option<&mut K> {
+        self.search(time).ok().map(|index| &mut self.keys[index])
+    }
+
+    pub fn remove(&mut self, time: u16) -> Option<K> {
+        self.search(time).ok().map(|index| self.keys.remove(index))
+    }
+
+    /// Keys whose time lies in `times`.
+    pub fn range(&self, times: impl RangeBounds<u16>) -> &[K] {
+        let start = match times.start_bound() {
+            Bound::Included(&t) => self.keys.partition_point(|k| k.time() < t),
+            Bound::Excluded(&t) => self.keys.partition_point(|k| k.time() <= t),
+            Bound::Unbounded => 0,
+        };
+        let end = match times.end_bound() {
+            Bound::Included(&t) => self.keys.partition_point(|k| k.time() <= t),
+            Bound::Excluded(&t) => self.keys.partition_point(|k| k.time() < t),
+            Bound::Unbounded => self.keys.len(),
+        };
+        &self.keys[start..end.max(start)]
+    }
+
+    /// Keep only the keys for which `keep` returns `true`.
+    pub fn retain(&mut self, keep: impl FnMut(&K) -> bool) {
+        self.keys.retain(keep);
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, K> {
+        self.keys.iter()
+    }
+
+    /// Mutable access to the key values; times are read-only through [`Keyframe::time`], so
+    /// only change them in a way that keeps the order.
+    pub fn iter_mut(&mut self) -> std::slice::IterMut<'_, K> {
+        self.keys.iter_mut()
+    }
+
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    pub fn as_slice(&self) -> &[K] {
+        &self.keys
+    }
+
+    pub fn as_sorted(&self) -> SortedKeys<'_, K> {
+        SortedKeys { keys: &self.keys }
+    }
+
+    pub fn into_vec(self) -> Vec<K> {
+        self.keys
+    }
+}
+
+/// Sorts `keys`, keeping the last of each duplicate time like
+/// [`Animation::cleanup_keys`](crate::Animation::cleanup_keys).
+impl<K: Keyframe> From<Vec<K>> for Track<K> {
+    fn from(keys: Vec<K>) -> Self {
+        Self::from_keys(&keys, DuplicateKeyStrategy::KeepLast)
+    }
+}
+
+impl<K> From<Track<K>> for Vec<K> {
+    fn from(track: Track<K>) -> Self {
+        track.keys
+    }
+}
+
+impl<'a, K> IntoIterator for &'a Track<K> {
+    type Item = &'a K;
+    type IntoIter = std::slice::Iter<'a, K>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.keys.iter()
+    }
+}
+
+impl<K> IntoIterator for Track<K> {
+    type Item = K;
+    type IntoIter = std::vec::IntoIter<K>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.keys.into_iter()
+    }
+}
+
+/// Keys known to be sorted by time with at most one key per time.
+///
+/// Functions that need sorted input take a `SortedKeys` instead of a slice, so forgetting to
+/// sort first is a type error rather than a wrong result. Get one from
+/// [`JointData::normalize`], [`Track::as_sorted`], or [`SortedKeys::new`], which checks.
+///
+/// # Example
+///
+/// ```rust
+/// use avatar_anim::{JointData, PositionKey};
+/// use glam::Vec3;
+///
+/// let mut joint = JointData {
+///     position_keys: vec![
+///         PositionKey { time: 200, pos: Vec3::X },
+///         PositionKey { time: 0, pos: Vec3::ZERO },
+///     ],
+///     ..Default::default()
+/// };
+/// let (_, positions) = joint.normalize();
+/// assert_eq!(positions.sample(100.0), Some(Vec3::new(0.5, 0.0, 0.0)));
+/// ```
+#[derive(Debug, PartialEq)]
+pub struct SortedKeys<'a, K> {
+    keys: &'a [K],
+}
+
+impl<K> Clone for SortedKeys<'_, K> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<K> Copy for SortedKeys<'_, K> {}
+
+impl<'a, K: Keyframe> SortedKeys<'a, K> {
+    /// `keys`, if their times strictly increase.
+    pub fn new(keys: &'a [K]) -> Option<Self> {
+        keys.is_sorted_by(|a, b| a.time() < b.time())
+            .then_some(Self { keys })
+    }
+
+    /// Value at `tick` (in key time units, `0..=65535`), interpolated between the surrounding
+    /// keys and held beyond the first and last. `None` without keys.
+    pub fn sample(&self, tick: f32) -> Option<K::Value> {
+        interpolate(self.keys, tick)
+    }
+
+    pub fn as_slice(&self) -> &'a [K] {
+        self.keys
+    }
+}
+
+impl<K> std::ops::Deref for SortedKeys<'_, K> {
+    type Target = [K];
+
+    fn deref(&self) -> &[K] {
+        self.keys
+    }
+}
+
+fn edit<K: Keyframe, R>(keys: &mut Vec<K>, edit: impl FnOnce(&mut Track<K>) -> R) -> R {
+    let mut track = Track::from(std::mem::take(keys));
+    let result = edit(&mut track);
+    *keys = track.into_vec();
+    result
+}
+
+impl JointData {
+    /// The rotation keys as a [`Track`], sorted with the last of each duplicate time kept.
+    pub fn rotation_track(&self) -> Track<RotationKey> {
+        Track::from_keys(&self.rotation_keys, DuplicateKeyStrategy::KeepLast)
+    }
+
+    /// The position keys as a [`Track`], sorted with the last of each duplicate time kept.
+    pub fn position_track(&self) -> Track<PositionKey> {
+        Track::from_keys(&self.position_keys, DuplicateKeyStrategy::KeepLast)
+    }
+
+    /// Sort both channels and keep the last of each duplicate time, like
+    /// [`Animation::cleanup_keys`], returning the now sorted rotation and position keys.
+    pub fn normalize(&mut self) -> (SortedKeys<'_, RotationKey>, SortedKeys<'_, PositionKey>) {
+        self.rotation_keys = self.rotation_track().into_vec();
+        self.position_keys = self.position_track().into_vec();
+        (
+            SortedKeys {
+                keys: &self.rotation_keys,
+            },
+            SortedKeys {
+                keys: &self.position_keys,
+            },
+        )
+    }
+
+    /// The rotation keys, if they are already sorted without duplicate times.
+    pub fn sorted_rotations(&self) -> Option<SortedKeys<'_, RotationKey>> {
+        SortedKeys::new(&self.rotation_keys)
+    }
+
+    /// The position keys, if they are already sorted without duplicate times.
+    pub fn sorted_positions(&self) -> Option<SortedKeys<'_, PositionKey>> {
+        SortedKeys::new(&self.position_keys)
+    }
+
+    /// Edit the rotation keys through a [`Track`]. The keys are sorted and deduplicated like
+    /// [`JointData::rotation_track`] first and stored back when `f` returns.
+    pub fn edit_rotations<R>(&mut self, f: impl FnOnce(&mut Track<RotationKey>) -> R) -> R {
+        edit(&mut self.rotation_keys, f)
+    }
+
+    /// Edit the position keys through a [`Track`], like [`JointData::edit_rotations`].
+    pub fn edit_positions<R>(&mut self, f: impl FnOnce(&mut Track<PositionKey>) -> R) -> R {
+        edit(&mut self.position_keys, f)
+    }
+}
+
+impl Animation {
+    /// Call `visit` with every key: joint name, channel, time and value. Joints are visited
+    /// in order, each joint's rotation keys before its position keys.
+    pub fn visit_keys<F>(&self, mut visit: F)
+    where
+        F: FnMut(&str, Channel, u16, KeyValue),
+    {
+        for joint in &self.joints {
+            for key in &joint.rotation_keys {
+                visit(
+                    &joint.name,
+                    Channel::Rotation,
+                    key.time,
+                    KeyValue::Rotation(key.rot),
+                );
+            }
+            for key in &joint.position_keys {
+                visit(
+                    &joint.name,
+                    Channel::Position,
+                    key.time,
+                    KeyValue::Position(key.pos),
+                );
+            }
+        }
+    }
+
+    /// Like [`Animation::visit_keys`], but `visit` may change each value in place.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use avatar_anim::track::KeyValueMut;
+    /// use avatar_anim::{Animation, JointData, PositionKey};
+    /// use glam::Vec3;
+    ///
+    /// let mut anim = Animation::new();
+    /// anim.joints.push(JointData {
+    ///     name: "mPelvis".into(),
+    ///     position_keys: vec![PositionKey { time: 0, pos: Vec3::new(0.0, 0.0, 0.2) }],
+    ///     ..Default::default()
+    /// });
+    /// // Halve every root offset.
+    /// anim.visit_keys_mut(|joint, _, _, value| {
+    ///     if let ("mPelvis", KeyValueMut::Position(pos)) = (joint, value) {
+    ///         *pos *= 0.5;
+    ///     }
+    /// });
+    /// assert_eq!(anim.joints[0].position_keys[0].pos, Vec3::new(0.0, 0.0, 0.1));
+    /// ```
+    pub fn visit_keys_mut<F>(&mut self, mut visit: F)
+    where
+        F: FnMut(&str, Channel, u16, KeyValueMut<'_>),
+    {
+        let mark = self.edit_mark();
+        for joint in &mut self.joints {
+            for key in &mut joint.rotation_keys {
+                visit(
+                    &joint.name,
+                    Channel::Rotation,
+                    key.time,
+                    KeyValueMut::Rotation(&mut key.rot),
+                );
+            }
+            for key in &mut joint.position_keys {
+                visit(
+                    &joint.name,
+                    Channel::Position,
+                    key.time,
+                    KeyValueMut::Position(&mut key.pos),
+                );
+            }
+        }
+        self.log_edit(mark, EditOperation::VisitKeysMut, &[]);
+    }
+}
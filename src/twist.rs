@@ -0,0 +1,82 @@
+use crate::JointData;
+use crate::math;
+use glam::{Quat, Vec3};
+
+/// Signed twist angle in radians (in `-π..=π`) of a pure twist about unit `axis`.
+fn twist_angle(twist: Quat, axis: Vec3) -> f32 {
+    let s = Vec3::new(twist.x, twist.y, twist.z).dot(axis);
+    let angle = 2.0 * s.atan2(twist.w);
+    if angle > std::f32::consts::PI {
+        angle - std::f32::consts::TAU
+    } else if angle < -std::f32::consts::PI {
+        angle + std::f32::consts::TAU
+    } else {
+        angle
+    }
+}
+
+impl JointData {
+    /// Rewrite the twist about `axis` (joint-local) of every rotation key, keeping the swing.
+    ///
+    /// `f` receives the signed twist angle in radians and returns the new one.
+    pub fn map_twist(&mut self, axis: Vec3, mut f: impl FnMut(f32) -> f32) -> &mut Self {
+        let axis = axis.normalize_or_zero();
+        if axis == Vec3::ZERO {
+            return self;
+        }
+        for key in &mut self.rotation_keys {
+            let (swing, twist) = math::swing_twist(key.rot, axis);
+            let angle = f(twist_angle(twist, axis));
+            key.rot = (swing * Quat::from_axis_angle(axis, angle)).normalize();
+        }
+        self
+    }
+
+    /// Rewrite the swing away from `axis` (joint-local) of every rotation key, keeping the twist.
+    pub fn map_swing(&mut self, axis: Vec3, mut f: impl FnMut(Quat) -> Quat) -> &mut Self {
+        let axis = axis.normalize_or_zero();
+        if axis == Vec3::ZERO {
+            return self;
+        }
+        for key in &mut self.rotation_keys {
+            let (swing, twist) = math::swing_twist(key.rot, axis);
+            key.rot = (f(swing) * twist).normalize();
+        }
+        self
+    }
+
+    /// Clamp the roll about `axis` to `±max_degrees`, leaving the swing untouched.
+    ///
+    /// This is the usual fix for forearm candy-wrapping in mocap: pass the bone axis
+    /// (`Vec3::X` for SL arm joints) and a limit around 90°.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use avatar_anim::{JointData, RotationKey};
+    /// use glam::{Quat, Vec3};
+    ///
+    /// let mut forearm = JointData {
+    ///     name: "mElbowLeft".into(),
+    ///     rotation_keys: vec![RotationKey { time: 0, rot: Quat::from_rotation_x(2.5) }],
+    ///     ..Default::default()
+    /// };
+    /// forearm.limit_twist(Vec3::X, 90.0);
+    /// let twisted = forearm.rotation_keys[0].rot;
+    /// assert!(twisted.abs_diff_eq(Quat::from_rotation_x(90f32.to_radians()), 1e-5));
+    /// ```
+    pub fn limit_twist(&mut self, axis: Vec3, max_degrees: f32) -> &mut Self {
+        let max = max_degrees.abs().to_radians();
+        self.map_twist(axis, |angle| angle.clamp(-max, max))
+    }
+
+    /// Multiply the twist about `axis` by `factor`, e.g. `0.0` to remove roll entirely.
+    pub fn scale_twist(&mut self, axis: Vec3, factor: f32) -> &mut Self {
+        self.map_twist(axis, |angle| angle * factor)
+    }
+
+    /// Multiply the swing away from `axis` by `factor`, keeping the twist.
+    pub fn scale_swing(&mut self, axis: Vec3, factor: f32) -> &mut Self {
+        self.map_swing(axis, |swing| math::exp(math::log(swing) * factor))
+    }
+}
@@ -0,0 +1,570 @@
+use crate::{AnimError, Animation, AnimationHeader, Result};
+
+/// Longest animation the viewer loads, in seconds (`MAX_ANIM_DURATION`).
+const MAX_DURATION: f32 = 60.0;
+/// Joints the viewer animates at most (`LL_CHARACTER_MAX_ANIMATED_JOINTS`).
+const MAX_JOINTS: usize = 216;
+/// Constraints the viewer loads at most.
+const MAX_CONSTRAINTS: usize = 10;
+/// Hand poses the viewer defines.
+const HAND_POSES: u32 = 14;
+/// Positions are encoded within this many meters of the joint's rest position.
+const MAX_OFFSET: f32 = 5.0;
+
+/// Where a written animation is headed, which decides what counts as a blocker.
+#[non_exhaustive]
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub enum Target {
+    /// Upload to Second Life: everything the viewer refuses to load, plus the uploader's
+    /// limits.
+    #[default]
+    SecondLife,
+    /// OpenSim grids, which accept any upload; only what the viewer refuses to load blocks.
+    OpenSim,
+}
+
+/// How bad an [`Issue`] is.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum Severity {
+    /// The viewer loads the animation but not as written, e.g. it clamps a value.
+    Warning,
+    /// The upload is rejected or the viewer refuses to play the animation.
+    Blocker,
+}
+
+/// A problem found before writing.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Issue {
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl std::fmt::Display for Issue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let severity = match self.severity {
+            Severity::Warning => "warning",
+            Severity::Blocker => "blocker",
+        };
+        write!(f, "{severity}: {}", self.message)
+    }
+}
+
+/// Everything [`Animation::validate`] found, blockers first.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ValidationReport {
+    pub issues: Vec<Issue>,
+}
+
+impl ValidationReport {
+    /// Whether nothing was found.
+    pub fn is_clean(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    /// Whether the target would reject the animation.
+    pub fn has_blockers(&self) -> bool {
+        self.blockers().next().is_some()
+    }
+
+    pub fn blockers(&self) -> impl Iterator<Item = &Issue> {
+        self.issues
+            .iter()
+            .filter(|issue| issue.severity == Severity::Blocker)
+    }
+
+    pub fn warnings(&self) -> impl Iterator<Item = &Issue> {
+        self.issues
+            .iter()
+            .filter(|issue| issue.severity == Severity::Warning)
+    }
+}
+
+/// How [`Animation::to_bytes_with_options`] treats problems.
+///
+/// # Example
+///
+/// ```rust
+/// use avatar_anim::{Animation, ErrorCode, WriteOptions};
+///
+/// let mut anim = Animation::new();
+/// anim.header.duration = 90.0;
+/// let strict = WriteOptions { strict: true, ..Default::default() };
+/// let err = anim.to_bytes_with_options(&strict).unwrap_err();
+/// assert_eq!(err.code(), ErrorCode::ValidationFailed);
+///
+/// let (bytes, issues) = anim.to_bytes_with_options(&WriteOptions::default()).unwrap();
+/// assert!(!bytes.is_empty() && !issues.is_empty());
+/// ```
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct WriteOptions {
+    /// Refuse to write when any [`Severity::Blocker`] is found, failing with
+    /// [`AnimError::ValidationFailed`]. Otherwise every issue is returned alongside the
+    /// output. Default `false`.
+    pub strict: bool,
+    pub target: Target,
+}
+
+/// A broken header field found by [`AnimationHeader::validate`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum HeaderViolation {
+    /// Only version 1.0 is read by the viewer.
+    UnsupportedVersion { version: u16, sub_version: u16 },
+    /// The base priority is outside `0..=7`.
+    PriorityOutOfRange { priority: i32 },
+    /// The duration is negative or not a number.
+    InvalidDuration { duration: f32 },
+    /// An ease duration is negative or not a number.
+    InvalidEase { ease_in: bool, value: f32 },
+    /// The hand pose is not one the viewer defines.
+    HandPoseOutOfRange { hand_pose: u32 },
+    /// A loop point is not a number, or a looped animation's points don't satisfy
+    /// `0 <= in <= out <= duration`.
+    InvalidLoop {
+        loop_in: f32,
+        loop_out: f32,
+        duration: f32,
+    },
+}
+
+impl std::fmt::Display for HeaderViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HeaderViolation::UnsupportedVersion {
+                version,
+                sub_version,
+            } => write!(f, "version {version}.{sub_version} is not 1.0"),
+            HeaderViolation::PriorityOutOfRange { priority } => {
+                write!(f, "priority {priority} is outside 0..=7")
+            }
+            HeaderViolation::InvalidDuration { duration } => {
+                write!(f, "duration {duration} is not a non-negative number")
+            }
+            HeaderViolation::InvalidEase { ease_in, value } => {
+                let which = if *ease_in { "ease in" } else { "ease out" };
+                write!(f, "{which} duration {value} is not a non-negative number")
+            }
+            HeaderViolation::HandPoseOutOfRange { hand_pose } => {
+                write!(f, "hand pose {hand_pose} is not defined")
+            }
+            HeaderViolation::InvalidLoop {
+                loop_in,
+                loop_out,
+                duration,
+            } => write!(
+                f,
+                "loop {loop_in}..{loop_out} does not fit the duration {duration}"
+            ),
+        }
+    }
+}
+
+impl AnimationHeader {
+    /// Every field that breaks the header's invariants, in field order; empty for a sound
+    /// header.
+    ///
+    /// Loop point order and range are only checked when `looped` is set, as the viewer ignores
+    /// the points otherwise. Writing does not check any of this; use
+    /// [`Animation::to_bytes_with_options`] in strict mode to refuse broken animations.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use avatar_anim::{AnimationHeader, HeaderViolation};
+    ///
+    /// assert!(AnimationHeader::default().validate().is_empty());
+    /// let header = AnimationHeader { base_priority: 9, hand_pose: 20, ..Default::default() };
+    /// assert_eq!(
+    ///     header.validate(),
+    ///     [
+    ///         HeaderViolation::PriorityOutOfRange { priority: 9 },
+    ///         HeaderViolation::HandPoseOutOfRange { hand_pose: 20 },
+    ///     ]
+    /// );
+    /// ```
+    pub fn validate(&self) -> Vec<HeaderViolation> {
+        let mut violations = Vec::new();
+        let sound = |value: f32| value.is_finite() && value >= 0.0;
+        if (self.version, self.sub_version) != (1, 0) {
+            violations.push(HeaderViolation::UnsupportedVersion {
+                version: self.version,
+                sub_version: self.sub_version,
+            });
+        }
+        if !(0..=7).contains(&self.base_priority) {
+            violations.push(HeaderViolation::PriorityOutOfRange {
+                priority: self.base_priority,
+            });
+        }
+        if !sound(self.duration) {
+            violations.push(HeaderViolation::InvalidDuration {
+                duration: self.duration,
+            });
+        }
+        let loop_points = [self.loop_in_point, self.loop_out_point];
+        if !loop_points.iter().all(|t| t.is_finite())
+            || self.looped != 0
+                && !(0.0 <= self.loop_in_point
+                    && self.loop_in_point <= self.loop_out_point
+                    && self.loop_out_point <= self.duration)
+        {
+            violations.push(HeaderViolation::InvalidLoop {
+                loop_in: self.loop_in_point,
+                loop_out: self.loop_out_point,
+                duration: self.duration,
+            });
+        }
+        for (ease_in, value) in [
+            (true, self.ease_in_duration),
+            (false, self.ease_out_duration),
+        ] {
+            if !sound(value) {
+                violations.push(HeaderViolation::InvalidEase { ease_in, value });
+            }
+        }
+        if self.hand_pose >= HAND_POSES {
+            violations.push(HeaderViolation::HandPoseOutOfRange {
+                hand_pose: self.hand_pose,
+            });
+        }
+        violations
+    }
+}
+
+impl Animation {
+    /// Problems that would make `target` reject this animation or play it differently than
+    /// written, blockers first.
+    pub fn upload_issues(&self, target: Target) -> Vec<Issue> {
+        let mut issues = Vec::new();
+        let mut report = |severity, message: String| issues.push(Issue { severity, message });
+        let header = &self.header;
+
+        if (header.version, header.sub_version) != (1, 0) {
+            report(
+                Severity::Blocker,
+                format!(
+                    "version {}.{} is not the 1.0 the viewer reads",
+                    header.version, header.sub_version
+                ),
+            );
+        }
+        if header.base_priority < 0 {
+            report(
+                Severity::Blocker,
+                format!("base priority {} is negative", header.base_priority),
+            );
+        } else if header.base_priority > 7 {
+            report(
+                Severity::Warning,
+                format!(
+                    "base priority {} is over 7 and will be clamped",
+                    header.base_priority
+                ),
+            );
+        }
+        if !(header.duration.is_finite() && header.duration >= 0.0) {
+            report(
+                Severity::Blocker,
+                format!("duration {} is not a non-negative number", header.duration),
+            );
+        } else if target == Target::SecondLife && header.duration > MAX_DURATION {
+            report(
+                Severity::Blocker,
+                format!(
+                    "duration {}s is over the {MAX_DURATION}s upload limit",
+                    header.duration
+                ),
+            );
+        }
+        for (name, value) in [
+            ("loop in point", header.loop_in_point),
+            ("loop out point", header.loop_out_point),
+            ("ease in duration", header.ease_in_duration),
+            ("ease out duration", header.ease_out_duration),
+        ] {
+            if !(value.is_finite() && value >= 0.0) {
+                report(
+                    Severity::Blocker,
+                    format!("{name} {value} is not a non-negative number"),
+                );
+            }
+        }
+        if header.looped != 0
+            && header.loop_out_point.is_finite()
+            && header.loop_out_point > header.duration
+        {
+            report(
+                Severity::Warning,
+                format!(
+                    "loop out point {} is past the {}s duration",
+                    header.loop_out_point, header.duration
+                ),
+            );
+        }
+        if header.hand_pose >= HAND_POSES {
+            report(
+                Severity::Blocker,
+                format!("hand pose {} is not defined", header.hand_pose),
+            );
+        }
+
+        if self.joints.is_empty() {
+            report(Severity::Blocker, "no joints are animated".into());
+        } else if self.joints.len() > MAX_JOINTS {
+            report(
+                Severity::Blocker,
+                format!(
+                    "{} joints are over the limit of {MAX_JOINTS}",
+                    self.joints.len()
+                ),
+            );
+        }
+        for joint in &self.joints {
+            let name = &joint.name;
+            if name.is_empty() || name == "mScreen" {
+                report(
+                    Severity::Blocker,
+                    format!("joint {name:?} can't be animated"),
+                );
+            }
+            if joint.priority < -1 {
+                report(
+                    Severity::Blocker,
+                    format!("{name} priority {} is below -1", joint.priority),
+                );
+            }
+            if joint.rotation_keys.iter().any(|k| !k.rot.is_finite()) {
+                report(
+                    Severity::Blocker,
+                    format!("{name} has a non-finite rotation"),
+                );
+            }
+            if joint.position_keys.iter().any(|k| !k.pos.is_finite()) {
+                report(
+                    Severity::Blocker,
+                    format!("{name} has a non-finite position"),
+                );
+            } else if joint
+                .position_keys
+                .iter()
+                .any(|k| k.pos.abs().max_element() > MAX_OFFSET)
+            {
+                report(
+                    Severity::Warning,
+                    format!("{name} moves more than {MAX_OFFSET} m and will be clamped"),
+                );
+            }
+        }
+
+        if self.constraints.len() > MAX_CONSTRAINTS {
+            report(
+                Severity::Blocker,
+                format!(
+                    "{} constraints are over the limit of {MAX_CONSTRAINTS}",
+                    self.constraints.len()
+                ),
+            );
+        }
+        for (index, constraint) in self.constraints.iter().enumerate() {
+            if constraint.constraint_type > 1 {
+                report(
+                    Severity::Blocker,
+                    format!(
+                        "constraint {index} has unknown type {}",
+                        constraint.constraint_type
+                    ),
+                );
+            }
+            let times = [
+                constraint.ease_in_start,
+                constraint.ease_in_stop,
+                constraint.ease_out_start,
+                constraint.ease_out_stop,
+            ];
+            if times.iter().any(|t| !t.is_finite()) {
+                report(
+                    Severity::Blocker,
+                    format!("constraint {index} has a non-finite ease time"),
+                );
+            }
+        }
+
+        issues.sort_by_key(|issue| std::cmp::Reverse(issue.severity));
+        issues
+    }
+
+    /// Check everything about this animation for upload to Second Life: what
+    /// [`Animation::upload_issues`] reports, plus the ordering and uniqueness of joints and
+    /// keys and the consistency of loop points and constraints.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use avatar_anim::{Animation, JointData, RotationKey};
+    /// use glam::Quat;
+    ///
+    /// let mut anim = Animation::new();
+    /// let key = |time| RotationKey { time, rot: Quat::IDENTITY };
+    /// anim.joints.push(JointData {
+    ///     name: "mHead".into(),
+    ///     rotation_keys: vec![key(200), key(100), key(100)],
+    ///     ..Default::default()
+    /// });
+    /// let report = anim.validate();
+    /// assert!(!report.has_blockers());
+    /// assert_eq!(report.warnings().count(), 2); // out of order, duplicate time
+    /// ```
+    pub fn validate(&self) -> ValidationReport {
+        self.validate_for(Target::default())
+    }
+
+    /// Like [`Animation::validate`], for uploading to `target`.
+    pub fn validate_for(&self, target: Target) -> ValidationReport {
+        let mut issues = self.upload_issues(target);
+        let mut report = |severity, message: String| issues.push(Issue { severity, message });
+        let header = &self.header;
+
+        if header.looped != 0 && header.loop_in_point > header.loop_out_point {
+            report(
+                Severity::Warning,
+                format!(
+                    "loop in point {} is after the loop out point {}",
+                    header.loop_in_point, header.loop_out_point
+                ),
+            );
+        }
+
+        for problem in self.check_ease() {
+            report(Severity::Warning, problem.to_string());
+        }
+
+        let mut seen = std::collections::HashSet::new();
+        for joint in &self.joints {
+            let name = &joint.name;
+            if !seen.insert(name.as_str()) {
+                report(
+                    Severity::Warning,
+                    format!("joint {name} appears more than once; only the first plays"),
+                );
+            }
+            if name.trim() != name {
+                report(
+                    Severity::Warning,
+                    format!("joint name {name:?} has surrounding whitespace"),
+                );
+            }
+            let channels: [(&str, Vec<u16>); 2] = [
+                (
+                    "rotation",
+                    joint.rotation_keys.iter().map(|k| k.time).collect(),
+                ),
+                (
+                    "position",
+                    joint.position_keys.iter().map(|k| k.time).collect(),
+                ),
+            ];
+            for (channel, mut times) in channels {
+                if !times.is_sorted() {
+                    report(
+                        Severity::Warning,
+                        format!("{channel} keys of {name} are out of order"),
+                    );
+                    times.sort_unstable();
+                }
+                let duplicates = times.windows(2).filter(|pair| pair[0] == pair[1]).count();
+                if duplicates > 0 {
+                    report(
+                        Severity::Warning,
+                        format!("{name} has {duplicates} duplicate {channel} key times"),
+                    );
+                }
+            }
+        }
+
+        for (index, constraint) in self.constraints.iter().enumerate() {
+            let chain = constraint.chain_length as usize;
+            if chain > self.joints.len() {
+                report(
+                    Severity::Blocker,
+                    format!(
+                        "constraint {index} chain of {chain} joints is longer than the {} animated",
+                        self.joints.len()
+                    ),
+                );
+            } else if chain == 0 {
+                report(
+                    Severity::Warning,
+                    format!("constraint {index} has an empty chain and does nothing"),
+                );
+            }
+            if constraint.source_volume.is_empty() {
+                report(
+                    Severity::Warning,
+                    format!("constraint {index} has no source volume"),
+                );
+            }
+            if constraint.ease_in_start > constraint.ease_in_stop
+                || constraint.ease_out_start > constraint.ease_out_stop
+            {
+                report(
+                    Severity::Warning,
+                    format!("constraint {index} has an ease window that ends before it starts"),
+                );
+            }
+            let times = [
+                constraint.ease_in_start,
+                constraint.ease_in_stop,
+                constraint.ease_out_start,
+                constraint.ease_out_stop,
+            ];
+            if header.duration.is_finite()
+                && times
+                    .iter()
+                    .any(|&t| t.is_finite() && !(0.0..=header.duration).contains(&t))
+            {
+                report(
+                    Severity::Warning,
+                    format!(
+                        "constraint {index} has ease times outside the {}s duration",
+                        header.duration
+                    ),
+                );
+            }
+        }
+
+        issues.sort_by_key(|issue| std::cmp::Reverse(issue.severity));
+        ValidationReport { issues }
+    }
+
+    /// Encode as an `.anim` blob after checking it against `options.target`.
+    ///
+    /// Returns the bytes and every issue found; see [`WriteOptions`].
+    ///
+    /// # Errors
+    ///
+    /// Fails with [`AnimError::ValidationFailed`] in strict mode when a blocker is found,
+    /// otherwise like [`Animation::to_bytes`].
+    pub fn to_bytes_with_options(&self, options: &WriteOptions) -> Result<(Vec<u8>, Vec<Issue>)> {
+        let issues = self.checked_issues(options)?;
+        Ok((self.to_bytes()?, issues))
+    }
+
+    /// Save to a `.anim` file after checking it against `options.target`, returning the
+    /// issues found. In strict mode nothing is written when a blocker is found.
+    pub fn to_file_with_options<P: AsRef<std::path::Path>>(
+        &self,
+        path: P,
+        options: &WriteOptions,
+    ) -> Result<Vec<Issue>> {
+        let issues = self.checked_issues(options)?;
+        self.to_file(path)?;
+        Ok(issues)
+    }
+
+    fn checked_issues(&self, options: &WriteOptions) -> Result<Vec<Issue>> {
+        let issues = self.upload_issues(options.target);
+        if options.strict && issues.iter().any(|i| i.severity == Severity::Blocker) {
+            return Err(AnimError::ValidationFailed { issues });
+        }
+        Ok(issues)
+    }
+}
@@ -0,0 +1,321 @@
+//! Borrowed, lazily decoded view of `.anim` bytes.
+//!
+//! [`AnimView::parse`] reads only the header; joints are walked on demand and their keys are
+//! decoded one at a time, so scanning a large library for names, counts or durations allocates
+//! nothing per file beyond the buffer the bytes live in.
+//!
+//! ```rust
+//! use avatar_anim::Animation;
+//! use avatar_anim::view::AnimView;
+//!
+//! # fn main() -> avatar_anim::Result<()> {
+//! let bytes = Animation::new().to_bytes()?;
+//! let view = AnimView::parse(&bytes)?;
+//! for joint in view.joints() {
+//!     let joint = joint?;
+//!     println!("{}: {} rotation keys", joint.name, joint.rotation_count());
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::io::{read_pos_vec3, read_rot_quat};
+use crate::{AnimError, AnimationHeader, PositionKey, Result, RotationKey};
+use binrw::Endian;
+use std::io::Cursor;
+
+/// Bytes per rotation or position key: a `u16` time and three `u16` components.
+pub(crate) const KEY_SIZE: usize = 8;
+/// Bytes per constraint.
+pub(crate) const CONSTRAINT_SIZE: usize = 86;
+
+fn decode_error(field: &'static str, offset: usize, reason: &str) -> AnimError {
+    AnimError::Decode {
+        offset,
+        joint: None,
+        joint_name: None,
+        field,
+        source: Box::new(AnimError::InvalidStructure(reason.into())),
+    }
+}
+
+fn truncated(what: &'static str, at: usize) -> AnimError {
+    decode_error(what, at, "data ends early")
+}
+
+/// Decode one encoded rotation key of [`KEY_SIZE`] bytes.
+pub(crate) fn rotation_key(key: &[u8]) -> RotationKey {
+    RotationKey {
+        time: u16::from_le_bytes([key[0], key[1]]),
+        rot: read_rot_quat(&mut Cursor::new(&key[2..]), Endian::Little, ())
+            .expect("six bytes hold a rotation"),
+    }
+}
+
+/// Decode one encoded position key of [`KEY_SIZE`] bytes.
+pub(crate) fn position_key(key: &[u8]) -> PositionKey {
+    PositionKey {
+        time: u16::from_le_bytes([key[0], key[1]]),
+        pos: read_pos_vec3(&mut Cursor::new(&key[2..]), Endian::Little, ())
+            .expect("six bytes hold a position"),
+    }
+}
+
+/// Little-endian reader over a byte slice that reports where it ran out.
+pub(crate) struct Bytes<'a> {
+    bytes: &'a [u8],
+    at: usize,
+}
+
+impl<'a> Bytes<'a> {
+    pub(crate) fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, at: 0 }
+    }
+
+    /// A reader starting at byte `at`.
+    pub(crate) fn starting_at(bytes: &'a [u8], at: usize) -> Self {
+        Self { bytes, at }
+    }
+
+    /// Current byte offset.
+    pub(crate) fn offset(&self) -> usize {
+        self.at
+    }
+
+    pub(crate) fn skip(&mut self, len: usize, what: &'static str) -> Result<()> {
+        self.take(len, what).map(|_| ())
+    }
+
+    /// Length of the null-terminated string at the cursor, which is moved past it.
+    pub(crate) fn str_len(&mut self, what: &'static str) -> Result<usize> {
+        let rest = self.bytes.get(self.at..).unwrap_or_default();
+        let len = rest
+            .iter()
+            .position(|&b| b == 0)
+            .ok_or_else(|| truncated(what, self.at))?;
+        self.at += len + 1;
+        Ok(len)
+    }
+
+    pub(crate) fn take(&mut self, len: usize, what: &'static str) -> Result<&'a [u8]> {
+        let slice = self
+            .at
+            .checked_add(len)
+            .and_then(|end| self.bytes.get(self.at..end))
+            .ok_or_else(|| truncated(what, self.at))?;
+        self.at += len;
+        Ok(slice)
+    }
+
+    fn array<const N: usize>(&mut self, what: &'static str) -> Result<[u8; N]> {
+        Ok(self.take(N, what)?.try_into().expect("slice has N bytes"))
+    }
+
+    pub(crate) fn u16(&mut self, what: &'static str) -> Result<u16> {
+        Ok(u16::from_le_bytes(self.array(what)?))
+    }
+
+    pub(crate) fn i32(&mut self, what: &'static str) -> Result<i32> {
+        Ok(i32::from_le_bytes(self.array(what)?))
+    }
+
+    pub(crate) fn u32(&mut self, what: &'static str) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.array(what)?))
+    }
+
+    pub(crate) fn f32(&mut self, what: &'static str) -> Result<f32> {
+        Ok(f32::from_le_bytes(self.array(what)?))
+    }
+
+    pub(crate) fn str(&mut self, what: &'static str) -> Result<&'a str> {
+        let rest = self.bytes.get(self.at..).unwrap_or_default();
+        let len = rest
+            .iter()
+            .position(|&b| b == 0)
+            .ok_or_else(|| truncated(what, self.at))?;
+        let text = std::str::from_utf8(&rest[..len])
+            .map_err(|_| decode_error(what, self.at, "not UTF-8"))?;
+        self.at += len + 1;
+        Ok(text)
+    }
+
+    pub(crate) fn count(&mut self, what: &'static str) -> Result<usize> {
+        let at = self.at;
+        usize::try_from(self.i32(what)?).map_err(|_| decode_error(what, at, "negative count"))
+    }
+}
+
+/// Header fields and joint table of an `.anim`, borrowed from its bytes.
+#[derive(Clone, Debug)]
+pub struct AnimView<'a> {
+    pub version: u16,
+    pub sub_version: u16,
+    pub base_priority: i32,
+    pub duration: f32,
+    pub emote_name: &'a str,
+    pub loop_in_point: f32,
+    pub loop_out_point: f32,
+    pub looped: i32,
+    pub ease_in_duration: f32,
+    pub ease_out_duration: f32,
+    pub hand_pose: u32,
+    joint_count: u32,
+    bytes: &'a [u8],
+    joints_at: usize,
+}
+
+impl<'a> AnimView<'a> {
+    /// Read the header of `bytes`; nothing after the joint count is touched.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the header is truncated or the emote name is not UTF-8.
+    pub fn parse(bytes: &'a [u8]) -> Result<Self> {
+        let mut r = Bytes::new(bytes);
+        const HEADER: &str = "the header";
+        Ok(Self {
+            version: r.u16(HEADER)?,
+            sub_version: r.u16(HEADER)?,
+            base_priority: r.i32(HEADER)?,
+            duration: r.f32(HEADER)?,
+            emote_name: r.str("the emote name")?,
+            loop_in_point: r.f32(HEADER)?,
+            loop_out_point: r.f32(HEADER)?,
+            looped: r.i32(HEADER)?,
+            ease_in_duration: r.f32(HEADER)?,
+            ease_out_duration: r.f32(HEADER)?,
+            hand_pose: r.u32(HEADER)?,
+            joint_count: r.u32("the joint count")?,
+            joints_at: r.at,
+            bytes,
+        })
+    }
+
+    /// Byte offset of the first joint.
+    pub(crate) fn joints_offset(&self) -> usize {
+        self.joints_at
+    }
+
+    /// Number of joints the header declares.
+    pub fn joint_count(&self) -> usize {
+        self.joint_count as usize
+    }
+
+    /// An owned copy of the header.
+    pub fn header(&self) -> AnimationHeader {
+        AnimationHeader {
+            version: self.version,
+            sub_version: self.sub_version,
+            base_priority: self.base_priority,
+            duration: self.duration,
+            emote_name: self.emote_name.to_string(),
+            loop_in_point: self.loop_in_point,
+            loop_out_point: self.loop_out_point,
+            looped: self.looped,
+            ease_in_duration: self.ease_in_duration,
+            ease_out_duration: self.ease_out_duration,
+            hand_pose: self.hand_pose,
+        }
+    }
+
+    /// The joints in file order, each located when the iterator reaches it.
+    ///
+    /// Iteration stops after the first error.
+    pub fn joints(&self) -> Joints<'a> {
+        Joints {
+            reader: Bytes {
+                bytes: self.bytes,
+                at: self.joints_at,
+            },
+            remaining: self.joint_count,
+        }
+    }
+
+    /// Number of constraints, found by skipping over the joint table.
+    pub fn constraint_count(&self) -> Result<usize> {
+        let mut joints = self.joints();
+        for joint in joints.by_ref() {
+            joint?;
+        }
+        joints.reader.count("the constraint count")
+    }
+}
+
+/// Iterator returned by [`AnimView::joints`].
+pub struct Joints<'a> {
+    reader: Bytes<'a>,
+    remaining: u32,
+}
+
+impl<'a> Iterator for Joints<'a> {
+    type Item = Result<JointView<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        let r = &mut self.reader;
+        let joint = (|| {
+            let name = r.str("a joint name")?;
+            let priority = r.i32("a joint priority")?;
+            let rotations = r.count("a rotation key count")?;
+            let rotations = r.take(rotations.saturating_mul(KEY_SIZE), "rotation keys")?;
+            let positions = r.count("a position key count")?;
+            let positions = r.take(positions.saturating_mul(KEY_SIZE), "position keys")?;
+            Ok(JointView {
+                name,
+                priority,
+                rotations,
+                positions,
+            })
+        })();
+        if joint.is_err() {
+            self.remaining = 0;
+        }
+        Some(joint)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.remaining as usize))
+    }
+}
+
+/// One joint of an [`AnimView`], with its keys still encoded.
+#[derive(Clone, Debug)]
+pub struct JointView<'a> {
+    pub name: &'a str,
+    pub priority: i32,
+    rotations: &'a [u8],
+    positions: &'a [u8],
+}
+
+impl<'a> JointView<'a> {
+    pub fn rotation_count(&self) -> usize {
+        self.rotations.len() / KEY_SIZE
+    }
+
+    pub fn position_count(&self) -> usize {
+        self.positions.len() / KEY_SIZE
+    }
+
+    /// The rotation keys as stored, [`KEY_SIZE`] bytes each.
+    pub(crate) fn encoded_rotations(&self) -> &'a [u8] {
+        self.rotations
+    }
+
+    /// The position keys as stored, [`KEY_SIZE`] bytes each.
+    pub(crate) fn encoded_positions(&self) -> &'a [u8] {
+        self.positions
+    }
+
+    /// Decode the rotation keys one by one, exactly as [`crate::Animation::from_bytes`] would.
+    pub fn rotation_keys(&self) -> impl Iterator<Item = RotationKey> + 'a {
+        self.rotations.chunks_exact(KEY_SIZE).map(rotation_key)
+    }
+
+    /// Decode the position keys one by one, exactly as [`crate::Animation::from_bytes`] would.
+    pub fn position_keys(&self) -> impl Iterator<Item = PositionKey> + 'a {
+        self.positions.chunks_exact(KEY_SIZE).map(position_key)
+    }
+}
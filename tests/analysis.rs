@@ -0,0 +1,81 @@
+use avatar_anim::analysis::{self, stride_profile};
+use avatar_anim::skeleton::Skeleton;
+use avatar_anim::{Animation, JointData, RotationKey};
+use glam::Quat;
+
+fn track(name: &str, keys: &[(f32, f32)]) -> JointData {
+    JointData {
+        name: name.into(),
+        rotation_keys: keys
+            .iter()
+            .map(|&(at, pitch)| RotationKey {
+                time: (at * 65535.0) as u16,
+                rot: Quat::from_rotation_y(pitch),
+            })
+            .collect(),
+        ..Default::default()
+    }
+}
+
+/// One-second walk cycle: each hip sweeps the planted leg from -0.3 to 0.3 rad (pushing the
+/// ankle backwards) while the other leg swings forward with a bent knee.
+fn walk() -> Animation {
+    let mut anim = Animation::new();
+    anim.header.duration = 1.0;
+    anim.header.loop_out_point = 1.0;
+    anim.joints = vec![
+        track("mHipLeft", &[(0.0, -0.3), (0.5, 0.3), (1.0, -0.3)]),
+        track("mHipRight", &[(0.0, 0.3), (0.5, -0.3), (1.0, 0.3)]),
+        track(
+            "mKneeLeft",
+            &[(0.0, 0.0), (0.5, 0.0), (0.75, 0.8), (1.0, 0.0)],
+        ),
+        track(
+            "mKneeRight",
+            &[(0.0, 0.0), (0.25, 0.8), (0.5, 0.0), (1.0, 0.0)],
+        ),
+    ];
+    anim
+}
+
+#[test]
+fn stride_profile_measures_planted_foot_speed() {
+    let skeleton = Skeleton::second_life();
+    let profile = stride_profile(&walk(), &skeleton).unwrap();
+    // Hip to ankle is about 0.96 m, swept over 0.6 rad in half a second.
+    assert!((profile.speed - 1.12).abs() < 0.1, "{profile:?}");
+    assert_eq!(profile.cycle, 1.0);
+    assert!((profile.stride_length - profile.speed).abs() < 1e-6);
+    assert!(profile.slides_at(analysis::WALK_SPEED, 0.15));
+    assert!(!profile.slides_at(profile.speed, 0.15));
+
+    let standing = stride_profile(&Animation::new(), &skeleton).unwrap();
+    assert_eq!(standing.speed, 0.0);
+    assert!(stride_profile(&walk(), &Skeleton::default()).is_none());
+}
+
+#[test]
+fn suggest_loop_finds_the_period_past_a_lead_in() {
+    use avatar_anim::analysis::suggest_loop;
+
+    // Half a second of stillness, then a sway with a 0.8 s period.
+    let mut keys = vec![(0.0, 0.0), (0.5 / 2.9, 0.0)];
+    for i in 1..=12 {
+        let t = 0.5 + i as f32 * 0.2;
+        let pitch = [0.3, 0.0, -0.3, 0.0][(i - 1) % 4];
+        keys.push((t / 2.9, pitch));
+    }
+    let mut anim = Animation::new();
+    anim.header.duration = 2.9;
+    anim.joints = vec![track("mChest", &keys)];
+
+    let suggestion = suggest_loop(&anim).unwrap();
+    assert!(suggestion.loop_in >= 0.45, "{suggestion:?}");
+    assert!((suggestion.loop_out - suggestion.loop_in - 0.8).abs() < 0.05);
+    assert!(suggestion.score > 0.9);
+    suggestion.apply(&mut anim);
+    assert_eq!(anim.header.loop_out_point, suggestion.loop_out);
+    assert_eq!(anim.header.looped, 1);
+
+    assert!(suggest_loop(&Animation::new()).is_none());
+}
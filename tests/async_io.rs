@@ -0,0 +1,29 @@
+#![cfg(feature = "tokio")]
+
+use avatar_anim::{Animation, JointData, RotationKey};
+use glam::Quat;
+
+#[tokio::test]
+async fn async_round_trip() {
+    let mut anim = Animation::new();
+    anim.joints.push(JointData {
+        name: "mHead".into(),
+        rotation_keys: vec![RotationKey {
+            time: 0,
+            rot: Quat::from_rotation_z(0.5),
+        }],
+        ..Default::default()
+    });
+
+    let mut sink = Vec::new();
+    anim.to_async_writer(&mut sink).await.unwrap();
+    assert_eq!(sink, anim.to_bytes().unwrap());
+    let back = Animation::from_async_reader(&sink[..]).await.unwrap();
+    assert_eq!(back, Animation::from_bytes(&sink).unwrap());
+    assert!(Animation::from_async_reader(&sink[..8]).await.is_err());
+    // An endless body stops at the size limit.
+    let err = Animation::from_async_reader(tokio::io::repeat(0))
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), avatar_anim::ErrorCode::LimitExceeded);
+}
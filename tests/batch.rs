@@ -0,0 +1,84 @@
+use avatar_anim::batch::{Batch, BatchObserver, BatchReport};
+use avatar_anim::{AnimError, Animation, WriteOutcome};
+use std::path::{Path, PathBuf};
+
+#[derive(Default)]
+struct Recorder(Vec<String>);
+
+impl BatchObserver for Recorder {
+    fn file_started(&mut self, input: &Path) {
+        self.0.push(format!("start {}", name(input)));
+    }
+    fn op_finished(&mut self, _: &Path, op: &str) {
+        self.0.push(format!("op {op}"));
+    }
+    fn warning(&mut self, _: &Path, op: &str, message: &str) {
+        self.0.push(format!("warn {op}: {message}"));
+    }
+    fn file_finished(&mut self, input: &Path, _: &Path, outcome: WriteOutcome) {
+        self.0.push(format!("done {} {outcome:?}", name(input)));
+    }
+    fn file_failed(&mut self, input: &Path, _: &AnimError) {
+        self.0.push(format!("failed {}", name(input)));
+    }
+}
+
+fn name(path: &Path) -> String {
+    path.file_name().unwrap().to_string_lossy().into_owned()
+}
+
+#[test]
+fn batch_reports_per_file_and_per_op_events() {
+    let dir = std::env::temp_dir().join(format!("avatar_anim_batch_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let good = dir.join("good.anim");
+    Animation::new().to_file(&good).unwrap();
+    let missing = dir.join("missing.anim");
+    let out: PathBuf = dir.join("out.anim");
+
+    let batch = Batch::new()
+        .op("priority", |anim, _| {
+            anim.set_priority(2);
+            Ok(())
+        })
+        .op("check", |anim, ctx| {
+            if anim.joints.is_empty() {
+                ctx.warn("no joints");
+            }
+            Ok(())
+        })
+        .if_changed(true);
+    let mut recorder = Recorder::default();
+    let jobs = vec![
+        (good.clone(), out.clone()),
+        (missing, dir.join("never.anim")),
+        (good, out.clone()),
+    ];
+    let report = batch.run(jobs, &mut recorder);
+
+    assert_eq!(
+        report,
+        BatchReport {
+            written: 1,
+            unchanged: 1,
+            failed: 1
+        }
+    );
+    assert_eq!(
+        recorder.0[..5],
+        [
+            "start good.anim",
+            "op priority",
+            "warn check: no joints",
+            "op check",
+            "done good.anim Written"
+        ]
+    );
+    assert_eq!(
+        recorder.0[5..7],
+        ["start missing.anim", "failed missing.anim"]
+    );
+    assert_eq!(recorder.0.last().unwrap(), "done good.anim Unchanged");
+    assert_eq!(Animation::from_file(&out).unwrap().header.base_priority, 2);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
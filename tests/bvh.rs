@@ -0,0 +1,185 @@
+#![cfg(feature = "bvh")]
+
+use avatar_anim::bvh::{BvhExportOptions, BvhOptions, sl_joint_name};
+use avatar_anim::frame::Frame;
+use avatar_anim::joints::{JointAliases, resolve_alias};
+use avatar_anim::{
+    Animation, JointData, PositionKey, RotationKey, Skeleton, SourceFormat, open_any,
+};
+use glam::{Quat, Vec3};
+
+const WAVE: &str = "HIERARCHY
+ROOT hip
+{
+  OFFSET 0.00 0.00 0.00
+  CHANNELS 6 Xposition Yposition Zposition Zrotation Xrotation Yrotation
+  JOINT abdomen
+  {
+    OFFSET 0.00 3.42 0.00
+    CHANNELS 3 Zrotation Xrotation Yrotation
+    JOINT tail
+    {
+      OFFSET 0.00 -2.00 -1.00
+      CHANNELS 3 Zrotation Xrotation Yrotation
+      End Site
+      {
+        OFFSET 0.00 0.00 -3.00
+      }
+    }
+  }
+}
+MOTION
+Frames: 3
+Frame Time: 0.5
+0 43 0 0 0 0 0 0 0 0 0 0
+0 43 0 0 0 0 0 0 90 5 5 5
+10 43 0 0 0 0 0 0 0 5 5 5
+";
+
+#[test]
+fn bvh_import_maps_joints_frames_and_axes() {
+    let anim = Animation::from_bvh_reader(WAVE.as_bytes()).unwrap();
+    assert_eq!(anim.header.duration, 0.5);
+    assert_eq!(anim.header.looped, 0);
+    let names: Vec<&str> = anim.joints.iter().map(|j| j.name.as_str()).collect();
+    assert_eq!(names, ["mPelvis", "mTorso"]);
+
+    let torso = &anim.joints[1];
+    assert_eq!(torso.rotation_keys.len(), 2);
+    assert_eq!(torso.rotation_keys[1].time, u16::MAX);
+    // 90° about BVH's up axis is 90° about SL's up axis.
+    let expected = Quat::from_rotation_z(90f32.to_radians());
+    assert!(torso.rotation_keys[0].rot.abs_diff_eq(expected, 1e-5));
+
+    // Root translation along BVH X (the avatar's left) in inches.
+    let pelvis = &anim.joints[0];
+    assert!(pelvis.position_keys[0].pos.abs_diff_eq(Vec3::ZERO, 1e-6));
+    assert!(
+        pelvis.position_keys[1]
+            .pos
+            .abs_diff_eq(Vec3::new(0.0, 0.254, 0.0), 1e-5)
+    );
+    assert_eq!(Frame::MayaYUp.vec_to_sl(Vec3::X), Vec3::Y);
+
+    let options = BvhOptions {
+        skip_reference_frame: false,
+        ..Default::default()
+    };
+    let all = Animation::from_bvh_reader_with(WAVE.as_bytes(), &options).unwrap();
+    assert_eq!(all.header.duration, 1.0);
+    assert_eq!(all.joints[1].rotation_keys.len(), 3);
+
+    assert_eq!(
+        open_any(WAVE.as_bytes()).unwrap(),
+        (anim, SourceFormat::Bvh)
+    );
+}
+
+#[test]
+fn bvh_names_and_errors() {
+    assert_eq!(sl_joint_name("mixamorig:LeftForeArm"), Some("mElbowLeft"));
+    assert_eq!(sl_joint_name("rShldr"), Some("mShoulderRight"));
+    assert_eq!(sl_joint_name("mFaceJaw"), Some("mFaceJaw"));
+    assert_eq!(sl_joint_name("tail"), None);
+
+    let truncated = &WAVE[..WAVE.len() - 10];
+    let err = Animation::from_bvh_reader(truncated.as_bytes()).unwrap_err();
+    assert!(err.to_string().contains("unexpected end of file"), "{err}");
+    let bad = WAVE.replace("Frame Time: 0.5", "Frame Time: x");
+    let err = Animation::from_bvh_reader(bad.as_bytes()).unwrap_err();
+    assert!(err.to_string().contains("line 23"), "{err}");
+}
+
+#[test]
+fn bvh_import_uses_custom_aliases() {
+    let options = BvhOptions {
+        aliases: JointAliases::parse("# custom rig\ntail = mTail1\nabdomen = -").unwrap(),
+        ..Default::default()
+    };
+    let anim = Animation::from_bvh_reader_with(WAVE.as_bytes(), &options).unwrap();
+    let names: Vec<&str> = anim.joints.iter().map(|j| j.name.as_str()).collect();
+    assert_eq!(names, ["mPelvis", "mTail1"]);
+
+    assert_eq!(resolve_alias("lShldrBend"), Some("mShoulderLeft"));
+    assert_eq!(resolve_alias("figureHair"), None);
+    let err = JointAliases::parse("hip mPelvis").unwrap_err();
+    assert!(err.to_string().contains("line 1"), "{err}");
+}
+
+#[test]
+fn bvh_export_round_trips() {
+    let mut anim = Animation::new();
+    anim.header.duration = 1.0;
+    anim.joints.push(JointData {
+        name: "mPelvis".into(),
+        position_keys: vec![
+            PositionKey {
+                time: 0,
+                pos: Vec3::ZERO,
+            },
+            PositionKey {
+                time: u16::MAX,
+                pos: Vec3::new(0.2, 0.0, -0.1),
+            },
+        ],
+        ..Default::default()
+    });
+    anim.joints.push(JointData {
+        name: "mHead".into(),
+        rotation_keys: vec![
+            RotationKey {
+                time: 0,
+                rot: Quat::IDENTITY,
+            },
+            RotationKey {
+                time: u16::MAX,
+                rot: Quat::from_rotation_y(0.6) * Quat::from_rotation_z(0.3),
+            },
+        ],
+        ..Default::default()
+    });
+
+    let options = BvhExportOptions {
+        only_animated_joints: true,
+        ..Default::default()
+    };
+    let mut bvh = Vec::new();
+    anim.to_bvh_writer(&mut bvh, &Skeleton::second_life(), &options)
+        .unwrap();
+    let text = String::from_utf8(bvh).unwrap();
+    assert!(text.contains("ROOT mPelvis") && text.contains("JOINT mNeck"));
+    assert!(!text.contains("mHipLeft"));
+    assert!(
+        text.contains("Frames: 32\nFrame Time: 0.033333335"),
+        "{text}"
+    );
+
+    let back = Animation::from_bvh_reader(text.as_bytes()).unwrap();
+    assert!((back.header.duration - 1.0).abs() < 1e-5);
+    for tick in [0.0, 20000.0, 65535.0] {
+        let head = back.joint("mHead").unwrap().rotation_at(tick).unwrap();
+        let expected = anim.joints[1].rotation_at(tick).unwrap();
+        assert!(
+            head.abs_diff_eq(expected, 1e-4),
+            "{tick}: {head} vs {expected}"
+        );
+        let pelvis = back.joint("mPelvis").unwrap().position_at(tick).unwrap();
+        let expected = anim.joints[0].position_at(tick).unwrap();
+        assert!(
+            pelvis.abs_diff_eq(expected, 1e-4),
+            "{tick}: {pelvis} vs {expected}"
+        );
+    }
+
+    let full = BvhExportOptions::default();
+    let mut bvh = Vec::new();
+    anim.to_bvh_writer(&mut bvh, &Skeleton::second_life(), &full)
+        .unwrap();
+    assert!(String::from_utf8(bvh).unwrap().contains("JOINT mToeLeft"));
+
+    for duration in [f32::INFINITY, f32::NAN, 1e9] {
+        anim.header.duration = duration;
+        let result = anim.to_bvh_writer(Vec::new(), &Skeleton::second_life(), &full);
+        assert!(result.is_err(), "{duration}");
+    }
+}
@@ -0,0 +1,39 @@
+use avatar_anim::cache::AnimCache;
+use avatar_anim::{Animation, JointData};
+use std::sync::Arc;
+
+#[test]
+fn cache_reuses_parses_until_files_change() {
+    let dir = std::env::temp_dir().join(format!("avatar_anim_cache_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let paths: Vec<_> = (0..3).map(|i| dir.join(format!("{i}.anim"))).collect();
+    for path in &paths {
+        Animation::new().to_file(path).unwrap();
+    }
+
+    let cache = AnimCache::new(2);
+    let first = cache.open(&paths[0]).unwrap();
+    assert!(Arc::ptr_eq(&first, &cache.open(&paths[0]).unwrap()));
+    assert!(cache.get_by_fingerprint(first.fingerprint()).is_some());
+
+    // A different length counts as a change even within the mtime resolution.
+    let mut changed = Animation::new();
+    changed.joints.push(JointData {
+        name: "mHead".into(),
+        ..Default::default()
+    });
+    changed.to_file(&paths[0]).unwrap();
+    assert_eq!(cache.open(&paths[0]).unwrap().joints.len(), 1);
+
+    cache.open(&paths[1]).unwrap();
+    cache.open(&paths[0]).unwrap();
+    cache.open(&paths[2]).unwrap(); // evicts 1, the least recently used
+    let stats = cache.stats();
+    assert_eq!((stats.hits, stats.misses, stats.evictions), (2, 4, 1));
+    assert_eq!(stats.len, 2);
+
+    cache.set_capacity(0);
+    assert_eq!(cache.stats().len, 0);
+    assert!(cache.open(dir.join("missing.anim")).is_err());
+    std::fs::remove_dir_all(&dir).ok();
+}
@@ -0,0 +1,160 @@
+#![cfg(feature = "cli")]
+
+use avatar_anim::cli::{ConvertOptions, run_convert};
+use avatar_anim::{Animation, JointData};
+
+#[test]
+fn run_convert_applies_edits() {
+    let dir = std::env::temp_dir().join(format!("avatar_anim_cli_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let (input, output) = (dir.join("in.anim"), dir.join("out.anim"));
+    let mut anim = Animation::new();
+    anim.joints.push(JointData {
+        name: "mPelvis".into(),
+        ..Default::default()
+    });
+    anim.to_file(&input).unwrap();
+
+    run_convert(ConvertOptions {
+        input,
+        output: Some(output.clone()),
+        priority: Some(5),
+        drop_joints: Some("mPelvis".into()),
+        insert: vec!["mHead:rot<0,0,0.5>@0".into()],
+        ..Default::default()
+    })
+    .unwrap();
+
+    let back = Animation::from_file(&output).unwrap();
+    std::fs::remove_dir_all(&dir).ok();
+    assert_eq!(back.header.base_priority, 5);
+    assert_eq!(back.joints.len(), 1);
+    let head = back.joint("mHead").unwrap();
+    assert_eq!(head.priority, 5);
+    assert_eq!(head.rotation_keys.len(), 1);
+}
+
+#[test]
+fn inspect_legacy_rewrites_the_modern_layout() {
+    let dir = std::env::temp_dir().join(format!("avatar_anim_cli_legacy_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let (input, output) = (dir.join("old.anim"), dir.join("new.anim"));
+    let mut bytes = Animation::new().to_bytes().unwrap();
+    bytes[..4].copy_from_slice(&[0, 0, 1, 0]);
+    bytes.truncate(bytes.len() - 4);
+    std::fs::write(&input, bytes).unwrap();
+
+    avatar_anim::cli::run_inspect_legacy(input, Some(output.clone())).unwrap();
+    let back = Animation::from_file(&output).unwrap();
+    std::fs::remove_dir_all(&dir).ok();
+    assert_eq!((back.header.version, back.header.sub_version), (1, 0));
+}
+
+#[test]
+fn key_mirror_copies_a_joint_to_its_counterpart() {
+    use avatar_anim::{PositionKey, RotationKey};
+    use glam::{Quat, Vec3};
+
+    let dir = std::env::temp_dir().join(format!("avatar_anim_cli_mirror_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let (input, output) = (dir.join("pose.anim"), dir.join("mirrored.anim"));
+    let mut anim = Animation::new();
+    anim.joints.push(JointData {
+        name: "mShoulderLeft".into(),
+        priority: 4,
+        rotation_keys: vec![RotationKey {
+            time: 0,
+            rot: Quat::from_rotation_x(0.5),
+        }],
+        position_keys: vec![PositionKey {
+            time: 0,
+            pos: Vec3::new(0.0, 0.1, 0.0),
+        }],
+    });
+    anim.to_file(&input).unwrap();
+
+    avatar_anim::cli::run_key_mirror(
+        input.clone(),
+        "mShoulderLeft".into(),
+        None,
+        Some(output.clone()),
+    )
+    .unwrap();
+    let back = Animation::from_file(&output).unwrap();
+    let err = avatar_anim::cli::run_key_mirror(input, "mHead".into(), None, None);
+    std::fs::remove_dir_all(&dir).ok();
+    assert!(err.is_err());
+    let right = back.joint("mShoulderRight").unwrap();
+    assert_eq!(right.priority, 4);
+    assert!(
+        right.rotation_keys[0]
+            .rot
+            .abs_diff_eq(Quat::from_rotation_x(-0.5), 1e-3)
+    );
+    assert!(
+        right.position_keys[0]
+            .pos
+            .abs_diff_eq(Vec3::new(0.0, -0.1, 0.0), 1e-3)
+    );
+    assert!(back.joint("mShoulderLeft").is_some());
+}
+
+#[test]
+fn dashboard_scans_subdirectories_and_reports_unreadable_files() {
+    use avatar_anim::cli::{DashboardFormat, run_dashboard};
+
+    let dir =
+        std::env::temp_dir().join(format!("avatar_anim_cli_dashboard_{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("ao")).unwrap();
+    Animation::new().to_file(dir.join("stand.anim")).unwrap();
+    Animation::new()
+        .to_file(dir.join("ao").join("stand.anim"))
+        .unwrap();
+    std::fs::write(dir.join("broken.anim"), [1, 0]).unwrap();
+    // A link back up the tree doesn't count the library twice.
+    #[cfg(unix)]
+    std::os::unix::fs::symlink(&dir, dir.join("ao").join("up")).unwrap();
+    let report = dir.join("dashboard.json");
+
+    run_dashboard(dir.clone(), Some(report.clone()), DashboardFormat::Json, 5).unwrap();
+    let json = std::fs::read_to_string(&report).unwrap();
+    std::fs::remove_dir_all(&dir).ok();
+    assert!(json.contains("\"animations\": 2"));
+    assert!(json.contains("\"name\": \"broken.anim\""));
+    let nested = std::path::Path::new("ao").join("stand.anim");
+    assert!(json.contains(&format!("\"{}\", \"stand.anim\"", nested.display())));
+}
+
+#[test]
+fn info_deep_explains_invalid_headers_without_crashing() {
+    let dir = std::env::temp_dir().join(format!("avatar_anim_cli_info_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    for (duration, loop_in) in [(-1.0, 0.0), (f32::NAN, 0.0), (2.0, f32::NAN)] {
+        let path = dir.join("bad.anim");
+        let mut anim = Animation::new();
+        anim.header.duration = duration;
+        anim.header.loop_in_point = loop_in;
+        anim.to_file(&path).unwrap();
+        avatar_anim::cli::run_info(path, true).unwrap();
+    }
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn sync_rejects_unbounded_beat_grids() {
+    use avatar_anim::cli::run_sync;
+
+    let dir = std::env::temp_dir().join(format!("avatar_anim_cli_sync_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("dance.anim");
+    let mut anim = Animation::new();
+    anim.header.duration = 2.0;
+    anim.to_file(&path).unwrap();
+    let huge_tempo = run_sync(path.clone(), 1e30, 0.0, None);
+    let endless_offset = run_sync(path.clone(), 120.0, f32::INFINITY, None);
+    anim.header.duration = f32::INFINITY;
+    anim.to_file(&path).unwrap();
+    let endless = run_sync(path, 120.0, 0.0, None);
+    std::fs::remove_dir_all(&dir).ok();
+    assert!(huge_tempo.is_err() && endless_offset.is_err() && endless.is_err());
+}
@@ -0,0 +1,87 @@
+#![cfg(feature = "collada")]
+
+use avatar_anim::collada::ColladaOptions;
+use avatar_anim::{Animation, JointData, PositionKey, RotationKey, Skeleton};
+use glam::{Mat4, Quat, Vec3};
+
+/// Floats of the `<float_array>` with the given id.
+fn float_array(dae: &str, id: &str) -> Vec<f32> {
+    let start = dae.find(&format!(r#"id="{id}""#)).unwrap();
+    let body = &dae[start..];
+    let body = &body[body.find('>').unwrap() + 1..body.find('<').unwrap()];
+    body.split_whitespace()
+        .map(|v| v.parse().unwrap())
+        .collect()
+}
+
+#[test]
+fn collada_export_bakes_joint_transforms() {
+    let mut anim = Animation::new();
+    anim.header.duration = 1.0;
+    anim.joints.push(JointData {
+        name: "mElbowLeft".into(),
+        rotation_keys: vec![
+            RotationKey {
+                time: 0,
+                rot: Quat::IDENTITY,
+            },
+            RotationKey {
+                time: u16::MAX,
+                rot: Quat::from_rotation_z(1.0),
+            },
+        ],
+        ..Default::default()
+    });
+    anim.joints.push(JointData {
+        name: "mPelvis".into(),
+        position_keys: vec![PositionKey {
+            time: 0,
+            pos: Vec3::new(0.0, 0.0, -0.2),
+        }],
+        ..Default::default()
+    });
+
+    let options = ColladaOptions {
+        frame_rate: 4.0,
+        only_animated_joints: true,
+    };
+    let mut out = Vec::new();
+    anim.to_collada_writer(&mut out, &Skeleton::second_life(), &options)
+        .unwrap();
+    let dae = String::from_utf8(out).unwrap();
+
+    assert!(
+        dae.contains(
+            r#"<node id="mCollarLeft" name="mCollarLeft" sid="mCollarLeft" type="JOINT">"#
+        )
+    );
+    assert!(!dae.contains("mHipLeft"));
+    assert!(
+        dae.contains(r##"<channel source="#mElbowLeft-sampler" target="mElbowLeft/transform"/>"##)
+    );
+    assert_eq!(
+        float_array(&dae, "mElbowLeft-input-array"),
+        [0.0, 0.25, 0.5, 0.75, 1.0]
+    );
+
+    // Row-major matrices: the last frame holds the full bend at the bind translation.
+    let values = float_array(&dae, "mElbowLeft-output-array");
+    assert_eq!(values.len(), 5 * 16);
+    let last = Mat4::from_cols_slice(&values[64..]).transpose();
+    let bind = Vec3::new(0.0, 0.248, 0.0);
+    let expected = Mat4::from_rotation_translation(Quat::from_rotation_z(1.0), bind);
+    assert!(last.abs_diff_eq(expected, 1e-5), "{last}");
+
+    // The root carries its position keys on top of the bind height.
+    let pelvis = float_array(&dae, "mPelvis-output-array");
+    assert!((pelvis[11] - (1.067 - 0.2)).abs() < 1e-5);
+
+    let bad = ColladaOptions {
+        frame_rate: 0.0,
+        ..Default::default()
+    };
+    assert!(
+        anim.to_collada_writer(Vec::new(), &Skeleton::second_life(), &bad)
+            .is_err()
+    );
+}
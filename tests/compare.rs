@@ -0,0 +1,109 @@
+use avatar_anim::compare::{Baseline, Tolerance, sampled_distance};
+use avatar_anim::{Animation, JointData, PositionKey, RotationKey};
+use glam::{Quat, Vec3};
+
+fn turn(duration: f32, ticks: &[u16]) -> Animation {
+    let mut anim = Animation::new();
+    anim.header.duration = duration;
+    anim.joints.push(JointData {
+        name: "mHead".into(),
+        rotation_keys: ticks
+            .iter()
+            .map(|&time| RotationKey {
+                time,
+                rot: Quat::from_rotation_z(time as f32 / u16::MAX as f32),
+            })
+            .collect(),
+        ..Default::default()
+    });
+    anim
+}
+
+#[test]
+fn sampled_distance_ignores_key_layout() {
+    let coarse = turn(2.0, &[0, u16::MAX]);
+    let fine = turn(2.0, &[0, 16384, 32768, 49152, u16::MAX]);
+    let distance = sampled_distance(&coarse, &fine, 24.0).unwrap();
+    assert_eq!(distance.times.len(), 49);
+    assert_eq!(distance.times.last(), Some(&2.0));
+    assert!(
+        distance.rotation.peak() < 1e-3,
+        "{}",
+        distance.rotation.peak()
+    );
+    assert!(distance.position.max.iter().all(|&e| e == 0.0));
+}
+
+#[test]
+fn sampled_distance_reports_per_joint_max_and_mean() {
+    let a = turn(1.0, &[0, u16::MAX]);
+    let mut b = a.clone();
+    // An extra joint only `b` moves, tilted by a constant 0.5 rad.
+    b.joints.push(JointData {
+        name: "mChest".into(),
+        rotation_keys: vec![RotationKey {
+            time: 0,
+            rot: Quat::from_rotation_x(0.5),
+        }],
+        position_keys: vec![PositionKey {
+            time: 0,
+            pos: Vec3::new(0.0, 0.03, 0.04),
+        }],
+        ..Default::default()
+    });
+    let distance = sampled_distance(&a, &b, 10.0).unwrap();
+    assert!((distance.rotation.peak() - 0.5).abs() < 1e-5);
+    assert!((distance.rotation.average() - 0.25).abs() < 1e-5);
+    assert!((distance.position.peak() - 0.05).abs() < 1e-6);
+
+    // A shorter animation holds its final pose.
+    let short = turn(0.5, &[0, u16::MAX]);
+    let distance = sampled_distance(&short, &a, 10.0).unwrap();
+    assert_eq!(distance.times.len(), 11);
+    assert!((distance.rotation.max[10]).abs() < 1e-5);
+    assert!((distance.rotation.max[5] - 0.5).abs() < 1e-5);
+
+    assert!(sampled_distance(&a, &b, 0.0).is_err());
+
+    // Endless and absurdly long durations take a bounded number of samples.
+    let endless = turn(f32::INFINITY, &[0, u16::MAX]);
+    assert_eq!(
+        sampled_distance(&endless, &a, 30.0).unwrap().times.len(),
+        31
+    );
+    let long = turn(1e9, &[0, u16::MAX]);
+    let distance = sampled_distance(&long, &a, 30.0).unwrap();
+    assert_eq!(distance.times.len(), 65536);
+    assert_eq!(distance.times.last(), Some(&1e9));
+    let baseline = Baseline::capture(&endless);
+    assert_eq!(baseline.check(&endless).distance.rotation.peak(), 0.0);
+}
+
+#[test]
+fn baseline_round_trips_and_flags_changed_motion() {
+    let approved = turn(2.0, &[0, 20000, u16::MAX]);
+    let baseline = Baseline::capture(&approved);
+    let bytes = baseline.to_bytes().unwrap();
+    assert_eq!(&bytes[..4], b"AABL");
+    let baseline = Baseline::from_bytes(&bytes).unwrap();
+    assert_eq!(baseline.tolerance, Tolerance::default());
+
+    // Same motion with another key layout passes.
+    let mut reexport = turn(2.0, &[0, 10000, 20000, 40000, u16::MAX]);
+    assert!(baseline.check(&reexport).passed);
+    assert!(baseline.check(&approved).distance.rotation.peak() < 1e-3);
+
+    // A visible change fails.
+    reexport.joints[0].rotation_keys[2].rot = Quat::from_rotation_z(0.6);
+    let check = baseline.check(&reexport);
+    assert!(!check.passed);
+    assert!(check.duration_matches);
+    assert!(check.distance.rotation.peak() > 0.2);
+
+    let mut longer = approved.clone();
+    longer.header.duration = 3.0;
+    assert!(!baseline.check(&longer).duration_matches);
+
+    assert!(Baseline::from_bytes(b"AABL\x02\x00").is_err());
+    assert!(Baseline::from_bytes(b"nope").is_err());
+}
@@ -0,0 +1,217 @@
+use avatar_anim::joints::UPPER_BODY;
+use avatar_anim::{Animation, JointData, MergeStrategy, PositionKey, RotationKey};
+use glam::{Quat, Vec3};
+
+fn joint(name: &str, priority: i32, angle: f32, pos: Option<Vec3>) -> JointData {
+    JointData {
+        name: name.into(),
+        priority,
+        rotation_keys: vec![RotationKey {
+            time: 0,
+            rot: Quat::from_rotation_z(angle),
+        }],
+        position_keys: pos
+            .map(|pos| PositionKey { time: 0, pos })
+            .into_iter()
+            .collect(),
+    }
+}
+
+#[test]
+fn overlay_mask_and_scale() {
+    let mut base = Animation::new();
+    base.joints = vec![
+        joint("mPelvis", 3, 0.1, Some(Vec3::new(0.0, 0.0, 0.2))),
+        joint("mHead", 3, 0.2, None),
+    ];
+    let mut top = Animation::new();
+    top.joints = vec![
+        joint("mPelvis", 5, 0.4, None),
+        joint("mWristLeft", 5, 0.6, None),
+    ];
+
+    let combined = base.overlay(&top);
+    let pelvis = combined.joint("mPelvis").unwrap();
+    assert_eq!(pelvis.priority, 5);
+    assert_eq!(pelvis.rotation_keys[0].rot, Quat::from_rotation_z(0.4));
+    assert_eq!(pelvis.position_keys, base.joints[0].position_keys);
+    assert_eq!(combined.joints.len(), 3);
+    assert_eq!(base.joints.len(), 2);
+
+    let upper = combined.masked(UPPER_BODY);
+    let names: Vec<&str> = upper.joints.iter().map(|j| j.name.as_str()).collect();
+    assert_eq!(names, ["mHead", "mWristLeft"]);
+
+    let half = combined.scaled(0.5);
+    let pelvis = half.joint("mPelvis").unwrap();
+    assert!(
+        pelvis.rotation_keys[0]
+            .rot
+            .abs_diff_eq(Quat::from_rotation_z(0.2), 1e-6)
+    );
+    assert!(
+        pelvis.position_keys[0]
+            .pos
+            .abs_diff_eq(Vec3::new(0.0, 0.0, 0.1), 1e-6)
+    );
+}
+
+#[test]
+fn underlay_crops_to_loop_and_drops_priority() {
+    let mut stand = Animation::new();
+    stand.header.duration = 3.0;
+    stand.header.base_priority = 3;
+    stand.header.looped = 1;
+    stand.header.loop_in_point = 1.0;
+    stand.header.loop_out_point = 2.5;
+    let mut sway = joint("mChest", 4, 0.0, None);
+    sway.rotation_keys.push(RotationKey {
+        time: u16::MAX,
+        rot: Quat::from_rotation_z(0.6),
+    });
+    stand.joints.push(sway);
+    stand.joints.push(joint("mHead", 4, 0.2, Some(Vec3::Z)));
+
+    let under = stand.to_underlay();
+    assert_eq!(under.header.base_priority, 0);
+    assert!(under.joints.iter().all(|j| j.priority == 0));
+    assert_eq!(under.header.duration, 1.5);
+    assert_eq!(
+        (under.header.loop_in_point, under.header.loop_out_point),
+        (0.0, 1.5)
+    );
+    let chest = &under.joints[0].rotation_keys;
+    assert_eq!((chest[0].time, chest[chest.len() - 1].time), (0, u16::MAX));
+    assert!(chest[0].rot.angle_between(Quat::from_rotation_z(0.2)) < 1e-3);
+    assert!(chest[1].rot.angle_between(Quat::from_rotation_z(0.5)) < 1e-3);
+
+    // Without a loop the whole animation is kept and made to loop.
+    stand.header.looped = 0;
+    let under = stand.to_underlay();
+    assert_eq!(under.header.duration, 3.0);
+    assert_eq!(under.header.looped, 1);
+    assert_eq!(under.header.loop_out_point, 3.0);
+}
+
+#[test]
+fn crossfade_blends_smoothly_over_the_overlap() {
+    let mut a = Animation::new();
+    a.header.duration = 2.0;
+    a.header.looped = 0;
+    a.joints.push(joint("mPelvis", 3, 0.0, Some(Vec3::ZERO)));
+    a.joints[0].rotation_keys.push(RotationKey {
+        time: u16::MAX,
+        rot: Quat::IDENTITY,
+    });
+    let mut b = Animation::new();
+    b.header.duration = 1.0;
+    b.header.looped = 1;
+    b.header.loop_in_point = 0.0;
+    b.header.loop_out_point = 1.0;
+    b.header.ease_out_duration = 0.3;
+    b.joints.push(joint("mPelvis", 5, 1.0, None));
+    b.joints.push(joint("mHead", 5, 0.5, None));
+
+    let faded = Animation::crossfade(&a, &b, 0.5).unwrap();
+    assert_eq!(faded.header.duration, 2.5);
+    assert_eq!(
+        (faded.header.loop_in_point, faded.header.loop_out_point),
+        (1.5, 2.5)
+    );
+    assert_eq!(faded.header.ease_out_duration, 0.3);
+    let pelvis = faded.joint("mPelvis").unwrap();
+    assert_eq!(pelvis.priority, 3);
+    // Halfway through the overlap the smoothstep weight is one half.
+    let mid = pelvis.rotation_at(1.75 / 2.5 * u16::MAX as f32).unwrap();
+    assert!(mid.angle_between(Quat::from_rotation_z(0.5)) < 1e-3);
+    let start = pelvis.rotation_at(0.0).unwrap();
+    assert!(start.abs_diff_eq(Quat::IDENTITY, 1e-6));
+    let end = pelvis.rotation_at(u16::MAX as f32).unwrap();
+    assert!(end.angle_between(Quat::from_rotation_z(1.0)) < 1e-3);
+    // The weight eases in, so the first step of the overlap moves less than linearly.
+    let early = pelvis
+        .rotation_at((1.5 + 0.05) / 2.5 * u16::MAX as f32)
+        .unwrap();
+    assert!(early.angle_between(Quat::IDENTITY) < 0.05);
+    // Positions only a had hold, and joints only b had come in with b's priority.
+    assert_eq!(pelvis.position_keys[0].pos, Vec3::ZERO);
+    assert_eq!(faded.joint("mHead").unwrap().priority, 5);
+
+    assert!(Animation::crossfade(&a, &b, 1.5).is_err());
+    let cut = Animation::crossfade(&a, &b, 0.0).unwrap();
+    assert_eq!(cut.header.duration, 3.0);
+
+    // A looping clip fading into a one-shot doesn't keep looping.
+    let mut looping = a.clone();
+    looping.header.looped = 1;
+    looping.header.loop_in_point = 0.5;
+    looping.header.loop_out_point = 2.0;
+    let mut once = b.clone();
+    once.header.looped = 0;
+    let faded = Animation::crossfade(&looping, &once, 0.5).unwrap();
+    assert_eq!(faded.header.looped, 0);
+    assert_eq!(
+        (faded.header.loop_in_point, faded.header.loop_out_point),
+        (0.0, 2.5)
+    );
+
+    // Huge overlaps sample at most once per tick.
+    a.header.duration = 1e12;
+    b.header.duration = 1e12;
+    let long = Animation::crossfade(&a, &b, 1e12).unwrap();
+    let pelvis = long.joint("mPelvis").unwrap();
+    assert!(pelvis.rotation_keys.len() <= u16::MAX as usize + 1);
+}
+
+#[test]
+fn merge_resolves_conflicts_by_strategy() {
+    let mut body = Animation::new();
+    body.joints = vec![
+        joint("mPelvis", 3, 0.1, Some(Vec3::new(0.0, 0.0, 0.2))),
+        joint("mHandIndex1Left", 2, 0.2, None),
+    ];
+    let mut hands = Animation::new();
+    hands.joints = vec![
+        joint("mHandIndex1Left", 4, 0.8, None),
+        joint("mHandThumb1Left", 4, 0.5, None),
+        joint("mPelvis", 1, 0.6, None),
+    ];
+    let rot = |anim: &Animation, name: &str| anim.joint(name).unwrap().rotation_keys[0].rot;
+
+    let mut kept = body.clone();
+    kept.merge(&hands, MergeStrategy::KeepSelf);
+    assert_eq!(rot(&kept, "mHandIndex1Left"), Quat::from_rotation_z(0.2));
+    assert_eq!(kept.joint("mHandThumb1Left").unwrap().priority, 4);
+    assert_eq!(kept.joints.len(), 3);
+
+    let mut layered = body.clone();
+    layered.merge(&hands, MergeStrategy::HigherPriority);
+    let index = layered.joint("mHandIndex1Left").unwrap();
+    assert_eq!(
+        (index.priority, index.rotation_keys[0].rot),
+        (4, Quat::from_rotation_z(0.8))
+    );
+    // The body's pelvis outranks the hands' and keeps its position keys either way.
+    let pelvis = layered.joint("mPelvis").unwrap();
+    assert_eq!(
+        (pelvis.priority, pelvis.rotation_keys[0].rot),
+        (3, Quat::from_rotation_z(0.1))
+    );
+    assert_eq!(pelvis.position_keys, body.joints[0].position_keys);
+
+    let mut other = body.clone();
+    other.merge(&hands, MergeStrategy::KeepOther);
+    assert_eq!(other.joint("mPelvis").unwrap().priority, 1);
+    assert_eq!(other.joint("mPelvis").unwrap().position_keys.len(), 1);
+
+    let mut blended = body.clone();
+    blended.merge(&hands, MergeStrategy::Blend(0.5));
+    let index = blended.joint("mHandIndex1Left").unwrap();
+    assert_eq!(index.priority, 4);
+    assert!(
+        index.rotation_keys[0]
+            .rot
+            .angle_between(Quat::from_rotation_z(0.5))
+            < 1e-5
+    );
+}
@@ -1,4 +1,6 @@
-use avatar_anim::{Animation, DuplicateKeyStrategy, JointData, PositionKey, RotationKey};
+use avatar_anim::{
+    Animation, DuplicateKeyStrategy, EditOperation, JointData, PositionKey, RotationKey,
+};
 use glam::{Quat, Vec3};
 use std::io::Cursor;
 
@@ -44,6 +46,24 @@ fn position_roundtrip_quant_error_bound() {
     );
 }
 
+#[test]
+fn time_quantization_matches_the_viewer() {
+    use avatar_anim::io::{time_to_u16, u16_to_time};
+
+    assert_eq!(time_to_u16(-1.0, 3.0), 0);
+    assert_eq!(time_to_u16(3.0, 3.0), u16::MAX);
+    assert_eq!(time_to_u16(1.5, 0.0), 0);
+    assert_eq!(time_to_u16(f32::NAN, 3.0), 0);
+    // floored, not rounded
+    assert_eq!(time_to_u16(3.0 * 0.99999, 3.0), 65534);
+    assert_eq!(u16_to_time(0, 3.0), 0.0);
+    assert!((u16_to_time(u16::MAX, 3.0) - 3.0).abs() < 1e-6);
+    for tick in [1u16, 100, 32768, 65534] {
+        let back = time_to_u16(u16_to_time(tick, 3.0), 3.0);
+        assert!(back == tick || back + 1 == tick);
+    }
+}
+
 #[test]
 fn duplicate_key_strategy_average() {
     let mut anim = Animation::default();
@@ -103,3 +123,482 @@ fn duplicate_key_strategy_keep_last() {
     let dot = expected.dot(joint.rotation_keys[0].rot);
     assert!(dot > 0.999, "Last key not preserved as expected");
 }
+
+#[test]
+fn duplicate_grouping_handles_unsorted_keys() {
+    use avatar_anim::track::{dedup_by_time, group_by_time};
+
+    let key = |time, x| PositionKey {
+        time,
+        pos: Vec3::new(x, 0.0, 0.0),
+    };
+    // Duplicates of 30 are not adjacent.
+    let keys = [
+        key(30, 1.0),
+        key(10, 0.0),
+        key(30, 5.0),
+        key(20, 2.0),
+        key(30, 3.0),
+    ];
+    assert_eq!(group_by_time(&keys), [vec![1], vec![3], vec![0, 2, 4]]);
+
+    let first = dedup_by_time(&keys, DuplicateKeyStrategy::KeepFirst);
+    assert_eq!(first.keys, [key(10, 0.0), key(20, 2.0), key(30, 1.0)]);
+    assert_eq!(first.merged, [vec![0, 2, 4]]);
+    let last = dedup_by_time(&keys, DuplicateKeyStrategy::KeepLast);
+    assert_eq!(last.keys[2], key(30, 3.0));
+    let average = dedup_by_time(&keys, DuplicateKeyStrategy::Average);
+    assert_eq!(average.keys[2], key(30, 3.0));
+
+    let clean = dedup_by_time(&first.keys, DuplicateKeyStrategy::Average);
+    assert_eq!(clean.keys, first.keys);
+    assert!(clean.merged.is_empty());
+}
+
+#[test]
+fn key_visitors_walk_every_key() {
+    use avatar_anim::track::{Channel, KeyValue, KeyValueMut};
+
+    let mut anim = Animation::new();
+    anim.joints.push(JointData {
+        name: "mHead".into(),
+        rotation_keys: vec![RotationKey {
+            time: 7,
+            rot: Quat::from_rotation_z(0.5),
+        }],
+        position_keys: vec![PositionKey {
+            time: 9,
+            pos: Vec3::X,
+        }],
+        ..Default::default()
+    });
+    anim.visit_keys_mut(|_, _, _, value| match value {
+        KeyValueMut::Rotation(rot) => *rot = rot.inverse(),
+        KeyValueMut::Position(pos) => *pos *= 2.0,
+    });
+
+    let mut seen = Vec::new();
+    anim.visit_keys(|joint, channel, time, value| {
+        seen.push((joint.to_string(), channel, time, value))
+    });
+    assert_eq!(seen.len(), 2);
+    assert_eq!((seen[0].1, seen[0].2), (Channel::Rotation, 7));
+    let KeyValue::Rotation(rot) = seen[0].3 else {
+        panic!("{:?}", seen[0]);
+    };
+    assert!(rot.abs_diff_eq(Quat::from_rotation_z(-0.5), 1e-6));
+    assert_eq!(
+        seen[1],
+        (
+            "mHead".into(),
+            Channel::Position,
+            9,
+            KeyValue::Position(Vec3::new(2.0, 0.0, 0.0))
+        )
+    );
+}
+
+#[test]
+fn track_keeps_keys_sorted_and_unique() {
+    use avatar_anim::Track;
+
+    let key = |time| RotationKey {
+        time,
+        rot: Quat::from_rotation_x(time as f32 / 1000.0),
+    };
+    let mut track = Track::from(vec![key(500), key(100), key(300)]);
+    assert_eq!(track.insert(key(200)), None);
+    assert_eq!(track.insert(key(300)), Some(key(300)));
+    let times: Vec<u16> = track.iter().map(|k| k.time).collect();
+    assert_eq!(times, [100, 200, 300, 500]);
+    assert_eq!(track.range(200..=300).len(), 2);
+    assert_eq!(track.range(..200), [key(100)]);
+    assert!(track.range(600..).is_empty());
+    assert_eq!(track.remove(200), Some(key(200)));
+    assert!(track.get(200).is_none());
+
+    let mut joint = JointData {
+        name: "mHead".into(),
+        rotation_keys: vec![key(40), key(10)],
+        ..Default::default()
+    };
+    let replaced = joint.edit_rotations(|track| track.insert(key(20)));
+    assert!(replaced.is_none());
+    assert_eq!(joint.rotation_keys, [key(10), key(20), key(40)]);
+    assert_eq!(joint.rotation_track().as_slice(), joint.rotation_keys);
+}
+
+#[test]
+fn sorted_keys_only_wrap_sorted_input() {
+    use avatar_anim::track::SortedKeys;
+
+    let key = |time, z| PositionKey {
+        time,
+        pos: Vec3::new(0.0, 0.0, z),
+    };
+    let mut joint = JointData {
+        name: "mPelvis".into(),
+        position_keys: vec![key(100, 1.0), key(0, 0.0), key(100, 2.0)],
+        ..Default::default()
+    };
+    assert!(joint.sorted_positions().is_none());
+    assert!(SortedKeys::new(&[key(5, 0.0), key(5, 1.0)]).is_none());
+
+    let (rotations, positions) = joint.normalize();
+    assert!(rotations.is_empty() && rotations.sample(10.0).is_none());
+    assert_eq!(positions.len(), 2);
+    assert_eq!(positions.sample(50.0), Some(Vec3::new(0.0, 0.0, 1.0)));
+    assert_eq!(positions.sample(1e6), Some(Vec3::new(0.0, 0.0, 2.0)));
+    assert!(joint.sorted_positions().is_some());
+}
+
+#[test]
+fn rewrite_preserving_patches_metadata_only() {
+    use binrw::BinWrite;
+
+    let mut anim = Animation::new();
+    anim.joints.push(JointData {
+        name: "mHead".into(),
+        priority: 3,
+        rotation_keys: vec![RotationKey {
+            time: 0,
+            rot: Quat::from_rotation_y(0.4),
+        }],
+        position_keys: vec![PositionKey {
+            time: 0,
+            pos: Vec3::new(0.0, 0.0, 0.1),
+        }],
+    });
+    let mut buf = Cursor::new(Vec::new());
+    anim.write(&mut buf).unwrap();
+    let original = buf.into_inner();
+    let loaded: Animation = binrw::BinRead::read(&mut Cursor::new(&original)).unwrap();
+
+    let mut edited = loaded.clone();
+    edited.header.emote_name = "express_smile".into();
+    edited.header.base_priority = 5;
+    edited.joints[0].priority = 6;
+    let patched = edited.rewrite_preserving(&original).unwrap();
+    assert_eq!(patched.len(), original.len() + "express_smile".len());
+    assert!(patched.ends_with(&original[original.len() - 20..]));
+    let reread: Animation = binrw::BinRead::read(&mut Cursor::new(&patched)).unwrap();
+    assert_eq!(reread.header, edited.header);
+    assert_eq!(reread.joints[0].priority, 6);
+
+    edited.joints[0].rotation_keys[0].rot = Quat::from_rotation_y(0.5);
+    let full = edited.rewrite_preserving(&original).unwrap();
+    let mut expected = Cursor::new(Vec::new());
+    edited.write(&mut expected).unwrap();
+    assert_eq!(full, expected.into_inner());
+}
+
+#[test]
+fn to_file_if_changed_skips_identical_output() {
+    use avatar_anim::WriteOutcome;
+
+    let path = std::env::temp_dir().join(format!(
+        "avatar_anim_if_changed_{}.anim",
+        std::process::id()
+    ));
+    let _ = std::fs::remove_file(&path);
+    let mut anim = Animation::new();
+    assert_eq!(
+        anim.to_file_if_changed(&path).unwrap(),
+        WriteOutcome::Written
+    );
+    assert_eq!(
+        anim.to_file_if_changed(&path).unwrap(),
+        WriteOutcome::Unchanged
+    );
+    anim.set_priority(2);
+    assert_eq!(
+        anim.to_file_if_changed(&path).unwrap(),
+        WriteOutcome::Written
+    );
+    assert_eq!(Animation::from_file(&path).unwrap(), anim);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn reader_and_writer_apis_round_trip() {
+    let mut anim = Animation::new();
+    anim.header.emote_name = "smile".into();
+    anim.joints.push(JointData {
+        name: "mHead".into(),
+        rotation_keys: vec![RotationKey {
+            time: 100,
+            rot: Quat::from_rotation_y(0.3),
+        }],
+        ..Default::default()
+    });
+
+    // Seekable, with trailing data left unread.
+    let mut cursor = Cursor::new(Vec::new());
+    anim.to_writer(&mut cursor).unwrap();
+    let len = cursor.position();
+    cursor.get_mut().extend_from_slice(b"trailer");
+    cursor.set_position(0);
+    let back = Animation::from_reader(&mut cursor).unwrap();
+    assert_eq!(cursor.position(), len);
+    assert_eq!(back.joints.len(), 1);
+
+    // Non-seekable sink and source.
+    let mut sink = Vec::new();
+    anim.to_stream(&mut sink).unwrap();
+    assert_eq!(sink.len() as u64, len);
+    let back = Animation::from_stream(std::io::Read::chain(&sink[..], &[][..])).unwrap();
+    assert_eq!(back, Animation::from_reader(Cursor::new(&sink)).unwrap());
+}
+
+#[test]
+fn streaming_writes_match_the_buffered_encoding() {
+    use avatar_anim::Constraint;
+
+    /// A sink that can't seek and records whether it was flushed.
+    struct Socket(Vec<u8>, bool);
+    impl std::io::Write for Socket {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            self.1 = true;
+            Ok(())
+        }
+    }
+
+    let mut anim = Animation::new();
+    anim.joints.push(JointData {
+        name: "mPelvis".into(),
+        rotation_keys: vec![RotationKey::default(); 3],
+        position_keys: vec![PositionKey::default(); 2],
+        ..Default::default()
+    });
+    anim.constraints.push(Constraint::default());
+    anim.trailing_data = b"vendor".to_vec();
+    let mut socket = Socket(Vec::new(), false);
+    anim.to_stream(&mut socket).unwrap();
+    assert_eq!(socket.0, anim.to_bytes().unwrap());
+    assert!(socket.1);
+
+    // The field writers take any `Write`.
+    let mut raw = Vec::new();
+    avatar_anim::io::write_rot_quat(&Quat::IDENTITY, &mut raw, binrw::Endian::Little, ()).unwrap();
+    assert_eq!(raw, [0xff, 0x7f, 0xff, 0x7f, 0xff, 0x7f]);
+}
+
+#[test]
+fn byte_apis_reserve_the_exact_size() {
+    use avatar_anim::Constraint;
+
+    let mut anim = Animation::new();
+    anim.header.emote_name = "express_smile".into();
+    anim.joints.push(JointData {
+        name: "mPelvis".into(),
+        rotation_keys: vec![RotationKey::default(); 3],
+        position_keys: vec![PositionKey::default(); 2],
+        ..Default::default()
+    });
+    anim.constraints.push(Constraint {
+        source_volume: "L_HAND".into(),
+        ..Default::default()
+    });
+
+    let bytes = anim.to_bytes().unwrap();
+    assert_eq!(bytes.len(), anim.encoded_len());
+    let back = Animation::from_bytes(&bytes).unwrap();
+    assert_eq!(back.joints.len(), 1);
+    assert_eq!(back.constraints, anim.constraints);
+    assert!(Animation::from_bytes(&bytes[..10]).is_err());
+}
+
+#[test]
+fn view_reads_joints_lazily() {
+    use avatar_anim::view::AnimView;
+
+    let mut anim = Animation::new();
+    anim.header.emote_name = "express_wink".into();
+    anim.joints.push(JointData {
+        name: "mHead".into(),
+        priority: 3,
+        rotation_keys: vec![
+            RotationKey {
+                time: 0,
+                rot: Quat::from_rotation_x(0.2),
+            },
+            RotationKey {
+                time: 900,
+                rot: Quat::from_rotation_y(-0.4),
+            },
+        ],
+        position_keys: vec![PositionKey {
+            time: 5,
+            pos: Vec3::new(0.1, -0.2, 0.3),
+        }],
+    });
+    anim.joints.push(JointData {
+        name: "mNeck".into(),
+        ..Default::default()
+    });
+    let bytes = anim.to_bytes().unwrap();
+    let decoded = Animation::from_bytes(&bytes).unwrap();
+
+    let view = AnimView::parse(&bytes).unwrap();
+    assert_eq!(view.emote_name, "express_wink");
+    assert_eq!(view.header(), anim.header);
+    assert_eq!(view.joint_count(), 2);
+    assert_eq!(view.constraint_count().unwrap(), 0);
+    let joints: Vec<_> = view.joints().map(|j| j.unwrap()).collect();
+    assert_eq!(joints[0].name, "mHead");
+    assert_eq!(joints[0].priority, 3);
+    assert_eq!(
+        joints[0].rotation_keys().collect::<Vec<_>>(),
+        decoded.joints[0].rotation_keys
+    );
+    assert_eq!(
+        joints[0].position_keys().collect::<Vec<_>>(),
+        decoded.joints[0].position_keys
+    );
+    assert_eq!(joints[1].rotation_count(), 0);
+
+    // The header alone is enough; a cut joint table only fails when reached.
+    let cut = &bytes[..bytes.len() - 12];
+    let view = AnimView::parse(cut).unwrap();
+    assert!(view.joints().next().unwrap().is_ok());
+    assert!(view.constraint_count().is_err());
+    assert!(AnimView::parse(&bytes[..20]).is_err());
+}
+
+#[test]
+fn capabilities_follow_the_build() {
+    use avatar_anim::{ReadOptions, SourceFormat, capabilities};
+
+    let caps = capabilities();
+    assert_eq!(caps.version, env!("CARGO_PKG_VERSION"));
+    assert!(caps.supports(SourceFormat::Anim));
+    assert_eq!(caps.has_feature("llsd"), cfg!(feature = "llsd"));
+    assert_eq!(
+        caps.supports(SourceFormat::LlsdBinary),
+        cfg!(feature = "llsd")
+    );
+    assert_eq!(caps.supports(SourceFormat::Json), cfg!(feature = "serde"));
+    assert_eq!(caps.gzip_input, caps.has_feature("gzip"));
+    assert_eq!(caps.read_limits, ReadOptions::default());
+}
+
+#[test]
+fn zero_snap_threshold_is_configurable() {
+    use avatar_anim::{ParseWarning, ReadOptions};
+
+    let mut anim = Animation::new();
+    anim.joints.push(JointData {
+        name: "mPelvis".into(),
+        rotation_keys: vec![RotationKey {
+            time: 0,
+            rot: Quat::from_rotation_z(0.004),
+        }],
+        position_keys: vec![PositionKey {
+            time: 0,
+            pos: Vec3::new(0.0, 0.0002, 0.01),
+        }],
+        ..Default::default()
+    });
+    let bytes = anim.to_bytes().unwrap();
+    let read = |zero_snap_quanta| {
+        let options = ReadOptions {
+            zero_snap_quanta,
+            ..Default::default()
+        };
+        let back = Animation::from_bytes_with_options(&bytes, &options).unwrap();
+        let pelvis = &back.joints[0];
+        (pelvis.rotation_keys[0].rot, pelvis.position_keys[0].pos)
+    };
+
+    let (rot, pos) = read(1);
+    assert_eq!(pos.x, 0.0);
+    assert_eq!(pos.y, 0.0, "the viewer zeroes a 0.2 mm offset");
+    assert!(rot.z > 0.0);
+    let (_, exact) = read(0);
+    assert!(exact.y > 0.0 && exact.x.abs() < 1e-4);
+    let (wide_rot, wide_pos) = read(100);
+    assert_eq!((wide_pos.y, wide_pos.z), (0.0, 0.0));
+    assert_eq!(wide_rot, Quat::IDENTITY);
+
+    let (_, report) = Animation::read_with_report(&bytes[..]).unwrap();
+    assert_eq!(
+        report.warnings,
+        [ParseWarning::ZeroSnapped {
+            joint: "mPelvis".into(),
+            rotation_components: 0,
+            position_components: 1,
+        }]
+    );
+}
+
+#[test]
+fn edit_log_records_operations() {
+    let mut anim = Animation::new();
+    anim.header.duration = 1.0;
+    anim.joints.push(JointData {
+        name: "mHead".into(),
+        rotation_keys: vec![
+            RotationKey {
+                time: 0,
+                rot: Quat::IDENTITY,
+            },
+            RotationKey {
+                time: 0,
+                rot: Quat::IDENTITY,
+            },
+        ],
+        position_keys: vec![PositionKey {
+            time: 0,
+            pos: Vec3::ZERO,
+        }],
+        ..Default::default()
+    });
+    anim.set_priority(2);
+    assert!(anim.edit_log.is_none(), "nothing is recorded without a log");
+
+    anim.start_edit_log();
+    anim.cleanup_keys()
+        .drop_position_keys()
+        .sync_to_beats(&[])
+        .unwrap();
+    let log = anim.edit_log.as_ref().unwrap();
+    let operations: Vec<EditOperation> = log.entries.iter().map(|e| e.operation).collect();
+    assert_eq!(
+        operations,
+        [
+            EditOperation::CleanupKeysWith,
+            EditOperation::DropPositionKeys,
+            EditOperation::SyncToBeats
+        ]
+    );
+    let cleanup = &log.entries[0];
+    assert_eq!(
+        cleanup.parameters,
+        [("strategy".to_string(), "KeepLast".to_string())]
+    );
+    assert_eq!(
+        (cleanup.before.rotation_keys, cleanup.after.rotation_keys),
+        (2, 1)
+    );
+    assert_eq!(log.entries[1].after.position_keys, 0);
+    assert_eq!(log.entries[2].warnings.len(), 1);
+
+    let json = log.to_json();
+    assert!(json.contains("\"operation\": \"drop_position_keys\""));
+    assert!(json.contains("\"parameters\": {\"strategy\": \"KeepLast\"}"));
+    assert!(
+        log.to_string()
+            .starts_with("1. cleanup_keys_with strategy=KeepLast: joints 1 -> 1, keys 3 -> 2")
+    );
+
+    // Copies carry the log; the file format doesn't.
+    let underlay = anim.to_underlay();
+    assert_eq!(underlay.edit_log.as_ref().unwrap().len(), 4);
+    let back = Animation::from_bytes(&underlay.to_bytes().unwrap()).unwrap();
+    assert!(back.edit_log.is_none());
+    assert_eq!(back, underlay, "the log is not part of equality");
+}
@@ -0,0 +1,79 @@
+use avatar_anim::cache::AnimCache;
+use avatar_anim::dashboard::LibraryStats;
+use avatar_anim::{AnimError, Animation, JointData, RotationKey};
+
+#[test]
+fn dashboard_aggregates_a_library() {
+    let mut walk = Animation::new();
+    walk.header.duration = 1.5;
+    walk.set_priority(3);
+    walk.joints.push(JointData {
+        name: "mPelvis".into(),
+        priority: 3,
+        rotation_keys: vec![RotationKey::default(); 3],
+        ..Default::default()
+    });
+    let mut wave = Animation::new();
+    wave.header.duration = 75.0;
+    wave.header.looped = 0;
+    wave.set_priority(4);
+    wave.joints.push(JointData {
+        name: "mWristRight".into(),
+        priority: 4,
+        rotation_keys: vec![RotationKey::default(); 5],
+        ..Default::default()
+    });
+
+    let mut stats = LibraryStats::new();
+    stats.add("walk", 100, &walk);
+    stats.add("wave", 200, &wave);
+    stats.add("walk copy", 100, &walk);
+    stats.add_failure("broken", &AnimError::InvalidStructure("truncated".into()));
+
+    assert_eq!((stats.animations, stats.bytes, stats.keys), (3, 400, 11));
+    assert_eq!(stats.looped, 2);
+    assert_eq!(stats.priorities.get(&3), Some(&2));
+    assert_eq!(stats.durations, [0, 2, 0, 0, 0, 0, 1]);
+    let joints = stats.most_keyed_joints(1);
+    assert_eq!((joints[0].name.as_str(), joints[0].keys), ("mPelvis", 6));
+    assert_eq!(stats.duplicates(), [["walk", "walk copy"]]);
+
+    let text = stats.to_text(5);
+    assert!(text.starts_with("Animations: 3 (400 bytes, 11 keys), 2 looped, 1 unreadable"));
+    assert!(text.contains("  walk, walk copy"));
+    let json = stats.to_json(5);
+    assert!(json.contains("\"priorities\": {\"3\": 2, \"4\": 1}"));
+    assert!(json.contains("\"duplicates\": [[\"walk\", \"walk copy\"]]"));
+    assert!(stats.to_html(5).contains("<li>walk, walk copy</li>"));
+}
+
+#[test]
+fn dashboard_rescans_through_a_cache() {
+    let dir = std::env::temp_dir().join(format!("avatar_anim_dashboard_{}", std::process::id()));
+    let nested = dir.join("dances");
+    std::fs::create_dir_all(&nested).unwrap();
+    Animation::new().to_file(dir.join("idle.anim")).unwrap();
+    Animation::new().to_file(nested.join("spin.ANIM")).unwrap();
+    std::fs::write(nested.join("broken.anim"), b"x").unwrap();
+    std::fs::write(dir.join("notes.txt"), b"x").unwrap();
+
+    let cache = AnimCache::new(16);
+    let first = LibraryStats::from_dir_with_cache(&dir, &cache).unwrap();
+    let second = LibraryStats::from_dir_with_cache(&dir, &cache).unwrap();
+    let uncached = LibraryStats::from_dir(&dir).unwrap();
+    std::fs::remove_dir_all(&dir).ok();
+
+    assert_eq!(first.animations, 2);
+    assert_eq!(first.failures.len(), 1);
+    assert!(first.failures[0].0.ends_with("broken.anim"));
+    assert_eq!(
+        (second.animations, second.bytes),
+        (first.animations, first.bytes)
+    );
+    assert_eq!(
+        (uncached.animations, uncached.bytes),
+        (first.animations, first.bytes)
+    );
+    // Failures are not cached, so only the two readable files are hits.
+    assert_eq!(cache.stats().hits, 2);
+}
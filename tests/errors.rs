@@ -0,0 +1,478 @@
+use avatar_anim::{Animation, ErrorCode, JointData, RotationKey};
+use binrw::{BinRead, BinWrite};
+use std::io::Cursor;
+
+fn encoded() -> Vec<u8> {
+    let mut anim = Animation::default();
+    anim.joints.push(JointData {
+        name: "mHead".into(),
+        rotation_keys: vec![RotationKey::default(); 2],
+        ..Default::default()
+    });
+    let mut cursor = Cursor::new(Vec::new());
+    anim.write(&mut cursor).unwrap();
+    cursor.into_inner()
+}
+
+fn code_of(bytes: &[u8]) -> ErrorCode {
+    let err = Animation::read(&mut Cursor::new(bytes)).unwrap_err();
+    avatar_anim::AnimError::from(err).code()
+}
+
+#[test]
+fn truncation_is_attributed_to_its_section() {
+    let bytes = encoded();
+    assert_eq!(code_of(&bytes[..10]), ErrorCode::TruncatedHeader);
+    assert_eq!(
+        code_of(&bytes[..bytes.len() - 8]),
+        ErrorCode::TruncatedJointTable
+    );
+    assert_eq!(
+        code_of(&bytes[..bytes.len() - 2]),
+        ErrorCode::TruncatedConstraints
+    );
+}
+
+#[test]
+fn invalid_names_and_llsd_shape() {
+    let mut bytes = encoded();
+    let at = bytes.windows(5).position(|w| w == b"mHead").unwrap();
+    bytes[at] = 0xff;
+    assert_eq!(code_of(&bytes), ErrorCode::InvalidUtf8Name);
+
+    let mut bytes = encoded();
+    let count_at = at + b"mHead\0".len() + 4;
+    bytes[count_at..count_at + 4].copy_from_slice(&(-1i32).to_le_bytes());
+    assert_eq!(code_of(&bytes), ErrorCode::CountOverflow);
+
+    let mut bytes = encoded();
+    let end = bytes.len();
+    bytes[end - 4..].copy_from_slice(&(-1i32).to_le_bytes());
+    assert_eq!(code_of(&bytes), ErrorCode::CountOverflow);
+
+    #[cfg(feature = "llsd")]
+    {
+        let err = Animation::from_llsd(&llsd_rs::Llsd::Integer(3), false).unwrap_err();
+        assert_eq!(err.code(), ErrorCode::LlsdShape);
+        assert_eq!(err.code().as_str(), "llsd_shape");
+    }
+}
+
+#[test]
+fn read_limits_reject_hostile_counts() {
+    use avatar_anim::{AnimError, Limit, ReadOptions};
+
+    let bytes = encoded();
+    let options = ReadOptions::default();
+    assert!(Animation::from_bytes_with_options(&bytes, &options).is_ok());
+
+    let limited =
+        |bytes: &[u8], options: &ReadOptions| match Animation::read_with_options(bytes, options)
+            .unwrap_err()
+        {
+            AnimError::LimitExceeded { limit, .. } => limit,
+            other => panic!("unexpected {other}"),
+        };
+    let at = bytes.windows(5).position(|w| w == b"mHead").unwrap();
+    let mut hostile = bytes.clone();
+    hostile[at - 4..at].copy_from_slice(&u32::MAX.to_le_bytes());
+    assert_eq!(limited(&hostile, &options), Limit::Joints);
+    let err = Animation::from_bytes(&hostile).unwrap_err();
+    assert_eq!(err.code(), ErrorCode::LimitExceeded);
+
+    let mut hostile = bytes.clone();
+    let count_at = at + b"mHead\0".len() + 4;
+    hostile[count_at..count_at + 4].copy_from_slice(&i32::MAX.to_le_bytes());
+    assert_eq!(limited(&hostile, &options), Limit::KeysPerJoint);
+
+    let strict = ReadOptions {
+        max_string_length: 4,
+        ..Default::default()
+    };
+    assert_eq!(limited(&bytes, &strict), Limit::StringLength);
+    let strict = ReadOptions {
+        max_keys_per_joint: 1,
+        ..Default::default()
+    };
+    assert_eq!(limited(&bytes, &strict), Limit::KeysPerJoint);
+    let small = ReadOptions {
+        max_bytes: bytes.len() - 1,
+        ..Default::default()
+    };
+    assert_eq!(limited(&bytes, &small), Limit::Size);
+    // An endless source is only read up to the limit.
+    let err = Animation::read_with_options(std::io::repeat(0), &small).unwrap_err();
+    assert!(matches!(
+        err,
+        AnimError::LimitExceeded { limit: Limit::Size, found, .. } if found == bytes.len()
+    ));
+    let err = Animation::read_with_report_and_options(std::io::repeat(0), &small).unwrap_err();
+    assert!(matches!(
+        err,
+        AnimError::LimitExceeded {
+            limit: Limit::Size,
+            ..
+        }
+    ));
+    let err = Animation::from_stream(std::io::repeat(0)).unwrap_err();
+    assert!(matches!(
+        err,
+        AnimError::LimitExceeded {
+            limit: Limit::Size,
+            ..
+        }
+    ));
+
+    // Truncation is still reported by the decoder, with its usual code.
+    let err = Animation::from_bytes(&bytes[..bytes.len() - 2]).unwrap_err();
+    assert_eq!(err.code(), ErrorCode::TruncatedConstraints);
+}
+
+#[test]
+fn read_partial_keeps_complete_keys() {
+    let bytes = encoded();
+    let full = Animation::read_partial(&bytes[..]).unwrap();
+    assert!(full.is_complete());
+    assert_eq!(full.offset, bytes.len());
+    assert_eq!(full.animation.joints[0].rotation_keys.len(), 2);
+
+    // Cut inside the second rotation key.
+    let partial = Animation::read_partial(&bytes[..bytes.len() - 12]).unwrap();
+    assert!(!partial.is_complete());
+    assert_eq!(partial.animation.joints.len(), 1);
+    assert_eq!(partial.animation.joints[0].rotation_keys.len(), 1);
+    assert_eq!(partial.offset, bytes.len() - 16);
+    assert!(partial.error.unwrap().to_string().contains("rotation keys"));
+
+    assert!(Animation::read_partial(&bytes[..10]).is_err());
+}
+
+#[test]
+fn strict_reads_reject_trailing_bytes() {
+    use avatar_anim::{AnimError, ReadOptions};
+
+    let strict = ReadOptions {
+        reject_trailing_bytes: true,
+        ..Default::default()
+    };
+    let mut bytes = encoded();
+    assert!(Animation::from_bytes_with_options(&bytes, &strict).is_ok());
+    bytes.extend_from_slice(&[0; 3]);
+    assert!(Animation::from_bytes(&bytes).is_ok());
+    let err = Animation::from_bytes_with_options(&bytes, &strict).unwrap_err();
+    assert!(matches!(err, AnimError::TrailingBytes { count: 3 }));
+    assert_eq!(err.code().as_str(), "trailing_bytes");
+}
+
+#[test]
+fn trailing_bytes_survive_a_round_trip_when_kept() {
+    use avatar_anim::ReadOptions;
+
+    let keep = ReadOptions {
+        keep_trailing_bytes: true,
+        ..Default::default()
+    };
+    let mut bytes = encoded();
+    bytes.extend_from_slice(b"\x01vendor\x00");
+    let mut anim = Animation::from_bytes_with_options(&bytes, &keep).unwrap();
+    assert_eq!(anim.trailing_data, b"\x01vendor\x00");
+    assert_eq!(anim.encoded_len(), bytes.len());
+    assert_eq!(anim.to_bytes().unwrap(), bytes);
+
+    // The metadata-only rewrite keeps them too, and dropping them is an edit like any other.
+    anim.header.base_priority = 5;
+    assert!(
+        anim.rewrite_preserving(&bytes)
+            .unwrap()
+            .ends_with(b"vendor\x00")
+    );
+    let with_trailer = anim.fingerprint();
+    anim.trailing_data.clear();
+    assert_ne!(anim.fingerprint(), with_trailer);
+    assert_eq!(
+        anim.rewrite_preserving(&bytes).unwrap().len(),
+        encoded().len()
+    );
+
+    assert!(
+        Animation::from_bytes(&bytes)
+            .unwrap()
+            .trailing_data
+            .is_empty()
+    );
+
+    let rebuilt = Animation::from_parts(
+        anim.header.clone(),
+        anim.joints.clone(),
+        anim.constraints.clone(),
+    );
+    assert!(rebuilt.trailing_data.is_empty());
+    assert_eq!(rebuilt.to_bytes().unwrap(), anim.to_bytes().unwrap());
+}
+
+#[test]
+fn decode_errors_name_the_joint_and_offset() {
+    use avatar_anim::AnimError;
+
+    let mut anim = Animation::default();
+    for name in ["mHead", "mNeck"] {
+        anim.joints.push(JointData {
+            name: name.into(),
+            rotation_keys: vec![RotationKey::default(); 3],
+            ..Default::default()
+        });
+    }
+    let bytes = anim.to_bytes().unwrap();
+    // Cut inside the last rotation key of mNeck.
+    let cut = bytes.len() - 4 - 4 - 3;
+    let err = Animation::from_bytes(&bytes[..cut]).unwrap_err();
+    assert_eq!(err.code(), ErrorCode::TruncatedJointTable);
+    let AnimError::Decode {
+        offset,
+        joint,
+        joint_name,
+        field,
+        ..
+    } = &err
+    else {
+        panic!("unexpected {err}");
+    };
+    assert_eq!(*offset, cut - 5);
+    assert_eq!(*joint, Some(1));
+    assert_eq!(joint_name.as_deref(), Some("mNeck"));
+    assert_eq!(*field, "rotation keys");
+    assert!(err.to_string().contains("in joint 1 (mNeck)"), "{err}");
+
+    let mut bad = bytes.clone();
+    let at = bad.windows(5).position(|w| w == b"mNeck").unwrap();
+    bad[at] = 0xFF;
+    let err = Animation::from_bytes(&bad).unwrap_err();
+    assert_eq!(err.code(), ErrorCode::InvalidUtf8Name);
+    assert!(matches!(err, AnimError::Decode { offset, joint: Some(1), .. } if offset == at));
+}
+
+#[test]
+fn read_with_report_collects_warnings() {
+    use avatar_anim::ParseWarning;
+
+    let mut anim = Animation::default();
+    anim.header.duration = 1.0;
+    anim.header.looped = 1;
+    anim.header.loop_out_point = 2.0;
+    for _ in 0..2 {
+        anim.joints.push(JointData {
+            name: "mHead".into(),
+            rotation_keys: vec![
+                RotationKey {
+                    time: 10,
+                    ..Default::default()
+                },
+                RotationKey::default(),
+            ],
+            ..Default::default()
+        });
+    }
+    let mut bytes = anim.to_bytes().unwrap();
+    // Saturate x, y and z of the very first rotation key.
+    let at = bytes.windows(6).position(|w| w == b"mHead\0").unwrap() + 6 + 4 + 4 + 2;
+    bytes[at..at + 6].fill(0xFF);
+    bytes.push(7);
+
+    let (read, report) = Animation::read_with_report(&bytes[..]).unwrap();
+    assert_eq!(read.joints.len(), 2);
+    let kinds: Vec<String> = report.warnings.iter().map(|w| format!("{w}")).collect();
+    assert_eq!(report.warnings.len(), 6, "{kinds:?}");
+    assert!(matches!(
+        report.warnings[0],
+        ParseWarning::LoopOutsideDuration { loop_out: 2.0, .. }
+    ));
+    assert!(matches!(
+        &report.warnings[1],
+        ParseWarning::NonNormalizedRotation { key: 0, length, .. } if *length > 1.7
+    ));
+    assert!(matches!(
+        report.warnings[2],
+        ParseWarning::UnsortedKeys { .. }
+    ));
+    assert!(matches!(
+        report.warnings[3],
+        ParseWarning::DuplicateJoint { .. }
+    ));
+    assert!(matches!(
+        report.warnings[4],
+        ParseWarning::UnsortedKeys { .. }
+    ));
+    assert_eq!(report.warnings[5], ParseWarning::TrailingBytes { count: 1 });
+
+    let (_, report) = Animation::read_with_report(&encoded()[..]).unwrap();
+    assert!(report.is_clean());
+}
+
+#[test]
+fn implausible_header_times_are_reported_and_sanitized() {
+    use avatar_anim::{ParseWarning, ReadOptions};
+
+    let mut anim = Animation::default();
+    anim.header.duration = f32::NAN;
+    anim.header.loop_out_point = f32::INFINITY;
+    anim.header.ease_in_duration = -0.5;
+    anim.header.ease_out_duration = 1e9;
+    let bytes = anim.to_bytes().unwrap();
+
+    let (read, report) = Animation::read_with_report(&bytes[..]).unwrap();
+    assert!(read.header.duration.is_nan(), "read as stored by default");
+    let fields: Vec<_> = report
+        .warnings
+        .iter()
+        .map(|w| match w {
+            ParseWarning::ImplausibleHeaderTime {
+                field,
+                replaced_with: None,
+                ..
+            } => *field,
+            other => panic!("unexpected {other}"),
+        })
+        .collect();
+    assert_eq!(
+        fields,
+        [
+            "duration",
+            "loop_out_point",
+            "ease_in_duration",
+            "ease_out_duration"
+        ]
+    );
+
+    let options = ReadOptions {
+        sanitize_header: true,
+        ..Default::default()
+    };
+    let (read, report) = Animation::read_with_report_and_options(&bytes[..], &options).unwrap();
+    let header = &read.header;
+    assert_eq!(header.duration, 0.0);
+    assert_eq!(header.loop_out_point, 0.0);
+    assert_eq!(
+        (header.ease_in_duration, header.ease_out_duration),
+        (0.0, 3600.0)
+    );
+    assert_eq!(
+        report.warnings[0].to_string(),
+        "duration NaN is not a plausible time in seconds; read as 0"
+    );
+}
+
+#[test]
+fn strict_writes_refuse_upload_blockers() {
+    use avatar_anim::{AnimError, Severity, Target, WriteOptions};
+
+    let mut anim = Animation::from_bytes(&encoded()).unwrap();
+    anim.header.duration = 75.0;
+    anim.header.loop_out_point = 80.0;
+    let strict = WriteOptions {
+        strict: true,
+        target: Target::SecondLife,
+    };
+    let err = anim.to_bytes_with_options(&strict).unwrap_err();
+    assert_eq!(err.code(), ErrorCode::ValidationFailed);
+    let AnimError::ValidationFailed { issues } = &err else {
+        panic!("{err}");
+    };
+    assert_eq!(issues.len(), 2);
+    assert_eq!(issues[0].severity, Severity::Blocker);
+    assert!(err.to_string().contains("60s upload limit"), "{err}");
+
+    // OpenSim has no duration limit, leaving only the loop warning.
+    let open_sim = WriteOptions {
+        target: Target::OpenSim,
+        ..strict
+    };
+    let (bytes, issues) = anim.to_bytes_with_options(&open_sim).unwrap();
+    assert_eq!(bytes, anim.to_bytes().unwrap());
+    assert_eq!(issues.len(), 1);
+    assert_eq!(issues[0].severity, Severity::Warning);
+
+    // Permissive writes go through and report the blocker.
+    let (_, issues) = anim
+        .to_bytes_with_options(&WriteOptions::default())
+        .unwrap();
+    assert_eq!(issues.len(), 2);
+    assert!(
+        Animation::new().upload_issues(Target::SecondLife)[0]
+            .message
+            .contains("no joints")
+    );
+}
+
+#[test]
+fn header_validation_lists_typed_violations() {
+    use avatar_anim::{AnimationHeader, HeaderViolation};
+
+    let header = AnimationHeader {
+        version: 0,
+        sub_version: 1,
+        duration: -1.0,
+        loop_in_point: 2.0,
+        loop_out_point: 1.0,
+        ease_out_duration: f32::NAN,
+        ..Default::default()
+    };
+    let violations = header.validate();
+    assert_eq!(violations.len(), 4, "{violations:?}");
+    assert_eq!(
+        violations[0],
+        HeaderViolation::UnsupportedVersion {
+            version: 0,
+            sub_version: 1
+        }
+    );
+    assert!(matches!(
+        violations[1],
+        HeaderViolation::InvalidDuration { .. }
+    ));
+    assert!(matches!(violations[2], HeaderViolation::InvalidLoop { .. }));
+    assert!(matches!(
+        violations[3],
+        HeaderViolation::InvalidEase { ease_in: false, .. }
+    ));
+    assert_eq!(violations[0].to_string(), "version 0.1 is not 1.0");
+
+    let unlooped = AnimationHeader {
+        looped: 0,
+        loop_in_point: 5.0,
+        ..Default::default()
+    };
+    assert!(unlooped.validate().is_empty());
+}
+
+#[test]
+fn validate_reports_structure_problems() {
+    use avatar_anim::{Constraint, Severity};
+
+    let mut anim = Animation::from_bytes(&encoded()).unwrap();
+    let report = anim.validate();
+    assert_eq!(report.warnings().count(), 1);
+    assert!(
+        report.issues[0]
+            .message
+            .contains("1 duplicate rotation key times")
+    );
+    anim.joints[0].rotation_keys.pop();
+    assert!(anim.validate().is_clean(), "{:?}", anim.validate());
+
+    let head = anim.joints[0].clone();
+    anim.joints.push(head);
+    anim.constraints.push(Constraint {
+        chain_length: 5,
+        source_volume: "LEFT_HAND".into(),
+        ..Default::default()
+    });
+    let report = anim.validate();
+    assert!(report.has_blockers());
+    assert_eq!(report.issues[0].severity, Severity::Blocker);
+    assert!(
+        report.issues[0].message.contains("chain of 5"),
+        "{report:?}"
+    );
+    assert_eq!(report.warnings().count(), 1);
+    assert!(report.issues[1].message.contains("more than once"));
+}
@@ -0,0 +1,121 @@
+use avatar_anim::gesture::{self, Gesture, Issue, Ledger, Step};
+use avatar_anim::manifest::ManifestEntry;
+use avatar_anim::{Animation, JointData};
+
+const WAVE: &str = "c541c47f-e0c0-058b-ad1a-d6ae3a4584d9";
+const NOD: &str = "00000000-0000-0000-0000-0000000000aa";
+const GONE: &str = "11111111-2222-3333-4444-555555555555";
+
+fn anim(duration: f32, joints: &[&str]) -> Animation {
+    let mut anim = Animation::new();
+    anim.header.duration = duration;
+    anim.header.looped = 0;
+    for name in joints {
+        anim.joints.push(JointData {
+            name: (*name).into(),
+            ..Default::default()
+        });
+    }
+    anim
+}
+
+#[test]
+fn parses_all_step_kinds() {
+    let text = format!(
+        "2\r\n0\r\n0\r\n/wave\r\nHi!\r\n4\r\n0\r\nWave\r\n{}\r\n0\r\n1\r\nBoing\r\n{NOD}\r\n0\r\n2\r\nhello there\r\n0\r\n3\r\n1.5\r\n1\r\n",
+        WAVE.to_uppercase()
+    );
+    let gesture = Gesture::parse(&text).unwrap();
+    assert_eq!(gesture.trigger, "/wave");
+    assert_eq!(gesture.replace_text, "Hi!");
+    assert_eq!(
+        gesture.steps,
+        vec![
+            Step::Animation {
+                name: "Wave".into(),
+                asset_id: WAVE.into(),
+                flags: 0,
+            },
+            Step::Sound {
+                name: "Boing".into(),
+                asset_id: NOD.into(),
+                flags: 0,
+            },
+            Step::Chat {
+                text: "hello there".into(),
+                flags: 0,
+            },
+            Step::Wait {
+                seconds: 1.5,
+                flags: gesture::WAIT_FLAG_TIME,
+            },
+        ]
+    );
+
+    assert!(Gesture::parse("1\n0\n0\n\n\n0\n").is_err());
+    assert!(Gesture::parse("2\n0\n0\n/x\n\n1\n0\nWave\nnot-a-uuid\n0\n").is_err());
+    assert!(Gesture::parse("2\n0\n0\n/x\n\n2\n2\nhi\n0\n").is_err());
+    assert!(Gesture::parse("2\n0\n0\n/x\n\n1\n7\n").is_err());
+}
+
+#[test]
+fn check_reports_broken_references_and_overlaps() {
+    let mut ledger = Ledger::new();
+    ledger.insert(
+        WAVE,
+        ManifestEntry::new("Wave", &anim(2.0, &["mShoulderRight"])),
+    );
+    ledger.insert(
+        NOD,
+        ManifestEntry::new("Nod old", &anim(1.0, &["mHead", "mShoulderRight"])),
+    );
+    let step = |name: &str, id: &str, flags: u32| Step::Animation {
+        name: name.into(),
+        asset_id: id.into(),
+        flags,
+    };
+    let wait = |seconds: f32, flags: u32| Step::Wait { seconds, flags };
+
+    let gesture = Gesture {
+        steps: vec![
+            step("Wave", WAVE, 0),
+            wait(0.5, gesture::WAIT_FLAG_TIME),
+            step("Nod", NOD, 0),
+            step("Shrug", GONE, 0),
+        ],
+        ..Default::default()
+    };
+    let issues = gesture::check(&gesture, &ledger);
+    assert_eq!(
+        issues,
+        vec![
+            Issue::NameMismatch {
+                step: 2,
+                gesture_name: "Nod".into(),
+                ledger_name: "Nod old".into(),
+            },
+            Issue::Overlap {
+                first: 0,
+                second: 2,
+                seconds: 1.0,
+                joints: vec!["mShoulderRight".into()],
+            },
+            Issue::MissingAnimation {
+                step: 3,
+                asset_id: GONE.into(),
+            },
+        ]
+    );
+
+    // Waiting for the wave to finish, or stopping it, removes the overlap.
+    for separator in [
+        wait(0.0, gesture::WAIT_FLAG_ALL_ANIM),
+        step("Wave", WAVE, gesture::ANIM_FLAG_STOP),
+    ] {
+        let gesture = Gesture {
+            steps: vec![step("Wave", WAVE, 0), separator, step("Nod old", NOD, 0)],
+            ..Default::default()
+        };
+        assert!(gesture::check(&gesture, &ledger).is_empty());
+    }
+}
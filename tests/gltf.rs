@@ -0,0 +1,220 @@
+#![cfg(feature = "gltf")]
+
+use avatar_anim::gltf::GltfOptions;
+use avatar_anim::{Animation, JointData, PositionKey, RotationKey, Skeleton};
+use glam::{Quat, Vec3};
+
+/// Hips (rest-rotated 90° about Y) with a child Head; one second of head rotation and hip sway.
+fn nod_glb() -> Vec<u8> {
+    let json = r#"{
+        "asset": {"version": "2.0"},
+        "nodes": [
+            {"name": "Hips", "translation": [0, 1, 0], "rotation": [0, 0.70710677, 0, 0.70710677], "children": [1]},
+            {"name": "Head"}
+        ],
+        "buffers": [{"byteLength": 64}],
+        "bufferViews": [
+            {"buffer": 0, "byteOffset": 0, "byteLength": 8},
+            {"buffer": 0, "byteOffset": 8, "byteLength": 32},
+            {"buffer": 0, "byteOffset": 40, "byteLength": 24}
+        ],
+        "accessors": [
+            {"bufferView": 0, "componentType": 5126, "count": 2, "type": "SCALAR", "min": [0], "max": [1]},
+            {"bufferView": 1, "componentType": 5126, "count": 2, "type": "VEC4"},
+            {"bufferView": 2, "componentType": 5126, "count": 2, "type": "VEC3"}
+        ],
+        "animations": [{
+            "channels": [
+                {"sampler": 0, "target": {"node": 1, "path": "rotation"}},
+                {"sampler": 1, "target": {"node": 0, "path": "translation"}}
+            ],
+            "samplers": [{"input": 0, "output": 1}, {"input": 0, "output": 2}]
+        }]
+    }"#;
+    let nod = Quat::from_rotation_x(0.5);
+    let floats: Vec<f32> = [0.0, 1.0]
+        .into_iter()
+        .chain(Quat::IDENTITY.to_array())
+        .chain(nod.to_array())
+        .chain([0.0, 1.0, 0.0, 0.1, 1.0, 0.0])
+        .collect();
+    pack_glb(json, &floats)
+}
+
+/// A binary glTF of `json` with `floats` as its buffer.
+fn pack_glb(json: &str, floats: &[f32]) -> Vec<u8> {
+    let bin: Vec<u8> = floats.iter().flat_map(|f| f.to_le_bytes()).collect();
+
+    let mut json = json.as_bytes().to_vec();
+    json.resize(json.len().next_multiple_of(4), b' ');
+    let mut glb = b"glTF".to_vec();
+    glb.extend(2u32.to_le_bytes());
+    glb.extend((12 + 8 + json.len() as u32 + 8 + bin.len() as u32).to_le_bytes());
+    glb.extend((json.len() as u32).to_le_bytes());
+    glb.extend(b"JSON");
+    glb.extend(json);
+    glb.extend((bin.len() as u32).to_le_bytes());
+    glb.extend(b"BIN\0");
+    glb.extend(bin);
+    glb
+}
+
+#[test]
+fn gltf_import_maps_nodes_rest_pose_and_axes() {
+    let mut options = GltfOptions::default();
+    options.joint_names.insert("Hips".into(), "mPelvis".into());
+    options.joint_names.insert("Head".into(), "mHead".into());
+    let anim = Animation::from_gltf_slice(&nod_glb(), &options).unwrap();
+
+    assert_eq!(anim.header.duration, 1.0);
+    let names: Vec<&str> = anim.joints.iter().map(|j| j.name.as_str()).collect();
+    assert_eq!(names, ["mHead", "mPelvis"]);
+
+    // The head nods about its local X, which the hips' rest turn points along glTF -Z,
+    // the avatar's backward axis in SL.
+    let head = &anim.joints[0].rotation_keys;
+    assert_eq!(head.len(), 2);
+    assert_eq!(head[1].time, u16::MAX);
+    let expected = Quat::from_axis_angle(Vec3::NEG_X, 0.5);
+    assert!(head[1].rot.abs_diff_eq(expected, 1e-5), "{}", head[1].rot);
+
+    // Sway along glTF X is along SL +Y, relative to the rest position.
+    let pelvis = &anim.joints[1];
+    assert!(pelvis.rotation_keys.is_empty());
+    assert!(pelvis.position_keys[0].pos.abs_diff_eq(Vec3::ZERO, 1e-6));
+    assert!(
+        pelvis.position_keys[1]
+            .pos
+            .abs_diff_eq(Vec3::new(0.0, 0.1, 0.0), 1e-6)
+    );
+}
+
+#[test]
+fn gltf_import_steps_up_to_the_last_tick() {
+    // The last two keys both round to the final tick.
+    let json = r#"{
+        "asset": {"version": "2.0"},
+        "nodes": [{"name": "Head"}],
+        "buffers": [{"byteLength": 60}],
+        "bufferViews": [
+            {"buffer": 0, "byteOffset": 0, "byteLength": 12},
+            {"buffer": 0, "byteOffset": 12, "byteLength": 48}
+        ],
+        "accessors": [
+            {"bufferView": 0, "componentType": 5126, "count": 3, "type": "SCALAR", "min": [0], "max": [100]},
+            {"bufferView": 1, "componentType": 5126, "count": 3, "type": "VEC4"}
+        ],
+        "animations": [{
+            "channels": [{"sampler": 0, "target": {"node": 0, "path": "rotation"}}],
+            "samplers": [{"input": 0, "output": 1, "interpolation": "STEP"}]
+        }]
+    }"#;
+    let turned = Quat::from_rotation_y(0.5);
+    let floats: Vec<f32> = [0.0, 99.9995, 100.0]
+        .into_iter()
+        .chain(Quat::IDENTITY.to_array())
+        .chain(Quat::IDENTITY.to_array())
+        .chain(turned.to_array())
+        .collect();
+    let mut options = GltfOptions::default();
+    options.joint_names.insert("Head".into(), "mHead".into());
+    let anim = Animation::from_gltf_slice(&pack_glb(json, &floats), &options).unwrap();
+    let head = &anim.joints[0].rotation_keys;
+    assert_eq!(head.last().unwrap().time, u16::MAX);
+}
+
+#[test]
+fn gltf_import_skips_unmapped_nodes_and_reports_errors() {
+    let anim = Animation::from_gltf_slice(&nod_glb(), &GltfOptions::default()).unwrap();
+    assert!(anim.joints.is_empty());
+
+    let options = GltfOptions {
+        animation: 1,
+        ..Default::default()
+    };
+    let err = Animation::from_gltf_slice(&nod_glb(), &options).unwrap_err();
+    assert!(err.to_string().contains("no animation 1"), "{err}");
+    assert!(Animation::from_gltf_slice(b"glTF", &GltfOptions::default()).is_err());
+}
+
+#[test]
+fn gltf_export_round_trips() {
+    let mut anim = Animation::new();
+    anim.header.duration = 2.0;
+    anim.header.emote_name = "wave \"hi\"".into();
+    anim.joints.push(JointData {
+        name: "mShoulderLeft".into(),
+        rotation_keys: vec![
+            RotationKey {
+                time: 0,
+                rot: Quat::IDENTITY,
+            },
+            RotationKey {
+                time: 30000,
+                rot: Quat::from_rotation_x(-1.2),
+            },
+            RotationKey {
+                time: u16::MAX,
+                rot: Quat::from_rotation_z(0.4),
+            },
+        ],
+        ..Default::default()
+    });
+    anim.joints.push(JointData {
+        name: "mPelvis".into(),
+        position_keys: vec![
+            PositionKey {
+                time: 0,
+                pos: Vec3::ZERO,
+            },
+            PositionKey {
+                time: u16::MAX,
+                pos: Vec3::new(0.3, -0.1, 0.05),
+            },
+        ],
+        ..Default::default()
+    });
+    anim.joints.push(JointData {
+        name: "mTail1".into(),
+        rotation_keys: vec![RotationKey {
+            time: 0,
+            rot: Quat::IDENTITY,
+        }],
+        ..Default::default()
+    });
+
+    let gltf = anim.to_gltf(&Skeleton::second_life()).unwrap();
+    assert!(gltf.contains(r#""name":"wave \"hi\"""#));
+    assert!(
+        !gltf.contains("mTail1"),
+        "joints outside the skeleton are skipped"
+    );
+
+    let back = Animation::from_gltf_slice(gltf.as_bytes(), &GltfOptions::default()).unwrap();
+    assert_eq!(back.header.duration, 2.0);
+    let names: Vec<&str> = back.joints.iter().map(|j| j.name.as_str()).collect();
+    assert_eq!(names, ["mShoulderLeft", "mPelvis"]);
+    for (a, b) in back.joints[0]
+        .rotation_keys
+        .iter()
+        .zip(&anim.joints[0].rotation_keys)
+    {
+        assert_eq!(a.time, b.time);
+        assert!(a.rot.abs_diff_eq(b.rot, 1e-5), "{} vs {}", a.rot, b.rot);
+    }
+    for (a, b) in back.joints[1]
+        .position_keys
+        .iter()
+        .zip(&anim.joints[1].position_keys)
+    {
+        assert_eq!(a.time, b.time);
+        assert!(a.pos.abs_diff_eq(b.pos, 1e-5), "{} vs {}", a.pos, b.pos);
+    }
+
+    assert!(Animation::new().to_gltf(&Skeleton::default()).is_err());
+    let empty = Animation::new().to_gltf(&Skeleton::second_life()).unwrap();
+    assert!(!empty.contains("animations"));
+
+    anim.header.duration = f32::INFINITY;
+    assert!(anim.to_gltf(&Skeleton::second_life()).is_err());
+}
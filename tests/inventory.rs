@@ -0,0 +1,90 @@
+#![cfg(feature = "llsd")]
+
+use avatar_anim::Animation;
+use avatar_anim::inventory::Inventory;
+
+const ROOT: &str = "10000000-0000-0000-0000-000000000001";
+const DANCES: &str = "10000000-0000-0000-0000-000000000002";
+const WAVE: &str = "C541C47F-E0C0-058B-AD1A-D6AE3A4584D9";
+
+fn cache() -> String {
+    let item = |id: &str, asset: &str, name: &str, kind: &str| {
+        format!(
+            "<llsd><map><key>item_id</key><uuid>{id}</uuid><key>parent_id</key><uuid>{DANCES}</uuid>\
+             <key>asset_id</key><uuid>{asset}</uuid><key>name</key><string>{name}</string>\
+             <key>type</key>{kind}</map></llsd>\n"
+        )
+    };
+    let mut text = format!(
+        "<llsd><map><key>inv_cache_version</key><integer>3</integer></map></llsd>\n\
+         <llsd><map><key>cat_id</key><uuid>{ROOT}</uuid><key>name</key><string>My Inventory</string></map></llsd>\n\
+         <llsd><map><key>cat_id</key><uuid>{DANCES}</uuid><key>parent_id</key><uuid>{ROOT}</uuid>\
+         <key>name</key><string>Dances</string></map></llsd>\n"
+    );
+    text += &item(
+        "20000000-0000-0000-0000-000000000001",
+        WAVE,
+        "Wave",
+        "<integer>20</integer>",
+    );
+    text += &item(
+        "20000000-0000-0000-0000-000000000002",
+        "30000000-0000-0000-0000-000000000000",
+        "Readme",
+        "<string>notecard</string>",
+    );
+    text
+}
+
+#[test]
+fn parses_viewer_inventory_cache() {
+    let inventory = Inventory::parse(cache().as_bytes()).unwrap();
+    assert_eq!(inventory.items.len(), 2);
+    let wave = inventory.animation(WAVE).unwrap();
+    assert_eq!(wave.name, "Wave");
+    assert_eq!(wave.asset_id, WAVE.to_ascii_lowercase());
+    assert_eq!(wave.folder, ["My Inventory", "Dances"]);
+    assert_eq!(inventory.animations().count(), 1);
+    assert!(
+        inventory
+            .animation("30000000-0000-0000-0000-000000000000")
+            .is_none()
+    );
+
+    assert!(Inventory::parse(b"<llsd><map><key>x</key></llsd>").is_err());
+
+    // A repeated item is listed once, even when other items sort between its entries.
+    let mut repeated = cache();
+    repeated += &format!(
+        "<llsd><map><key>item_id</key><uuid>20000000-0000-0000-0000-000000000001</uuid>\
+         <key>parent_id</key><uuid>{DANCES}</uuid><key>asset_id</key><uuid>{WAVE}</uuid>\
+         <key>name</key><string>A wave</string><key>type</key><integer>20</integer></map></llsd>\n"
+    );
+    let inventory = Inventory::parse(repeated.as_bytes()).unwrap();
+    assert_eq!(inventory.items.len(), 2);
+}
+
+#[test]
+fn names_cached_animations_in_the_ledger() {
+    let dir = std::env::temp_dir().join(format!("avatar_anim_cache_{}", std::process::id()));
+    let nested = dir.join("c");
+    std::fs::create_dir_all(&nested).unwrap();
+    let mut anim = Animation::new();
+    anim.header.duration = 2.5;
+    anim.to_file(nested.join(format!("{}.anim", WAVE.to_ascii_lowercase())))
+        .unwrap();
+    std::fs::write(dir.join("30000000-0000-0000-0000-000000000000.asset"), b"x").unwrap();
+    std::fs::write(dir.join("not-an-animation.bin"), b"x").unwrap();
+    // A link back up the tree is walked once, not forever.
+    #[cfg(unix)]
+    std::os::unix::fs::symlink(&dir, nested.join("loop")).unwrap();
+
+    let inventory = Inventory::parse(cache().as_bytes()).unwrap();
+    let ledger = inventory.ledger_from_cache(&dir).unwrap();
+    std::fs::remove_dir_all(&dir).ok();
+
+    assert_eq!(ledger.len(), 1);
+    let entry = ledger.get(WAVE).unwrap();
+    assert_eq!(entry.name, "Wave");
+    assert_eq!(entry.duration, 2.5);
+}
@@ -0,0 +1,129 @@
+#![cfg(feature = "serde")]
+
+use avatar_anim::json::RotationFormat;
+use avatar_anim::{Animation, Constraint, JointData, PositionKey, RotationKey};
+use glam::{Quat, Vec3};
+
+fn sample() -> Animation {
+    let mut anim = Animation::new();
+    anim.header.duration = 2.3;
+    anim.header.emote_name = "smile".into();
+    anim.joints.push(JointData {
+        name: "mHead".into(),
+        priority: 4,
+        rotation_keys: vec![
+            RotationKey {
+                time: 0,
+                rot: Quat::IDENTITY,
+            },
+            RotationKey {
+                time: 12345,
+                rot: Quat::from_rotation_y(0.3),
+            },
+            RotationKey {
+                time: u16::MAX,
+                rot: Quat::from_euler(glam::EulerRot::XYZ, 0.1, -0.2, 0.7),
+            },
+        ],
+        position_keys: vec![PositionKey {
+            time: 777,
+            pos: Vec3::new(0.0, 0.1, -0.05),
+        }],
+    });
+    anim.constraints.push(Constraint {
+        chain_length: 2,
+        source_volume: "L_HAND".into(),
+        target_volume: "R_HAND".into(),
+        ..Default::default()
+    });
+    anim
+}
+
+#[test]
+fn json_round_trips_exact_and_euler() {
+    let anim = sample();
+    let text = anim.to_json().unwrap();
+    assert!(text.contains("\"looped\": true"), "{text}");
+    assert_eq!(Animation::from_json(&text).unwrap(), anim);
+
+    let text = anim.to_json_with(RotationFormat::Euler).unwrap();
+    assert!(text.contains("\"euler\"") && !text.contains("\"quat\""));
+    let back = Animation::from_json(&text).unwrap();
+    for (a, b) in back.joints[0]
+        .rotation_keys
+        .iter()
+        .zip(&anim.joints[0].rotation_keys)
+    {
+        assert_eq!(a.time, b.time);
+        assert!(a.rot.angle_between(b.rot) < 1e-4);
+    }
+}
+
+#[test]
+fn json_hand_written_input_and_errors() {
+    let text = r#"{
+        "version": 1, "sub_version": 0, "base_priority": 3, "duration": 1.0,
+        "loop_in_point": 0.0, "loop_out_point": 1.0, "looped": false,
+        "ease_in_duration": 0.2, "ease_out_duration": 0.2, "hand_pose": 1,
+        "joints": [{"name": "mHead", "priority": 3, "rotations": [
+            {"time": 0.5, "euler": [0.0, 0.0, 90.0]}
+        ]}]
+    }"#;
+    let anim = Animation::from_json(text).unwrap();
+    assert_eq!(anim.header.looped, 0);
+    let key = &anim.joints[0].rotation_keys[0];
+    assert_eq!(key.time, 32768);
+    assert!(
+        key.rot
+            .abs_diff_eq(Quat::from_rotation_z(90f32.to_radians()), 1e-6)
+    );
+
+    let late = text.replace("\"time\": 0.5", "\"time\": 1.5");
+    let err = Animation::from_json(&late).unwrap_err();
+    assert!(err.to_string().contains("outside"), "{err}");
+    let neither = text.replace(", \"euler\": [0.0, 0.0, 90.0]", "");
+    let err = Animation::from_json(&neither).unwrap_err();
+    assert!(err.to_string().contains("exactly one"), "{err}");
+    assert!(Animation::from_json("{").is_err());
+}
+
+#[test]
+fn json_key_times_as_seconds_ticks_or_both() {
+    use avatar_anim::json::{JsonOptions, TimeFormat};
+
+    let mut anim = sample();
+    // Adjacent ticks of a long animation: seconds only round-trip to within a tick.
+    anim.joints[0].position_keys = vec![
+        PositionKey {
+            time: 40000,
+            pos: Vec3::ZERO,
+        },
+        PositionKey {
+            time: 40001,
+            pos: Vec3::X,
+        },
+    ];
+    anim.header.duration = 60.0;
+
+    let both = anim.to_json().unwrap();
+    assert!(both.contains("\"tick\": 40001") && both.contains("\"time\""));
+    assert_eq!(Animation::from_json(&both).unwrap(), anim);
+
+    let options = |times| JsonOptions {
+        times,
+        ..Default::default()
+    };
+    let ticks = anim
+        .to_json_with_options(&options(TimeFormat::Ticks))
+        .unwrap();
+    assert!(!ticks.contains("\"time\""));
+    assert_eq!(Animation::from_json(&ticks).unwrap(), anim);
+
+    let seconds = anim
+        .to_json_with_options(&options(TimeFormat::Seconds))
+        .unwrap();
+    assert!(!seconds.contains("\"tick\""));
+    let back = Animation::from_json(&seconds).unwrap();
+    let time = back.joints[0].position_keys[1].time;
+    assert!(time.abs_diff(40001) <= 1, "{time}");
+}
@@ -0,0 +1,91 @@
+use avatar_anim::legacy::LegacyQuirk;
+use avatar_anim::math::quat_from_viewer_euler;
+use avatar_anim::{Animation, JointData, RotationKey};
+use glam::{Quat, Vec3};
+
+fn header(version: (u16, u16), duration: f32) -> Vec<u8> {
+    let mut b = Vec::new();
+    b.extend_from_slice(&version.0.to_le_bytes());
+    b.extend_from_slice(&version.1.to_le_bytes());
+    b.extend_from_slice(&3i32.to_le_bytes());
+    b.extend_from_slice(&duration.to_le_bytes());
+    b.extend_from_slice(b"\0");
+    for v in [0.0f32, duration] {
+        b.extend_from_slice(&v.to_le_bytes());
+    }
+    b.extend_from_slice(&1i32.to_le_bytes());
+    for v in [0.5f32, 0.5] {
+        b.extend_from_slice(&v.to_le_bytes());
+    }
+    b.extend_from_slice(&0u32.to_le_bytes());
+    b
+}
+
+fn float_key(b: &mut Vec<u8>, values: [f32; 4]) {
+    for v in values {
+        b.extend_from_slice(&v.to_le_bytes());
+    }
+}
+
+#[test]
+fn float_keys_are_quantized_into_the_modern_layout() {
+    let mut b = header((0, 1), 2.0);
+    b.extend_from_slice(&1u32.to_le_bytes());
+    b.extend_from_slice(b"mHead\0");
+    b.extend_from_slice(&4i32.to_le_bytes());
+    b.extend_from_slice(&3i32.to_le_bytes());
+    float_key(&mut b, [0.0, 0.0, 0.0, 0.0]);
+    float_key(&mut b, [1.0, 0.0, 0.0, 90.0]);
+    float_key(&mut b, [1.5, 30.0, -20.0, 45.0]);
+    b.extend_from_slice(&1i32.to_le_bytes());
+    float_key(&mut b, [3.0, 0.0, 0.0, 0.1]);
+    // no constraint count
+
+    let legacy = Animation::read_legacy(&b).unwrap();
+    assert_eq!(legacy.version, (0, 1));
+    assert_eq!(
+        legacy.quirks,
+        vec![
+            LegacyQuirk::FloatKeys,
+            LegacyQuirk::KeyTimesOutOfRange { count: 1 },
+            LegacyQuirk::MissingConstraintCount,
+        ]
+    );
+    let anim = &legacy.animation;
+    assert_eq!((anim.header.version, anim.header.sub_version), (1, 0));
+    assert_eq!(anim.header.base_priority, 3);
+    let head = anim.joint("mHead").unwrap();
+    assert_eq!(head.priority, 4);
+    assert_eq!(head.rotation_keys[1].time, 32768);
+    let expected = Quat::from_rotation_z(std::f32::consts::FRAC_PI_2);
+    assert!(head.rotation_keys[1].rot.dot(expected).abs() > 0.99999);
+    // Multi-axis keys use the viewer's Euler order, not glam's XYZ.
+    let expected = quat_from_viewer_euler(Vec3::new(30.0, -20.0, 45.0));
+    assert!(head.rotation_keys[2].rot.dot(expected).abs() > 0.99999);
+    assert_eq!(head.position_keys[0].time, u16::MAX);
+    assert!((head.position_keys[0].pos.z - 0.1).abs() < 1e-6);
+
+    let modern = Animation::from_bytes(&anim.to_bytes().unwrap()).unwrap();
+    assert_eq!(modern.joints.len(), 1);
+}
+
+#[test]
+fn modern_files_read_without_quirks() {
+    let mut anim = Animation::new();
+    anim.joints.push(JointData {
+        name: "mPelvis".into(),
+        rotation_keys: vec![RotationKey {
+            time: 100,
+            rot: Quat::IDENTITY,
+        }],
+        ..Default::default()
+    });
+    let legacy = Animation::read_legacy(&anim.to_bytes().unwrap()).unwrap();
+    assert!(!legacy.is_legacy());
+    assert_eq!(legacy.animation, anim);
+}
+
+#[test]
+fn unknown_versions_are_rejected() {
+    assert!(Animation::read_legacy(&header((2, 0), 1.0)).is_err());
+}
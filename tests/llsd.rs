@@ -0,0 +1,131 @@
+#![cfg(feature = "llsd")]
+
+use avatar_anim::{Animation, JointData, Pose, PositionKey, RotationKey};
+use glam::{EulerRot, Quat, Vec3};
+
+#[test]
+fn to_llsd_writes_final_pose_for_the_poser() {
+    let lifted = Quat::from_euler(EulerRot::XYZ, 0.1, -0.4, 0.25);
+    let mut anim = Animation::new();
+    anim.joints.push(JointData {
+        name: "mShoulderLeft".into(),
+        rotation_keys: vec![
+            RotationKey {
+                time: 0,
+                rot: Quat::IDENTITY,
+            },
+            RotationKey {
+                time: u16::MAX,
+                rot: lifted,
+            },
+        ],
+        ..Default::default()
+    });
+    anim.joints.push(JointData {
+        name: "mPelvis".into(),
+        position_keys: vec![PositionKey {
+            time: 0,
+            pos: Vec3::new(0.0, 0.0, -0.3),
+        }],
+        ..Default::default()
+    });
+
+    let llsd = anim.to_llsd();
+    let map = llsd.as_map().unwrap();
+    assert_eq!(map.len(), 2);
+    assert!(map["mPelvis"].as_map().unwrap().get("rotation").is_none());
+
+    let path = std::env::temp_dir().join(format!("avatar_anim_pose_{}.xml", std::process::id()));
+    anim.to_llsd_file(&path).unwrap();
+    let back = Animation::from_llsd_file(&path, true).unwrap();
+    std::fs::remove_file(&path).ok();
+
+    let shoulder = back.joint("mShoulderLeft").unwrap();
+    assert_eq!(shoulder.rotation_keys.len(), 1);
+    assert!(shoulder.rotation_keys[0].rot.abs_diff_eq(lifted, 1e-6));
+    let pelvis = back.joint("mPelvis").unwrap();
+    assert!(pelvis.rotation_keys.is_empty());
+    assert_eq!(pelvis.position_keys[0].pos, Vec3::new(0.0, 0.0, -0.3));
+}
+
+#[test]
+fn binary_pose_files_round_trip() {
+    let turned = Quat::from_euler(EulerRot::XYZ, 0.0, 0.3, -0.2);
+    let mut anim = Animation::new();
+    anim.joints.push(JointData {
+        name: "mHead".into(),
+        rotation_keys: vec![RotationKey {
+            time: 0,
+            rot: turned,
+        }],
+        ..Default::default()
+    });
+
+    let bytes = anim.to_llsd_binary().unwrap();
+    assert!(bytes.starts_with(b"<? LLSD/Binary ?>\n{"));
+    let dir = std::env::temp_dir();
+    let pid = std::process::id();
+    let with_header = dir.join(format!("avatar_anim_pose_{pid}.llsd"));
+    let bare = dir.join(format!("avatar_anim_pose_bare_{pid}.llsd"));
+    anim.to_llsd_binary_file(&with_header).unwrap();
+    std::fs::write(&bare, &bytes[b"<? LLSD/Binary ?>\n".len()..]).unwrap();
+    for path in [&with_header, &bare] {
+        let back = Animation::from_llsd_file(path, true).unwrap();
+        std::fs::remove_file(path).ok();
+        let head = back.joint("mHead").unwrap();
+        assert!(head.rotation_keys[0].rot.abs_diff_eq(turned, 1e-6));
+    }
+}
+
+#[test]
+fn pose_round_trips_through_llsd() {
+    let mut pose = Pose::new();
+    pose.set_rotation("mHead", Quat::from_euler(EulerRot::XYZ, 0.2, 0.1, -0.3))
+        .set_position("mPelvis", Vec3::new(0.1, 0.0, 0.9));
+    let back = Pose::from_llsd(&pose.to_llsd(), false).unwrap();
+    assert_eq!(back.len(), 2);
+    let head = back.rotation("mHead").unwrap();
+    assert!(
+        head.abs_diff_eq(pose.rotation("mHead").unwrap(), 1e-4),
+        "{head}"
+    );
+    let pelvis = back.position("mPelvis").unwrap();
+    assert!(pelvis.abs_diff_eq(Vec3::new(0.1, 0.0, 0.9), 1e-4));
+}
+
+#[test]
+fn poser_rotations_apply_roll_first() {
+    use avatar_anim::math::{euler_for_viewer, quat_from_viewer_euler};
+    use llsd_rs::Llsd;
+    use std::collections::HashMap;
+
+    // A head rolled by 30° and then turned by 40° about the up axis.
+    let (roll, yaw) = (30f32.to_radians(), 40f32.to_radians());
+    let expected = Quat::from_rotation_z(yaw) * Quat::from_rotation_x(roll);
+    let entry = HashMap::from([
+        ("enabled".to_string(), Llsd::Boolean(true)),
+        (
+            "rotation".to_string(),
+            Llsd::Array(vec![
+                Llsd::Real(roll as f64),
+                Llsd::Real(0.0),
+                Llsd::Real(yaw as f64),
+            ]),
+        ),
+    ]);
+    let llsd = Llsd::Map(HashMap::from([("mHead".to_string(), Llsd::Map(entry))]));
+    let anim = Animation::from_llsd(&llsd, true).unwrap();
+    let rot = anim.joint("mHead").unwrap().rotation_keys[0].rot;
+    assert!(rot.abs_diff_eq(expected, 1e-6), "{rot}");
+
+    assert!(euler_for_viewer(rot).abs_diff_eq(Vec3::new(30.0, 0.0, 40.0), 1e-3));
+    assert!(quat_from_viewer_euler(Vec3::new(30.0, 0.0, 40.0)).abs_diff_eq(expected, 1e-6));
+    let written = anim.to_llsd();
+    let rotation = written.as_map().unwrap()["mHead"].as_map().unwrap()["rotation"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|v| *v.as_real().unwrap() as f32)
+        .collect::<Vec<_>>();
+    assert!((rotation[0] - roll).abs() < 1e-5 && (rotation[2] - yaw).abs() < 1e-5);
+}
@@ -0,0 +1,70 @@
+use avatar_anim::Animation;
+use avatar_anim::manifest::{self, ManifestEntry, Slot};
+
+#[test]
+fn slot_suggestion_prefers_specific_keywords() {
+    let anim = Animation::default();
+    assert_eq!(Slot::suggest("My GSit 3", &anim), Slot::GroundSit);
+    assert_eq!(Slot::suggest("sit_chair", &anim), Slot::Sit);
+    assert_eq!(Slot::suggest("crouch-walk", &anim), Slot::CrouchWalk);
+    assert_eq!(Slot::suggest("GroundSit2", &anim), Slot::GroundSit);
+    assert_eq!(Slot::suggest("MaleWalking", &anim), Slot::Walk);
+    assert_eq!(Slot::suggest("sexy_dancing 3", &anim), Slot::Dance);
+    // Keywords inside other words don't count.
+    for name in ["position", "island_dance", "return", "prune"] {
+        let slot = Slot::suggest(name, &anim);
+        assert!(
+            ![Slot::Sit, Slot::Land, Slot::Turn, Slot::Run].contains(&slot),
+            "{name}: {slot:?}"
+        );
+    }
+    assert_eq!(Slot::suggest("island_dance", &anim), Slot::Dance);
+    let mut gesture = Animation::default();
+    gesture.header.looped = 0;
+    assert_eq!(Slot::suggest("wave", &gesture), Slot::Gesture);
+}
+
+#[test]
+fn manifest_renders_json_and_notecard() {
+    let mut anim = Animation::default();
+    anim.set_priority(4);
+    let entry = ManifestEntry::new("stand \"1\"", &anim);
+    assert_eq!(entry.fingerprint, anim.fingerprint());
+    let json = manifest::to_json(std::slice::from_ref(&entry));
+    assert!(json.contains("\"name\": \"stand \\\"1\\\"\""));
+    assert!(json.contains("\"slot\": \"stand\""));
+    let card = manifest::to_notecard(&[entry]);
+    assert!(
+        card.lines()
+            .nth(1)
+            .unwrap()
+            .starts_with("stand \"1\"|0.0170|4|1|stand|")
+    );
+}
+
+#[test]
+fn lsl_templates() {
+    use avatar_anim::manifest::LslTemplate;
+    let anim = Animation::default();
+    let entries = [
+        ManifestEntry::new("walk 1", &anim),
+        ManifestEntry::new("stand", &anim),
+        ManifestEntry::new("walk 2", &anim),
+    ];
+    let ao = manifest::to_lsl(&entries, LslTemplate::Ao);
+    assert!(ao.contains("integer ANIM_STRIDE = 3;\n"));
+    assert!(ao.contains(
+        "list WALK_ANIMS = [\n    \"walk 1\", 0.0170, TRUE,\n    \"walk 2\", 0.0170, TRUE\n];"
+    ));
+    assert!(ao.contains("list STAND_ANIMS = [\n    \"stand\", 0.0170, TRUE\n];"));
+    let hud = manifest::to_lsl(&entries, LslTemplate::DanceHud);
+    assert!(hud.contains("    \"stand\", 0.0170, TRUE,\n"));
+
+    let mut broken = ManifestEntry::new("spin", &anim);
+    broken.duration = f32::NAN;
+    let hud = manifest::to_lsl(&[broken], LslTemplate::DanceHud);
+    assert!(
+        hud.contains("    \"spin\", 0.0000, TRUE // invalid duration NaN\n"),
+        "{hud}"
+    );
+}
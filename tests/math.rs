@@ -0,0 +1,126 @@
+use avatar_anim::math;
+use glam::{Quat, Vec3};
+
+#[test]
+fn log_exp_round_trip_and_sign_invariance() {
+    let q = Quat::from_axis_angle(Vec3::new(1.0, 2.0, -0.5).normalize(), 2.5);
+    assert!(math::exp(math::log(q)).abs_diff_eq(q, 1e-5));
+    assert!(math::log(-q).abs_diff_eq(math::log(q), 1e-6));
+    assert_eq!(math::log(Quat::IDENTITY), Vec3::ZERO);
+
+    let a = Quat::from_rotation_z(0.3);
+    let b = Quat::from_rotation_z(0.3 + 1e-3);
+    assert!((math::geodesic_distance(a, -b) - 1e-3).abs() < 1e-5);
+    // 3.2 rad the long way round is 2π - 3.2 the short way.
+    let far = math::geodesic_distance(a, Quat::from_rotation_z(-2.9));
+    assert!((far - (std::f32::consts::TAU - 3.2)).abs() < 1e-4);
+
+    let w = math::angular_velocity(a, -b, 0.5);
+    assert!(w.abs_diff_eq(Vec3::Z * 2e-3, 1e-5));
+}
+
+#[test]
+fn twist_edits_leave_swing_alone() {
+    use avatar_anim::{JointData, RotationKey};
+
+    let swing = Quat::from_rotation_z(0.6);
+    let mut joint = JointData {
+        name: "mElbowRight".into(),
+        rotation_keys: [-2.8f32, 0.5, 2.8]
+            .iter()
+            .enumerate()
+            .map(|(i, &roll)| RotationKey {
+                time: i as u16 * 100,
+                rot: swing * Quat::from_rotation_x(roll),
+            })
+            .collect(),
+        ..Default::default()
+    };
+    joint.limit_twist(Vec3::X, 60.0);
+    let limit = 60f32.to_radians();
+    for (key, roll) in joint.rotation_keys.iter().zip([-limit, 0.5, limit]) {
+        let expected = swing * Quat::from_rotation_x(roll);
+        assert!(math::geodesic_distance(key.rot, expected) < 1e-4);
+    }
+
+    joint.scale_swing(Vec3::X, 0.0);
+    let (s, _) = math::swing_twist(joint.rotation_keys[1].rot, Vec3::X);
+    assert!(math::geodesic_distance(s, Quat::IDENTITY) < 1e-4);
+    assert!(math::geodesic_distance(joint.rotation_keys[1].rot, Quat::from_rotation_x(0.5)) < 1e-4);
+}
+
+#[test]
+fn frame_conversions_and_bvh_orders() {
+    use avatar_anim::frame::{Frame, RotationOrder, convert_rot, convert_vec};
+
+    let forward = Vec3::X;
+    assert!(
+        Frame::BlenderZUp
+            .vec_from_sl(forward)
+            .abs_diff_eq(Vec3::NEG_Y, 1e-6)
+    );
+    assert!(
+        Frame::MayaYUp
+            .vec_from_sl(Vec3::Z)
+            .abs_diff_eq(Vec3::Y, 1e-6)
+    );
+    let p = Vec3::new(0.1, -0.2, 1.1);
+    let there = convert_vec(p, Frame::SecondLife, Frame::MayaYUp);
+    assert!(convert_vec(there, Frame::MayaYUp, Frame::SecondLife).abs_diff_eq(p, 1e-6));
+
+    // A rotation moves vectors the same way in every frame.
+    let q = Quat::from_euler(glam::EulerRot::XYZ, 0.3, -0.7, 1.2);
+    let maya = convert_rot(q, Frame::SecondLife, Frame::MayaYUp);
+    let moved = Frame::MayaYUp.vec_to_sl(maya * Frame::MayaYUp.vec_from_sl(p));
+    assert!(moved.abs_diff_eq(q * p, 1e-5));
+
+    let order =
+        RotationOrder::from_bvh_channels(&["Xposition", "Zrotation", "Xrotation", "Yrotation"]);
+    assert_eq!(order, Some(RotationOrder::Zxy));
+    let order = order.unwrap();
+    assert_eq!(
+        order.bvh_channels(),
+        ["Zrotation", "Xrotation", "Yrotation"]
+    );
+    let expected =
+        Quat::from_rotation_z(0.2) * Quat::from_rotation_x(0.4) * Quat::from_rotation_y(-0.6);
+    let angles = [0.2f32, 0.4, -0.6].map(f32::to_degrees);
+    assert!(order.compose(angles).abs_diff_eq(expected, 1e-5));
+    let back = order.decompose(expected);
+    for (a, b) in back.iter().zip(angles) {
+        assert!((a - b).abs() < 1e-3);
+    }
+    assert_eq!(
+        RotationOrder::from_bvh_channels(&["Xrotation", "Xrotation", "Yrotation"]),
+        None
+    );
+}
+
+#[test]
+fn average_quats_ignores_sign_and_order() {
+    use avatar_anim::{Animation, DuplicateKeyStrategy, JointData, RotationKey};
+
+    let q = [
+        Quat::from_rotation_y(0.1),
+        -Quat::from_rotation_y(0.3),
+        Quat::from_rotation_y(0.5),
+        -Quat::from_rotation_y(0.7),
+    ];
+    let mean = math::average_quats(&q);
+    assert!(math::geodesic_distance(mean, Quat::from_rotation_y(0.4)) < 1e-4);
+    let reversed: Vec<Quat> = q.iter().rev().copied().collect();
+    assert!(math::geodesic_distance(math::average_quats(&reversed), mean) < 1e-5);
+    assert_eq!(math::average_quats(&[]), Quat::IDENTITY);
+
+    // Duplicate keys that alternate sign average to the middle rotation.
+    let mut anim = Animation::default();
+    anim.joints.push(JointData {
+        name: "mChest".into(),
+        rotation_keys: q.iter().map(|&rot| RotationKey { time: 5, rot }).collect(),
+        ..Default::default()
+    });
+    anim.cleanup_keys_with(DuplicateKeyStrategy::Average);
+    let keys = &anim.joints[0].rotation_keys;
+    assert_eq!(keys.len(), 1);
+    assert!(math::geodesic_distance(keys[0].rot, Quat::from_rotation_y(0.4)) < 1e-4);
+}
@@ -0,0 +1,132 @@
+#![cfg(all(feature = "llsd", feature = "gzip"))]
+
+use avatar_anim::{Animation, SourceFormat, open_any};
+use binrw::BinWrite;
+use std::io::{Cursor, Write};
+
+const POSE_XML: &str = r#"<?xml version="1.0" ?>
+<llsd><map>
+  <key>mHead</key><map>
+    <key>enabled</key><boolean>1</boolean>
+    <key>rotation</key><array><real>0.1</real><real>0.2</real><real>0.3</real></array>
+  </map>
+  <key>mNeck</key><map>
+    <key>enabled</key><boolean>0</boolean>
+    <key>rotation</key><array><real>0.5</real><real>0</real><real>0</real></array>
+  </map>
+</map></llsd>"#;
+
+fn gzip(bytes: &[u8]) -> Vec<u8> {
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(bytes).unwrap();
+    encoder.finish().unwrap()
+}
+
+#[test]
+fn open_any_sniffs_llsd_variants() {
+    let llsd = llsd_rs::xml::from_str(POSE_XML).unwrap();
+    let expected = Animation::from_llsd(&llsd, true).unwrap();
+    assert_eq!(expected.joints.len(), 1);
+
+    let notation =
+        llsd_rs::notation::to_string(&llsd, &llsd_rs::notation::FormatterContext::new()).unwrap();
+    let binary = llsd_rs::binary::to_vec(&llsd).unwrap();
+    let mut headed = b"<? LLSD/Binary ?>\n".to_vec();
+    headed.extend_from_slice(&binary);
+
+    let cases = [
+        (POSE_XML.as_bytes().to_vec(), SourceFormat::LlsdXml),
+        (notation.into_bytes(), SourceFormat::LlsdNotation),
+        (binary, SourceFormat::LlsdBinary),
+        (headed.clone(), SourceFormat::LlsdBinary),
+        (gzip(&headed), SourceFormat::LlsdBinary),
+    ];
+    for (bytes, format) in cases {
+        let (anim, detected) = open_any(&bytes).unwrap();
+        assert_eq!(detected, format);
+        assert_eq!(anim, expected);
+    }
+}
+
+#[test]
+fn open_any_reads_anim_files_and_buffers() {
+    let mut anim = Animation::new();
+    anim.set_priority(4);
+    let mut buf = Cursor::new(Vec::new());
+    anim.write(&mut buf).unwrap();
+    let bytes = buf.into_inner();
+
+    assert_eq!(
+        open_any(&bytes).unwrap(),
+        (anim.clone(), SourceFormat::Anim)
+    );
+    let path =
+        std::env::temp_dir().join(format!("avatar_anim_open_{}.anim.gz", std::process::id()));
+    std::fs::write(&path, gzip(&bytes)).unwrap();
+    assert_eq!(open_any(&path).unwrap(), (anim, SourceFormat::Anim));
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(open_any(b"HIERARCHY\nROOT hip".as_slice()).is_err());
+}
+
+#[test]
+fn gzip_output_is_bounded() {
+    use avatar_anim::{AnimError, Limit, ReadOptions};
+
+    let max = ReadOptions::default().max_bytes;
+    let bomb = gzip(&vec![0; max + 1]);
+    let err = open_any(&bomb).unwrap_err();
+    assert!(matches!(
+        err,
+        AnimError::LimitExceeded {
+            limit: Limit::Size,
+            ..
+        }
+    ));
+}
+
+#[test]
+fn every_supported_format_round_trips() {
+    use avatar_anim::{JointData, RotationKey, open_as};
+    use glam::Quat;
+
+    let mut anim = Animation::new();
+    anim.header.duration = 1.0;
+    anim.joints.push(JointData {
+        name: "mHead".into(),
+        rotation_keys: vec![RotationKey {
+            time: u16::MAX,
+            rot: Quat::from_rotation_x(0.4),
+        }],
+        ..Default::default()
+    });
+    for format in SourceFormat::ALL {
+        assert_eq!(SourceFormat::from_name(format.name()), Some(format));
+        if !format.is_supported() {
+            assert!(anim.to_format(format).is_err());
+            continue;
+        }
+        let bytes = anim.to_format(format).unwrap();
+        if format.is_export_only() {
+            assert!(open_as(&bytes, format).is_err());
+            continue;
+        }
+        let back = open_as(&bytes, format).unwrap();
+        let head = back.joint("mHead").unwrap();
+        let rot = head.rotation_at(u16::MAX as f32).unwrap();
+        assert!(
+            rot.angle_between(Quat::from_rotation_x(0.4)) < 0.01,
+            "{}",
+            format.name()
+        );
+    }
+    assert_eq!(
+        SourceFormat::from_extension("walk.BVH.gz".as_ref()),
+        Some(SourceFormat::Bvh)
+    );
+    assert_eq!(
+        SourceFormat::from_extension("walk.dae".as_ref()),
+        Some(SourceFormat::Collada)
+    );
+    assert_eq!(SourceFormat::from_extension("walk".as_ref()), None);
+}
@@ -0,0 +1,109 @@
+use avatar_anim::{Animation, JointData, PositionKey, RotationKey};
+use glam::{Quat, Vec3};
+
+fn dense_joint(name: &str, keys: u16) -> JointData {
+    let step = u16::MAX / (keys - 1);
+    JointData {
+        name: name.into(),
+        rotation_keys: (0..keys)
+            .map(|i| RotationKey {
+                time: i * step,
+                rot: Quat::from_rotation_z((i as f32 * 0.3).sin()),
+            })
+            .collect(),
+        position_keys: (0..keys)
+            .map(|i| PositionKey {
+                time: i * step,
+                pos: Vec3::new(0.0, 0.0, i as f32 * 0.01),
+            })
+            .collect(),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn reduce_keys_drops_linear_positions() {
+    let mut joint = dense_joint("mPelvis", 50);
+    joint.reduce_keys(1e-3);
+    assert_eq!(joint.position_keys.len(), 2);
+    assert!(joint.rotation_keys.len() > 2);
+}
+
+#[test]
+fn key_budget_only_touches_joints_over_budget() {
+    let mut anim = Animation::default();
+    anim.joints.push(dense_joint("mPelvis", 120));
+    anim.joints.push(dense_joint("mHead", 8));
+    anim.enforce_key_budget(20, 1e-4);
+    let pelvis = anim.joint("mPelvis").unwrap();
+    assert!(pelvis.key_count() <= 20);
+    assert_eq!(pelvis.rotation_keys.first().unwrap().time, 0);
+    assert_eq!(anim.joint("mHead").unwrap().key_count(), 16);
+}
+
+#[test]
+fn adaptive_reduction_spends_keys_on_curvature() {
+    use avatar_anim::ReductionMode;
+    // A near-linear sweep with a small wobble that snaps back sharply near the end.
+    let joint = JointData {
+        name: "mHead".into(),
+        rotation_keys: (0..=100u16)
+            .map(|i| {
+                let t = i as f32 / 100.0;
+                let angle = if t < 0.8 {
+                    t + 0.012 * (t * 20.0).sin()
+                } else {
+                    0.8 - (t - 0.8) * 3.0
+                };
+                RotationKey {
+                    time: i * 600,
+                    rot: Quat::from_rotation_y(angle),
+                }
+            })
+            .collect(),
+        ..Default::default()
+    };
+    let mut fixed = joint.clone();
+    fixed.reduce_keys_with(0.01, ReductionMode::Fixed);
+    let mut adaptive = joint.clone();
+    adaptive.reduce_keys_with(0.01, ReductionMode::Adaptive);
+    assert!(adaptive.rotation_keys.len() < fixed.rotation_keys.len());
+    assert!(adaptive.rotation_keys.iter().any(|k| k.time == 80 * 600));
+}
+
+#[test]
+fn prune_drops_only_interpolatable_keys() {
+    let mut joint = dense_joint("mPelvis", 50);
+    // A kink in the middle of the linear position track must survive.
+    joint.position_keys[25].pos.x = 0.2;
+    let rotations = joint.rotation_keys.clone();
+    let removed = joint.prune_redundant_keys(1e-4);
+    let times: Vec<u16> = joint.position_keys.iter().map(|k| k.time).collect();
+    let step = u16::MAX / 49;
+    assert_eq!(times, [0, 24 * step, 25 * step, 26 * step, 49 * step]);
+    assert_eq!(removed, 45 + rotations.len() - joint.rotation_keys.len());
+    for key in &rotations {
+        let rot = joint.rotation_at(key.time as f32).unwrap();
+        assert!(rot.angle_between(key.rot) < 1e-3);
+    }
+}
+
+#[test]
+fn lod_variants_match_separate_reductions() {
+    let mut anim = Animation::default();
+    anim.joints.push(dense_joint("mPelvis", 90));
+    anim.joints.push(dense_joint("mHead", 2));
+    let tolerances = [1e-4, 1e-3, 1e-2, 0.1];
+    let variants = anim.lod_variants(&tolerances);
+    assert_eq!(variants.len(), tolerances.len());
+    for (variant, &tolerance) in variants.iter().zip(&tolerances) {
+        let mut reduced = anim.clone();
+        reduced.reduce_keys(tolerance);
+        assert_eq!(variant, &reduced, "tolerance {tolerance}");
+    }
+    let counts: Vec<usize> = variants.iter().map(|v| v.joints[0].key_count()).collect();
+    assert!(
+        counts.windows(2).all(|pair| pair[0] >= pair[1]),
+        "{counts:?}"
+    );
+}
@@ -0,0 +1,634 @@
+use avatar_anim::{Animation, JointData, Pose, PositionKey, RotationKey, WrapMode};
+use glam::Quat;
+use glam::Vec3;
+
+fn looped_anim() -> Animation {
+    let mut anim = Animation::default();
+    anim.header.duration = 4.0;
+    anim.header.loop_in_point = 1.0;
+    anim.header.loop_out_point = 3.0;
+    anim.joints.push(JointData {
+        name: "mPelvis".into(),
+        position_keys: vec![
+            PositionKey {
+                time: 0,
+                pos: Vec3::ZERO,
+            },
+            PositionKey {
+                time: u16::MAX,
+                pos: Vec3::new(4.0, 0.0, 0.0),
+            },
+        ],
+        ..Default::default()
+    });
+    anim
+}
+
+#[test]
+fn wrap_modes_map_time_into_range() {
+    let anim = looped_anim();
+    assert_eq!(anim.wrap_time(5.0, WrapMode::Clamp), 4.0);
+    assert_eq!(anim.wrap_time(0.5, WrapMode::Loop), 0.5);
+    assert_eq!(anim.wrap_time(3.5, WrapMode::Loop), 1.5);
+    assert_eq!(anim.wrap_time(3.5, WrapMode::PingPong), 2.5);
+    assert_eq!(anim.wrap_time(5.5, WrapMode::PingPong), 1.5);
+}
+
+#[test]
+fn unusable_loop_points_clamp_instead_of_looping() {
+    let mut anim = looped_anim();
+    anim.header.loop_in_point = f32::NAN;
+    assert_eq!(anim.wrap_time(5.0, WrapMode::Loop), 4.0);
+    assert!(anim.sample(0.5).position("mPelvis").is_some());
+    let bytes = anim.to_bytes().unwrap();
+    let read = Animation::from_bytes(&bytes).unwrap();
+    assert!(
+        read.sample_joint("mPelvis", 3.5, WrapMode::PingPong)
+            .is_some()
+    );
+
+    anim.header.loop_in_point = 3.0;
+    anim.header.loop_out_point = 1.0;
+    assert_eq!(anim.wrap_time(3.5, WrapMode::Loop), 3.5);
+}
+
+#[test]
+fn sample_joint_interpolates_positions() {
+    let anim = looped_anim();
+    let sample = anim.sample_joint("mPelvis", 2.0, WrapMode::Clamp).unwrap();
+    assert!(sample.rotation.is_none());
+    let pos = sample.position.unwrap();
+    assert!((pos.x - 2.0).abs() < 1e-3, "unexpected position {pos}");
+    let looped = anim.sample_joint("mPelvis", 4.0, WrapMode::Loop).unwrap();
+    assert!((looped.position.unwrap().x - 2.0).abs() < 1e-3);
+}
+
+#[test]
+fn time_warp_retimes_keys_and_header() {
+    use avatar_anim::{Animation, JointData, RotationKey};
+    use glam::Quat;
+
+    let mut anim = Animation::new();
+    anim.header.duration = 2.0;
+    anim.header.loop_in_point = 1.0;
+    anim.header.loop_out_point = 2.0;
+    anim.joints.push(JointData {
+        name: "mHead".into(),
+        rotation_keys: vec![
+            RotationKey {
+                time: 0,
+                rot: Quat::IDENTITY,
+            },
+            RotationKey {
+                time: u16::MAX,
+                rot: Quat::from_rotation_z(1.0),
+            },
+        ],
+        ..Default::default()
+    });
+    let original = anim.clone();
+
+    // First second plays twice as slowly, the second one twice as fast.
+    anim.time_warp(&[(0.0, 0.0), (1.0, 2.0), (2.0, 2.5)])
+        .unwrap();
+    assert_eq!(anim.header.duration, 2.5);
+    assert_eq!(anim.header.loop_in_point, 2.0);
+    assert_eq!(anim.header.loop_out_point, 2.5);
+    let keys = &anim.joints[0].rotation_keys;
+    assert_eq!(keys.len(), 3, "breakpoint inside the track becomes a key");
+    for (warped, input) in [(0.5f32, 0.25f32), (2.0, 1.0), (2.25, 1.5)] {
+        let a = anim
+            .sample_joint("mHead", warped, Default::default())
+            .unwrap();
+        let b = original
+            .sample_joint("mHead", input, Default::default())
+            .unwrap();
+        assert!(a.rotation.unwrap().angle_between(b.rotation.unwrap()) < 1e-3);
+    }
+
+    let before = anim.clone();
+    assert!(anim.time_warp(&[(0.0, 0.0), (1.0, 0.0)]).is_err());
+    assert!(anim.time_warp(&[(0.0, 0.0)]).is_err());
+    assert_eq!(anim, before);
+}
+
+#[test]
+fn sync_to_beats_moves_extremes_onto_the_grid() {
+    use avatar_anim::{Animation, JointData, RotationKey};
+    use glam::Quat;
+
+    // Swing out and back: the motion settles at 0.9s, where the arm turns around.
+    let mut anim = Animation::new();
+    anim.header.duration = 2.0;
+    anim.joints.push(JointData {
+        name: "mShoulderLeft".into(),
+        rotation_keys: (0..=20u16)
+            .map(|i| {
+                let t = i as f32 * 0.1;
+                let angle = (std::f32::consts::PI * t / 1.8).sin();
+                RotationKey {
+                    time: (t / 2.0 * u16::MAX as f32).round() as u16,
+                    rot: Quat::from_rotation_y(angle),
+                }
+            })
+            .collect(),
+        ..Default::default()
+    });
+    let peak = |anim: &Animation| {
+        (0..=200)
+            .map(|i| i as f32 * anim.header.duration / 200.0)
+            .max_by(|&a, &b| {
+                let angle = |t| {
+                    let s = anim
+                        .sample_joint("mShoulderLeft", t, Default::default())
+                        .unwrap();
+                    s.rotation.unwrap().angle_between(Quat::IDENTITY)
+                };
+                angle(a).total_cmp(&angle(b))
+            })
+            .unwrap()
+    };
+    assert!((peak(&anim) - 0.9).abs() < 0.05);
+
+    anim.sync_to_beats(&[0.5, 1.0, 1.5, 2.0]).unwrap();
+    assert!((peak(&anim) - 1.0).abs() < 0.05, "peak at {}", peak(&anim));
+}
+
+#[test]
+fn loop_subset_loops_selected_joints_and_freezes_the_rest() {
+    use avatar_anim::RotationKey;
+    use glam::Quat;
+
+    let swing = |name: &str| JointData {
+        name: name.into(),
+        rotation_keys: (0..=4)
+            .map(|i| RotationKey {
+                time: (i * u16::MAX as u32 / 4) as u16,
+                rot: Quat::from_rotation_z(i as f32 * 0.25),
+            })
+            .collect(),
+        ..Default::default()
+    };
+    let mut anim = Animation::new();
+    anim.header.duration = 4.0;
+    anim.joints.push(swing("mTail1"));
+    anim.joints.push(swing("mHead"));
+    let original = anim.clone();
+
+    anim.loop_subset(|j| j.name.starts_with("mTail"), 1.5..3.5)
+        .unwrap();
+    assert_eq!(anim.header.duration, 2.0);
+    assert_eq!(anim.header.looped, 1);
+    assert_eq!(
+        (anim.header.loop_in_point, anim.header.loop_out_point),
+        (0.0, 2.0)
+    );
+    let tail = &anim.joints[0].rotation_keys;
+    assert_eq!(tail.len(), 4, "region ends plus the two keys inside");
+    for t in [0.0f32, 0.5, 1.7, 2.0] {
+        let a = anim.sample_joint("mTail1", t, Default::default()).unwrap();
+        let b = original
+            .sample_joint("mTail1", t + 1.5, Default::default())
+            .unwrap();
+        assert!(a.rotation.unwrap().angle_between(b.rotation.unwrap()) < 1e-3);
+    }
+    let head = &anim.joints[1].rotation_keys;
+    assert_eq!(head.len(), 1);
+    assert!(head[0].rot.angle_between(Quat::from_rotation_z(0.375)) < 1e-3);
+
+    let before = anim.clone();
+    assert!(anim.loop_subset(|_| true, 1.0..3.0).is_err());
+    assert!(anim.loop_subset(|_| true, 1.0..1.0).is_err());
+    assert_eq!(anim, before);
+}
+
+#[test]
+fn pose_at_freezes_one_frame() {
+    let mut anim = looped_anim();
+    anim.header.looped = 0;
+    let pose = anim.pose_at(1.0);
+    let joint = pose.joint("mPelvis").unwrap();
+    assert!(joint.rotation_keys.is_empty());
+    assert_eq!(joint.position_keys.len(), 1);
+    assert!(
+        joint.position_keys[0]
+            .pos
+            .abs_diff_eq(Vec3::new(1.0, 0.0, 0.0), 1e-3)
+    );
+    // Past the end of a one-shot animation holds the last frame.
+    let last = anim.pose_at(10.0);
+    assert!(
+        last.joints[0].position_keys[0]
+            .pos
+            .abs_diff_eq(Vec3::new(4.0, 0.0, 0.0), 1e-3)
+    );
+
+    // Looping animations wrap like `sample` does.
+    let mut looped = anim.clone();
+    looped.header.looped = 1;
+    let wrapped = looped.pose_at(3.5);
+    assert_eq!(wrapped.header, looped.header);
+    let expected = looped.sample(3.5).to_animation();
+    assert_eq!(wrapped.joints[0].priority, looped.joints[0].priority);
+    assert_eq!(
+        wrapped.joints[0].position_keys,
+        expected.joints[0].position_keys
+    );
+}
+
+#[test]
+fn sample_respects_looping() {
+    let mut anim = looped_anim();
+    anim.header.looped = 1;
+    // 3.5 s wraps to 1.5 s inside the 1..3 loop.
+    let pose = anim.sample(3.5);
+    assert_eq!(pose.len(), 1);
+    assert!(pose.rotation("mPelvis").is_none());
+    let pos = pose.position("mPelvis").unwrap();
+    assert!(pos.abs_diff_eq(Vec3::new(1.5, 0.0, 0.0), 1e-3), "{pos}");
+
+    anim.header.looped = 0;
+    let pos = anim.sample(3.5).position("mPelvis").unwrap();
+    assert!(pos.abs_diff_eq(Vec3::new(3.5, 0.0, 0.0), 1e-3), "{pos}");
+    assert!(anim.sample(3.5).get("mHead").is_none());
+}
+
+#[test]
+fn pose_converts_to_and_from_a_still_animation() {
+    let anim = looped_anim();
+    let pose = Pose::from_animation(&anim);
+    let pos = pose.position("mPelvis").unwrap();
+    assert!(pos.abs_diff_eq(Vec3::new(4.0, 0.0, 0.0), 1e-3), "{pos}");
+
+    let still = pose.to_animation();
+    let joint = still.joint("mPelvis").unwrap();
+    assert!(joint.rotation_keys.is_empty());
+    assert_eq!(joint.position_keys.len(), 1);
+    assert_eq!(joint.position_keys[0].time, 0);
+    assert_eq!(Pose::from_animation(&still), pose);
+}
+
+#[test]
+fn pose_difference_composes_back() {
+    let mut base = Pose::new();
+    base.set_rotation("mHead", Quat::from_rotation_y(0.4))
+        .set_position("mPelvis", Vec3::new(0.0, 0.0, 1.0));
+    let mut target = Pose::new();
+    target
+        .set_rotation("mHead", Quat::from_rotation_x(-0.3))
+        .set_rotation("mNeck", Quat::from_rotation_z(0.2))
+        .set_position("mPelvis", Vec3::new(0.5, 0.0, 1.2));
+
+    let delta = target.difference(&base);
+    let rebuilt = base.compose(&delta);
+    for joint in ["mHead", "mNeck"] {
+        let (got, want) = (
+            rebuilt.rotation(joint).unwrap(),
+            target.rotation(joint).unwrap(),
+        );
+        assert!(got.abs_diff_eq(want, 1e-5), "{joint}: {got} != {want}");
+    }
+    let pos = rebuilt.position("mPelvis").unwrap();
+    assert!(pos.abs_diff_eq(Vec3::new(0.5, 0.0, 1.2), 1e-6));
+
+    let identity = base.compose(&base.inverse());
+    assert!(
+        identity
+            .rotation("mHead")
+            .unwrap()
+            .abs_diff_eq(Quat::IDENTITY, 1e-6)
+    );
+    assert_eq!(identity.position("mPelvis"), Some(Vec3::ZERO));
+}
+
+#[test]
+fn resample_keys_at_a_fixed_rate() {
+    let mut anim = looped_anim();
+    anim.joints.push(JointData {
+        name: "mHead".into(),
+        ..Default::default()
+    });
+    anim.resample(2.0).unwrap();
+    let pelvis = anim.joint("mPelvis").unwrap();
+    let times: Vec<u16> = pelvis.position_keys.iter().map(|k| k.time).collect();
+    assert_eq!(times.len(), 9);
+    assert_eq!((times[0], times[8]), (0, u16::MAX));
+    for (i, key) in pelvis.position_keys.iter().enumerate() {
+        let x = i as f32 * 0.5;
+        assert!(
+            key.pos.abs_diff_eq(Vec3::new(x, 0.0, 0.0), 1e-3),
+            "{i}: {}",
+            key.pos
+        );
+    }
+    assert!(anim.joint("mHead").unwrap().position_keys.is_empty());
+
+    assert!(anim.resample(0.0).is_err());
+    assert_eq!(anim.joint("mPelvis").unwrap().position_keys.len(), 9);
+
+    // More frames than ticks keep one key per tick; an endless duration is refused.
+    anim.header.duration = 1e9;
+    anim.resample(60.0).unwrap();
+    assert_eq!(anim.joint("mPelvis").unwrap().position_keys.len(), 65536);
+    anim.header.duration = f32::INFINITY;
+    assert!(anim.resample(30.0).is_err());
+}
+
+#[test]
+fn recompute_duration_trims_to_the_last_key() {
+    let mut anim = looped_anim();
+    let before = anim.clone();
+    anim.recompute_duration(false);
+    assert_eq!(anim, before);
+
+    let pelvis = &mut anim.joints[0];
+    pelvis.position_keys[1].time = 16384;
+    let at = anim.sample(0.5).joints["mPelvis"].position.unwrap();
+    anim.recompute_duration(false);
+    assert!((anim.header.duration - 1.0).abs() < 1e-3);
+    assert_eq!(anim.header.loop_in_point, 1.0);
+    assert_eq!(anim.header.loop_out_point, anim.header.duration);
+    assert!(
+        anim.sample(0.5).joints["mPelvis"]
+            .position
+            .unwrap()
+            .abs_diff_eq(at, 1e-3)
+    );
+}
+
+#[test]
+fn fix_loop_points_clamps_swaps_and_snaps() {
+    let mut anim = looped_anim();
+    anim.header.loop_in_point = f32::NAN;
+    anim.header.loop_out_point = 2.5;
+    let fix = anim.fix_loop_points(true);
+    assert!(fix.clamped && !fix.swapped && fix.snapped);
+    // the only keys are at 0s and 4s
+    assert_eq!(fix.after, (0.0, 4.0));
+    assert_eq!(
+        (anim.header.loop_in_point, anim.header.loop_out_point),
+        fix.after
+    );
+
+    anim.header.loop_in_point = 3.0;
+    anim.header.loop_out_point = 1.0;
+    let fix = anim.fix_loop_points(false);
+    assert!(fix.swapped && !fix.clamped && !fix.snapped);
+    assert_eq!(fix.after, (1.0, 3.0));
+}
+
+#[test]
+fn root_yaw_drift_counts_full_turns_and_is_removed() {
+    let lean = Quat::from_rotation_y(0.2);
+    let mut anim = Animation::new();
+    anim.header.duration = 3.0;
+    anim.header.looped = 1;
+    anim.header.loop_in_point = 1.0;
+    anim.header.loop_out_point = 3.0;
+    anim.joints.push(JointData {
+        name: "mPelvis".into(),
+        rotation_keys: [0.0f32, 0.0, 2.0, 4.0]
+            .iter()
+            .enumerate()
+            .map(|(i, &yaw)| RotationKey {
+                time: (i as u32 * 65535 / 3) as u16,
+                rot: Quat::from_rotation_z(yaw) * lean,
+            })
+            .collect(),
+        ..Default::default()
+    });
+    assert!((anim.root_yaw_drift() - 4.0).abs() < 1e-3);
+
+    let before = anim.joints[0].rotation_keys[1].rot;
+    anim.remove_root_yaw_drift();
+    let keys = &anim.joints[0].rotation_keys;
+    assert!(anim.root_yaw_drift().abs() < 1e-3);
+    assert!(keys[0].rot.abs_diff_eq(lean, 1e-5));
+    assert!(keys[1].rot.abs_diff_eq(before, 1e-5));
+    assert!(keys[3].rot.abs_diff_eq(lean, 1e-3));
+
+    let mut still = Animation::new();
+    still.remove_root_yaw_drift();
+    assert_eq!(still.root_yaw_drift(), 0.0);
+}
+
+#[test]
+fn root_yaw_drift_is_removed_when_loop_points_fall_between_keys() {
+    let mut anim = Animation::new();
+    anim.header.duration = 2.0;
+    anim.header.looped = 1;
+    anim.header.loop_in_point = 0.5;
+    anim.header.loop_out_point = 1.5;
+    anim.joints.push(JointData {
+        name: "mPelvis".into(),
+        rotation_keys: vec![
+            RotationKey {
+                time: 0,
+                rot: Quat::IDENTITY,
+            },
+            RotationKey {
+                time: 65535,
+                rot: Quat::from_rotation_z(0.4),
+            },
+        ],
+        ..Default::default()
+    });
+    assert!((anim.root_yaw_drift() - 0.2).abs() < 1e-3);
+
+    anim.remove_root_yaw_drift();
+    assert!(anim.root_yaw_drift().abs() < 1e-4);
+    let pelvis = &anim.joints[0];
+    let times: Vec<u16> = pelvis.rotation_keys.iter().map(|k| k.time).collect();
+    assert_eq!(times, [0, 16384, 49151, 65535]);
+    assert!(
+        pelvis.rotation_keys[0]
+            .rot
+            .abs_diff_eq(Quat::IDENTITY, 1e-6)
+    );
+    assert!(
+        pelvis.rotation_keys[1]
+            .rot
+            .abs_diff_eq(Quat::from_rotation_z(0.1), 1e-4)
+    );
+}
+
+#[test]
+fn ease_windows_are_checked_and_rebalanced() {
+    use avatar_anim::EaseProblem;
+
+    // Looped: the pelvis moves from 0s to 4s, the loop ends at 3s.
+    let mut anim = looped_anim();
+    anim.header.looped = 1;
+    anim.header.ease_in_duration = 3.5;
+    anim.header.ease_out_duration = 0.5;
+    assert_eq!(
+        anim.check_ease(),
+        [EaseProblem::SwallowsLoop {
+            ease_in: 3.5,
+            loop_out: 3.0
+        }]
+    );
+    assert!(
+        anim.validate()
+            .warnings()
+            .any(|issue| issue.message.contains("loop out point"))
+    );
+    let fix = anim.fix_ease_windows();
+    assert_eq!(fix.after, (3.0, 0.5));
+    assert!(anim.check_ease().is_empty());
+
+    // Not looped: the motion ends with the last key at 4s, so any ease out fades it.
+    anim.header.looped = 0;
+    anim.header.ease_out_duration = 1.0;
+    let problems = anim.check_ease();
+    assert!(matches!(problems[..], [EaseProblem::CutsMotion { .. }]));
+    let fix = anim.fix_ease_windows();
+    assert!(fix.changed());
+    assert_eq!(fix.after.1, 0.0);
+    assert!(anim.check_ease().is_empty());
+}
+
+#[test]
+fn time_operations_retime_constraint_ease_windows() {
+    use avatar_anim::Constraint;
+
+    let mut anim = looped_anim();
+    anim.constraints.push(Constraint {
+        chain_length: 1,
+        source_volume: "L_FOOT".into(),
+        ease_in_start: 0.5,
+        ease_in_stop: 1.0,
+        ease_out_start: 3.0,
+        ease_out_stop: 3.5,
+        ..Default::default()
+    });
+    let windows = |anim: &Animation| {
+        let c = &anim.constraints[0];
+        [
+            c.ease_in_start,
+            c.ease_in_stop,
+            c.ease_out_start,
+            c.ease_out_stop,
+        ]
+    };
+
+    let mut slow = anim.clone();
+    slow.time_warp(&[(0.0, 0.0), (4.0, 8.0)]).unwrap();
+    assert_eq!(windows(&slow), [1.0, 2.0, 6.0, 7.0]);
+
+    let mut cut = anim.clone();
+    cut.loop_subset(|_| true, 1.0..3.2).unwrap();
+    let cut_windows = windows(&cut);
+    assert_eq!(cut_windows[..3], [0.0, 0.0, 2.0]);
+    assert!((cut_windows[3] - 2.2).abs() < 1e-6);
+    assert!(
+        !cut.validate()
+            .warnings()
+            .any(|issue| issue.message.contains("ease times outside"))
+    );
+
+    anim.header.duration = 3.0;
+    assert!(anim.validate().warnings().any(|issue| {
+        issue
+            .message
+            .contains("constraint 0 has ease times outside")
+    }));
+}
+
+#[test]
+fn reverse_plays_backwards_and_round_trips() {
+    use avatar_anim::{Constraint, RotationKey};
+
+    let mut anim = looped_anim();
+    anim.joints[0].rotation_keys = vec![
+        RotationKey {
+            time: 0,
+            rot: Quat::IDENTITY,
+        },
+        RotationKey {
+            time: 16384,
+            rot: Quat::from_rotation_x(0.4),
+        },
+        RotationKey {
+            time: 65535,
+            rot: Quat::from_rotation_z(0.8),
+        },
+    ];
+    anim.constraints.push(Constraint {
+        ease_in_start: 0.0,
+        ease_in_stop: 0.1,
+        ease_out_start: 0.5,
+        ease_out_stop: 0.8,
+        ..Default::default()
+    });
+    // Sample both ends without wrapping into the loop region.
+    anim.header.looped = 0;
+    let original = anim.clone();
+    let duration = anim.header.duration;
+    let early = anim.sample(0.2 * duration);
+
+    anim.reverse();
+    let keys = &anim.joints[0].rotation_keys;
+    assert_eq!(
+        keys.iter().map(|k| k.time).collect::<Vec<_>>(),
+        [0, 49151, 65535]
+    );
+    let late = anim.sample(0.8 * duration);
+    let (a, b) = (
+        early.rotation("mPelvis").unwrap(),
+        late.rotation("mPelvis").unwrap(),
+    );
+    assert!(a.abs_diff_eq(b, 1e-3), "{a} vs {b}");
+    let constraint = &anim.constraints[0];
+    assert!((constraint.ease_in_start - (duration - 0.8)).abs() < 1e-6);
+    assert!((constraint.ease_out_stop - duration).abs() < 1e-6);
+
+    anim.reverse();
+    assert_eq!(anim.joints, original.joints);
+    assert_eq!(anim.header, original.header);
+}
+
+#[test]
+fn append_builds_an_intro_and_loop_sequence() {
+    use avatar_anim::{Constraint, RotationKey};
+
+    let mut intro = looped_anim();
+    intro.header.looped = 0;
+    intro.header.duration = 1.0;
+    intro.header.ease_out_duration = 0.1;
+    let mut cycle = looped_anim();
+    cycle.joints[0].position_keys[0].pos = Vec3::new(9.0, 0.0, 0.0);
+    cycle.joints.push(JointData {
+        name: "mHead".into(),
+        priority: 5,
+        rotation_keys: vec![RotationKey {
+            time: 0,
+            rot: Quat::from_rotation_z(0.3),
+        }],
+        ..Default::default()
+    });
+    cycle.constraints.push(Constraint {
+        ease_in_stop: 0.5,
+        ..Default::default()
+    });
+
+    intro.append(&cycle, 1.0).unwrap();
+    let header = &intro.header;
+    assert_eq!(header.duration, 6.0);
+    assert_eq!((header.loop_in_point, header.loop_out_point), (3.0, 5.0));
+    assert_eq!(header.looped, 1);
+    assert_eq!(header.ease_out_duration, cycle.header.ease_out_duration);
+
+    let pelvis = &intro.joints[0];
+    let times: Vec<u16> = pelvis.position_keys.iter().map(|k| k.time).collect();
+    assert_eq!(times, [0, 10923, 21845, 65535]);
+    assert_eq!(pelvis.position_keys[2].pos, Vec3::new(9.0, 0.0, 0.0));
+    let head = intro.joint("mHead").unwrap();
+    assert_eq!((head.priority, head.rotation_keys[0].time), (5, 21845));
+    assert_eq!(intro.constraints[0].ease_in_stop, 2.5);
+
+    assert!(intro.append(&cycle, -1.0).is_err());
+    assert!(intro.append(&Animation::new(), 0.0).is_ok());
+    cycle.header.duration = 0.0;
+    assert!(intro.append(&cycle, 0.0).is_err());
+}
@@ -0,0 +1,84 @@
+#![cfg(feature = "serde")]
+
+use avatar_anim::service::{self, ConversionRequest, ConversionResponse, Step};
+use avatar_anim::{Animation, ErrorCode, JointData, RotationKey, SourceFormat};
+
+fn input() -> Vec<u8> {
+    let mut anim = Animation::new();
+    anim.header.duration = 1.0;
+    anim.joints.push(JointData {
+        name: "mHead".into(),
+        rotation_keys: vec![RotationKey::default()],
+        ..Default::default()
+    });
+    anim.joints.push(JointData {
+        name: "mNeck".into(),
+        ..Default::default()
+    });
+    anim.to_bytes().unwrap()
+}
+
+#[test]
+fn requests_deserialize_and_execute() {
+    let json = format!(
+        r#"{{"input": {:?}, "to": "json", "steps": [
+            {{"op": "drop_empty_joints"}},
+            {{"op": "set_priority", "priority": 5}}
+        ]}}"#,
+        input()
+    );
+    let request: ConversionRequest = serde_json::from_str(&json).unwrap();
+    assert_eq!(request.from, None);
+    assert_eq!(request.steps[1], Step::SetPriority { priority: 5 });
+
+    let response = service::execute(&request).unwrap();
+    assert_eq!(response.source_format, SourceFormat::Anim);
+    assert_eq!(response.format, SourceFormat::Json);
+    assert_eq!(response.joints, 1);
+    assert!(response.warnings.is_empty(), "{:?}", response.warnings);
+    let anim = Animation::from_json(std::str::from_utf8(&response.output).unwrap()).unwrap();
+    assert_eq!(anim.header.base_priority, 5);
+
+    let text = serde_json::to_string(&response).unwrap();
+    let back: ConversionResponse = serde_json::from_str(&text).unwrap();
+    assert_eq!(back, response);
+
+    assert!(serde_json::from_str::<ConversionRequest>(r#"{"input": [], "to": "fbx"}"#).is_err());
+}
+
+#[test]
+fn strict_requests_reject_trailing_bytes() {
+    let mut bytes = input();
+    bytes.push(0);
+    let mut request = ConversionRequest::new(bytes);
+    assert!(service::execute(&request).is_ok());
+    request.strict = true;
+    let err = service::execute(&request).unwrap_err();
+    assert_eq!(err.code(), ErrorCode::TrailingBytes);
+
+    let response = service::execute(&ConversionRequest::new(
+        Animation::new().to_bytes().unwrap(),
+    ))
+    .unwrap();
+    assert_eq!(response.warnings, ["blocker: no joints are animated"]);
+}
+
+#[test]
+fn implausible_headers_are_sanitized_before_export() {
+    let mut anim = Animation::new();
+    anim.header.duration = f32::INFINITY;
+    anim.joints.push(JointData {
+        name: "mHead".into(),
+        rotation_keys: vec![RotationKey::default()],
+        ..Default::default()
+    });
+    let mut request = ConversionRequest::new(anim.to_bytes().unwrap());
+    request.to = SourceFormat::Bvh;
+    let response = service::execute(&request).unwrap();
+    assert_eq!(response.duration, 0.0);
+    assert!(
+        response.warnings[0].starts_with("duration inf is not a plausible time"),
+        "{:?}",
+        response.warnings
+    );
+}
@@ -0,0 +1,354 @@
+use avatar_anim::skeleton::Skeleton;
+
+#[test]
+fn default_skeleton_hierarchy() {
+    let skeleton = Skeleton::second_life();
+    assert_eq!(skeleton.joints()[0].name, "mPelvis");
+    assert_eq!(skeleton.parent("mElbowLeft").unwrap().name, "mShoulderLeft");
+    assert!(skeleton.parent("mPelvis").is_none());
+}
+
+#[cfg(feature = "toml")]
+#[test]
+fn joint_offsets_from_toml() {
+    use avatar_anim::skeleton::JointOffsets;
+
+    let offsets = JointOffsets::from_toml_str(
+        "[mKneeLeft]\nposition = [0.0, -0.05, -0.52]\n\n[mKneeRight]\nposition = [0.0, 0.05, -0.52]\n",
+    )
+    .unwrap();
+    let mut skeleton = Skeleton::second_life();
+    skeleton.apply_offsets(&offsets).unwrap();
+    assert_eq!(skeleton.joint("mKneeLeft").unwrap().position.z, -0.52);
+
+    let bad = JointOffsets::from_toml_str("[mKnee]\nposition = [0.0, 0.0, 0.0]\n").unwrap();
+    let before = skeleton.clone();
+    assert!(skeleton.apply_offsets(&bad).is_err());
+    assert_eq!(skeleton, before);
+}
+
+#[test]
+fn body_presets_by_name() {
+    use avatar_anim::skeleton::BodyPreset;
+    assert_eq!(BodyPreset::from_name("Petite"), Some(BodyPreset::Petite));
+    assert!("ogre".parse::<BodyPreset>().is_err());
+    assert_eq!(BodyPreset::Legacy.skeleton(), Skeleton::second_life());
+    let tall = BodyPreset::Tall.skeleton();
+    let pelvis = |s: &Skeleton| s.joint("mPelvis").unwrap().position.z;
+    assert!(pelvis(&tall) > pelvis(&Skeleton::second_life()));
+}
+
+#[test]
+fn retarget_scales_pelvis_positions_with_leg_length() {
+    use avatar_anim::skeleton::BodyPreset;
+    use avatar_anim::{Animation, JointData, PositionKey};
+    let mut anim = Animation::default();
+    anim.joints.push(JointData {
+        name: "mPelvis".into(),
+        position_keys: vec![PositionKey {
+            time: 0,
+            pos: glam::Vec3::new(0.0, 0.0, -0.2),
+        }],
+        ..Default::default()
+    });
+    let from = Skeleton::second_life();
+    let to = BodyPreset::Tall.skeleton();
+    anim.retarget(&from, &to);
+    let expected = -0.2 * to.joint("mPelvis").unwrap().position.z / 1.067;
+    let z = anim.joint("mPelvis").unwrap().position_keys[0].pos.z;
+    assert!((z - expected).abs() < 1e-5);
+}
+
+#[test]
+fn chain_selects_joints_between_two_joints() {
+    use avatar_anim::{Animation, JointData};
+
+    let skeleton = Skeleton::second_life();
+    assert_eq!(
+        skeleton.chain("mChest", "mWristLeft").unwrap(),
+        [
+            "mChest",
+            "mCollarLeft",
+            "mShoulderLeft",
+            "mElbowLeft",
+            "mWristLeft"
+        ]
+    );
+    assert_eq!(skeleton.chain("mHead", "mHead").unwrap(), ["mHead"]);
+    assert!(skeleton.chain("mWristLeft", "mChest").is_none());
+    assert!(skeleton.chain("mChest", "mKneeLeft").is_none());
+    assert!(skeleton.chain("mChest", "mTail1").is_none());
+
+    let mut anim = Animation::new();
+    for name in ["mPelvis", "mElbowLeft", "mHead", "mShoulderLeft"] {
+        anim.joints.push(JointData {
+            name: name.into(),
+            ..Default::default()
+        });
+    }
+    assert!(anim.retain_chain(&skeleton, "mChest", "mKneeLeft").is_err());
+    assert_eq!(anim.joints.len(), 4);
+    anim.retain_chain(&skeleton, "mChest", "mWristLeft")
+        .unwrap();
+    let names: Vec<&str> = anim.joints.iter().map(|j| j.name.as_str()).collect();
+    assert_eq!(names, ["mElbowLeft", "mShoulderLeft"]);
+}
+
+#[test]
+fn registry_is_shared_across_threads() {
+    use avatar_anim::skeleton::registry;
+    use glam::Vec3;
+
+    let handles: Vec<_> = (0..4)
+        .map(|i| {
+            std::thread::spawn(move || {
+                registry()
+                    .register(&format!("mRegistryTest{i}"), Some("mChest"), Vec3::Z)
+                    .unwrap();
+            })
+        })
+        .collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+    let skeleton = registry().skeleton();
+    for i in 0..4 {
+        let name = format!("mRegistryTest{i}");
+        assert_eq!(skeleton.parent(&name).unwrap().name, "mChest");
+    }
+    assert!(registry().contains("mPelvis"));
+    assert!(
+        registry()
+            .register("mRegistryTest0", None, Vec3::ZERO)
+            .is_err()
+    );
+    assert!(
+        registry()
+            .register("mOrphan", Some("mMissing"), Vec3::ZERO)
+            .is_err()
+    );
+    assert!(!registry().contains("mOrphan"));
+}
+
+#[test]
+fn bento_skeleton_is_built_in() {
+    let bento = Skeleton::bento();
+    assert_eq!(bento.joints().len(), 133);
+    assert_eq!(bento.collision_volumes().len(), 26);
+    for (index, joint) in bento.joints().iter().enumerate() {
+        assert!(
+            joint.parent.is_none_or(|parent| parent < index),
+            "{}",
+            joint.name
+        );
+    }
+    for joint in Skeleton::second_life().joints() {
+        assert!(bento.joint(&joint.name).is_some(), "{}", joint.name);
+    }
+    assert_eq!(
+        bento.chain("mPelvis", "mChest").unwrap(),
+        [
+            "mPelvis", "mSpine1", "mSpine2", "mTorso", "mSpine3", "mSpine4", "mChest"
+        ]
+    );
+    assert_eq!(
+        bento.chain("mWristRight", "mHandIndex3Right").unwrap(),
+        [
+            "mWristRight",
+            "mHandIndex1Right",
+            "mHandIndex2Right",
+            "mHandIndex3Right"
+        ]
+    );
+    assert_eq!(
+        bento.parent("mFaceTongueTip").unwrap().name,
+        "mFaceTongueBase"
+    );
+    assert_eq!(
+        bento.joint("mHandThumb1Left").unwrap().position.y,
+        -bento.joint("mHandThumb1Right").unwrap().position.y
+    );
+
+    let head = bento.collision_volume("HEAD").unwrap();
+    assert_eq!(head.bone, "mHead");
+    assert!(
+        bento
+            .collision_volumes()
+            .iter()
+            .all(|volume| bento.joint(&volume.bone).is_some())
+    );
+    assert!(Skeleton::second_life().collision_volumes().is_empty());
+}
+
+#[test]
+fn avatar_skeleton_xml_is_loaded() {
+    let xml = r#"<?xml version="1.0" encoding="US-ASCII" standalone="yes"?>
+<linden_skeleton num_bones="4" num_collision_volumes="2" version="2.0">
+  <!-- a custom grid moves the hips and adds a bone -->
+  <bone name="mPelvis" pos="0.000 0.000 1.100" rot="0 0 0" scale="1 1 1" group="Torso">
+    <collision_volume name="PELVIS" pos="-0.01 0.0 -0.02" rot="0 0 0" scale="0.12 0.16 0.17"/>
+    <bone name="mTorso" pos="0.000 0.000 0.084" aliases="abdomen">
+      <collision_volume name="BELLY" pos="0.028 0.0 0.04" scale="0.09 0.13 0.15"/>
+    </bone>
+    <bone name="mHipLeft" pos="0.034 0.127 -0.041"></bone>
+    <bone name="mGrid" pos="0 0 -0.1"/>
+  </bone>
+</linden_skeleton>
+"#;
+    let skeleton = Skeleton::from_avatar_skeleton_str(xml).unwrap();
+    let names: Vec<&str> = skeleton.joints().iter().map(|j| j.name.as_str()).collect();
+    assert_eq!(names, ["mPelvis", "mTorso", "mHipLeft", "mGrid"]);
+    assert_eq!(skeleton.joint("mPelvis").unwrap().position.z, 1.1);
+    assert_eq!(skeleton.parent("mGrid").unwrap().name, "mPelvis");
+    let belly = skeleton.collision_volume("BELLY").unwrap();
+    assert_eq!((belly.bone.as_str(), belly.scale.y), ("mTorso", 0.13));
+
+    let path =
+        std::env::temp_dir().join(format!("avatar_anim_skeleton_{}.xml", std::process::id()));
+    std::fs::write(&path, xml).unwrap();
+    let from_file = Skeleton::from_avatar_skeleton_xml(&path);
+    std::fs::remove_file(&path).ok();
+    assert_eq!(from_file.unwrap(), skeleton);
+
+    for bad in [
+        r#"<bone name="mPelvis" pos="0 0">"#,
+        r#"<bone pos="0 0 1"></bone>"#,
+        r#"<bone name="mPelvis"></bone><bone name="mPelvis"></bone>"#,
+        r#"<collision_volume name="HEAD"/>"#,
+        "<linden_skeleton></linden_skeleton>",
+    ] {
+        assert!(Skeleton::from_avatar_skeleton_str(bad).is_err(), "{bad}");
+    }
+}
+
+#[test]
+fn world_transforms_follow_the_hierarchy() {
+    use avatar_anim::Pose;
+    use glam::{Quat, Vec3};
+
+    let skeleton = Skeleton::bento();
+    let at = |world: &[avatar_anim::skeleton::JointTransform], name: &str| {
+        world[skeleton.index_of(name).unwrap()].translation
+    };
+
+    let rest = skeleton.world_transforms(&Pose::new());
+    assert_eq!(rest.len(), skeleton.joints().len());
+    assert!(at(&rest, "mPelvis").abs_diff_eq(Vec3::new(0.0, 0.0, 1.067), 1e-6));
+    // mSpine1 and mSpine2 cancel out, so the torso sits where the legacy skeleton has it.
+    assert!(at(&rest, "mTorso").abs_diff_eq(Vec3::new(0.0, 0.0, 1.151), 1e-5));
+
+    let mut pose = Pose::new();
+    pose.set_position("mPelvis", Vec3::new(0.0, 0.0, -0.5));
+    pose.set_rotation("mPelvis", Quat::from_rotation_z(std::f32::consts::PI));
+    pose.set_position("mHead", Vec3::new(0.0, 0.0, 0.2));
+    pose.set_rotation("mNotAJoint", Quat::from_rotation_x(1.0));
+    let world = skeleton.world_transforms(&pose);
+    assert!(at(&world, "mPelvis").abs_diff_eq(Vec3::new(0.0, 0.0, 0.567), 1e-6));
+    // Turned around: the left hip is now on the right.
+    assert!(at(&world, "mHipLeft").y < 0.0);
+    let neck = world[skeleton.index_of("mNeck").unwrap()];
+    assert!(at(&world, "mHead").abs_diff_eq(neck.translation + Vec3::new(0.0, 0.0, 0.2), 1e-5));
+    let head = world[skeleton.index_of("mHead").unwrap()];
+    assert!(
+        head.to_mat4()
+            .transform_point3(Vec3::X)
+            .abs_diff_eq(head.transform_point(Vec3::X), 1e-6)
+    );
+}
+
+#[test]
+fn subtree_operations_strip_whole_limbs() {
+    use avatar_anim::{Animation, JointData};
+
+    let bento = Skeleton::bento();
+    let arm = bento.joints_below("mShoulderLeft").unwrap();
+    assert_eq!(arm.len(), 17);
+    assert_eq!(arm[..2], ["mElbowLeft", "mWristLeft"]);
+    assert!(arm.contains(&"mHandPinky3Left"));
+    assert!(!arm.contains(&"mShoulderLeft") && !arm.contains(&"mHandPinky3Right"));
+    assert_eq!(bento.joints_below("mToeLeft").unwrap(), Vec::<&str>::new());
+    assert!(bento.joints_below("mNope").is_none());
+    assert_eq!(bento.joints_below("mFaceRoot").unwrap().len(), 45);
+
+    let mut anim = Animation::new();
+    for name in [
+        "mPelvis",
+        "mHead",
+        "mFaceJaw",
+        "mFaceTongueTip",
+        "mCustomHelper",
+    ] {
+        anim.joints.push(JointData {
+            name: name.into(),
+            ..Default::default()
+        });
+    }
+    let mut face = anim.clone();
+    face.keep_subtree(&bento, "mFaceRoot").unwrap();
+    let names: Vec<&str> = face.joints.iter().map(|j| j.name.as_str()).collect();
+    assert_eq!(names, ["mFaceJaw", "mFaceTongueTip"]);
+
+    anim.drop_subtree(&bento, "mHead").unwrap();
+    let names: Vec<&str> = anim.joints.iter().map(|j| j.name.as_str()).collect();
+    assert_eq!(names, ["mPelvis", "mCustomHelper"]);
+    assert!(anim.drop_subtree(&bento, "mNope").is_err());
+    assert_eq!(anim.joints.len(), 2);
+}
+
+#[test]
+fn mirror_swaps_sides_volumes_and_hand_pose() {
+    use avatar_anim::{Animation, Constraint, JointData, PositionKey, RotationKey};
+    use glam::{Quat, Vec3};
+
+    let bento = Skeleton::bento();
+    let mut anim = Animation::new();
+    anim.header.hand_pose = 5;
+    for name in ["mCollarLeft", "mHandThumb1Right", "mPelvis", "mTailLeft"] {
+        anim.joints.push(JointData {
+            name: name.into(),
+            rotation_keys: vec![RotationKey {
+                time: 0,
+                rot: Quat::from_rotation_x(0.3) * Quat::from_rotation_z(0.2),
+            }],
+            position_keys: vec![PositionKey {
+                time: 0,
+                pos: Vec3::new(0.1, 0.2, 0.3),
+            }],
+            ..Default::default()
+        });
+    }
+    anim.constraints.push(Constraint {
+        source_volume: "L_HAND".into(),
+        target_volume: "PELVIS".into(),
+        source_offset: [0.0, 0.1, 0.0],
+        target_dir: [1.0, 1.0, 0.0],
+        ..Default::default()
+    });
+    let original = anim.clone();
+    anim.mirror(&bento);
+
+    let names: Vec<&str> = anim.joints.iter().map(|j| j.name.as_str()).collect();
+    assert_eq!(
+        names,
+        ["mCollarRight", "mHandThumb1Left", "mPelvis", "mTailRight"]
+    );
+    assert_eq!(
+        anim.joints[2].position_keys[0].pos,
+        Vec3::new(0.1, -0.2, 0.3)
+    );
+    assert!(anim.joints[0].rotation_keys[0].rot.abs_diff_eq(
+        Quat::from_rotation_x(-0.3) * Quat::from_rotation_z(-0.2),
+        1e-6
+    ));
+    assert_eq!(anim.header.hand_pose, 8);
+    let constraint = &anim.constraints[0];
+    assert_eq!(constraint.source_volume, "R_HAND");
+    assert_eq!(constraint.target_volume, "PELVIS");
+    assert_eq!(constraint.source_offset, [0.0, -0.1, 0.0]);
+    assert_eq!(constraint.target_dir, [1.0, -1.0, 0.0]);
+
+    anim.mirror(&bento);
+    assert_eq!(anim.joints[0].name, original.joints[0].name);
+    assert_eq!(anim.header.hand_pose, 5);
+    assert_eq!(anim.constraints, original.constraints);
+}